@@ -33,6 +33,8 @@ fn main() -> anyhow::Result<()> {
             test_directory_module(out, "tests/misc_testsuite/multi-memory", strategy)?;
             test_directory_module(out, "tests/misc_testsuite/module-linking", strategy)?;
             test_directory_module(out, "tests/misc_testsuite/threads", strategy)?;
+            test_directory_module(out, "tests/misc_testsuite/memory64", strategy)?;
+            test_directory_module(out, "tests/misc_testsuite/simd", strategy)?;
             Ok(())
         })?;
 
@@ -192,6 +194,7 @@ fn ignore(testsuite: &str, testname: &str, strategy: &str) -> bool {
             ("multi_value", _) => return true,
             ("reference_types", _) => return true,
             ("bulk_memory_operations", _) => return true,
+            ("memory64", _) => return true,
             _ => (),
         },
         "Cranelift" => match (testsuite, testname) {
@@ -201,6 +204,17 @@ fn ignore(testsuite: &str, testname: &str, strategy: &str) -> bool {
                 return env::var("CARGO_CFG_TARGET_ARCH").unwrap() != "x86_64";
             }
 
+            // 64-bit memories are only supported on 64-bit hosts.
+            ("memory64", _) => {
+                return env::var("CARGO_CFG_TARGET_POINTER_WIDTH").unwrap() != "64";
+            }
+
+            // The legacy x86 backend doesn't support atomic instructions.
+            ("threads", "atomics_bounds") => {
+                return env::var("CARGO_CFG_TARGET_ARCH").unwrap() == "x86_64"
+                    && !cfg!(feature = "experimental_x64");
+            }
+
             // Ignore all x64 SIMD tests for now (#2470).
             ("simd", _) if cfg!(feature = "experimental_x64") => {
                 return env::var("CARGO_CFG_TARGET_ARCH").unwrap() == "x86_64";
@@ -1928,6 +1928,7 @@ fn define_simd(
     for (ty, swiden_opcode, uwiden_opcode) in &[
         (I8, &PMOVSXBW[..], &PMOVZXBW[..]),
         (I16, &PMOVSXWD[..], &PMOVZXWD[..]),
+        (I32, &PMOVSXDQ[..], &PMOVZXDQ[..]),
     ] {
         let isap = Some(use_sse41_simd);
         let swiden_low = swiden_low.bind(vector(*ty, sse_vector_size));
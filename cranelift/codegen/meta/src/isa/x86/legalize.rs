@@ -792,7 +792,7 @@ fn define_simd(
     }
 
     // SIMD widen
-    for ty in &[I8, I16] {
+    for ty in &[I8, I16, I32] {
         let swiden_high = swiden_high.bind(vector(*ty, sse_vector_size));
         narrow.legalize(
             def!(b = swiden_high(a)),
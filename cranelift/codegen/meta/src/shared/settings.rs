@@ -45,8 +45,53 @@ pub(crate) fn define() -> SettingGroup {
         - speed: Generate the fastest possible code
         - speed_and_size: like "speed", but also perform transformations
           aimed at reducing code size.
+        - size: favor small code over fast code. Optimizations which tend to
+          grow the code, such as loop-invariant code motion, are skipped.
         "#,
-        vec!["none", "speed", "speed_and_size"],
+        vec!["none", "speed", "speed_and_size", "size"],
+    );
+
+    // Optimization pass toggles.
+
+    settings.add_bool(
+        "enable_preopt",
+        r#"
+        Run the pre-legalization peephole optimizer.
+
+        Like the other optimization pass toggles, this only has an effect when `opt_level` is
+        not `none`, and allows trading compile time for code quality pass by pass.
+        "#,
+        true,
+    );
+
+    settings.add_bool(
+        "enable_licm",
+        r#"
+        Run loop-invariant code motion.
+
+        This only has an effect when `opt_level` is not `none`.
+        "#,
+        true,
+    );
+
+    settings.add_bool(
+        "enable_gvn",
+        r#"
+        Run global value numbering.
+
+        This only has an effect when `opt_level` is not `none`.
+        "#,
+        true,
+    );
+
+    settings.add_bool(
+        "enable_redundant_load_elim",
+        r#"
+        Run redundant load elimination.
+
+        This only has an effect when `opt_level` is not `none`.
+        "#,
+        true,
     );
 
     settings.add_bool(
@@ -283,5 +328,22 @@ pub(crate) fn define() -> SettingGroup {
         true,
     );
 
+    settings.add_bool(
+        "enable_table_access_spectre_mitigation",
+        r#"
+        Enable Spectre mitigation on table bounds checks.
+
+        When enabled, the address computed by `table_addr` is replaced with a
+        null pointer using a conditional move if the index is out of bounds,
+        so that a mispredicted bounds check can't be used to speculatively
+        read past the end of the table.
+
+        This option is enabled by default because it is highly
+        recommended for secure sandboxing. The embedder should consider
+        the security implications carefully before disabling this option.
+        "#,
+        true,
+    );
+
     settings.build()
 }
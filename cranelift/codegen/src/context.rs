@@ -16,7 +16,7 @@ use crate::binemit::{
 use crate::dce::do_dce;
 use crate::dominator_tree::DominatorTree;
 use crate::flowgraph::ControlFlowGraph;
-use crate::ir::Function;
+use crate::ir::{Function, Opcode};
 use crate::isa::TargetIsa;
 use crate::legalize_function;
 use crate::legalizer::simple_legalize;
@@ -25,10 +25,11 @@ use crate::loop_analysis::LoopAnalysis;
 use crate::machinst::{MachCompileResult, MachStackMap};
 use crate::nan_canonicalization::do_nan_canonicalization;
 use crate::postopt::do_postopt;
+use crate::redundant_load_elim::do_redundant_load_elim;
 use crate::redundant_reload_remover::RedundantReloadRemover;
 use crate::regalloc;
 use crate::remove_constant_phis::do_remove_constant_phis;
-use crate::result::CodegenResult;
+use crate::result::{CodegenError, CodegenResult};
 use crate::settings::{FlagsOrIsa, OptLevel};
 use crate::simple_gvn::do_simple_gvn;
 use crate::simple_preopt::do_preopt;
@@ -169,7 +170,7 @@ impl Context {
         );
 
         self.compute_cfg();
-        if opt_level != OptLevel::None {
+        if opt_level != OptLevel::None && isa.flags().enable_preopt() {
             self.preopt(isa)?;
         }
         if isa.flags().enable_nan_canonicalization() {
@@ -180,9 +181,16 @@ impl Context {
         if opt_level != OptLevel::None {
             self.postopt(isa)?;
             self.compute_domtree();
-            self.compute_loop_analysis();
-            self.licm(isa)?;
-            self.simple_gvn(isa)?;
+            if isa.flags().enable_licm() && opt_level != OptLevel::Size {
+                self.compute_loop_analysis();
+                self.licm(isa)?;
+            }
+            if isa.flags().enable_gvn() {
+                self.simple_gvn(isa)?;
+            }
+            if isa.flags().enable_redundant_load_elim() {
+                self.redundant_load_elim(isa)?;
+            }
         }
 
         self.compute_domtree();
@@ -201,10 +209,10 @@ impl Context {
         } else {
             self.regalloc(isa)?;
             self.prologue_epilogue(isa)?;
-            if opt_level == OptLevel::Speed || opt_level == OptLevel::SpeedAndSize {
+            if opt_level != OptLevel::None {
                 self.redundant_reload_remover(isa)?;
             }
-            if opt_level == OptLevel::SpeedAndSize {
+            if opt_level == OptLevel::SpeedAndSize || opt_level == OptLevel::Size {
                 self.shrink_instructions(isa)?;
             }
             let result = self.relax_branches(isa);
@@ -364,6 +372,20 @@ impl Context {
             simple_legalize(&mut self.func, &mut self.cfg, isa);
             self.verify_if(isa)
         } else {
+            // The legacy backends have no encodings for atomic instructions;
+            // report that instead of failing to encode them later on.
+            if let Some(inst) = self.func.layout.blocks().find_map(|block| {
+                self.func
+                    .layout
+                    .block_insts(block)
+                    .find(|&inst| is_atomic(self.func.dfg[inst].opcode()))
+            }) {
+                return Err(CodegenError::Unsupported(format!(
+                    "atomic instruction `{}` is not supported by the legacy backend for {}",
+                    self.func.dfg[inst].opcode(),
+                    isa.name()
+                )));
+            }
             legalize_function(&mut self.func, &mut self.cfg, isa);
             debug!("Legalized:\n{}", self.func.display(isa));
             self.verify_if(isa)
@@ -405,6 +427,15 @@ impl Context {
         self.verify_if(fisa)
     }
 
+    /// Perform redundant load elimination on the function.
+    pub fn redundant_load_elim<'a, FOI: Into<FlagsOrIsa<'a>>>(
+        &mut self,
+        fisa: FOI,
+    ) -> CodegenResult<()> {
+        do_redundant_load_elim(&mut self.func);
+        self.verify_if(fisa)
+    }
+
     /// Perform LICM on the function.
     pub fn licm(&mut self, isa: &dyn TargetIsa) -> CodegenResult<()> {
         do_licm(
@@ -470,6 +501,9 @@ impl Context {
         &self,
         isa: &dyn TargetIsa,
     ) -> CodegenResult<ValueLabelsRanges> {
+        if let Some(result) = &self.mach_compile_result {
+            return Ok(result.value_labels_ranges.clone());
+        }
         Ok(build_value_labels_ranges::<ComparableSourceLoc>(
             &self.func,
             &self.regalloc,
@@ -487,3 +521,15 @@ impl Context {
         Ok(())
     }
 }
+
+/// Is `opcode` one of the atomic memory instructions?
+fn is_atomic(opcode: Opcode) -> bool {
+    match opcode {
+        Opcode::AtomicRmw
+        | Opcode::AtomicCas
+        | Opcode::AtomicLoad
+        | Opcode::AtomicStore
+        | Opcode::Fence => true,
+        _ => false,
+    }
+}
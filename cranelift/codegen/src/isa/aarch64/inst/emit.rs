@@ -462,6 +462,34 @@ fn enc_stxr(ty: Type, rs: Writable<Reg>, rt: Reg, rn: Reg) -> u32 {
         | machreg_to_gpr(rt)
 }
 
+fn enc_ldar(ty: Type, rt: Writable<Reg>, rn: Reg) -> u32 {
+    let sz = match ty {
+        I64 => 0b11,
+        I32 => 0b10,
+        I16 => 0b01,
+        I8 => 0b00,
+        _ => unreachable!(),
+    };
+    0b00001000_11011111_11111100_00000000
+        | (sz << 30)
+        | (machreg_to_gpr(rn) << 5)
+        | machreg_to_gpr(rt.to_reg())
+}
+
+fn enc_stlr(ty: Type, rt: Reg, rn: Reg) -> u32 {
+    let sz = match ty {
+        I64 => 0b11,
+        I32 => 0b10,
+        I16 => 0b01,
+        I8 => 0b00,
+        _ => unreachable!(),
+    };
+    0b00001000_10011111_11111100_00000000
+        | (sz << 30)
+        | (machreg_to_gpr(rn) << 5)
+        | machreg_to_gpr(rt)
+}
+
 fn enc_asimd_mod_imm(rd: Writable<Reg>, q_op: u32, cmode: u32, imm: u8) -> u32 {
     let abc = (imm >> 5) as u32;
     let defgh = (imm & 0b11111) as u32;
@@ -1250,43 +1278,18 @@ impl MachInstEmit for Inst {
                 sink.put4(enc_dmb_ish()); // dmb ish
             }
             &Inst::AtomicLoad { ty, r_data, r_addr } => {
-                let op = match ty {
-                    I8 => 0b0011100001,
-                    I16 => 0b0111100001,
-                    I32 => 0b1011100001,
-                    I64 => 0b1111100001,
-                    _ => unreachable!(),
-                };
-                sink.put4(enc_dmb_ish()); // dmb ish
-
                 let srcloc = state.cur_srcloc();
                 if srcloc != SourceLoc::default() {
                     sink.add_trap(srcloc, TrapCode::HeapOutOfBounds);
                 }
-                let uimm12scaled_zero = UImm12Scaled::zero(I8 /*irrelevant*/);
-                sink.put4(enc_ldst_uimm12(
-                    op,
-                    uimm12scaled_zero,
-                    r_addr,
-                    r_data.to_reg(),
-                ));
+                sink.put4(enc_ldar(ty, r_data, r_addr)); // ldar{b,h} r_data, [r_addr]
             }
             &Inst::AtomicStore { ty, r_data, r_addr } => {
-                let op = match ty {
-                    I8 => 0b0011100000,
-                    I16 => 0b0111100000,
-                    I32 => 0b1011100000,
-                    I64 => 0b1111100000,
-                    _ => unreachable!(),
-                };
-
                 let srcloc = state.cur_srcloc();
                 if srcloc != SourceLoc::default() {
                     sink.add_trap(srcloc, TrapCode::HeapOutOfBounds);
                 }
-                let uimm12scaled_zero = UImm12Scaled::zero(I8 /*irrelevant*/);
-                sink.put4(enc_ldst_uimm12(op, uimm12scaled_zero, r_addr, r_data));
-                sink.put4(enc_dmb_ish()); // dmb ish
+                sink.put4(enc_stlr(ty, r_data, r_addr)); // stlr{b,h} r_data, [r_addr]
             }
             &Inst::Fence {} => {
                 sink.put4(enc_dmb_ish()); // dmb ish
@@ -1963,9 +1966,13 @@ impl MachInstEmit for Inst {
                     VecALUOp::Zip1 => (0b01001110_00_0 | enc_size << 1, 0b001110),
                     VecALUOp::Smull => (0b000_01110_00_1 | enc_size << 1, 0b110000),
                     VecALUOp::Smull2 => (0b010_01110_00_1 | enc_size << 1, 0b110000),
+                    VecALUOp::Umull => (0b001_01110_00_1 | enc_size << 1, 0b110000),
+                    VecALUOp::Umull2 => (0b011_01110_00_1 | enc_size << 1, 0b110000),
                 };
                 let top11 = match alu_op {
-                    VecALUOp::Smull | VecALUOp::Smull2 => top11,
+                    VecALUOp::Smull | VecALUOp::Smull2 | VecALUOp::Umull | VecALUOp::Umull2 => {
+                        top11
+                    }
                     _ if is_float => top11 | (q << 9) | enc_float_size << 1,
                     _ => top11 | (q << 9),
                 };
@@ -2133,19 +2140,18 @@ impl MachInstEmit for Inst {
                 sink.put4(enc_jump26(0b000101, not_taken.as_offset26_or_zero()));
             }
             &Inst::TrapIf { kind, trap_code } => {
+                // The udf is outlined, out of the way of the hot path; only a
+                // (normally not taken) conditional branch to it stays inline.
+                let label = sink.defer_trap(
+                    trap_code,
+                    state.cur_srcloc(),
+                    state.take_stack_map(),
+                    &0xd4a00000u32.to_le_bytes(), // udf
+                );
                 // condbr KIND, LABEL
                 let off = sink.cur_offset();
-                let label = sink.get_label();
-                sink.put4(enc_conditional_br(
-                    BranchTarget::Label(label),
-                    kind.invert(),
-                ));
+                sink.put4(enc_conditional_br(BranchTarget::Label(label), kind));
                 sink.use_label_at_offset(off, label, LabelUse::Branch19);
-                // udf
-                let trap = Inst::Udf { trap_code };
-                trap.emit(sink, emit_info, state);
-                // LABEL:
-                sink.bind_label(label);
             }
             &Inst::IndirectBr { rn, .. } => {
                 sink.put4(enc_br(rn));
@@ -2354,6 +2360,9 @@ impl MachInstEmit for Inst {
                 );
                 state.virtual_sp_offset += offset;
             }
+            &Inst::ValueLabelMarker { .. } => {
+                // Nothing; this is only used to compute debug info.
+            }
             &Inst::EmitIsland { needed_space } => {
                 if sink.island_needed(needed_space + 4) {
                     let jump_around_label = sink.get_label();
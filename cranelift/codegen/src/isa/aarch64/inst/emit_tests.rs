@@ -3451,6 +3451,42 @@ fn test_aarch64_binemit() {
         "smull2 v8.2d, v12.4s, v14.4s",
     ));
 
+    insns.push((
+        Inst::VecRRR {
+            alu_op: VecALUOp::Umull,
+            rd: writable_vreg(16),
+            rn: vreg(12),
+            rm: vreg(1),
+            size: VectorSize::Size8x16,
+        },
+        "90C1212E",
+        "umull v16.8h, v12.8b, v1.8b",
+    ));
+
+    insns.push((
+        Inst::VecRRR {
+            alu_op: VecALUOp::Umull,
+            rd: writable_vreg(8),
+            rn: vreg(12),
+            rm: vreg(14),
+            size: VectorSize::Size32x4,
+        },
+        "88C1AE2E",
+        "umull v8.2d, v12.2s, v14.2s",
+    ));
+
+    insns.push((
+        Inst::VecRRR {
+            alu_op: VecALUOp::Umull2,
+            rd: writable_vreg(2),
+            rn: vreg(13),
+            rm: vreg(6),
+            size: VectorSize::Size16x8,
+        },
+        "A2C1666E",
+        "umull2 v2.4s, v13.8h, v6.8h",
+    ));
+
     insns.push((
         Inst::VecMisc {
             op: VecMisc2::Not,
@@ -4234,144 +4270,144 @@ fn test_aarch64_binemit() {
             trap_code: TrapCode::Interrupt,
             kind: CondBrKind::NotZero(xreg(8)),
         },
-        "480000B40000A0D4",
-        "cbz x8, 8 ; udf",
+        "280000B50000A0D4",
+        "cbnz x8, trap",
     ));
     insns.push((
         Inst::TrapIf {
             trap_code: TrapCode::Interrupt,
             kind: CondBrKind::Zero(xreg(8)),
         },
-        "480000B50000A0D4",
-        "cbnz x8, 8 ; udf",
+        "280000B40000A0D4",
+        "cbz x8, trap",
     ));
     insns.push((
         Inst::TrapIf {
             trap_code: TrapCode::Interrupt,
             kind: CondBrKind::Cond(Cond::Ne),
         },
-        "400000540000A0D4",
-        "b.eq 8 ; udf",
+        "210000540000A0D4",
+        "b.ne trap",
     ));
     insns.push((
         Inst::TrapIf {
             trap_code: TrapCode::Interrupt,
             kind: CondBrKind::Cond(Cond::Eq),
         },
-        "410000540000A0D4",
-        "b.ne 8 ; udf",
+        "200000540000A0D4",
+        "b.eq trap",
     ));
     insns.push((
         Inst::TrapIf {
             trap_code: TrapCode::Interrupt,
             kind: CondBrKind::Cond(Cond::Lo),
         },
-        "420000540000A0D4",
-        "b.hs 8 ; udf",
+        "230000540000A0D4",
+        "b.lo trap",
     ));
     insns.push((
         Inst::TrapIf {
             trap_code: TrapCode::Interrupt,
             kind: CondBrKind::Cond(Cond::Hs),
         },
-        "430000540000A0D4",
-        "b.lo 8 ; udf",
+        "220000540000A0D4",
+        "b.hs trap",
     ));
     insns.push((
         Inst::TrapIf {
             trap_code: TrapCode::Interrupt,
             kind: CondBrKind::Cond(Cond::Pl),
         },
-        "440000540000A0D4",
-        "b.mi 8 ; udf",
+        "250000540000A0D4",
+        "b.pl trap",
     ));
     insns.push((
         Inst::TrapIf {
             trap_code: TrapCode::Interrupt,
             kind: CondBrKind::Cond(Cond::Mi),
         },
-        "450000540000A0D4",
-        "b.pl 8 ; udf",
+        "240000540000A0D4",
+        "b.mi trap",
     ));
     insns.push((
         Inst::TrapIf {
             trap_code: TrapCode::Interrupt,
             kind: CondBrKind::Cond(Cond::Vc),
         },
-        "460000540000A0D4",
-        "b.vs 8 ; udf",
+        "270000540000A0D4",
+        "b.vc trap",
     ));
     insns.push((
         Inst::TrapIf {
             trap_code: TrapCode::Interrupt,
             kind: CondBrKind::Cond(Cond::Vs),
         },
-        "470000540000A0D4",
-        "b.vc 8 ; udf",
+        "260000540000A0D4",
+        "b.vs trap",
     ));
     insns.push((
         Inst::TrapIf {
             trap_code: TrapCode::Interrupt,
             kind: CondBrKind::Cond(Cond::Ls),
         },
-        "480000540000A0D4",
-        "b.hi 8 ; udf",
+        "290000540000A0D4",
+        "b.ls trap",
     ));
     insns.push((
         Inst::TrapIf {
             trap_code: TrapCode::Interrupt,
             kind: CondBrKind::Cond(Cond::Hi),
         },
-        "490000540000A0D4",
-        "b.ls 8 ; udf",
+        "280000540000A0D4",
+        "b.hi trap",
     ));
     insns.push((
         Inst::TrapIf {
             trap_code: TrapCode::Interrupt,
             kind: CondBrKind::Cond(Cond::Lt),
         },
-        "4A0000540000A0D4",
-        "b.ge 8 ; udf",
+        "2B0000540000A0D4",
+        "b.lt trap",
     ));
     insns.push((
         Inst::TrapIf {
             trap_code: TrapCode::Interrupt,
             kind: CondBrKind::Cond(Cond::Ge),
         },
-        "4B0000540000A0D4",
-        "b.lt 8 ; udf",
+        "2A0000540000A0D4",
+        "b.ge trap",
     ));
     insns.push((
         Inst::TrapIf {
             trap_code: TrapCode::Interrupt,
             kind: CondBrKind::Cond(Cond::Le),
         },
-        "4C0000540000A0D4",
-        "b.gt 8 ; udf",
+        "2D0000540000A0D4",
+        "b.le trap",
     ));
     insns.push((
         Inst::TrapIf {
             trap_code: TrapCode::Interrupt,
             kind: CondBrKind::Cond(Cond::Gt),
         },
-        "4D0000540000A0D4",
-        "b.le 8 ; udf",
+        "2C0000540000A0D4",
+        "b.gt trap",
     ));
     insns.push((
         Inst::TrapIf {
             trap_code: TrapCode::Interrupt,
             kind: CondBrKind::Cond(Cond::Nv),
         },
-        "4E0000540000A0D4",
-        "b.al 8 ; udf",
+        "2F0000540000A0D4",
+        "b.nv trap",
     ));
     insns.push((
         Inst::TrapIf {
             trap_code: TrapCode::Interrupt,
             kind: CondBrKind::Cond(Cond::Al),
         },
-        "4F0000540000A0D4",
-        "b.nv 8 ; udf",
+        "2E0000540000A0D4",
+        "b.al trap",
     ));
 
     insns.push((
@@ -5225,8 +5261,8 @@ fn test_aarch64_binemit() {
             r_data: writable_xreg(7),
             r_addr: xreg(28),
         },
-        "BF3B03D587034039",
-        "atomically { x7 = zero_extend_8_bits_at[x28] }",
+        "87FFDF08",
+        "ldarb w7, [x28]",
     ));
 
     insns.push((
@@ -5235,8 +5271,8 @@ fn test_aarch64_binemit() {
             r_data: writable_xreg(28),
             r_addr: xreg(7),
         },
-        "BF3B03D5FC0040F9",
-        "atomically { x28 = zero_extend_64_bits_at[x7] }",
+        "FCFCDFC8",
+        "ldar x28, [x7]",
     ));
 
     insns.push((
@@ -5245,8 +5281,8 @@ fn test_aarch64_binemit() {
             r_data: xreg(17),
             r_addr: xreg(8),
         },
-        "11010079BF3B03D5",
-        "atomically { 16_bits_at[x8] = x17 }",
+        "11FD9F48",
+        "stlrh w17, [x8]",
     ));
 
     insns.push((
@@ -5255,8 +5291,8 @@ fn test_aarch64_binemit() {
             r_data: xreg(18),
             r_addr: xreg(7),
         },
-        "F20000B9BF3B03D5",
-        "atomically { 32_bits_at[x7] = x18 }",
+        "F2FC9F88",
+        "stlr w18, [x7]",
     ));
 
     insns.push((Inst::Fence {}, "BF3B03D5", "dmb ish"));
@@ -7,7 +7,7 @@ use crate::binemit::CodeOffset;
 use crate::ir::types::{
     B1, B128, B16, B32, B64, B8, F32, F64, FFLAGS, I128, I16, I32, I64, I8, I8X16, IFLAGS, R32, R64,
 };
-use crate::ir::{ExternalName, MemFlags, Opcode, SourceLoc, TrapCode, Type};
+use crate::ir::{ExternalName, MemFlags, Opcode, SourceLoc, TrapCode, Type, ValueLabel};
 use crate::isa::CallConv;
 use crate::machinst::*;
 use crate::{settings, CodegenError, CodegenResult};
@@ -294,6 +294,10 @@ pub enum VecALUOp {
     Smull,
     /// Signed multiply long (high halves)
     Smull2,
+    /// Unsigned multiply long (low halves)
+    Umull,
+    /// Unsigned multiply long (high halves)
+    Umull2,
 }
 
 /// A Vector miscellaneous operation with two registers.
@@ -710,17 +714,17 @@ pub enum Inst {
     },
 
     /// Read `ty` bits from address `r_addr`, zero extend the loaded value to 64 bits and put it
-    /// in `r_data`.  The load instruction is preceded by a fence at least as comprehensive as
-    /// that of the `Fence` instruction below.  This instruction is sequentially consistent.
+    /// in `r_data`.  Emitted as a load-acquire ("ldar"), which together with the store-release
+    /// used for `AtomicStore` gives sequentially consistent semantics.
     AtomicLoad {
         ty: Type, // I8, I16, I32 or I64
         r_data: Writable<Reg>,
         r_addr: Reg,
     },
 
-    /// Write the lowest `ty` bits of `r_data` to address `r_addr`, with a memory fence
-    /// instruction following the store.  The fence is at least as comprehensive as that of the
-    /// `Fence` instruction below.  This instruction is sequentially consistent.
+    /// Write the lowest `ty` bits of `r_data` to address `r_addr`.  Emitted as a store-release
+    /// ("stlr"), which together with the load-acquire used for `AtomicLoad` gives sequentially
+    /// consistent semantics.
     AtomicStore {
         ty: Type, // I8, I16, I32 or I64
         r_data: Reg,
@@ -1114,8 +1118,9 @@ pub enum Inst {
     /// unit to the register allocator.
     ///
     /// The `CondBrKind` gives the conditional-branch condition that will
-    /// *execute* the embedded `Inst`. (In the emitted code, we use the inverse
-    /// of this condition in a branch that skips the trap instruction.)
+    /// *execute* the embedded `Inst`. (In the emitted code, this condition
+    /// branches to the trap instruction, which is placed out of line at the
+    /// next island or at the end of the function.)
     TrapIf {
         kind: CondBrKind,
         trap_code: TrapCode,
@@ -1184,6 +1189,12 @@ pub enum Inst {
         offset: i64,
     },
 
+    /// A definition of a value label.
+    ValueLabelMarker {
+        reg: Reg,
+        label: ValueLabel,
+    },
+
     /// Meta-insn, no-op in generated code: emit constant/branch veneer island
     /// at this point (with a guard jump around it) if less than the needed
     /// space is available before the next branch deadline. See the `MachBuffer`
@@ -2015,6 +2026,9 @@ fn aarch64_get_regs(inst: &Inst, collector: &mut RegUsageCollector) {
             memarg_regs(mem, collector);
         }
         &Inst::VirtualSPOffsetAdj { .. } => {}
+        &Inst::ValueLabelMarker { reg, .. } => {
+            collector.add_use(reg);
+        }
         &Inst::EmitIsland { .. } => {}
     }
 }
@@ -2764,6 +2778,9 @@ fn aarch64_map_regs<RUM: RegUsageMapper>(inst: &mut Inst, mapper: &RUM) {
             map_mem(mapper, mem);
         }
         &mut Inst::VirtualSPOffsetAdj { .. } => {}
+        &mut Inst::ValueLabelMarker { ref mut reg, .. } => {
+            map_use(mapper, reg);
+        }
         &mut Inst::EmitIsland { .. } => {}
     }
 }
@@ -2960,6 +2977,50 @@ impl MachInst for Inst {
     fn ref_type_regclass(_: &settings::Flags) -> RegClass {
         RegClass::I64
     }
+
+    fn gen_value_label_marker(label: ValueLabel, reg: Reg) -> Option<Self> {
+        Some(Inst::ValueLabelMarker { label, reg })
+    }
+
+    fn defines_value_label(&self) -> Option<(ValueLabel, Reg)> {
+        match self {
+            &Inst::ValueLabelMarker { label, reg } => Some((label, reg)),
+            _ => None,
+        }
+    }
+
+    fn stack_op_info(&self) -> Option<MachInstStackOpInfo> {
+        match self {
+            &Inst::Store8 { rd, ref mem, .. }
+            | &Inst::Store16 { rd, ref mem, .. }
+            | &Inst::Store32 { rd, ref mem, .. }
+            | &Inst::Store64 { rd, ref mem, .. }
+            | &Inst::FpuStore32 { rd, ref mem, .. }
+            | &Inst::FpuStore64 { rd, ref mem, .. }
+            | &Inst::FpuStore128 { rd, ref mem, .. } => match mem {
+                &AMode::NominalSPOffset(off, _) => {
+                    Some(MachInstStackOpInfo::StoreNomSPOff(rd, off))
+                }
+                _ => None,
+            },
+            &Inst::ULoad8 { rd, ref mem, .. }
+            | &Inst::SLoad8 { rd, ref mem, .. }
+            | &Inst::ULoad16 { rd, ref mem, .. }
+            | &Inst::SLoad16 { rd, ref mem, .. }
+            | &Inst::ULoad32 { rd, ref mem, .. }
+            | &Inst::SLoad32 { rd, ref mem, .. }
+            | &Inst::ULoad64 { rd, ref mem, .. }
+            | &Inst::FpuLoad32 { rd, ref mem, .. }
+            | &Inst::FpuLoad64 { rd, ref mem, .. }
+            | &Inst::FpuLoad128 { rd, ref mem, .. } => match mem {
+                &AMode::NominalSPOffset(off, _) => {
+                    Some(MachInstStackOpInfo::LoadNomSPOff(rd.to_reg(), off))
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
 }
 
 //=============================================================================
@@ -3278,22 +3339,30 @@ impl Inst {
             &Inst::AtomicLoad {
                 ty, r_data, r_addr, ..
             } => {
-                format!(
-                    "atomically {{ {} = zero_extend_{}_bits_at[{}] }}",
-                    r_data.show_rru(mb_rru),
-                    ty.bits(),
-                    r_addr.show_rru(mb_rru)
-                )
+                let (op, size) = match ty {
+                    I8 => ("ldarb", OperandSize::Size32),
+                    I16 => ("ldarh", OperandSize::Size32),
+                    I32 => ("ldar", OperandSize::Size32),
+                    I64 => ("ldar", OperandSize::Size64),
+                    _ => unreachable!(),
+                };
+                let r_data = show_ireg_sized(r_data.to_reg(), mb_rru, size);
+                let r_addr = r_addr.show_rru(mb_rru);
+                format!("{} {}, [{}]", op, r_data, r_addr)
             }
             &Inst::AtomicStore {
                 ty, r_data, r_addr, ..
             } => {
-                format!(
-                    "atomically {{ {}_bits_at[{}] = {} }}",
-                    ty.bits(),
-                    r_addr.show_rru(mb_rru),
-                    r_data.show_rru(mb_rru)
-                )
+                let (op, size) = match ty {
+                    I8 => ("stlrb", OperandSize::Size32),
+                    I16 => ("stlrh", OperandSize::Size32),
+                    I32 => ("stlr", OperandSize::Size32),
+                    I64 => ("stlr", OperandSize::Size64),
+                    _ => unreachable!(),
+                };
+                let r_data = show_ireg_sized(r_data, mb_rru, size);
+                let r_addr = r_addr.show_rru(mb_rru);
+                format!("{} {}, [{}]", op, r_data, r_addr)
             }
             &Inst::Fence {} => {
                 format!("dmb ish")
@@ -3704,13 +3773,19 @@ impl Inst {
                     VecALUOp::Zip1 => ("zip1", size),
                     VecALUOp::Smull => ("smull", size),
                     VecALUOp::Smull2 => ("smull2", size),
+                    VecALUOp::Umull => ("umull", size),
+                    VecALUOp::Umull2 => ("umull2", size),
                 };
                 let rd_size = match alu_op {
-                    VecALUOp::Umlal | VecALUOp::Smull | VecALUOp::Smull2 => size.widen(),
+                    VecALUOp::Umlal
+                    | VecALUOp::Smull
+                    | VecALUOp::Smull2
+                    | VecALUOp::Umull
+                    | VecALUOp::Umull2 => size.widen(),
                     _ => size,
                 };
                 let rn_size = match alu_op {
-                    VecALUOp::Smull => size.halve(),
+                    VecALUOp::Smull | VecALUOp::Umull => size.halve(),
                     _ => size,
                 };
                 let rm_size = rn_size;
@@ -3941,15 +4016,15 @@ impl Inst {
             &Inst::TrapIf { ref kind, .. } => match kind {
                 &CondBrKind::Zero(reg) => {
                     let reg = reg.show_rru(mb_rru);
-                    format!("cbnz {}, 8 ; udf", reg)
+                    format!("cbz {}, trap", reg)
                 }
                 &CondBrKind::NotZero(reg) => {
                     let reg = reg.show_rru(mb_rru);
-                    format!("cbz {}, 8 ; udf", reg)
+                    format!("cbnz {}, trap", reg)
                 }
                 &CondBrKind::Cond(c) => {
-                    let c = c.invert().show_rru(mb_rru);
-                    format!("b.{} 8 ; udf", c)
+                    let c = c.show_rru(mb_rru);
+                    format!("b.{} trap", c)
                 }
             },
             &Inst::Adr { rd, off } => {
@@ -4068,6 +4143,9 @@ impl Inst {
                 format!("virtual_sp_offset_adjust {}", offset)
             }
             &Inst::EmitIsland { needed_space } => format!("emit_island {}", needed_space),
+            &Inst::ValueLabelMarker { label, reg } => {
+                format!("value_label {}, {}", label, reg.show_rru(mb_rru))
+            }
         }
     }
 }
@@ -231,6 +231,23 @@ pub(crate) fn lower_insn_to_regs<C: LowerCtx<I = Inst>>(
 
         Opcode::Imul => {
             let rd = get_output_reg(ctx, outputs[0]).only_reg().unwrap();
+
+            // Multiplications of two vectors widened from the same halves,
+            // e.g. Wasm's extended multiplications, map to a long multiply.
+            if let Some((alu_op, a, b)) = match_vec_widening_mul(ctx, &inputs) {
+                let rn = put_input_in_reg(ctx, a, NarrowValueMode::None);
+                let rm = put_input_in_reg(ctx, b, NarrowValueMode::None);
+                let size = VectorSize::from_ty(ctx.input_ty(a.insn, a.input));
+                ctx.emit(Inst::VecRRR {
+                    alu_op,
+                    rd,
+                    rn,
+                    rm,
+                    size,
+                });
+                return Ok(());
+            }
+
             let rn = put_input_in_reg(ctx, inputs[0], NarrowValueMode::None);
             let rm = put_input_in_reg(ctx, inputs[1], NarrowValueMode::None);
             let ty = ty.unwrap();
@@ -3066,6 +3083,10 @@ pub(crate) fn lower_insn_to_regs<C: LowerCtx<I = Inst>>(
                 (I32, Opcode::SwidenHigh) => (VecExtendOp::Sxtl16, true),
                 (I32, Opcode::UwidenLow) => (VecExtendOp::Uxtl16, false),
                 (I32, Opcode::UwidenHigh) => (VecExtendOp::Uxtl16, true),
+                (I64, Opcode::SwidenLow) => (VecExtendOp::Sxtl32, false),
+                (I64, Opcode::SwidenHigh) => (VecExtendOp::Sxtl32, true),
+                (I64, Opcode::UwidenLow) => (VecExtendOp::Uxtl32, false),
+                (I64, Opcode::UwidenHigh) => (VecExtendOp::Uxtl32, true),
                 _ => {
                     return Err(CodegenError::Unsupported(format!(
                         "Unsupported SIMD vector lane type: {:?}",
@@ -3088,6 +3109,46 @@ pub(crate) fn lower_insn_to_regs<C: LowerCtx<I = Inst>>(
     Ok(())
 }
 
+/// Matches a vector multiplication whose operands are both widened from the
+/// same half of narrower vectors, returning the long multiplication computing
+/// it along with the narrow operands.
+fn match_vec_widening_mul<C: LowerCtx<I = Inst>>(
+    ctx: &mut C,
+    inputs: &[InsnInput],
+) -> Option<(VecALUOp, InsnInput, InsnInput)> {
+    let (op, a) = maybe_input_widen(ctx, inputs[0])?;
+    let (op_b, b) = maybe_input_widen(ctx, inputs[1])?;
+    if op != op_b {
+        return None;
+    }
+    let alu_op = match op {
+        Opcode::SwidenLow => VecALUOp::Smull,
+        Opcode::SwidenHigh => VecALUOp::Smull2,
+        Opcode::UwidenLow => VecALUOp::Umull,
+        Opcode::UwidenHigh => VecALUOp::Umull2,
+        _ => unreachable!(),
+    };
+    Some((
+        alu_op,
+        InsnInput { insn: a, input: 0 },
+        InsnInput { insn: b, input: 0 },
+    ))
+}
+
+fn maybe_input_widen<C: LowerCtx<I = Inst>>(
+    ctx: &mut C,
+    input: InsnInput,
+) -> Option<(Opcode, IRInst)> {
+    [
+        Opcode::SwidenLow,
+        Opcode::SwidenHigh,
+        Opcode::UwidenLow,
+        Opcode::UwidenHigh,
+    ]
+    .iter()
+    .find_map(|&op| maybe_input_insn(ctx, input, op).map(|insn| (op, insn)))
+}
+
 pub(crate) fn lower_branch<C: LowerCtx<I = Inst>>(
     ctx: &mut C,
     branches: &[IRInst],
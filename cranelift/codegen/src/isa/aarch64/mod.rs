@@ -2,6 +2,8 @@
 
 use crate::ir::condcodes::IntCC;
 use crate::ir::Function;
+#[cfg(feature = "unwind")]
+use crate::isa::unwind::systemv;
 use crate::isa::Builder as IsaBuilder;
 use crate::machinst::{compile, MachBackend, MachCompileResult, TargetIsaAdapter, VCode};
 use crate::result::CodegenResult;
@@ -10,6 +12,8 @@ use crate::settings;
 use alloc::boxed::Box;
 
 use regalloc::{PrettyPrint, RealRegUniverse};
+#[cfg(feature = "unwind")]
+use regalloc::{Reg, RegClass};
 use target_lexicon::{Aarch64Architecture, Architecture, Triple};
 
 // New backend:
@@ -65,6 +69,7 @@ impl MachBackend for AArch64Backend {
         let buffer = vcode.emit();
         let frame_size = vcode.frame_size();
         let unwind_info = vcode.unwind_info()?;
+        let value_labels_ranges = vcode.value_labels_ranges();
 
         let disasm = if want_disasm {
             Some(vcode.show_rru(Some(&create_reg_universe(flags))))
@@ -79,6 +84,7 @@ impl MachBackend for AArch64Backend {
             frame_size,
             disasm,
             unwind_info,
+            value_labels_ranges,
         })
     }
 
@@ -135,6 +141,15 @@ impl MachBackend for AArch64Backend {
     fn create_systemv_cie(&self) -> Option<gimli::write::CommonInformationEntry> {
         Some(inst::unwind::systemv::create_cie())
     }
+
+    #[cfg(feature = "unwind")]
+    fn map_reg_to_dwarf(&self, reg: Reg) -> Result<u16, systemv::RegisterMappingError> {
+        match reg.get_class() {
+            // The vector registers V0-V31 are DWARF registers 64-95.
+            RegClass::V128 => Ok(64 + reg.get_hw_encoding() as u16),
+            _ => inst::unwind::systemv::map_reg(reg).map(|reg| reg.0),
+        }
+    }
 }
 
 /// Create a new `isa::Builder`.
@@ -271,4 +286,72 @@ mod test {
 
         assert_eq!(code, &golden[..]);
     }
+
+    #[test]
+    fn test_value_labels() {
+        use crate::entity::EntityRef;
+        use crate::ir::{SourceLoc, ValueLabel, ValueLabelAssignments, ValueLabelStart};
+        use crate::isa::aarch64::inst::regs::xreg;
+        use crate::value_label::{LabelValueLoc, ValueLocRange};
+
+        let name = ExternalName::testcase("test0");
+        let mut sig = Signature::new(CallConv::SystemV);
+        sig.params.push(AbiParam::new(I32));
+        sig.returns.push(AbiParam::new(I32));
+        let mut func = Function::with_name_signature(name, sig);
+
+        let bb0 = func.dfg.make_block();
+        let arg0 = func.dfg.append_block_param(bb0, I32);
+
+        let mut pos = FuncCursor::new(&mut func);
+        pos.insert_block(bb0);
+        let v0 = pos.ins().iconst(I32, 0x1234);
+        let v1 = pos.ins().iadd(arg0, v0);
+        pos.ins().return_(&[v1]);
+
+        let label0 = ValueLabel::new(0);
+        let label1 = ValueLabel::new(1);
+        func.dfg.collect_debug_info();
+        let values_labels = func.dfg.values_labels.as_mut().unwrap();
+        for &(value, label) in &[(arg0, label0), (v1, label1)] {
+            values_labels.insert(
+                value,
+                ValueLabelAssignments::Starts(vec![ValueLabelStart {
+                    from: SourceLoc::new(1),
+                    label,
+                }]),
+            );
+        }
+
+        let mut shared_flags = settings::builder();
+        shared_flags.set("opt_level", "none").unwrap();
+        let backend = AArch64Backend::new_with_flags(
+            Triple::from_str("aarch64").unwrap(),
+            settings::Flags::new(shared_flags),
+        );
+        let result = backend.compile_function(&mut func, false).unwrap();
+
+        // The value-label markers do not generate code; this is the same
+        // function as in `test_compile_function()` above:
+        //
+        // stp x29, x30, [sp, #-16]!
+        // mov x29, sp
+        // mov x1, #0x1234
+        // add w0, w0, w1
+        // mov sp, x29
+        // ldp x29, x30, [sp], #16
+        // ret
+        assert_eq!(result.buffer.data.len(), 28);
+
+        // `arg0` lives in x0 from the end of the prologue until it is
+        // overwritten by the `add`.
+        assert_eq!(
+            result.value_labels_ranges[&label0],
+            vec![ValueLocRange {
+                loc: LabelValueLoc::Reg(xreg(0)),
+                start: 8,
+                end: 16,
+            }]
+        );
+    }
 }
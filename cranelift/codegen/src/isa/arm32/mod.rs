@@ -60,6 +60,7 @@ impl MachBackend for Arm32Backend {
         let vcode = self.compile_vcode(func, flags.clone())?;
         let buffer = vcode.emit();
         let frame_size = vcode.frame_size();
+        let value_labels_ranges = vcode.value_labels_ranges();
 
         let disasm = if want_disasm {
             Some(vcode.show_rru(Some(&create_reg_universe())))
@@ -74,6 +75,7 @@ impl MachBackend for Arm32Backend {
             frame_size,
             disasm,
             unwind_info: None,
+            value_labels_ranges,
         })
     }
 
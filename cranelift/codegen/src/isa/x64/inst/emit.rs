@@ -1756,16 +1756,15 @@ pub(crate) fn emit(
         }
 
         Inst::TrapIf { cc, trap_code } => {
-            let else_label = sink.get_label();
-
-            // Jump over if the invert of CC is set (i.e. CC is not set).
-            one_way_jmp(sink, cc.invert(), else_label);
-
-            // Trap!
-            let inst = Inst::trap(*trap_code);
-            inst.emit(sink, info, state);
-
-            sink.bind_label(else_label);
+            // The trap itself is outlined, out of the way of the hot path;
+            // only a (normally not taken) conditional jump to it stays inline.
+            let trap_label = sink.defer_trap(
+                *trap_code,
+                state.cur_srcloc(),
+                state.take_stack_map(),
+                &[0x0f, 0x0b], // ud2
+            );
+            one_way_jmp(sink, *cc, trap_label);
         }
 
         Inst::XmmUnaryRmR {
@@ -2876,6 +2875,10 @@ pub(crate) fn emit(
             state.virtual_sp_offset += offset;
         }
 
+        Inst::ValueLabelMarker { .. } => {
+            // Nothing; this is only used to compute debug info.
+        }
+
         Inst::Nop { len } => {
             // These encodings can all be found in Intel's architecture manual, at the NOP
             // instruction description.
@@ -1,7 +1,7 @@
 //! This module defines x86_64-specific machine instruction types.
 
 use crate::binemit::{CodeOffset, StackMap};
-use crate::ir::{types, ExternalName, Opcode, SourceLoc, TrapCode, Type};
+use crate::ir::{types, ExternalName, Opcode, SourceLoc, TrapCode, Type, ValueLabel};
 use crate::isa::x64::settings as x64_settings;
 use crate::machinst::*;
 use crate::{settings, settings::Flags, CodegenError, CodegenResult};
@@ -19,6 +19,7 @@ pub mod args;
 mod emit;
 #[cfg(test)]
 mod emit_tests;
+mod peephole;
 pub mod regs;
 pub mod unwind;
 
@@ -461,6 +462,9 @@ pub enum Inst {
     /// controls how MemArg::NominalSPOffset args are lowered.
     VirtualSPOffsetAdj { offset: i64 },
 
+    /// A definition of a value label.
+    ValueLabelMarker { reg: Reg, label: ValueLabel },
+
     /// Provides a way to tell the register allocator that the upcoming sequence of instructions
     /// will overwrite `dst` so it should be considered as a `def`; use this with care.
     ///
@@ -528,6 +532,7 @@ impl Inst {
             | Inst::Ud2 { .. }
             | Inst::UnaryRmR { .. }
             | Inst::VirtualSPOffsetAdj { .. }
+            | Inst::ValueLabelMarker { .. }
             | Inst::XmmCmove { .. }
             | Inst::XmmCmpRmR { .. }
             | Inst::XmmLoadConst { .. }
@@ -1722,7 +1727,7 @@ impl PrettyPrint for Inst {
             ),
 
             Inst::TrapIf { cc, trap_code, .. } => {
-                format!("j{} ; ud2 {} ;", cc.invert().to_string(), trap_code)
+                format!("j{} trap_{}", cc.to_string(), trap_code)
             }
 
             Inst::LoadExtName {
@@ -1759,6 +1764,10 @@ impl PrettyPrint for Inst {
 
             Inst::VirtualSPOffsetAdj { offset } => format!("virtual_sp_offset_adjust {}", offset),
 
+            Inst::ValueLabelMarker { label, reg } => {
+                format!("value_label {}, {}", label, reg.show_rru(mb_rru))
+            }
+
             Inst::Hlt => "hlt".into(),
 
             Inst::Ud2 { trap_code } => format!("ud2 {}", trap_code),
@@ -2021,6 +2030,10 @@ fn x64_get_regs(inst: &Inst, collector: &mut RegUsageCollector) {
         | Inst::Fence { .. } => {
             // No registers are used.
         }
+
+        Inst::ValueLabelMarker { reg, .. } => {
+            collector.add_use(*reg);
+        }
     }
 }
 
@@ -2400,6 +2413,8 @@ fn x64_map_regs<RUM: RegUsageMapper>(inst: &mut Inst, mapper: &RUM) {
             // Instruction doesn't explicitly mention any regs, so it can't have any virtual
             // regs that we'd need to remap.  Hence no action required.
         }
+
+        Inst::ValueLabelMarker { ref mut reg, .. } => map_use(mapper, reg),
     }
 }
 
@@ -2636,6 +2651,88 @@ impl MachInst for Inst {
         RegClass::I64
     }
 
+    fn gen_value_label_marker(label: ValueLabel, reg: Reg) -> Option<Self> {
+        Some(Inst::ValueLabelMarker { label, reg })
+    }
+
+    fn defines_value_label(&self) -> Option<(ValueLabel, Reg)> {
+        match self {
+            Inst::ValueLabelMarker { label, reg } => Some((*label, *reg)),
+            _ => None,
+        }
+    }
+
+    fn peephole(first: &Self, second: &Self) -> Option<Peephole<Self>> {
+        apply_peepholes(peephole::PEEPHOLE_RULES, first, second)
+    }
+
+    fn stack_op_info(&self) -> Option<MachInstStackOpInfo> {
+        // Spills and reloads are generated by `Inst::store()` and
+        // `Inst::load()`; recognize their forms when they address a
+        // nominal-SP-relative slot.
+        let is_xmm_mov = |op: &SseOpcode| match op {
+            SseOpcode::Movss
+            | SseOpcode::Movsd
+            | SseOpcode::Movups
+            | SseOpcode::Movupd
+            | SseOpcode::Movdqu => true,
+            _ => false,
+        };
+        match self {
+            Inst::MovRM {
+                size: 8,
+                src,
+                dst: SyntheticAmode::NominalSPOffset { simm32 },
+            } => Some(MachInstStackOpInfo::StoreNomSPOff(
+                *src,
+                *simm32 as i32 as i64,
+            )),
+            Inst::XmmMovRM {
+                op,
+                src,
+                dst: SyntheticAmode::NominalSPOffset { simm32 },
+            } if is_xmm_mov(op) => Some(MachInstStackOpInfo::StoreNomSPOff(
+                *src,
+                *simm32 as i32 as i64,
+            )),
+            Inst::Mov64MR {
+                src: SyntheticAmode::NominalSPOffset { simm32 },
+                dst,
+            }
+            | Inst::MovsxRmR {
+                src:
+                    RegMem::Mem {
+                        addr: SyntheticAmode::NominalSPOffset { simm32 },
+                    },
+                dst,
+                ..
+            }
+            | Inst::MovzxRmR {
+                src:
+                    RegMem::Mem {
+                        addr: SyntheticAmode::NominalSPOffset { simm32 },
+                    },
+                dst,
+                ..
+            } => Some(MachInstStackOpInfo::LoadNomSPOff(
+                dst.to_reg(),
+                *simm32 as i32 as i64,
+            )),
+            Inst::XmmUnaryRmR {
+                op,
+                src:
+                    RegMem::Mem {
+                        addr: SyntheticAmode::NominalSPOffset { simm32 },
+                    },
+                dst,
+            } if is_xmm_mov(op) => Some(MachInstStackOpInfo::LoadNomSPOff(
+                dst.to_reg(),
+                *simm32 as i32 as i64,
+            )),
+            _ => None,
+        }
+    }
+
     type LabelUse = LabelUse;
 }
 
@@ -0,0 +1,152 @@
+//! Peephole rules for x86-64, applied after register allocation.
+
+use crate::isa::x64::inst::args::*;
+use crate::isa::x64::inst::Inst;
+use crate::machinst::Peephole;
+use regalloc::Reg;
+
+/// Define a table of peephole rules.
+///
+/// Each rule is written as
+///
+/// ```text
+/// name: (first_pattern, second_pattern) if guard => rewrite;
+/// ```
+///
+/// where the patterns match two adjacent instructions, the optional guard
+/// may refer to bindings from both, and the rewrite is a `Peephole` applied
+/// to the second instruction. Rules are tried in order.
+macro_rules! peephole_rules {
+    (
+        $table:ident: $inst:ty;
+        $(
+            $(#[$attr:meta])*
+            $name:ident: ($first:pat, $second:pat) $(if $guard:expr)? => $rewrite:expr;
+        )*
+    ) => {
+        pub(crate) static $table: &[crate::machinst::PeepholeRule<$inst>] = &[
+            $(
+                crate::machinst::PeepholeRule {
+                    name: stringify!($name),
+                    apply: {
+                        $(#[$attr])*
+                        fn $name(first: &$inst, second: &$inst) -> Option<Peephole<$inst>> {
+                            match (first, second) {
+                                ($first, $second) $(if $guard)? => Some($rewrite),
+                                _ => None,
+                            }
+                        }
+                        $name
+                    },
+                },
+            )*
+        ];
+    };
+}
+
+/// Does `inst` write `reg` with a 32-bit result, thereby zeroing its upper
+/// 32 bits?
+fn zero_extends_32(inst: &Inst, reg: Reg) -> bool {
+    match inst {
+        Inst::AluRmiR {
+            is_64: false, dst, ..
+        }
+        | Inst::MovRR {
+            is_64: false, dst, ..
+        }
+        | Inst::Imm {
+            dst_is_64: false,
+            dst,
+            ..
+        }
+        | Inst::MovzxRmR { dst, .. } => dst.to_reg() == reg,
+        _ => false,
+    }
+}
+
+peephole_rules! {
+    PEEPHOLE_RULES: Inst;
+
+    /// `mov %a, %b; mov %b, %a`: after the first move both registers hold
+    /// the same value, so the second one does nothing.
+    redundant_move_back: (
+        Inst::MovRR { is_64: true, src: a, dst: b },
+        Inst::MovRR { is_64: true, src: b2, dst: a2 }
+    ) if *b2 == b.to_reg() && a2.to_reg() == *a => Peephole::Delete;
+
+    /// `op32 ..., %a; movzx.lq %a, %a`: a 32-bit operation already zeroes
+    /// the upper half of its destination.
+    redundant_uextend_32: (
+        first,
+        Inst::MovzxRmR { ext_mode: ExtMode::LQ, src: RegMem::Reg { reg }, dst }
+    ) if dst.to_reg() == *reg && zero_extends_32(first, *reg) => Peephole::Delete;
+
+    /// `op32 ..., %a; movl %a, %a`: likewise.
+    redundant_movl_self: (
+        first,
+        Inst::MovRR { is_64: false, src, dst }
+    ) if dst.to_reg() == *src && zero_extends_32(first, *src) => Peephole::Delete;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::isa::x64::inst::regs;
+    use crate::machinst::apply_peepholes;
+    use regalloc::Writable;
+
+    fn apply(first: &Inst, second: &Inst) -> bool {
+        match apply_peepholes(PEEPHOLE_RULES, first, second) {
+            Some(Peephole::Delete) => true,
+            None => false,
+            Some(Peephole::Replace(_)) => panic!("unexpected replacement"),
+        }
+    }
+
+    #[test]
+    fn test_redundant_move_back() {
+        let rax = regs::rax();
+        let rcx = regs::rcx();
+        let rdx = regs::rdx();
+        let mov = |src, dst| Inst::mov_r_r(true, src, Writable::from_reg(dst));
+
+        assert!(apply(&mov(rax, rcx), &mov(rcx, rax)));
+        assert!(!apply(&mov(rax, rcx), &mov(rcx, rdx)));
+        assert!(!apply(&mov(rax, rcx), &mov(rdx, rax)));
+
+        // A 32-bit move back clears the upper half of the original register.
+        assert!(!apply(
+            &mov(rax, rcx),
+            &Inst::mov_r_r(false, rcx, Writable::from_reg(rax))
+        ));
+    }
+
+    #[test]
+    fn test_redundant_uextend_32() {
+        let rax = regs::rax();
+        let rcx = regs::rcx();
+        let add = |is_64| {
+            Inst::alu_rmi_r(
+                is_64,
+                AluRmiROpcode::Add,
+                RegMemImm::reg(rcx),
+                Writable::from_reg(rax),
+            )
+        };
+        let uext =
+            |src, dst| Inst::movzx_rm_r(ExtMode::LQ, RegMem::reg(src), Writable::from_reg(dst));
+        let movl = |src, dst| Inst::mov_r_r(false, src, Writable::from_reg(dst));
+
+        assert!(apply(&add(false), &uext(rax, rax)));
+        assert!(apply(&add(false), &movl(rax, rax)));
+
+        // The upper half of a 64-bit result may be nonzero.
+        assert!(!apply(&add(true), &uext(rax, rax)));
+        assert!(!apply(&add(true), &movl(rax, rax)));
+
+        // Not an in-place extension, or not of the register just written.
+        assert!(!apply(&add(false), &uext(rax, rcx)));
+        assert!(!apply(&add(false), &uext(rcx, rcx)));
+        assert!(!apply(&add(false), &movl(rcx, rcx)));
+    }
+}
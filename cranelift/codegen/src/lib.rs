@@ -60,7 +60,7 @@ use std::collections::{hash_map, HashMap, HashSet};
 
 pub use crate::context::Context;
 pub use crate::legalizer::legalize_function;
-pub use crate::value_label::{ValueLabelsRanges, ValueLocRange};
+pub use crate::value_label::{LabelValueLoc, ValueLabelsRanges, ValueLocRange};
 pub use crate::verifier::verify_function;
 pub use crate::write::write_function;
 
@@ -101,6 +101,7 @@ mod nan_canonicalization;
 mod partition_slice;
 mod postopt;
 mod predicates;
+mod redundant_load_elim;
 mod redundant_reload_remover;
 mod regalloc;
 mod remove_constant_phis;
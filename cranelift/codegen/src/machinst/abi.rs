@@ -123,6 +123,9 @@ pub trait ABICallee {
         from_reg: ValueRegs<Reg>,
     ) -> SmallInstVec<Self::I>;
 
+    /// Get the offset of a spillslot from nominal SP.
+    fn spillslot_offset(&self, slot: SpillSlot) -> i64;
+
     /// Load from a spillslot.
     fn load_spillslot(
         &self,
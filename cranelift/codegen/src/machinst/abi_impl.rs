@@ -1038,6 +1038,14 @@ impl<M: ABIMachineSpec> ABICallee for ABICalleeImpl<M> {
         M::gen_get_stack_addr(StackAMode::NominalSPOffset(sp_off, I8), into_reg, I8)
     }
 
+    /// Get the offset of a spillslot from nominal SP.
+    fn spillslot_offset(&self, slot: SpillSlot) -> i64 {
+        // Offset from beginning of spillslot area, which is at nominal SP + stackslots_size.
+        let islot = slot.get() as i64;
+        let spill_off = islot * M::word_bytes() as i64;
+        self.stackslots_size as i64 + spill_off
+    }
+
     /// Load from a spillslot.
     fn load_spillslot(
         &self,
@@ -1045,10 +1053,7 @@ impl<M: ABIMachineSpec> ABICallee for ABICalleeImpl<M> {
         ty: Type,
         into_regs: ValueRegs<Writable<Reg>>,
     ) -> SmallInstVec<Self::I> {
-        // Offset from beginning of spillslot area, which is at nominal SP + stackslots_size.
-        let islot = slot.get() as i64;
-        let spill_off = islot * M::word_bytes() as i64;
-        let sp_off = self.stackslots_size as i64 + spill_off;
+        let sp_off = self.spillslot_offset(slot);
         trace!("load_spillslot: slot {:?} -> sp_off {}", slot, sp_off);
         gen_load_stack_multi::<M>(StackAMode::NominalSPOffset(sp_off, ty), into_regs, ty)
     }
@@ -1060,10 +1065,7 @@ impl<M: ABIMachineSpec> ABICallee for ABICalleeImpl<M> {
         ty: Type,
         from_regs: ValueRegs<Reg>,
     ) -> SmallInstVec<Self::I> {
-        // Offset from beginning of spillslot area, which is at nominal SP + stackslots_size.
-        let islot = slot.get() as i64;
-        let spill_off = islot * M::word_bytes() as i64;
-        let sp_off = self.stackslots_size as i64 + spill_off;
+        let sp_off = self.spillslot_offset(slot);
         trace!("store_spillslot: slot {:?} -> sp_off {}", slot, sp_off);
         gen_store_stack_multi::<M>(StackAMode::NominalSPOffset(sp_off, ty), from_regs, ty)
     }
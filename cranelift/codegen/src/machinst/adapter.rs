@@ -11,6 +11,7 @@ use crate::settings::Flags;
 use crate::regalloc::RegDiversions;
 
 use core::any::Any;
+use regalloc::Reg;
 use std::borrow::Cow;
 use std::fmt;
 use target_lexicon::Triple;
@@ -134,6 +135,14 @@ impl TargetIsa for TargetIsaAdapter {
         self.backend.create_systemv_cie()
     }
 
+    #[cfg(feature = "unwind")]
+    fn map_regalloc_reg_to_dwarf(
+        &self,
+        r: Reg,
+    ) -> Result<u16, crate::isa::unwind::systemv::RegisterMappingError> {
+        self.backend.map_reg_to_dwarf(r)
+    }
+
     fn as_any(&self) -> &dyn Any {
         self as &dyn Any
     }
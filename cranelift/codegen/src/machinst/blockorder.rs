@@ -377,6 +377,28 @@ impl BlockLoweringOrder {
 
         postorder.reverse();
         let mut rpo = postorder;
+
+        // Sink cold blocks, along with the edge blocks leading into and out of
+        // them, to the end of the order (but before any fallthrough-return
+        // block), so that they stay out of the way of the hot path. The
+        // relative order within each group is kept. The entry block always
+        // stays first.
+        let is_cold = |lb: &LoweredBlock| match *lb {
+            LoweredBlock::Orig { block }
+            | LoweredBlock::OrigAndEdge { block, .. }
+            | LoweredBlock::EdgeAndOrig { block, .. } => {
+                Some(block) != f.layout.entry_block() && f.layout.is_cold(block)
+            }
+            LoweredBlock::Edge { pred, succ, .. } => {
+                f.layout.is_cold(pred) || f.layout.is_cold(succ)
+            }
+        };
+        if rpo.iter().any(|(lb, _)| is_cold(lb)) {
+            let (hot, cold): (Vec<_>, Vec<_>) = rpo.into_iter().partition(|(lb, _)| !is_cold(lb));
+            rpo = hot;
+            rpo.extend(cold);
+        }
+
         if let Some(d) = deferred_last {
             rpo.push(d);
         }
@@ -526,6 +548,29 @@ mod test {
         assert!(order.lowered_order[5].out_edge().is_none());
     }
 
+    #[test]
+    fn test_blockorder_cold() {
+        let mut func = build_test_func(4, &[(0, 1), (0, 2), (1, 3), (2, 3)]);
+        func.layout.set_cold(Block::from_u32(1));
+        let order = BlockLoweringOrder::new(&func);
+
+        assert_eq!(order.lowered_order.len(), 6);
+
+        // The hot path comes first: 0, 0->2 + 2, 2->3, 3.
+        assert!(order.lowered_order[0].orig_block().unwrap().as_u32() == 0);
+        assert!(order.lowered_order[1].orig_block().unwrap().as_u32() == 2);
+        assert!(order.lowered_order[2].orig_block().is_none());
+        assert!(order.lowered_order[2].out_edge().unwrap().0.as_u32() == 2);
+        assert!(order.lowered_order[3].orig_block().unwrap().as_u32() == 3);
+
+        // Then the cold block and its out-edge: 0->1 + 1, 1->3.
+        assert!(order.lowered_order[4].orig_block().unwrap().as_u32() == 1);
+        assert!(order.lowered_order[4].in_edge().unwrap().0.as_u32() == 0);
+        assert!(order.lowered_order[5].orig_block().is_none());
+        assert!(order.lowered_order[5].out_edge().unwrap().0.as_u32() == 1);
+        assert!(order.lowered_order[5].out_edge().unwrap().2.as_u32() == 3);
+    }
+
     #[test]
     fn test_blockorder_critedge() {
         //            0
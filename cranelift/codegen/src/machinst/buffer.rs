@@ -189,6 +189,9 @@ pub struct MachBuffer<I: VCodeInst> {
     label_aliases: SmallVec<[MachLabel; 16]>,
     /// Constants that must be emitted at some point.
     pending_constants: SmallVec<[MachLabelConstant; 16]>,
+    /// Trap sequences that have been outlined from the code that branches to
+    /// them, and must be emitted at some point.
+    pending_traps: SmallVec<[MachLabelTrap; 16]>,
     /// Fixups that must be performed after all code is emitted.
     fixup_records: SmallVec<[MachLabelFixup<I>; 16]>,
     /// Current deadline at which all constants are flushed and all code labels
@@ -303,6 +306,7 @@ impl<I: VCodeInst> MachBuffer<I> {
             label_offsets: SmallVec::new(),
             label_aliases: SmallVec::new(),
             pending_constants: SmallVec::new(),
+            pending_traps: SmallVec::new(),
             fixup_records: SmallVec::new(),
             island_deadline: UNKNOWN_LABEL_OFFSET,
             island_worst_case_size: 0,
@@ -1053,6 +1057,71 @@ impl<I: VCodeInst> MachBuffer<I> {
         }
     }
 
+    /// Emit a trap instruction at some point in the future, out of line, and
+    /// return a label bound to it. `data` is the machine code of the trap
+    /// instruction, and `srcloc`, `code` and `stack_map` are recorded for it as
+    /// if it had been emitted directly.
+    ///
+    /// This keeps rarely-taken trap sequences out of the hot path: the caller
+    /// only needs to emit a conditional branch to the returned label. The trap
+    /// is emitted along with the next island, or at the end of the function.
+    pub fn defer_trap(
+        &mut self,
+        code: TrapCode,
+        srcloc: SourceLoc,
+        stack_map: Option<StackMap>,
+        data: &[u8],
+    ) -> MachLabel {
+        let label = self.get_label();
+        trace!(
+            "defer_trap: eventually emit trap {:?} at label {:?}",
+            code,
+            label
+        );
+        self.island_worst_case_size += data.len() as CodeOffset;
+        self.island_worst_case_size =
+            (self.island_worst_case_size + I::LabelUse::ALIGN - 1) & !(I::LabelUse::ALIGN - 1);
+        self.pending_traps.push(MachLabelTrap {
+            label,
+            code,
+            srcloc,
+            stack_map,
+            data: SmallVec::from(data),
+        });
+        label
+    }
+
+    /// Emit all traps deferred with `defer_trap()` so far, at the current
+    /// offset. Label uses referring to them are fixed up along with the next
+    /// island, or at the end of the function.
+    ///
+    /// Unlike constants, deferred traps are part of the function's code: they
+    /// need to be covered by its unwind info, so they must be emitted before
+    /// the code size is recorded for it.
+    pub fn emit_pending_traps(&mut self) {
+        // Branches to a trap's label can't be edited once code follows them.
+        self.latest_branches.clear();
+
+        let pending_traps = mem::replace(&mut self.pending_traps, SmallVec::new());
+        for MachLabelTrap {
+            label,
+            code,
+            srcloc,
+            stack_map,
+            data,
+        } in pending_traps.into_iter()
+        {
+            self.align_to(I::LabelUse::ALIGN);
+            self.bind_label(label);
+            self.add_trap(srcloc, code);
+            if let Some(stack_map) = stack_map {
+                let extent = StackMapExtent::UpcomingBytes(data.len() as CodeOffset);
+                self.add_stack_map(extent, stack_map);
+            }
+            self.put_data(&data[..]);
+        }
+    }
+
     /// Is an island needed within the next N bytes?
     pub fn island_needed(&self, distance: CodeOffset) -> bool {
         let worst_case_end_of_island = self.cur_offset() + distance + self.island_worst_case_size;
@@ -1067,6 +1136,8 @@ impl<I: VCodeInst> MachBuffer<I> {
         // anymore.
         self.latest_branches.clear();
 
+        self.emit_pending_traps();
+
         let pending_constants = mem::replace(&mut self.pending_constants, SmallVec::new());
         for MachLabelConstant { label, align, data } in pending_constants.into_iter() {
             self.align_to(align);
@@ -1178,8 +1249,11 @@ impl<I: VCodeInst> MachBuffer<I> {
     pub fn finish(mut self) -> MachBufferFinalized {
         let _tt = timing::vcode_emit_finish();
 
-        while !self.pending_constants.is_empty() || !self.fixup_records.is_empty() {
-            // `emit_island()` will emit any pending veneers and constants, and
+        while !self.pending_constants.is_empty()
+            || !self.pending_traps.is_empty()
+            || !self.fixup_records.is_empty()
+        {
+            // `emit_island()` will emit any pending veneers, traps and constants, and
             // as a side-effect, will also take care of any fixups with resolved
             // labels eagerly.
             self.emit_island();
@@ -1359,6 +1433,20 @@ struct MachLabelConstant {
     data: SmallVec<[u8; 16]>,
 }
 
+/// A trap sequence that is deferred to the next island.
+struct MachLabelTrap {
+    /// This label will refer to the trap instruction's offset.
+    label: MachLabel,
+    /// The trap code.
+    code: TrapCode,
+    /// The original source location.
+    srcloc: SourceLoc,
+    /// The stack map for the trap instruction, if it is a safepoint.
+    stack_map: Option<StackMap>,
+    /// The machine code of the trap instruction.
+    data: SmallVec<[u8; 4]>,
+}
+
 /// A fixup to perform on the buffer once code is emitted. Fixups always refer
 /// to labels and patch the code based on label offsets. Hence, they are like
 /// relocations, but internal to one buffer.
@@ -1622,6 +1710,47 @@ mod test {
         assert_eq!(&buf.data[0..8], &buf2.data[..]);
     }
 
+    #[test]
+    fn test_deferred_trap() {
+        let info = EmitInfo::new(settings::Flags::new(settings::builder()));
+        let mut buf = MachBuffer::new();
+        let mut state = Default::default();
+
+        buf.reserve_labels_for_blocks(1);
+
+        buf.bind_label(label(0));
+        let inst = Inst::TrapIf {
+            kind: CondBrKind::NotZero(xreg(0)),
+            trap_code: TrapCode::HeapOutOfBounds,
+        };
+        inst.emit(&mut buf, &info, &mut state);
+        let inst = Inst::Nop4;
+        inst.emit(&mut buf, &info, &mut state);
+        inst.emit(&mut buf, &info, &mut state);
+
+        let buf = buf.finish();
+
+        // The trap itself is placed after the rest of the code, and only the
+        // conditional branch to it remains inline.
+        assert_eq!(16, buf.total_size());
+        assert_eq!(1, buf.traps.len());
+        assert_eq!(12, buf.traps[0].offset);
+        assert_eq!(TrapCode::HeapOutOfBounds, buf.traps[0].code);
+
+        let mut buf2 = MachBuffer::new();
+        let mut state = Default::default();
+        let inst = Inst::CondBr {
+            kind: CondBrKind::NotZero(xreg(0)),
+            taken: BranchTarget::ResolvedOffset(12),
+            not_taken: BranchTarget::ResolvedOffset(4),
+        };
+        inst.emit(&mut buf2, &info, &mut state);
+
+        let buf2 = buf2.finish();
+
+        assert_eq!(&buf.data[0..4], &buf2.data[0..4]);
+    }
+
     #[test]
     fn test_island_backward() {
         let info = EmitInfo::new(settings::Flags::new(settings::builder()));
@@ -72,6 +72,15 @@ where
         None
     };
 
+    // If the verifier is enabled, remember which instructions define
+    // reference-typed values, so that we can check the stack maps that
+    // register allocation produces.
+    let ref_defs = if sri.is_some() && vcode.flags().enable_verifier() {
+        Some(vcode.reftyped_defs())
+    } else {
+        None
+    };
+
     let result = {
         let _tt = timing::regalloc();
         allocate_registers_with_opts(
@@ -101,6 +110,11 @@ where
         vcode.replace_insns_from_regalloc(result);
     }
 
+    if let Some(ref_defs) = ref_defs {
+        let _tt = timing::verifier();
+        vcode.verify_stack_maps(&ref_defs)?;
+    }
+
     debug!(
         "vcode after regalloc: final version:\n{}",
         vcode.show_rru(Some(b.reg_universe()))
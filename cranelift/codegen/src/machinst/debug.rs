@@ -0,0 +1,251 @@
+//! Debug info analysis: computes value-label ranges from value-label markers in
+//! generated VCode.
+//!
+//! We "reverse-engineer" debug info like this because it is far more reliable
+//! than generating it while emitting code and keeping it in sync with every
+//! later transformation (register allocation, spill/reload insertion, move
+//! elision, and so on).
+//!
+//! During lowering, the machine-independent lowering driver emits a
+//! `value_label` marker pseudo-instruction, via
+//! `MachInst::gen_value_label_marker()`, whenever a labeled value is defined.
+//! The marker uses the vreg holding the value, so after register allocation it
+//! names the real register that holds the label's new value. From there we run
+//! a forward dataflow analysis over the final (post-regalloc) instruction
+//! sequence that tracks, for every label, the set of registers and stack slots
+//! that currently hold its value:
+//!
+//! - A marker sets the label's location set to exactly the marked register.
+//! - A move (`MachInst::is_move()`) copies membership from source to
+//!   destination register.
+//! - A spill or reload to or from a nominal-SP-relative slot
+//!   (`MachInst::stack_op_info()`) copies membership between register and
+//!   stack slot.
+//! - Any other def or mod of a register removes that register from every
+//!   label's location set.
+//!
+//! At control-flow merges, the location sets are intersected. Finally, we walk
+//! the code once more, using the instruction offsets recorded at emission, and
+//! produce for each label a list of code ranges with one location each.
+
+use crate::binemit::CodeOffset;
+use crate::ir::ValueLabel;
+use crate::machinst::*;
+use crate::value_label::{LabelValueLoc, ValueLabelsRanges, ValueLocRange};
+use crate::{HashMap, HashSet};
+use alloc::vec::Vec;
+use log::trace;
+use regalloc::{BlockIx, Reg, RegUsageCollector};
+
+/// Location of a label's value, as tracked by this analysis.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Loc {
+    /// In a register.
+    Reg(Reg),
+    /// In a stack slot, at the given offset from nominal SP.
+    Stack(i64),
+}
+
+impl Loc {
+    /// A total order on locations, used to make the choice of a reported
+    /// location deterministic when a label lives in several places at once.
+    /// Registers are preferred over stack slots.
+    fn sort_key(&self) -> (u8, i64) {
+        match self {
+            Loc::Reg(reg) => (0, reg.get_index() as i64),
+            Loc::Stack(off) => (1, *off),
+        }
+    }
+}
+
+/// The analysis state at a program point: for each label, the set of
+/// locations that currently hold its value.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+struct AnalysisInfo {
+    label_to_locs: HashMap<ValueLabel, HashSet<Loc>>,
+}
+
+impl AnalysisInfo {
+    /// The location `loc` has been overwritten; no label lives there anymore.
+    fn clobber(&mut self, loc: Loc) {
+        self.label_to_locs.retain(|_, locs| {
+            locs.remove(&loc);
+            !locs.is_empty()
+        });
+    }
+
+    /// `label` has a new value, which lives only in `loc`.
+    fn def_label(&mut self, label: ValueLabel, loc: Loc) {
+        self.clobber(loc);
+        let mut locs = HashSet::new();
+        locs.insert(loc);
+        self.label_to_locs.insert(label, locs);
+    }
+
+    /// The value in `from` has been copied to `to`.
+    fn copy(&mut self, from: Loc, to: Loc) {
+        if from == to {
+            return;
+        }
+        self.clobber(to);
+        for locs in self.label_to_locs.values_mut() {
+            if locs.contains(&from) {
+                locs.insert(to);
+            }
+        }
+    }
+
+    /// Meet function: a label is known to live at a location after a merge
+    /// only if it lives there on every incoming path.
+    fn meet(&mut self, other: &AnalysisInfo) {
+        self.label_to_locs.retain(|label, locs| {
+            if let Some(other_locs) = other.label_to_locs.get(label) {
+                locs.retain(|loc| other_locs.contains(loc));
+                !locs.is_empty()
+            } else {
+                false
+            }
+        });
+    }
+
+    /// Update the state to reflect the effects of `inst`.
+    fn step<I: VCodeInst>(&mut self, inst: &I) {
+        if let Some((label, reg)) = inst.defines_value_label() {
+            self.def_label(label, Loc::Reg(reg));
+        } else if let Some((dst, src)) = inst.is_move() {
+            self.copy(Loc::Reg(src), Loc::Reg(dst.to_reg()));
+        } else if let Some(info) = inst.stack_op_info() {
+            match info {
+                MachInstStackOpInfo::StoreNomSPOff(reg, off) => {
+                    self.copy(Loc::Reg(reg), Loc::Stack(off));
+                }
+                MachInstStackOpInfo::LoadNomSPOff(reg, off) => {
+                    self.copy(Loc::Stack(off), Loc::Reg(reg));
+                }
+            }
+        } else {
+            let mut reg_vecs = RegUsageCollector::get_empty_reg_vecs_test_framework_only(false);
+            let mut collector = RegUsageCollector::new(&mut reg_vecs);
+            inst.get_regs(&mut collector);
+            for &reg in reg_vecs.defs.iter().chain(reg_vecs.mods.iter()) {
+                self.clobber(Loc::Reg(reg));
+            }
+        }
+    }
+
+    /// The location to report for `label`, if it is live anywhere.
+    fn report_loc(&self, label: ValueLabel) -> Option<Loc> {
+        self.label_to_locs
+            .get(&label)
+            .and_then(|locs| locs.iter().min_by_key(|loc| loc.sort_key()).cloned())
+    }
+}
+
+/// Compute the value-label ranges for a function body.
+///
+/// - `insts` is the final instruction sequence, and `block_ranges` gives the
+///   `(start, end)` instruction indices of each block.
+/// - `succs` returns the successors of each block.
+/// - `inst_starts` and `inst_ends` are the code offsets at which each
+///   instruction starts and ends.
+/// - `skip_insts` are instruction ranges (prologue and epilogues) for which no
+///   ranges are reported, because the frame is not fully set up there.
+/// - `nominal_sp_to_cfa` is the distance from nominal SP up to the canonical
+///   frame address, if known; if it is `None`, values in stack slots are not
+///   reported.
+pub(crate) fn compute<'a, I: VCodeInst>(
+    insts: &[I],
+    block_ranges: &[(InsnIndex, InsnIndex)],
+    succs: impl Fn(BlockIndex) -> &'a [BlockIx],
+    inst_starts: &[CodeOffset],
+    inst_ends: &[CodeOffset],
+    skip_insts: &[InsnRange],
+    nominal_sp_to_cfa: Option<i64>,
+) -> ValueLabelsRanges {
+    let num_blocks = block_ranges.len();
+    if num_blocks == 0 || !insts.iter().any(|i| i.defines_value_label().is_some()) {
+        return HashMap::new();
+    }
+
+    // Forward dataflow to a fixpoint. `None` means "not yet reached".
+    let mut block_in: Vec<Option<AnalysisInfo>> = vec![None; num_blocks];
+    block_in[0] = Some(AnalysisInfo::default());
+    let mut worklist = vec![0 as BlockIndex];
+    let mut on_worklist = vec![false; num_blocks];
+    on_worklist[0] = true;
+    while let Some(block) = worklist.pop() {
+        on_worklist[block as usize] = false;
+        let mut state = block_in[block as usize].clone().unwrap();
+        let (start, end) = block_ranges[block as usize];
+        for inst in &insts[start as usize..end as usize] {
+            state.step(inst);
+        }
+        for succ in succs(block) {
+            let succ = succ.get() as usize;
+            let changed = match block_in[succ].as_mut() {
+                None => {
+                    block_in[succ] = Some(state.clone());
+                    true
+                }
+                Some(succ_in) => {
+                    let old = succ_in.clone();
+                    succ_in.meet(&state);
+                    *succ_in != old
+                }
+            };
+            if changed && !on_worklist[succ] {
+                on_worklist[succ] = true;
+                worklist.push(succ as BlockIndex);
+            }
+        }
+    }
+
+    // Walk the code once more and record, for each instruction, where each
+    // label lives while it executes.
+    let mut ranges: ValueLabelsRanges = HashMap::new();
+    for (block, &(start, end)) in block_ranges.iter().enumerate() {
+        let mut state = match &block_in[block] {
+            Some(state) => state.clone(),
+            None => continue,
+        };
+        for iix in start..end {
+            let skip = skip_insts.iter().any(|r| r.contains(&iix));
+            let (code_start, code_end) = (inst_starts[iix as usize], inst_ends[iix as usize]);
+            if !skip && code_start < code_end {
+                for &label in state.label_to_locs.keys() {
+                    let loc = match state.report_loc(label) {
+                        Some(Loc::Reg(reg)) if reg.is_real() => LabelValueLoc::Reg(reg),
+                        Some(Loc::Stack(off)) if nominal_sp_to_cfa.is_some() => {
+                            LabelValueLoc::CFAOffset(off - nominal_sp_to_cfa.unwrap())
+                        }
+                        _ => continue,
+                    };
+                    add_range(&mut ranges, label, loc, code_start, code_end);
+                }
+            }
+            state.step(&insts[iix as usize]);
+        }
+    }
+
+    trace!("value labels ranges: {:?}", ranges);
+    ranges
+}
+
+/// Append a range to the list for `label`, extending the last range instead if
+/// it is contiguous and has the same location.
+fn add_range(
+    ranges: &mut ValueLabelsRanges,
+    label: ValueLabel,
+    loc: LabelValueLoc,
+    start: CodeOffset,
+    end: CodeOffset,
+) {
+    let list = ranges.entry(label).or_insert_with(Vec::new);
+    if let Some(last) = list.last_mut() {
+        if last.loc == loc && last.end == start {
+            last.end = end;
+            return;
+        }
+    }
+    list.push(ValueLocRange { loc, start, end });
+}
@@ -13,6 +13,7 @@ use crate::ir::instructions::BranchInfo;
 use crate::ir::{
     ArgumentPurpose, Block, Constant, ConstantData, ExternalName, Function, GlobalValueData, Inst,
     InstructionData, MemFlags, Opcode, Signature, SourceLoc, Type, Value, ValueDef,
+    ValueLabelAssignments, ValueLabelStart,
 };
 use crate::machinst::{
     writable_value_regs, ABICallee, BlockIndex, BlockLoweringOrder, LoweredBlock, MachLabel, VCode,
@@ -704,6 +705,9 @@ impl<'func, I: VCodeInst> Lower<'func, I> {
             if has_side_effect || value_needed {
                 debug!("lowering: inst {}: {:?}", inst, self.f.dfg[inst]);
                 backend.lower(self, inst)?;
+                // Emit value-label markers if needed, to later recover debug
+                // mappings.
+                self.emit_value_label_markers_for_inst(inst);
             }
             if data.opcode().is_return() {
                 // Return: handle specially, using ABI-appropriate sequence.
@@ -723,6 +727,89 @@ impl<'func, I: VCodeInst> Lower<'func, I> {
         Ok(())
     }
 
+    fn get_value_labels(&self, val: Value, depth: usize) -> Option<&[ValueLabelStart]> {
+        if let Some(ref values_labels) = self.f.dfg.values_labels {
+            let val = self.f.dfg.resolve_aliases(val);
+            debug!(
+                "get_value_labels: val {} -> {:?}",
+                val,
+                values_labels.get(&val)
+            );
+            match values_labels.get(&val) {
+                Some(&ValueLabelAssignments::Starts(ref list)) => Some(&list[..]),
+                Some(&ValueLabelAssignments::Alias { value, .. }) if depth < 10 => {
+                    self.get_value_labels(value, depth + 1)
+                }
+                _ => None,
+            }
+        } else {
+            None
+        }
+    }
+
+    fn emit_value_label_marks_for_value(&mut self, val: Value) {
+        let mut markers: SmallVec<[I; 4]> = SmallVec::new();
+        let regs = self.value_regs[val];
+        // Only single-register values are tracked for now.
+        let reg = match regs.only_reg() {
+            Some(reg) => reg,
+            None => return,
+        };
+
+        if let Some(label_starts) = self.get_value_labels(val, 0) {
+            let mut labels: SmallVec<[_; 4]> = SmallVec::new();
+            for &ValueLabelStart { label, .. } in label_starts {
+                if !labels.contains(&label) {
+                    labels.push(label);
+                }
+            }
+            for label in labels {
+                debug!(
+                    "value labeling: defines val {:?} -> reg {:?} -> label {:?}",
+                    val, reg, label,
+                );
+                markers.extend(I::gen_value_label_marker(label, reg));
+            }
+        }
+
+        for marker in markers {
+            self.emit(marker);
+        }
+    }
+
+    fn emit_value_label_markers_for_inst(&mut self, inst: Inst) {
+        if self.f.dfg.values_labels.is_none() {
+            return;
+        }
+
+        debug!(
+            "value labeling: srcloc {}: inst {}",
+            self.srcloc(inst),
+            inst
+        );
+        for &val in self.f.dfg.inst_results(inst) {
+            self.emit_value_label_marks_for_value(val);
+        }
+    }
+
+    fn emit_value_label_markers_for_block_args(&mut self, block: Block) {
+        if self.f.dfg.values_labels.is_none() {
+            return;
+        }
+
+        debug!("value labeling: block {}", block);
+        let is_entry = self.f.layout.entry_block() == Some(block);
+        for (i, &arg) in self.f.dfg.block_params(block).iter().enumerate() {
+            // Arguments that are unused in the body are never copied into
+            // their vregs, so there is nothing to mark.
+            if is_entry && !self.vcode.abi().arg_is_needed_in_body(i) {
+                continue;
+            }
+            self.emit_value_label_marks_for_value(arg);
+        }
+        self.finish_ir_inst(SourceLoc::default());
+    }
+
     fn finish_ir_inst(&mut self, loc: SourceLoc) {
         // `bb_insts` is kept in reverse order, so emit the instructions in
         // reverse order.
@@ -864,6 +951,7 @@ impl<'func, I: VCodeInst> Lower<'func, I> {
             // Original block body.
             if let Some(bb) = lb.orig_block() {
                 self.lower_clif_block(backend, bb)?;
+                self.emit_value_label_markers_for_block_args(bb);
             }
             // In-edge phi moves.
             if let Some((pred, inst, succ)) = lb.in_edge() {
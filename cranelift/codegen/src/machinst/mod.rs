@@ -98,10 +98,13 @@
 
 use crate::binemit::{CodeInfo, CodeOffset, StackMap};
 use crate::ir::condcodes::IntCC;
-use crate::ir::{Function, SourceLoc, Type};
+use crate::ir::{Function, SourceLoc, Type, ValueLabel};
 use crate::isa::unwind::input as unwind_input;
+#[cfg(feature = "unwind")]
+use crate::isa::unwind::systemv;
 use crate::result::CodegenResult;
 use crate::settings::Flags;
+use crate::value_label::ValueLabelsRanges;
 
 use alloc::boxed::Box;
 use alloc::vec::Vec;
@@ -137,6 +140,10 @@ pub mod inst_common;
 pub use inst_common::*;
 pub mod valueregs;
 pub use valueregs::*;
+pub mod peephole;
+pub use peephole::*;
+mod debug;
+mod stack_maps;
 
 /// A machine instruction.
 pub trait MachInst: Clone + Debug {
@@ -223,11 +230,47 @@ pub trait MachInst: Clone + Debug {
     /// be dependent on compilation flags.
     fn ref_type_regclass(_flags: &Flags) -> RegClass;
 
+    /// Does this instruction define a ValueLabel? Returns the `Reg` whose value
+    /// becomes the new value of the `ValueLabel` after this instruction.
+    fn defines_value_label(&self) -> Option<(ValueLabel, Reg)> {
+        None
+    }
+
+    /// Create a marker instruction that defines a value label. Backends that
+    /// do not track value labels return `None`.
+    fn gen_value_label_marker(_label: ValueLabel, _reg: Reg) -> Option<Self> {
+        None
+    }
+
+    /// Is this an instruction that moves a register to or from a fixed
+    /// (nominal-SP-relative) stack slot? Used to track value labels through
+    /// spills and reloads.
+    fn stack_op_info(&self) -> Option<MachInstStackOpInfo> {
+        None
+    }
+
+    /// Apply the backend's peephole rules to a pair of adjacent instructions
+    /// after register allocation, returning the rewrite for the second one if
+    /// any rule matches. See the `peephole` module.
+    fn peephole(_first: &Self, _second: &Self) -> Option<Peephole<Self>> {
+        None
+    }
+
     /// A label-use kind: a type that describes the types of label references that
     /// can occur in an instruction.
     type LabelUse: MachInstLabelUse;
 }
 
+/// Describes a load or store of a register to a fixed stack location, as
+/// returned by `MachInst::stack_op_info()`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MachInstStackOpInfo {
+    /// Load from an offset from the nominal stack pointer into the given reg.
+    LoadNomSPOff(Reg, i64),
+    /// Store to an offset from the nominal stack pointer from the given reg.
+    StoreNomSPOff(Reg, i64),
+}
+
 /// A descriptor of a label reference (use) in an instruction set.
 pub trait MachInstLabelUse: Clone + Copy + Debug + Eq {
     /// Required alignment for any veneer. Usually the required instruction
@@ -330,6 +373,8 @@ pub struct MachCompileResult {
     pub disasm: Option<String>,
     /// Unwind info.
     pub unwind_info: Option<unwind_input::UnwindInfo<Reg>>,
+    /// Debug info: value labels to registers/stackslots at code offsets.
+    pub value_labels_ranges: ValueLabelsRanges,
 }
 
 impl MachCompileResult {
@@ -393,6 +438,12 @@ pub trait MachBackend {
         // By default, an ISA cannot create a System V CIE
         None
     }
+
+    /// Maps a regalloc::Reg to a DWARF register number.
+    #[cfg(feature = "unwind")]
+    fn map_reg_to_dwarf(&self, _: Reg) -> Result<u16, systemv::RegisterMappingError> {
+        Err(systemv::RegisterMappingError::UnsupportedArchitecture)
+    }
 }
 
 /// Expected unwind info type.
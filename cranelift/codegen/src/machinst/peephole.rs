@@ -0,0 +1,57 @@
+//! Peephole optimization over machine instructions.
+//!
+//! Lowering works one CLIF instruction at a time, and register allocation
+//! may coalesce values into the same register, so the final instruction
+//! stream can contain pairs of instructions where the second is redundant
+//! given the first: a move back into the register just copied from, or a
+//! zero-extension of a value that is already zero-extended. Such pairs are
+//! only recognizable once registers are allocated.
+//!
+//! Backends describe their rewrites declaratively, as a table of
+//! `PeepholeRule`s, each matching a pair of adjacent instructions in a block
+//! and saying what to do with the second one. The table is applied by
+//! `VCode::replace_insns_from_regalloc()` as it builds the final instruction
+//! sequence, when optimizations are enabled.
+
+use crate::machinst::MachInst;
+
+/// The rewrite that a peephole rule applies to the second instruction of a
+/// matched pair.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Peephole<I: MachInst> {
+    /// The instruction is redundant; delete it.
+    Delete,
+    /// Replace the instruction with the given one.
+    Replace(I),
+}
+
+/// A single peephole rule.
+pub struct PeepholeRule<I: MachInst> {
+    /// The rule's name, for logging.
+    pub name: &'static str,
+    /// Match the pair `(first, second)` of adjacent instructions, returning
+    /// the rewrite for `second` if the rule applies.
+    pub apply: fn(&I, &I) -> Option<Peephole<I>>,
+}
+
+/// Apply the first matching rule in `rules` to the adjacent instructions
+/// `first` and `second`.
+pub fn apply_peepholes<I: MachInst>(
+    rules: &[PeepholeRule<I>],
+    first: &I,
+    second: &I,
+) -> Option<Peephole<I>> {
+    rules.iter().find_map(|rule| {
+        let result = (rule.apply)(first, second);
+        if result.is_some() {
+            log::trace!(
+                "peephole {}: {:?} after {:?} -> {:?}",
+                rule.name,
+                second,
+                first,
+                result
+            );
+        }
+        result
+    })
+}
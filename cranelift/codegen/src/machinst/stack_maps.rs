@@ -0,0 +1,344 @@
+//! Stack-map verification: checks, after register allocation, that the stack
+//! maps recorded at each safepoint cover every reference-typed value that is
+//! live across it.
+//!
+//! A missing stack-map entry does not fail loudly: the GC simply does not see
+//! the reference, and the program later uses a freed (or moved) object. This
+//! pass, which runs only when the `enable_verifier` setting is on, catches
+//! such rooting bugs at compile time instead.
+//!
+//! Before register allocation, we record which register operands of each
+//! instruction define a reference-typed vreg (`reftyped_defs()`). After
+//! register allocation, we run two dataflow analyses over the final
+//! instruction sequence, tracking registers and nominal-SP-relative stack
+//! slots alike:
+//!
+//! - a forward "may hold a reference" analysis, seeded at the recorded defs
+//!   and propagated through moves (`MachInst::is_move()`) and spills and
+//!   reloads (`MachInst::stack_op_info()`); and
+//! - a backward liveness analysis.
+//!
+//! At each safepoint, every location that may hold a reference and is live
+//! after the safepoint (excluding the safepoint's own defs) must be a stack
+//! slot listed in the safepoint's stack map. A reference held only in a
+//! register, or in an unlisted slot, is reported as an error.
+
+use crate::ir::entities::AnyEntity;
+use crate::machinst::*;
+use crate::verifier::VerifierErrors;
+use crate::{HashMap, HashSet};
+use alloc::vec::Vec;
+use regalloc::{BlockIx, Reg, RegUsageCollector, SpillSlot};
+use smallvec::SmallVec;
+
+/// For each instruction (by pre-regalloc index) that defines reference-typed
+/// vregs, the positions of those defs among the instruction's defs and mods,
+/// in the order that `get_regs()` reports them.
+pub(crate) type RefDefs = HashMap<InsnIndex, SmallVec<[usize; 2]>>;
+
+/// A location that may hold a reference.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Loc {
+    /// In a register.
+    Reg(Reg),
+    /// In a stack slot, at the given offset from nominal SP.
+    Stack(i64),
+}
+
+/// The registers used, defined and modified by an instruction.
+struct InstRegs {
+    uses: Vec<Reg>,
+    defs: Vec<Reg>,
+    mods: Vec<Reg>,
+}
+
+impl InstRegs {
+    fn of<I: VCodeInst>(inst: &I) -> Self {
+        let mut reg_vecs = RegUsageCollector::get_empty_reg_vecs_test_framework_only(false);
+        let mut collector = RegUsageCollector::new(&mut reg_vecs);
+        inst.get_regs(&mut collector);
+        InstRegs {
+            uses: reg_vecs.uses,
+            defs: reg_vecs.defs,
+            mods: reg_vecs.mods,
+        }
+    }
+
+    /// The defs and mods, in the order that positions in `RefDefs` refer to.
+    fn written(&self) -> impl Iterator<Item = Reg> + '_ {
+        self.defs.iter().chain(self.mods.iter()).cloned()
+    }
+}
+
+/// Record which defs of which instructions are of reference-typed vregs.
+/// `is_ref` tells whether a vreg is reference-typed.
+pub(crate) fn reftyped_defs<I: VCodeInst>(insts: &[I], is_ref: impl Fn(Reg) -> bool) -> RefDefs {
+    let mut ref_defs = HashMap::new();
+    for (iix, inst) in insts.iter().enumerate() {
+        let positions: SmallVec<[usize; 2]> = InstRegs::of(inst)
+            .written()
+            .enumerate()
+            .filter(|&(_, reg)| reg.is_virtual() && is_ref(reg))
+            .map(|(pos, _)| pos)
+            .collect();
+        if !positions.is_empty() {
+            ref_defs.insert(iix as InsnIndex, positions);
+        }
+    }
+    ref_defs
+}
+
+/// Forward transfer function for the "may hold a reference" analysis.
+/// `ref_positions` are the positions of the instruction's reference-typed
+/// defs, if it is an original (not regalloc-inserted) instruction.
+fn step_refs<I: VCodeInst>(
+    refs: &mut HashSet<Loc>,
+    inst: &I,
+    ref_positions: Option<&SmallVec<[usize; 2]>>,
+) {
+    let regs = InstRegs::of(inst);
+    let copy = |refs: &mut HashSet<Loc>, from: Loc, to: Loc| {
+        if refs.contains(&from) {
+            refs.insert(to);
+        } else {
+            refs.remove(&to);
+        }
+    };
+    if let Some((dst, src)) = inst.is_move() {
+        copy(refs, Loc::Reg(src), Loc::Reg(dst.to_reg()));
+    } else if let Some(info) = inst.stack_op_info() {
+        match info {
+            MachInstStackOpInfo::StoreNomSPOff(reg, off) => {
+                copy(refs, Loc::Reg(reg), Loc::Stack(off));
+            }
+            MachInstStackOpInfo::LoadNomSPOff(reg, off) => {
+                copy(refs, Loc::Stack(off), Loc::Reg(reg));
+            }
+        }
+    } else {
+        for reg in regs.written() {
+            refs.remove(&Loc::Reg(reg));
+        }
+    }
+    if let Some(positions) = ref_positions {
+        let written: SmallVec<[Reg; 4]> = regs.written().collect();
+        for &pos in positions {
+            refs.insert(Loc::Reg(written[pos]));
+        }
+    }
+}
+
+/// Backward transfer function for liveness.
+fn step_live<I: VCodeInst>(live: &mut HashSet<Loc>, inst: &I) {
+    let regs = InstRegs::of(inst);
+    for &reg in &regs.defs {
+        live.remove(&Loc::Reg(reg));
+    }
+    let stack_op = inst.stack_op_info();
+    if let Some(MachInstStackOpInfo::StoreNomSPOff(_, off)) = stack_op {
+        live.remove(&Loc::Stack(off));
+    }
+    for &reg in regs.uses.iter().chain(regs.mods.iter()) {
+        live.insert(Loc::Reg(reg));
+    }
+    if let Some(MachInstStackOpInfo::LoadNomSPOff(_, off)) = stack_op {
+        live.insert(Loc::Stack(off));
+    }
+}
+
+/// Verify the stack maps of a function body after register allocation.
+///
+/// - `insts` is the final instruction sequence, and `block_ranges` gives the
+///   `(start, end)` instruction indices of each block.
+/// - `succs` returns the successors of each block.
+/// - `orig_insts` gives, for each final instruction, the index of the
+///   pre-regalloc instruction it came from, if any; `ref_defs` is indexed by
+///   the latter.
+/// - `safepoint_insns` and `safepoint_slots` are the safepoints and their
+///   spillslot lists, as produced by register allocation.
+/// - `spillslot_offset` maps a spillslot to its offset from nominal SP.
+pub(crate) fn verify<'a, I: VCodeInst>(
+    insts: &[I],
+    block_ranges: &[(InsnIndex, InsnIndex)],
+    succs: impl Fn(BlockIndex) -> &'a [BlockIx],
+    orig_insts: &[Option<InsnIndex>],
+    ref_defs: &RefDefs,
+    safepoint_insns: &[InsnIndex],
+    safepoint_slots: &[Vec<SpillSlot>],
+    spillslot_offset: impl Fn(SpillSlot) -> i64,
+) -> Result<(), VerifierErrors> {
+    let num_blocks = block_ranges.len();
+    let ref_positions = |iix: usize| orig_insts[iix].and_then(|orig| ref_defs.get(&orig));
+
+    // Forward "may hold a reference" analysis: union at merges, iterated to a
+    // fixpoint.
+    let mut refs_in: Vec<HashSet<Loc>> = vec![HashSet::new(); num_blocks];
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for block in 0..num_blocks {
+            let mut refs = refs_in[block].clone();
+            let (start, end) = block_ranges[block];
+            for iix in start as usize..end as usize {
+                step_refs(&mut refs, &insts[iix], ref_positions(iix));
+            }
+            for succ in succs(block as BlockIndex) {
+                let succ_in = &mut refs_in[succ.get() as usize];
+                for &loc in &refs {
+                    changed |= succ_in.insert(loc);
+                }
+            }
+        }
+    }
+
+    // Backward liveness, likewise.
+    let mut live_in: Vec<HashSet<Loc>> = vec![HashSet::new(); num_blocks];
+    let live_out = |live_in: &Vec<HashSet<Loc>>, block: usize| {
+        let mut live = HashSet::new();
+        for succ in succs(block as BlockIndex) {
+            live.extend(live_in[succ.get() as usize].iter().cloned());
+        }
+        live
+    };
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for block in (0..num_blocks).rev() {
+            let mut live = live_out(&live_in, block);
+            let (start, end) = block_ranges[block];
+            for inst in insts[start as usize..end as usize].iter().rev() {
+                step_live(&mut live, inst);
+            }
+            if live != live_in[block] {
+                live_in[block] = live;
+                changed = true;
+            }
+        }
+    }
+
+    // Record what is live just after each safepoint, leaving out whatever the
+    // safepoint itself defines.
+    let mut live_after_safepoint: HashMap<InsnIndex, HashSet<Loc>> = HashMap::new();
+    for block in 0..num_blocks {
+        let (start, end) = block_ranges[block];
+        if !safepoint_insns.iter().any(|&i| start <= i && i < end) {
+            continue;
+        }
+        let mut live = live_out(&live_in, block);
+        for iix in (start..end).rev() {
+            let inst = &insts[iix as usize];
+            if safepoint_insns.contains(&iix) {
+                let mut live_after = live.clone();
+                for reg in InstRegs::of(inst).written() {
+                    live_after.remove(&Loc::Reg(reg));
+                }
+                live_after_safepoint.insert(iix, live_after);
+            }
+            step_live(&mut live, inst);
+        }
+    }
+
+    // Check each safepoint.
+    let mut errors = VerifierErrors::new();
+    for block in 0..num_blocks {
+        let mut refs = refs_in[block].clone();
+        let (start, end) = block_ranges[block];
+        for iix in start..end {
+            if let Some(live_after) = live_after_safepoint.get(&iix) {
+                let idx = safepoint_insns.iter().position(|&i| i == iix).unwrap();
+                let mapped: HashSet<i64> = safepoint_slots[idx]
+                    .iter()
+                    .map(|&slot| spillslot_offset(slot))
+                    .collect();
+                for loc in refs.intersection(live_after) {
+                    let message = match *loc {
+                        Loc::Reg(reg) => format!(
+                            "reference in register {:?} is live across the safepoint at inst {}",
+                            reg, iix
+                        ),
+                        Loc::Stack(off) if !mapped.contains(&off) => format!(
+                            "reference in stack slot at nominal SP+{} is live across the \
+                             safepoint at inst {} but missing from its stack map",
+                            off, iix
+                        ),
+                        Loc::Stack(_) => continue,
+                    };
+                    errors.report((AnyEntity::Function, message));
+                }
+            }
+            step_refs(&mut refs, &insts[iix as usize], ref_positions(iix as usize));
+        }
+    }
+
+    errors.into()
+}
+
+#[cfg(all(test, feature = "arm64"))]
+mod test {
+    use super::*;
+    use crate::ir::types::I64;
+    use crate::ir::MemFlags;
+    use crate::isa::aarch64::inst::{
+        writable_xreg, xreg, AMode, Inst, MoveWideConst, OperandSize, SImm9,
+    };
+
+    /// Defines a reference in x0, spills it to nominal SP+0, reaches a
+    /// safepoint, then reloads and uses it.
+    fn spill_across_safepoint() -> (Vec<Inst>, RefDefs) {
+        let insts = vec![
+            Inst::MovZ {
+                rd: writable_xreg(0),
+                imm: MoveWideConst::maybe_from_u64(0).unwrap(),
+                size: OperandSize::Size64,
+            },
+            Inst::Store64 {
+                rd: xreg(0),
+                mem: AMode::NominalSPOffset(0, I64),
+                flags: MemFlags::trusted(),
+            },
+            Inst::Nop4,
+            Inst::ULoad64 {
+                rd: writable_xreg(0),
+                mem: AMode::NominalSPOffset(0, I64),
+                flags: MemFlags::trusted(),
+            },
+            Inst::Store64 {
+                rd: xreg(0),
+                mem: AMode::Unscaled(xreg(1), SImm9::zero()),
+                flags: MemFlags::trusted(),
+            },
+        ];
+        let mut ref_defs = RefDefs::new();
+        ref_defs.insert(0, SmallVec::from_slice(&[0]));
+        (insts, ref_defs)
+    }
+
+    fn verify_with_slots(slots: Vec<SpillSlot>) -> Result<(), VerifierErrors> {
+        let (insts, ref_defs) = spill_across_safepoint();
+        let orig_insts = [Some(0), None, Some(1), None, Some(2)];
+        verify(
+            &insts,
+            &[(0, 5)],
+            |_| &[],
+            &orig_insts,
+            &ref_defs,
+            &[2],
+            &[slots],
+            |slot| i64::from(slot.get()) * 8,
+        )
+    }
+
+    #[test]
+    fn valid_stack_map() {
+        assert!(verify_with_slots(vec![SpillSlot::new(0)]).is_ok());
+    }
+
+    #[test]
+    fn missing_stack_map_entry() {
+        let errors = verify_with_slots(vec![]).unwrap_err();
+        assert_eq!(errors.0.len(), 1);
+        assert!(errors.0[0]
+            .message
+            .contains("nominal SP+0 is live across the safepoint at inst 2"));
+    }
+}
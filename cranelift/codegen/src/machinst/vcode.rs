@@ -21,6 +21,7 @@ use crate::ir::{self, types, Constant, ConstantData, SourceLoc};
 use crate::machinst::*;
 use crate::settings;
 use crate::timing;
+use crate::value_label::ValueLabelsRanges;
 
 use regalloc::Function as RegallocFunction;
 use regalloc::Set as RegallocSet;
@@ -107,12 +108,21 @@ pub struct VCode<I: VCodeInst> {
     /// post-regalloc.
     safepoint_slots: Vec<Vec<SpillSlot>>,
 
+    /// For each instruction, the index of the pre-regalloc instruction it came
+    /// from, if any. Filled in post-regalloc, and only when the verifier is
+    /// enabled and there are reference-typed values, for stack-map
+    /// verification.
+    orig_insts: Vec<Option<InsnIndex>>,
+
     /// Ranges for prologue and epilogue instructions.
     prologue_epilogue_ranges: Option<(InsnRange, Box<[InsnRange]>)>,
 
     /// Instruction end offsets
     insts_layout: RefCell<(Vec<u32>, u32)>,
 
+    /// Instruction start offsets
+    insts_start_layout: RefCell<Vec<u32>>,
+
     /// Constants.
     constants: VCodeConstants,
 }
@@ -313,8 +323,10 @@ impl<I: VCodeInst> VCode<I> {
             emit_info,
             safepoint_insns: vec![],
             safepoint_slots: vec![],
+            orig_insts: vec![],
             prologue_epilogue_ranges: None,
             insts_layout: RefCell::new((vec![], 0)),
+            insts_start_layout: RefCell::new(vec![]),
             constants,
         }
     }
@@ -375,7 +387,10 @@ impl<I: VCodeInst> VCode<I> {
         let mut final_block_ranges = vec![(0, 0); self.num_blocks()];
         let mut final_srclocs = vec![];
         let mut final_safepoint_insns = vec![];
+        let mut final_orig_insts = vec![];
         let mut safept_idx = 0;
+        let record_orig_insts = self.have_ref_values && self.flags().enable_verifier();
+        let run_peepholes = self.flags().opt_level() != settings::OptLevel::None;
 
         let mut prologue_start = None;
         let mut prologue_end = None;
@@ -399,8 +414,12 @@ impl<I: VCodeInst> VCode<I> {
                 let len = prologue.len();
                 final_insns.extend(prologue.into_iter());
                 final_srclocs.extend(iter::repeat(SourceLoc::default()).take(len));
+                if record_orig_insts {
+                    final_orig_insts.extend(iter::repeat(None).take(len));
+                }
                 prologue_end = Some(final_insns.len() as InsnIndex);
             }
+            let body_start = final_insns.len();
 
             for i in start..end {
                 let insn = &result.insns[i];
@@ -411,6 +430,25 @@ impl<I: VCodeInst> VCode<I> {
                     continue;
                 }
 
+                let is_safepoint = safept_idx < result.new_safepoint_insns.len()
+                    && (result.new_safepoint_insns[safept_idx].get() as usize) == i;
+
+                // Likewise, clean up instructions made redundant by the one
+                // before them in the block.
+                let can_peephole = run_peepholes && !is_safepoint && final_insns.len() > body_start;
+                let replacement;
+                let insn = match final_insns.last() {
+                    Some(prev) if can_peephole => match I::peephole(prev, insn) {
+                        Some(Peephole::Delete) => continue,
+                        Some(Peephole::Replace(new_insn)) => {
+                            replacement = new_insn;
+                            &replacement
+                        }
+                        None => insn,
+                    },
+                    _ => insn,
+                };
+
                 // Is there a srcloc associated with this insn? Look it up based on original
                 // instruction index (if new insn corresponds to some original insn, i.e., is not
                 // an inserted load/spill/move).
@@ -431,16 +469,24 @@ impl<I: VCodeInst> VCode<I> {
                     final_insns.extend(epilogue.into_iter());
                     final_srclocs.extend(iter::repeat(srcloc).take(len));
                     epilogue_islands.push(epilogue_start..final_insns.len() as InsnIndex);
+                    if record_orig_insts {
+                        final_orig_insts.extend(iter::repeat(None).take(len));
+                    }
                 } else {
                     final_insns.push(insn.clone());
                     final_srclocs.push(srcloc);
+                    if record_orig_insts {
+                        final_orig_insts.push(if orig_iix.is_invalid() {
+                            None
+                        } else {
+                            Some(orig_iix.get())
+                        });
+                    }
                 }
 
                 // Was this instruction a safepoint instruction? Add its final
                 // index to the safepoint insn-index list if so.
-                if safept_idx < result.new_safepoint_insns.len()
-                    && (result.new_safepoint_insns[safept_idx].get() as usize) == i
-                {
+                if is_safepoint {
                     let idx = final_insns.len() - 1;
                     final_safepoint_insns.push(idx as InsnIndex);
                     safept_idx += 1;
@@ -457,6 +503,7 @@ impl<I: VCodeInst> VCode<I> {
         self.srclocs = final_srclocs;
         self.block_ranges = final_block_ranges;
         self.safepoint_insns = final_safepoint_insns;
+        self.orig_insts = final_orig_insts;
 
         // Save safepoint slot-lists. These will be passed to the `EmitState`
         // for the machine backend during emission so that it can do
@@ -485,6 +532,7 @@ impl<I: VCodeInst> VCode<I> {
         buffer.reserve_labels_for_constants(&self.constants);
 
         let mut insts_layout = vec![0; self.insts.len()];
+        let mut insts_start_layout = vec![0; self.insts.len()];
 
         let mut safepoint_idx = 0;
         let mut cur_srcloc = None;
@@ -524,6 +572,7 @@ impl<I: VCodeInst> VCode<I> {
                     safepoint_idx += 1;
                 }
 
+                insts_start_layout[iix as usize] = buffer.cur_offset();
                 self.insts[iix as usize].emit(&mut buffer, &self.emit_info, &mut state);
 
                 insts_layout[iix as usize] = buffer.cur_offset();
@@ -553,7 +602,12 @@ impl<I: VCodeInst> VCode<I> {
             buffer.defer_constant(label, data.alignment(), data.as_slice(), u32::max_value());
         }
 
+        // Emit the outlined traps now rather than in `finish()`, so that the
+        // code size recorded for unwind info covers them.
+        buffer.emit_pending_traps();
+
         *self.insts_layout.borrow_mut() = (insts_layout, buffer.cur_offset());
+        *self.insts_start_layout.borrow_mut() = insts_start_layout;
 
         buffer
     }
@@ -574,6 +628,61 @@ impl<I: VCodeInst> VCode<I> {
         I::UnwindInfo::create_unwind_info(context)
     }
 
+    /// Generates value-label ranges, i.e. debug info locations for values
+    /// carrying `ValueLabel`s. Must be called after `emit()`.
+    pub fn value_labels_ranges(&self) -> ValueLabelsRanges {
+        let layout = &self.insts_layout.borrow();
+        let start_layout = &self.insts_start_layout.borrow();
+        let (prologue, epilogues) = self.prologue_epilogue_ranges.as_ref().unwrap();
+        let mut skip_insts = vec![prologue.clone()];
+        skip_insts.extend(epilogues.iter().cloned());
+        // Stack slots are addressed from nominal SP, which sits `frame_size()`
+        // bytes below FP; FP in turn points 16 bytes (saved FP and return
+        // address) below the CFA. Baldrdash frames do not follow this layout.
+        let nominal_sp_to_cfa = if self.abi.call_conv().extends_baldrdash() {
+            None
+        } else {
+            Some(self.frame_size() as i64 + 16)
+        };
+        debug::compute(
+            &self.insts[..],
+            &self.block_ranges[..],
+            |block| self.succs(block),
+            &start_layout[..],
+            &layout.0[..],
+            &skip_insts[..],
+            nominal_sp_to_cfa,
+        )
+    }
+
+    /// Record which instructions define reference-typed vregs. Must be called
+    /// before register allocation; the result is consumed by
+    /// `verify_stack_maps()`.
+    pub(crate) fn reftyped_defs(&self) -> stack_maps::RefDefs {
+        stack_maps::reftyped_defs(&self.insts[..], |reg| {
+            is_reftype(self.vreg_types[reg.get_index()])
+        })
+    }
+
+    /// Check, after register allocation, that the stack map at every safepoint
+    /// covers all reference-typed values live across it.
+    pub(crate) fn verify_stack_maps(
+        &self,
+        ref_defs: &stack_maps::RefDefs,
+    ) -> crate::result::CodegenResult<()> {
+        stack_maps::verify(
+            &self.insts[..],
+            &self.block_ranges[..],
+            |block| self.succs(block),
+            &self.orig_insts[..],
+            ref_defs,
+            &self.safepoint_insns[..],
+            &self.safepoint_slots[..],
+            |slot| self.abi.spillslot_offset(slot),
+        )?;
+        Ok(())
+    }
+
     /// Get the IR block for a BlockIndex, if one exists.
     pub fn bindex_to_bb(&self, block: BlockIndex) -> Option<ir::Block> {
         self.block_order.lowered_order()[block as usize].orig_block()
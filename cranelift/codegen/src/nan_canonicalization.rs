@@ -1,6 +1,8 @@
 //! A NaN-canonicalizing rewriting pass. Patch floating point arithmetic
 //! instructions that may return a NaN result with a sequence of operations
 //! that will replace nondeterministic NaN's with a single canonical NaN value.
+//!
+//! Both scalar and vector (`f32x4`, `f64x2`) results are canonicalized.
 
 use crate::cursor::{Cursor, FuncCursor};
 use crate::ir::condcodes::FloatCC;
@@ -38,6 +40,8 @@ fn is_fp_arith(pos: &mut FuncCursor, inst: Inst) -> bool {
                 || opcode == Opcode::Nearest
                 || opcode == Opcode::Sqrt
                 || opcode == Opcode::Trunc
+                || opcode == Opcode::Fdemote
+                || opcode == Opcode::Fpromote
         }
         InstructionData::Binary { opcode, .. } => {
             opcode == Opcode::Fadd
@@ -64,10 +68,21 @@ fn add_nan_canon_seq(pos: &mut FuncCursor, inst: Inst) {
     // Insert a comparison instruction, to check if `inst_res` is NaN. Select
     // the canonical NaN value if `val` is NaN, assign the result to `inst`.
     let is_nan = pos.ins().fcmp(FloatCC::NotEqual, new_res, new_res);
-    let canon_nan = insert_nan_const(pos, val_type);
-    pos.ins()
-        .with_result(val)
-        .select(is_nan, canon_nan, new_res);
+    if val_type.is_vector() {
+        // Vector comparisons produce a lane mask rather than a single boolean,
+        // so select the canonical NaN bitwise, lane by lane.
+        let canon_nan = insert_nan_const(pos, val_type.lane_type());
+        let canon_nan = pos.ins().splat(val_type, canon_nan);
+        let is_nan = pos.ins().raw_bitcast(val_type, is_nan);
+        pos.ins()
+            .with_result(val)
+            .bitselect(is_nan, canon_nan, new_res);
+    } else {
+        let canon_nan = insert_nan_const(pos, val_type);
+        pos.ins()
+            .with_result(val)
+            .select(is_nan, canon_nan, new_res);
+    }
 
     pos.prev_inst(); // Step backwards so the pass does not skip instructions.
 }
@@ -0,0 +1,87 @@
+//! A redundant load elimination pass.
+//!
+//! Within a basic block, a load from the same address with the same type, flags and offset as an
+//! earlier load is replaced with the earlier load's result, as long as no instruction in between
+//! may have written to memory. This removes the repeated loads of `vmctx` fields and heap bases
+//! which the wasm translator emits for every memory access.
+//!
+//! The alias analysis used here is deliberately conservative: any store, call or instruction with
+//! other side effects is assumed to clobber all of memory.
+
+use crate::cursor::{Cursor, FuncCursor};
+use crate::fx::FxHashMap;
+use crate::ir::immediates::Offset32;
+use crate::ir::{Function, Inst, InstructionData, MemFlags, Opcode, Type, Value};
+use crate::timing;
+use alloc::vec::Vec;
+
+/// Everything which determines the value produced by a load, given unchanged memory.
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct LoadKey {
+    opcode: Opcode,
+    ty: Type,
+    flags: MemFlags,
+    offset: Offset32,
+    args: Vec<Value>,
+}
+
+/// Test whether `opcode` may write to memory as far as this pass is concerned.
+fn clobbers_memory(opcode: Opcode) -> bool {
+    opcode.can_store() || opcode.is_call() || opcode.other_side_effects()
+}
+
+/// Build the key for `inst` if it's a plain load this pass knows how to handle.
+fn load_key(func: &Function, inst: Inst) -> Option<LoadKey> {
+    let (flags, offset) = match func.dfg[inst] {
+        InstructionData::Load { flags, offset, .. }
+        | InstructionData::LoadComplex { flags, offset, .. } => (flags, offset),
+        _ => return None,
+    };
+    let opcode = func.dfg[inst].opcode();
+    if !opcode.can_load() || opcode.other_side_effects() {
+        return None;
+    }
+    Some(LoadKey {
+        opcode,
+        ty: func.dfg.ctrl_typevar(inst),
+        flags,
+        offset,
+        args: func.dfg.inst_args(inst).to_vec(),
+    })
+}
+
+/// Perform redundant load elimination on `func`.
+pub fn do_redundant_load_elim(func: &mut Function) {
+    let _tt = timing::redundant_load_elim();
+
+    let mut available: FxHashMap<LoadKey, Inst> = FxHashMap::default();
+    let mut pos = FuncCursor::new(func);
+    while let Some(_block) = pos.next_block() {
+        available.clear();
+        while let Some(inst) = pos.next_inst() {
+            // Resolve aliases, particularly aliases we created earlier, so
+            // that loads from the same address compare equal.
+            pos.func.dfg.resolve_aliases_in_arguments(inst);
+
+            let opcode = pos.func.dfg[inst].opcode();
+            if clobbers_memory(opcode) {
+                available.clear();
+                continue;
+            }
+
+            let key = match load_key(pos.func, inst) {
+                Some(key) => key,
+                None => continue,
+            };
+            match available.get(&key) {
+                Some(&earlier) => {
+                    pos.func.dfg.replace_with_aliases(inst, earlier);
+                    pos.remove_inst_and_step_back();
+                }
+                None => {
+                    available.insert(key, inst);
+                }
+            }
+        }
+    }
+}
@@ -387,6 +387,10 @@ tls_model = "none"
 libcall_call_conv = "isa_default"
 baldrdash_prologue_words = 0
 probestack_size_log2 = 12
+enable_preopt = true
+enable_licm = true
+enable_gvn = true
+enable_redundant_load_elim = true
 enable_verifier = true
 is_pic = false
 use_colocated_libcalls = false
@@ -403,6 +407,7 @@ enable_probestack = true
 probestack_func_adjusts_sp = false
 enable_jump_tables = true
 enable_heap_access_spectre_mitigation = true
+enable_table_access_spectre_mitigation = true
 "#
         );
         assert_eq!(f.opt_level(), super::OptLevel::None);
@@ -60,6 +60,7 @@ define_passes! {
     dce: "Dead code elimination",
     legalize: "Legalization",
     gvn: "Global value numbering",
+    redundant_load_elim: "Redundant load elimination",
     licm: "Loop invariant code motion",
     unreachable_code: "Remove unreachable blocks",
     remove_constant_phis: "Remove constant phi-nodes",
@@ -145,6 +146,22 @@ mod details {
         pass: [PassTime; NUM_PASSES],
     }
 
+    impl PassTimes {
+        /// Returns the description of every pass that has run, along with the
+        /// total time spent in it and the time spent in it excluding child
+        /// passes.
+        pub fn passes(&self) -> impl Iterator<Item = (&'static str, Duration, Duration)> + '_ {
+            self.pass
+                .iter()
+                .zip(&DESCRIPTIONS[..])
+                .filter(|(time, _)| time.total != Duration::default())
+                .map(|(time, desc)| {
+                    let own = time.total.checked_sub(time.child).unwrap_or_default();
+                    (*desc, time.total, own)
+                })
+        }
+    }
+
     impl Default for PassTimes {
         fn default() -> Self {
             Self {
@@ -238,10 +255,17 @@ mod details {
 #[cfg(not(feature = "std"))]
 mod details {
     use super::Pass;
+    use core::time::Duration;
     /// Dummy `TimingToken`
     pub struct TimingToken;
     /// Dummy `PassTimes`
     pub struct PassTimes;
+    impl PassTimes {
+        /// Returns no passes
+        pub fn passes(&self) -> impl Iterator<Item = (&'static str, Duration, Duration)> {
+            core::iter::empty()
+        }
+    }
     /// Returns dummy `PassTimes`
     pub fn take_current() -> PassTimes {
         PassTimes
@@ -265,4 +289,22 @@ mod tests {
         assert_eq!(Pass::None.to_string(), "<no pass>");
         assert_eq!(Pass::regalloc.to_string(), "Register allocation");
     }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn passes() {
+        {
+            let _compile = compile();
+            let _regalloc = regalloc();
+        }
+        let times = take_current();
+        let passes = times.passes().collect::<alloc::vec::Vec<_>>();
+        assert_eq!(passes.len(), 2);
+        let (compile, regalloc) = (passes[0], passes[1]);
+        assert_eq!(compile.0, "Compilation passes");
+        assert_eq!(regalloc.0, "Register allocation");
+        assert!(compile.1 >= regalloc.1);
+        assert_eq!(compile.2, compile.1 - regalloc.1);
+        assert_eq!(regalloc.1, regalloc.2);
+    }
 }
@@ -8,16 +8,37 @@ use core::cmp::Ordering;
 use core::iter::Iterator;
 use core::ops::Bound::*;
 use core::ops::Deref;
+use regalloc::Reg;
 
 #[cfg(feature = "enable-serde")]
 use serde::{Deserialize, Serialize};
 
+/// Location of a labeled value: either a legacy `ValueLoc`, as computed by
+/// the old backends' register allocator, or a location produced by the
+/// MachInst backends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+pub enum LabelValueLoc {
+    /// Old-backend location: RegUnit, StackSlot, or Unassigned.
+    ValueLoc(ValueLoc),
+    /// New-backend Reg. This is always a real register.
+    Reg(Reg),
+    /// New-backend offset from the canonical frame address (CFA).
+    CFAOffset(i64),
+}
+
+impl From<ValueLoc> for LabelValueLoc {
+    fn from(v: ValueLoc) -> Self {
+        LabelValueLoc::ValueLoc(v)
+    }
+}
+
 /// Value location range.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
 pub struct ValueLocRange {
-    /// The ValueLoc containing a ValueLabel during this range.
-    pub loc: ValueLoc,
+    /// The location containing a ValueLabel during this range.
+    pub loc: LabelValueLoc,
     /// The start of the range. It is an offset in the generated code.
     pub start: u32,
     /// The end of the range. It is an offset in the generated code.
@@ -91,7 +112,8 @@ pub fn build_value_labels_ranges<T>(
 where
     T: From<SourceLoc> + Deref<Target = SourceLoc> + Ord + Copy,
 {
-    // FIXME(#1523): New-style backend does not yet have debug info.
+    // The MachInst backends compute their own ranges; see
+    // `machinst::debug`.
     if isa.get_mach_backend().is_some() {
         return HashMap::new();
     }
@@ -113,7 +135,7 @@ where
             .entry(label)
             .or_insert_with(Vec::new)
             .push(ValueLocRange {
-                loc,
+                loc: LabelValueLoc::ValueLoc(loc),
                 start: range.0,
                 end: range.1,
             });
@@ -11,7 +11,7 @@ use crate::ir::{
 };
 use crate::isa::{RegInfo, TargetIsa};
 use crate::packed_option::ReservedValue;
-use crate::value_label::ValueLabelsRanges;
+use crate::value_label::{LabelValueLoc, ValueLabelsRanges};
 use crate::HashSet;
 use alloc::string::String;
 use alloc::vec::Vec;
@@ -263,26 +263,30 @@ pub fn write_block_header(
     let regs = regs.as_ref();
 
     let mut args = func.dfg.block_params(block).iter().cloned();
-    match args.next() {
-        None => return writeln!(w, ":"),
-        Some(arg) => {
-            write!(w, "(")?;
+    if let Some(arg) = args.next() {
+        write!(w, "(")?;
+        write_arg(w, func, regs, arg)?;
+        // Remaining arguments.
+        for arg in args {
+            write!(w, ", ")?;
             write_arg(w, func, regs, arg)?;
         }
+        write!(w, ")")?;
     }
-    // Remaining arguments.
-    for arg in args {
-        write!(w, ", ")?;
-        write_arg(w, func, regs, arg)?;
+
+    if func.layout.is_cold(block) {
+        write!(w, " cold")?;
     }
-    writeln!(w, "):")
+    writeln!(w, ":")
 }
 
-fn write_valueloc(w: &mut dyn Write, loc: ValueLoc, regs: &RegInfo) -> fmt::Result {
+fn write_valueloc(w: &mut dyn Write, loc: LabelValueLoc, regs: &RegInfo) -> fmt::Result {
     match loc {
-        ValueLoc::Reg(r) => write!(w, "{}", regs.display_regunit(r)),
-        ValueLoc::Stack(ss) => write!(w, "{}", ss),
-        ValueLoc::Unassigned => write!(w, "?"),
+        LabelValueLoc::ValueLoc(ValueLoc::Reg(r)) => write!(w, "{}", regs.display_regunit(r)),
+        LabelValueLoc::ValueLoc(ValueLoc::Stack(ss)) => write!(w, "{}", ss),
+        LabelValueLoc::ValueLoc(ValueLoc::Unassigned) => write!(w, "?"),
+        LabelValueLoc::Reg(r) => write!(w, "{:?}", r),
+        LabelValueLoc::CFAOffset(off) => write!(w, "CFA{:+}", off),
     }
 }
 
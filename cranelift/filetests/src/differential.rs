@@ -0,0 +1,133 @@
+//! Differential execution of CLIF functions.
+//!
+//! [compare_with_interpreter] runs a function both in the Cranelift interpreter and as native code
+//! compiled for the host, and reports any difference between the two results. This catches
+//! miscompilations in a backend without having to write down the expected results by hand, and is
+//! intended to be driven by fuzz targets as well as by `clif-util interpret --compare`.
+
+use crate::function_runner::SingleFunctionCompiler;
+use cranelift_codegen::data_value::DataValue;
+use cranelift_codegen::ir::Function;
+use cranelift_interpreter::environment::FunctionStore;
+use cranelift_interpreter::interpreter::{Interpreter, InterpreterState};
+use cranelift_interpreter::step::ControlFlow;
+use thiserror::Error;
+
+/// The outcome of a successful [compare_with_interpreter] run.
+#[derive(Debug, PartialEq)]
+pub enum DifferentialOutcome {
+    /// The interpreter and the compiled code both returned these values.
+    Returned(Vec<DataValue>),
+    /// The interpreter trapped with this message. The compiled code is not run in this case, as a
+    /// trap in native code would bring down the whole process.
+    Trapped(String),
+}
+
+/// The ways a [compare_with_interpreter] run can fail.
+#[derive(Error, Debug)]
+pub enum DifferentialError {
+    /// The interpreter could not execute the function, e.g. because it uses an instruction the
+    /// interpreter doesn't implement yet.
+    #[error("failed to interpret {0}: {1}")]
+    Interpreter(String, String),
+    /// The function could not be compiled for the host.
+    #[error("failed to compile {0}: {1}")]
+    Compilation(String, String),
+    /// The compiled code returned different values than the interpreter.
+    #[error(
+        "{name} returned different results for arguments {args:?}: \
+         interpreter returned {interpreted:?} but compiled code returned {compiled:?}"
+    )]
+    Mismatch {
+        /// The name of the function.
+        name: String,
+        /// The arguments the function was called with.
+        args: Vec<DataValue>,
+        /// The values returned by the interpreter.
+        interpreted: Vec<DataValue>,
+        /// The values returned by the compiled code.
+        compiled: Vec<DataValue>,
+    },
+}
+
+/// Call `func` with `args` in both the interpreter and as code compiled by `compiler`, and check
+/// that both return the same values.
+///
+/// The function must be self-contained: like [SingleFunctionCompiler], this can't handle calls,
+/// global values or anything else referring outside of the function. Floating-point results are
+/// compared bit for bit, so functions which may produce NaNs with host-specific payloads should
+/// be compiled with NaN canonicalization enabled.
+pub fn compare_with_interpreter(
+    compiler: &mut SingleFunctionCompiler,
+    func: &Function,
+    args: &[DataValue],
+) -> Result<DifferentialOutcome, DifferentialError> {
+    let name = func.name.to_string();
+
+    let mut env = FunctionStore::default();
+    env.add(name.clone(), func);
+    let state = InterpreterState::default().with_function_store(env);
+    let interpreted = match Interpreter::new(state).call_by_name(&name, args) {
+        Ok(ControlFlow::Return(results)) => results.to_vec(),
+        Ok(ControlFlow::Trap(trap)) => return Ok(DifferentialOutcome::Trapped(trap.to_string())),
+        Ok(_) => panic!("Unexpected returned control flow--this is likely a bug."),
+        Err(e) => return Err(DifferentialError::Interpreter(name, e.to_string())),
+    };
+
+    let compiled_fn = compiler
+        .compile(func.clone())
+        .map_err(|e| DifferentialError::Compilation(name.clone(), e.to_string()))?;
+    let compiled = compiled_fn.call(args);
+
+    if interpreted == compiled {
+        Ok(DifferentialOutcome::Returned(compiled))
+    } else {
+        Err(DifferentialError::Mismatch {
+            name,
+            args: args.to_vec(),
+            interpreted,
+            compiled,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cranelift_reader::parse_functions;
+
+    fn parse(code: &str) -> Function {
+        parse_functions(code).unwrap().remove(0)
+    }
+
+    #[test]
+    fn same_results() {
+        let func = parse(
+            "function %add(i32, i32) -> i32 {
+            block0(v0: i32, v1: i32):
+                v2 = iadd v0, v1
+                return v2
+            }",
+        );
+        let mut compiler = SingleFunctionCompiler::with_default_host_isa();
+        let args = [DataValue::I32(40), DataValue::I32(2)];
+        let outcome = compare_with_interpreter(&mut compiler, &func, &args).unwrap();
+        assert_eq!(
+            outcome,
+            DifferentialOutcome::Returned(vec![DataValue::I32(42)])
+        );
+    }
+
+    #[test]
+    fn traps_are_not_executed() {
+        let func = parse(
+            "function %trap() {
+            block0:
+                trap user0
+            }",
+        );
+        let mut compiler = SingleFunctionCompiler::with_default_host_isa();
+        let outcome = compare_with_interpreter(&mut compiler, &func, &[]).unwrap();
+        assert!(matches!(outcome, DifferentialOutcome::Trapped(_)));
+    }
+}
@@ -22,6 +22,7 @@
     )
 )]
 
+pub use crate::differential::{compare_with_interpreter, DifferentialError, DifferentialOutcome};
 pub use crate::function_runner::SingleFunctionCompiler;
 use crate::runner::TestRunner;
 use cranelift_codegen::timing;
@@ -30,6 +31,7 @@ use std::path::Path;
 use std::time;
 
 mod concurrent;
+mod differential;
 mod function_runner;
 mod match_directive;
 mod runner;
@@ -48,6 +50,7 @@ mod test_peepmatic;
 mod test_postopt;
 mod test_preopt;
 mod test_print_cfg;
+mod test_redundant_load_elim;
 mod test_regalloc;
 mod test_rodata;
 mod test_run;
@@ -129,6 +132,7 @@ fn new_subtest(parsed: &TestCommand) -> anyhow::Result<Box<dyn subtest::SubTest>
         "postopt" => test_postopt::subtest(parsed),
         "preopt" => test_preopt::subtest(parsed),
         "print-cfg" => test_print_cfg::subtest(parsed),
+        "redundant-load-elim" => test_redundant_load_elim::subtest(parsed),
         "regalloc" => test_regalloc::subtest(parsed),
         "rodata" => test_rodata::subtest(parsed),
         "run" => test_run::subtest(parsed),
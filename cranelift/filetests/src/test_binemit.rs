@@ -161,7 +161,7 @@ impl SubTest for TestBinEmit {
                                 recipe_constraints.satisfied(inst, &divert, &func)
                             });
 
-                        if opt_level == OptLevel::SpeedAndSize {
+                        if opt_level == OptLevel::SpeedAndSize || opt_level == OptLevel::Size {
                             // Get the smallest legal encoding
                             legal_encodings
                                 .min_by_key(|&e| encinfo.byte_size(e, inst, &divert, &func))
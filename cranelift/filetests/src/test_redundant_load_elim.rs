@@ -0,0 +1,43 @@
+//! Test command for testing the redundant load elimination pass.
+//!
+//! The `redundant-load-elim` test command runs each function through the redundant load
+//! elimination pass.
+//!
+//! The resulting function is sent to `filecheck`.
+
+use crate::subtest::{run_filecheck, Context, SubTest};
+use cranelift_codegen;
+use cranelift_codegen::ir::Function;
+use cranelift_reader::TestCommand;
+use std::borrow::Cow;
+
+struct TestRedundantLoadElim;
+
+pub fn subtest(parsed: &TestCommand) -> anyhow::Result<Box<dyn SubTest>> {
+    assert_eq!(parsed.command, "redundant-load-elim");
+    if !parsed.options.is_empty() {
+        anyhow::bail!("No options allowed on {}", parsed);
+    }
+    Ok(Box::new(TestRedundantLoadElim))
+}
+
+impl SubTest for TestRedundantLoadElim {
+    fn name(&self) -> &'static str {
+        "redundant-load-elim"
+    }
+
+    fn is_mutating(&self) -> bool {
+        true
+    }
+
+    fn run(&self, func: Cow<Function>, context: &Context) -> anyhow::Result<()> {
+        let mut comp_ctx = cranelift_codegen::Context::for_function(func.into_owned());
+
+        comp_ctx
+            .redundant_load_elim(context.flags_or_isa())
+            .map_err(|e| crate::pretty_anyhow_error(&comp_ctx.func, context.isa, Into::into(e)))?;
+
+        let text = comp_ctx.func.display(context.isa).to_string();
+        run_filecheck(&text, context)
+    }
+}
@@ -2042,7 +2042,7 @@ impl<'a> Parser<'a> {
     // Parse a basic block, add contents to `ctx`.
     //
     // extended-basic-block ::= * block-header { instruction }
-    // block-header           ::= Block(block) [block-params] ":"
+    // block-header           ::= Block(block) [block-params] ["cold"] ":"
     //
     fn parse_basic_block(&mut self, ctx: &mut Context) -> ParseResult<()> {
         // Collect comments for the next block.
@@ -2056,8 +2056,14 @@ impl<'a> Parser<'a> {
         }
 
         if !self.optional(Token::Colon) {
-            // block-header ::= Block(block) [ * block-params ] ":"
-            self.parse_block_params(ctx, block)?;
+            // block-header ::= Block(block) [ * block-params ] ["cold"] ":"
+            if self.token() != Some(Token::Identifier("cold")) {
+                self.parse_block_params(ctx, block)?;
+            }
+            // block-header ::= Block(block) [block-params] [ * "cold" ] ":"
+            if self.optional(Token::Identifier("cold")) {
+                ctx.function.layout.set_cold(block);
+            }
             self.match_token(Token::Colon, "expected ':' after block parameters")?;
         }
 
@@ -1,6 +1,7 @@
 //! CLI tool to interpret Cranelift IR files.
 
 use crate::utils::iterate_files;
+use cranelift_filetests::{compare_with_interpreter, DifferentialOutcome, SingleFunctionCompiler};
 use cranelift_interpreter::environment::FunctionStore;
 use cranelift_interpreter::interpreter::{Interpreter, InterpreterState};
 use cranelift_interpreter::step::ControlFlow;
@@ -25,6 +26,11 @@ pub struct Options {
     /// Be more verbose
     #[structopt(short = "v", long = "verbose")]
     verbose: bool,
+
+    /// Also compile each function for the host and check that the compiled code returns the same
+    /// results as the interpreter
+    #[structopt(long = "compare")]
+    compare: bool,
 }
 
 /// Run files through the Cranelift interpreter, interpreting any functions with annotations.
@@ -35,7 +41,7 @@ pub fn run(options: &Options) -> anyhow::Result<()> {
     let mut errors = 0;
     for file in iterate_files(&options.files) {
         total += 1;
-        let runner = FileInterpreter::from_path(file)?;
+        let runner = FileInterpreter::from_path(file)?.compare(options.compare);
         match runner.run() {
             Ok(_) => {
                 if options.verbose {
@@ -70,6 +76,7 @@ pub fn run(options: &Options) -> anyhow::Result<()> {
 pub struct FileInterpreter {
     path: Option<PathBuf>,
     contents: String,
+    compare: bool,
 }
 
 impl FileInterpreter {
@@ -81,6 +88,7 @@ impl FileInterpreter {
         Ok(Self {
             path: Some(path),
             contents,
+            compare: false,
         })
     }
 
@@ -91,9 +99,17 @@ impl FileInterpreter {
         Self {
             path: None,
             contents,
+            compare: false,
         }
     }
 
+    /// Configure whether functions are also compiled for the host, checking that the compiled code
+    /// returns the same results as the interpreter.
+    pub fn compare(mut self, compare: bool) -> Self {
+        self.compare = compare;
+        self
+    }
+
     /// Return the path of the file runner or `[inline code]`.
     pub fn path(&self) -> String {
         match self.path {
@@ -117,7 +133,7 @@ impl FileInterpreter {
                 if let Some(command) = parse_run_command(comment.text, &func.signature)
                     .map_err(|e| FileInterpreterFailure::ParsingClif(self.path(), e))?
                 {
-                    commands.push(command);
+                    commands.push((func, command));
                 }
             }
             // Note: func.name may truncate the function name
@@ -125,9 +141,22 @@ impl FileInterpreter {
         }
 
         // Run assertion commands
-        for command in commands {
+        let mut compiler = if self.compare {
+            Some(SingleFunctionCompiler::with_default_host_isa())
+        } else {
+            None
+        };
+        for (func, command) in commands {
             command
                 .run(|func_name, args| {
+                    if let Some(compiler) = compiler.as_mut() {
+                        return match compare_with_interpreter(compiler, func, args) {
+                            Ok(DifferentialOutcome::Returned(results)) => Ok(results),
+                            Ok(DifferentialOutcome::Trapped(trap)) => Err(trap),
+                            Err(e) => Err(e.to_string()),
+                        };
+                    }
+
                     // Because we have stored function names with a leading %, we need to re-add it.
                     let func_name = &format!("%{}", func_name);
                     let state = InterpreterState::default().with_function_store(env.clone());
@@ -176,12 +205,31 @@ mod test {
         FileInterpreter::from_inline_code(code).run().unwrap()
     }
 
+    #[test]
+    fn compare() {
+        let code = String::from(
+            "
+            function %add(i32, i32) -> i32 {
+            block0(v0: i32, v1: i32):
+                v2 = iadd v0, v1
+                return v2
+            }
+            ; run: %add(40, 2) == 42
+            ",
+        );
+        FileInterpreter::from_inline_code(code)
+            .compare(true)
+            .run()
+            .unwrap()
+    }
+
     #[test]
     fn filetests() {
         run(&Options {
             files: vec![PathBuf::from("../filetests/filetests/interpreter")],
             debug: true,
             verbose: true,
+            compare: false,
         })
         .unwrap()
     }
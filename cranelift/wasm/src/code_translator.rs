@@ -265,6 +265,7 @@ pub fn translate_operator<FE: FuncEnvironment + ?Sized>(
         }
         Operator::If { ty } => {
             let val = state.pop1();
+            let hint = environ.branch_hint(builder.srcloc());
 
             let (params, results) = blocktype_params_results(validator, *ty)?;
             let (destination, else_data) = if params.clone().eq(results.clone()) {
@@ -285,10 +286,16 @@ pub fn translate_operator<FE: FuncEnvironment + ?Sized>(
                 let else_block = block_with_params(builder, params.clone(), environ)?;
                 canonicalise_then_brz(builder, val, else_block, state.peekn(params.len()));
                 builder.seal_block(else_block);
+                if hint == Some(true) {
+                    builder.func.layout.set_cold(else_block);
+                }
                 (destination, ElseData::WithElse { else_block })
             };
 
             let next_block = builder.create_block();
+            if hint == Some(false) {
+                builder.func.layout.set_cold(next_block);
+            }
             canonicalise_then_jump(builder, next_block, &[]);
             builder.seal_block(next_block); // Only predecessor is the current block.
             builder.switch_to_block(next_block);
@@ -440,7 +447,10 @@ pub fn translate_operator<FE: FuncEnvironment + ?Sized>(
             state.popn(return_count);
             state.reachable = false;
         }
-        Operator::BrIf { relative_depth } => translate_br_if(*relative_depth, builder, state),
+        Operator::BrIf { relative_depth } => {
+            let hint = environ.branch_hint(builder.srcloc());
+            translate_br_if(*relative_depth, hint, builder, state)
+        }
         Operator::BrTable { table } => {
             let mut depths = table.targets().collect::<Result<Vec<_>, _>>()?;
             let default = depths.pop().unwrap().0;
@@ -1836,7 +1846,34 @@ pub fn translate_operator<FE: FuncEnvironment + ?Sized>(
         | Operator::I64x2ExtMulHighI32x4S
         | Operator::I64x2ExtMulLowI32x4U
         | Operator::I64x2ExtMulHighI32x4U => {
-            return Err(wasm_unsupported!("proposed simd operator {:?}", op));
+            // Extended multiplications widen both operands from the same half
+            // and then multiply them; backends can fuse this into a single
+            // long multiplication.
+            let (input_ty, signed, high) = match op {
+                Operator::I16x8ExtMulLowI8x16S => (I8X16, true, false),
+                Operator::I16x8ExtMulHighI8x16S => (I8X16, true, true),
+                Operator::I16x8ExtMulLowI8x16U => (I8X16, false, false),
+                Operator::I16x8ExtMulHighI8x16U => (I8X16, false, true),
+                Operator::I32x4ExtMulLowI16x8S => (I16X8, true, false),
+                Operator::I32x4ExtMulHighI16x8S => (I16X8, true, true),
+                Operator::I32x4ExtMulLowI16x8U => (I16X8, false, false),
+                Operator::I32x4ExtMulHighI16x8U => (I16X8, false, true),
+                Operator::I64x2ExtMulLowI32x4S => (I32X4, true, false),
+                Operator::I64x2ExtMulHighI32x4S => (I32X4, true, true),
+                Operator::I64x2ExtMulLowI32x4U => (I32X4, false, false),
+                Operator::I64x2ExtMulHighI32x4U => (I32X4, false, true),
+                _ => unreachable!(),
+            };
+            let widen = |builder: &mut FunctionBuilder, v| match (signed, high) {
+                (true, false) => builder.ins().swiden_low(v),
+                (true, true) => builder.ins().swiden_high(v),
+                (false, false) => builder.ins().uwiden_low(v),
+                (false, true) => builder.ins().uwiden_high(v),
+            };
+            let (a, b) = pop2_with_bitcast(state, input_ty, builder);
+            let a = widen(builder, a);
+            let b = widen(builder, b);
+            state.push1(builder.ins().imul(a, b))
         }
         Operator::ReturnCall { .. } | Operator::ReturnCallIndirect { .. } => {
             return Err(wasm_unsupported!("proposed tail-call operator {:?}", op));
@@ -2164,18 +2201,33 @@ fn fold_atomic_mem_addr(
 ) -> Value {
     let access_ty_bytes = access_ty.bytes();
     let final_lma = if memarg.offset > 0 {
-        assert!(builder.func.dfg.value_type(linear_mem_addr) == I32);
-        let linear_mem_addr = builder.ins().uextend(I64, linear_mem_addr);
-        let a = builder
-            .ins()
-            .iadd_imm(linear_mem_addr, i64::from(memarg.offset));
-        let cflags = builder.ins().ifcmp_imm(a, 0x1_0000_0000i64);
-        builder.ins().trapif(
-            IntCC::UnsignedGreaterThanOrEqual,
-            cflags,
-            ir::TrapCode::HeapOutOfBounds,
-        );
-        builder.ins().ireduce(I32, a)
+        if builder.func.dfg.value_type(linear_mem_addr) == I64 {
+            // Addresses into 64-bit memories can't be widened, so check the
+            // addition for overflow instead.
+            let a = builder
+                .ins()
+                .iadd_imm(linear_mem_addr, i64::from(memarg.offset));
+            let overflow = builder
+                .ins()
+                .icmp(IntCC::UnsignedLessThan, a, linear_mem_addr);
+            builder
+                .ins()
+                .trapnz(overflow, ir::TrapCode::HeapOutOfBounds);
+            a
+        } else {
+            assert!(builder.func.dfg.value_type(linear_mem_addr) == I32);
+            let linear_mem_addr = builder.ins().uextend(I64, linear_mem_addr);
+            let a = builder
+                .ins()
+                .iadd_imm(linear_mem_addr, i64::from(memarg.offset));
+            let cflags = builder.ins().ifcmp_imm(a, 0x1_0000_0000i64);
+            builder.ins().trapif(
+                IntCC::UnsignedGreaterThanOrEqual,
+                cflags,
+                ir::TrapCode::HeapOutOfBounds,
+            );
+            builder.ins().ireduce(I32, a)
+        }
     } else {
         linear_mem_addr
     };
@@ -2202,13 +2254,14 @@ fn finalise_atomic_mem_addr<FE: FuncEnvironment + ?Sized>(
     state: &mut FuncTranslationState,
     environ: &mut FE,
 ) -> WasmResult<Value> {
-    // Check the alignment of `linear_mem_addr`.
+    // Check the alignment of `linear_mem_addr`. Only the low bits of the sum
+    // matter here, so it doesn't matter if adding the offset wraps around.
     let access_ty_bytes = access_ty.bytes();
-    let final_lma = builder
-        .ins()
-        .iadd_imm(linear_mem_addr, i64::from(memarg.offset));
     if access_ty_bytes != 1 {
         assert!(access_ty_bytes == 2 || access_ty_bytes == 4 || access_ty_bytes == 8);
+        let final_lma = builder
+            .ins()
+            .iadd_imm(linear_mem_addr, i64::from(memarg.offset));
         let final_lma_misalignment = builder
             .ins()
             .band_imm(final_lma, i64::from(access_ty_bytes - 1));
@@ -2220,13 +2273,15 @@ fn finalise_atomic_mem_addr<FE: FuncEnvironment + ?Sized>(
             .trapif(IntCC::NotEqual, f, ir::TrapCode::HeapMisaligned);
     }
 
-    // Compute the final effective address.
+    // Compute the final effective address. The offset is folded into the
+    // bounds check, the same way as for ordinary loads and stores, so that
+    // an address plus offset which wraps around can't pass the check.
     let heap = state.get_heap(builder.func, memarg.memory, environ)?;
     let (base, offset) = get_heap_addr(
         heap,
-        final_lma,
-        /*offset=*/ 0,
-        access_ty.bytes(),
+        linear_mem_addr,
+        memarg.offset,
+        access_ty_bytes,
         environ.pointer_type(),
         builder,
     );
@@ -2457,15 +2512,35 @@ fn translate_vector_fcmp(
 
 fn translate_br_if(
     relative_depth: u32,
+    hint: Option<bool>,
     builder: &mut FunctionBuilder,
     state: &mut FuncTranslationState,
 ) {
     let val = state.pop1();
     let (br_destination, inputs) = translate_br_if_args(relative_depth, state);
-    canonicalise_then_brnz(builder, val, br_destination, inputs);
-
     let next_block = builder.create_block();
-    canonicalise_then_jump(builder, next_block, &[]);
+
+    match hint {
+        Some(false) => {
+            // The destination may also be reached from hot code, so route the
+            // unlikely branch through a cold block of its own.
+            let cold_block = builder.create_block();
+            builder.func.layout.set_cold(cold_block);
+            canonicalise_then_brnz(builder, val, cold_block, &[]);
+            canonicalise_then_jump(builder, next_block, &[]);
+            builder.seal_block(cold_block); // The only predecessor is the current block.
+            builder.switch_to_block(cold_block);
+            canonicalise_then_jump(builder, br_destination, inputs);
+        }
+        _ => {
+            if hint == Some(true) {
+                builder.func.layout.set_cold(next_block);
+            }
+            canonicalise_then_brnz(builder, val, br_destination, inputs);
+            canonicalise_then_jump(builder, next_block, &[]);
+        }
+    }
+
     builder.seal_block(next_block); // The only predecessor is the current block.
     builder.switch_to_block(next_block);
 }
@@ -287,7 +287,7 @@ impl<'dummy_environment> FuncEnvironment for DummyFuncEnvironment<'dummy_environ
         })
     }
 
-    fn make_heap(&mut self, func: &mut ir::Function, _index: MemoryIndex) -> WasmResult<ir::Heap> {
+    fn make_heap(&mut self, func: &mut ir::Function, index: MemoryIndex) -> WasmResult<ir::Heap> {
         // Create a static heap whose base address is stored at `vmctx+0`.
         let addr = func.create_global_value(ir::GlobalValueData::VMContext);
         let gv = func.create_global_value(ir::GlobalValueData::Load {
@@ -304,7 +304,11 @@ impl<'dummy_environment> FuncEnvironment for DummyFuncEnvironment<'dummy_environ
             style: ir::HeapStyle::Static {
                 bound: 0x1_0000_0000.into(),
             },
-            index_type: I32,
+            index_type: if self.mod_info.memories[index].entity.memory64 {
+                I64
+            } else {
+                I32
+            },
         }))
     }
 
@@ -463,19 +467,21 @@ impl<'dummy_environment> FuncEnvironment for DummyFuncEnvironment<'dummy_environ
         &mut self,
         mut pos: FuncCursor,
         _index: MemoryIndex,
-        _heap: ir::Heap,
+        heap: ir::Heap,
         _val: ir::Value,
     ) -> WasmResult<ir::Value> {
-        Ok(pos.ins().iconst(I32, -1))
+        let index_type = pos.func.heaps[heap].index_type;
+        Ok(pos.ins().iconst(index_type, -1))
     }
 
     fn translate_memory_size(
         &mut self,
         mut pos: FuncCursor,
         _index: MemoryIndex,
-        _heap: ir::Heap,
+        heap: ir::Heap,
     ) -> WasmResult<ir::Value> {
-        Ok(pos.ins().iconst(I32, -1))
+        let index_type = pos.func.heaps[heap].index_type;
+        Ok(pos.ins().iconst(index_type, -1))
     }
 
     fn translate_memory_copy(
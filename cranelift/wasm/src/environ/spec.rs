@@ -633,6 +633,18 @@ pub trait FuncEnvironment: TargetEnvironment {
         Ok(())
     }
 
+    /// Returns the branch hint, as given by the branch-hinting proposal's
+    /// `metadata.code.branch_hint` custom section, for the `if` or `br_if`
+    /// operator at source location `srcloc`: `Some(true)` if the branch is
+    /// likely taken, `Some(false)` if it is not, and `None` if there is no
+    /// hint.
+    ///
+    /// Blocks on the unlikely side of a hinted branch are marked cold, so
+    /// that backends can move them out of the hot path.
+    fn branch_hint(&self, _srcloc: ir::SourceLoc) -> Option<bool> {
+        None
+    }
+
     /// Optional callback for the `FunctionEnvironment` performing this translation to maintain
     /// internal state or prepare custom state for the operator to translate
     fn before_translate_operator(
@@ -655,6 +667,13 @@ pub trait FuncEnvironment: TargetEnvironment {
         Ok(())
     }
 
+    /// Optional callback invoked once all of a function's locals, including its parameters, have
+    /// been declared.
+    ///
+    /// Locals are declared as the variables `0..num_locals_defined`, so an environment which needs
+    /// variables of its own can allocate them starting at `num_locals_defined`.
+    fn after_locals(&mut self, _num_locals_defined: usize) {}
+
     /// Optional callback for the `FunctionEnvironment` performing this translation to perform work
     /// before the function body is translated.
     fn before_translate_function(
@@ -45,7 +45,7 @@ fn entity_type(
         ImportSectionEntryType::Instance(sig) => {
             EntityType::Instance(environ.type_to_instance_type(TypeIndex::from_u32(sig))?)
         }
-        ImportSectionEntryType::Memory(ty) => EntityType::Memory(memory(ty)),
+        ImportSectionEntryType::Memory(ty) => EntityType::Memory(memory(ty)?),
         ImportSectionEntryType::Event(evt) => EntityType::Event(event(evt)),
         ImportSectionEntryType::Global(ty) => {
             EntityType::Global(global(ty, environ, GlobalInit::Import)?)
@@ -54,15 +54,28 @@ fn entity_type(
     })
 }
 
-fn memory(ty: MemoryType) -> Memory {
+fn memory(ty: MemoryType) -> WasmResult<Memory> {
     match ty {
-        MemoryType::M32 { limits, shared } => Memory {
+        MemoryType::M32 { limits, shared } => Ok(Memory {
             minimum: limits.initial,
             maximum: limits.maximum,
             shared: shared,
-        },
-        // FIXME(#2361)
-        MemoryType::M64 { .. } => unimplemented!(),
+            memory64: false,
+        }),
+        // 64-bit memories are indexed with 64-bit addresses, but their sizes
+        // are still limited to what a 32-bit page count can describe.
+        MemoryType::M64 { limits, shared } => {
+            let pages = |n: u64| {
+                u32::try_from(n)
+                    .map_err(|_| wasm_unsupported!("64-bit memory with more than 2^32 pages"))
+            };
+            Ok(Memory {
+                minimum: pages(limits.initial)?,
+                maximum: limits.maximum.map(pages).transpose()?,
+                shared: shared,
+                memory64: true,
+            })
+        }
     }
 }
 
@@ -185,7 +198,7 @@ pub fn parse_import_section<'data>(
                 )?;
             }
             ImportSectionEntryType::Memory(ty) => {
-                environ.declare_memory_import(memory(ty), import.module, import.field)?;
+                environ.declare_memory_import(memory(ty)?, import.module, import.field)?;
             }
             ImportSectionEntryType::Event(e) => {
                 environ.declare_event_import(event(e), import.module, import.field)?;
@@ -249,7 +262,7 @@ pub fn parse_memory_section(
     environ.reserve_memories(memories.get_count())?;
 
     for entry in memories {
-        let memory = memory(entry?);
+        let memory = memory(entry?)?;
         environ.declare_memory(memory)?;
     }
 
@@ -437,6 +450,11 @@ pub fn parse_data_section<'data>(
                 let mut init_expr_reader = init_expr.get_binary_reader();
                 let (base, offset) = match init_expr_reader.read_operator()? {
                     Operator::I32Const { value } => (None, value as u32 as usize),
+                    // Offsets into 64-bit memories which don't fit in a
+                    // `usize` are always out of bounds, so saturate them.
+                    Operator::I64Const { value } => {
+                        (None, usize::try_from(value as u64).unwrap_or(usize::MAX))
+                    }
                     Operator::GlobalGet { global_index } => {
                         (Some(GlobalIndex::from_u32(global_index)), 0)
                     }
@@ -230,6 +230,9 @@ pub struct Memory {
     pub maximum: Option<u32>,
     /// Whether the memory may be shared between multiple threads.
     pub shared: bool,
+    /// Whether the memory is indexed with 64-bit addresses, as introduced by
+    /// the memory64 proposal.
+    pub memory64: bool,
 }
 
 /// WebAssembly event.
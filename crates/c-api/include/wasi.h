@@ -57,6 +57,14 @@ WASI_API_EXTERN own wasi_config_t* wasi_config_new();
  */
 WASI_API_EXTERN void wasi_config_set_argv(wasi_config_t* config, int argc, const char* argv[]);
 
+/**
+ * \brief Appends an argument to the argv list for this configuration object.
+ *
+ * The argument is copied into the `config` object as part of this function
+ * call, so the `arg` pointer only needs to stay alive for this function call.
+ */
+WASI_API_EXTERN void wasi_config_push_arg(wasi_config_t* config, const char* arg);
+
 /**
  * \brief Indicates that the argv list should be inherited from this process's
  * argv list.
@@ -77,6 +85,15 @@ WASI_API_EXTERN void wasi_config_inherit_argv(wasi_config_t* config);
  */
 WASI_API_EXTERN void wasi_config_set_env(wasi_config_t* config, int envc, const char* names[], const char* values[]);
 
+/**
+ * \brief Adds an environment variable to the WASI instance's environment.
+ *
+ * The name and value are copied into the `config` object as part of this
+ * function call, so the pointers only need to stay alive for this function
+ * call.
+ */
+WASI_API_EXTERN void wasi_config_push_env(wasi_config_t* config, const char* name, const char* value);
+
 /**
  * \brief Indicates that the entire environment of the calling process should be
  * inherited by this WASI configuration.
@@ -94,6 +111,14 @@ WASI_API_EXTERN void wasi_config_inherit_env(wasi_config_t* config);
  */
 WASI_API_EXTERN bool wasi_config_set_stdin_file(wasi_config_t* config, const char* path);
 
+/**
+ * \brief Configures standard input to read the specified bytes.
+ *
+ * This function takes ownership of the `binary` argument, whose contents are
+ * read by the WASI program as stdin, followed by end-of-file.
+ */
+WASI_API_EXTERN void wasi_config_set_stdin_bytes(wasi_config_t* config, own wasm_byte_vec_t* binary);
+
 /**
  * \brief Configures this process's own stdin stream to be used as stdin for
  * this WASI configuration.
@@ -111,6 +136,13 @@ WASI_API_EXTERN void wasi_config_inherit_stdin(wasi_config_t* config);
  */
 WASI_API_EXTERN bool wasi_config_set_stdout_file(wasi_config_t* config, const char* path);
 
+/**
+ * \brief Configures standard output to be captured in an in-memory buffer.
+ *
+ * The output can be retrieved with #wasi_instance_stdout_buffer.
+ */
+WASI_API_EXTERN void wasi_config_set_stdout_buffer(wasi_config_t* config);
+
 /**
  * \brief Configures this process's own stdout stream to be used as stdout for
  * this WASI configuration.
@@ -128,6 +160,13 @@ WASI_API_EXTERN void wasi_config_inherit_stdout(wasi_config_t* config);
  */
 WASI_API_EXTERN bool wasi_config_set_stderr_file(wasi_config_t* config, const char* path);
 
+/**
+ * \brief Configures standard error to be captured in an in-memory buffer.
+ *
+ * The output can be retrieved with #wasi_instance_stderr_buffer.
+ */
+WASI_API_EXTERN void wasi_config_set_stderr_buffer(wasi_config_t* config);
+
 /**
  * \brief Configures this process's own stderr stream to be used as stderr for
  * this WASI configuration.
@@ -146,6 +185,27 @@ WASI_API_EXTERN void wasi_config_inherit_stderr(wasi_config_t* config);
  */
 WASI_API_EXTERN bool wasi_config_preopen_dir(wasi_config_t* config, const char* path, const char* guest_path);
 
+/**
+ * \brief Configures a "preopened directory" with restricted rights.
+ *
+ * This is like #wasi_config_preopen_dir, except that the directory only has
+ * the rights in `rights_base`, and files and directories opened through it
+ * only have the rights in `rights_inheriting`. The rights are bitmasks of the
+ * WASI `rights` type, such as `__WASI_RIGHTS_FD_READ`; for example, leaving
+ * out the rights to write and create files makes the directory read-only.
+ * Bits that don't correspond to a right are ignored, so `UINT64_MAX` grants
+ * every right.
+ *
+ * Returns `false` if the directory can't be opened.
+ */
+WASI_API_EXTERN bool wasi_config_preopen_dir_with_rights(
+  wasi_config_t* config,
+  const char* path,
+  const char* guest_path,
+  uint64_t rights_base,
+  uint64_t rights_inheriting
+);
+
 /**
  * \typedef wasi_instance_t
  * \brief Convenience alias for #wasi_instance_t
@@ -163,7 +223,8 @@ WASI_DECLARE_OWN(instance)
  *
  * \param store the store which functions will be attached to
  * \param name the WASI module name that is being instantiated, currently either
- * `wasi_unstable` or `wasi_snapshot_preview`.
+ * `wasi_unstable` or `wasi_snapshot_preview1`, which selects the version of WASI
+ * that the instance provides.
  * \param config the configuration object which has settings for how WASI APIs
  * will behave.
  * \param trap a location, if `NULL` is returned, that contains information
@@ -204,6 +265,32 @@ WASI_API_EXTERN const wasm_extern_t* wasi_instance_bind_import(
   const wasm_importtype_t* import
 );
 
+/**
+ * \brief Copies the output captured in the stdout buffer of a #wasi_instance_t.
+ *
+ * \param instance the WASI instance to read the output of
+ * \param out where to store the output, owned by the caller afterwards
+ *
+ * \return `true` if the instance's configuration used
+ * #wasi_config_set_stdout_buffer, in which case the output so far is stored in
+ * `out`, or `false` otherwise.
+ */
+WASI_API_EXTERN bool wasi_instance_stdout_buffer(
+  const wasi_instance_t* instance,
+  own wasm_byte_vec_t* out
+);
+
+/**
+ * \brief Copies the output captured in the stderr buffer of a #wasi_instance_t.
+ *
+ * This is like #wasi_instance_stdout_buffer, for a configuration that used
+ * #wasi_config_set_stderr_buffer.
+ */
+WASI_API_EXTERN bool wasi_instance_stderr_buffer(
+  const wasi_instance_t* instance,
+  own wasm_byte_vec_t* out
+);
+
 #undef own
 
 #ifdef __cplusplus
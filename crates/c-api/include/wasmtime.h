@@ -102,6 +102,9 @@ enum wasmtime_opt_level_enum { // OptLevel
   /// Generated code will be optimized, but some speed optimizations are
   /// disabled if they cause the generated code to be significantly larger.
   WASMTIME_OPT_LEVEL_SPEED_AND_SIZE,
+  /// Generated code will be optimized for size, skipping optimizations which
+  /// tend to make the generated code larger.
+  WASMTIME_OPT_LEVEL_SIZE,
 };
 
 /**
@@ -152,6 +155,16 @@ WASMTIME_CONFIG_PROP(void, debug_info, bool)
  */
 WASMTIME_CONFIG_PROP(void, interruptable, bool)
 
+/**
+ * \brief Whether or not fuel is enabled for generated code.
+ *
+ * This setting is `false` by default. When enabled it will enable fuel counting
+ * meaning that fuel will be consumed every time a wasm instruction is executed,
+ * and trap when reaching zero. Fuel is added to a store with
+ * #wasmtime_store_add_fuel, and stores start with no fuel.
+ */
+WASMTIME_CONFIG_PROP(void, consume_fuel, bool)
+
 /**
  * \brief Configures the maximum stack size, in bytes, that JIT code can use.
  *
@@ -318,6 +331,35 @@ WASM_API_EXTERN own wasmtime_error_t* wasmtime_wat2wasm(
  */
 WASM_API_EXTERN void wasmtime_store_gc(wasm_store_t* store);
 
+/**
+ * \brief Adds fuel to this store for wasm to consume while executing.
+ *
+ * For this function to work fuel consumption must be enabled via
+ * #wasmtime_config_consume_fuel_set. By default a store starts with 0 fuel
+ * for wasm to execute with (meaning it will immediately trap). This function
+ * must be called for the store to have some fuel to allow WebAssembly to
+ * execute.
+ *
+ * Note that at this time when fuel is entirely consumed it will cause
+ * wasm to trap. More usages of fuel are planned for the future.
+ *
+ * If fuel is not enabled within this store then an error is returned. If fuel
+ * is successfully added then NULL is returned.
+ */
+WASM_API_EXTERN own wasmtime_error_t *wasmtime_store_add_fuel(wasm_store_t *store, uint64_t fuel);
+
+/**
+ * \brief Returns the amount of fuel consumed by this store's execution so far.
+ *
+ * If fuel consumption is not enabled via #wasmtime_config_consume_fuel_set
+ * then this function will return false. Otherwise true is returned and the
+ * fuel parameter is filled in with fuel consumed so far.
+ *
+ * Also note that fuel, if enabled, must be originally configured via
+ * #wasmtime_store_add_fuel.
+ */
+WASM_API_EXTERN bool wasmtime_store_fuel_consumed(wasm_store_t *store, uint64_t *fuel);
+
 /**
  * \typedef wasmtime_linker_t
  * \brief Convenience alias for #wasmtime_linker_t
@@ -876,6 +918,57 @@ WASM_API_EXTERN wasmtime_error_t *wasmtime_funcref_table_grow(
     wasm_table_size_t *prev_size
 );
 
+/**
+ * \brief Grows a memory by `delta` pages.
+ *
+ * This function is the same as #wasm_memory_grow, except that in the case of an
+ * error a #wasmtime_error_t is returned, and on success the previous size of the
+ * memory, in pages, is written to `prev_size` if it isn't `NULL`.
+ *
+ * Growing a memory may move it, invalidating pointers previously returned by
+ * #wasm_memory_data.
+ */
+WASM_API_EXTERN own wasmtime_error_t *wasmtime_memory_grow(
+    wasm_memory_t *memory,
+    wasm_memory_pages_t delta,
+    wasm_memory_pages_t *prev_size
+);
+
+/**
+ * \brief Copies `len` bytes of a memory starting at `offset` into `buffer`.
+ *
+ * Unlike reading through the pointer returned by #wasm_memory_data, the access
+ * is bounds-checked, and isn't affected by the memory moving when it grows. An
+ * error is returned if `offset + len` is beyond the end of the memory, in which
+ * case `buffer` isn't modified.
+ *
+ * This function does not take ownership of its arguments, but the returned
+ * error is owned by the caller.
+ */
+WASM_API_EXTERN own wasmtime_error_t *wasmtime_memory_read(
+    const wasm_memory_t *memory,
+    size_t offset,
+    uint8_t *buffer,
+    size_t len
+);
+
+/**
+ * \brief Copies `len` bytes from `buffer` into a memory starting at `offset`.
+ *
+ * This is the counterpart of #wasmtime_memory_read. An error is returned if
+ * `offset + len` is beyond the end of the memory, in which case the memory isn't
+ * modified.
+ *
+ * This function does not take ownership of its arguments, but the returned
+ * error is owned by the caller.
+ */
+WASM_API_EXTERN own wasmtime_error_t *wasmtime_memory_write(
+    wasm_memory_t *memory,
+    size_t offset,
+    const uint8_t *buffer,
+    size_t len
+);
+
 /**
  * \brief Create a new `externref` value.
  *
@@ -969,6 +1062,54 @@ WASM_API_EXTERN own wasmtime_error_t *wasmtime_module_deserialize(
     own wasm_module_t **ret
 );
 
+/**
+ * \brief Checks whether serialized module data is compatible with an engine.
+ *
+ * \param engine the engine the data would be deserialized with
+ * \param serialized the output of #wasmtime_module_serialize or
+ *   #wasmtime_engine_precompile
+ * \param ret filled in with whether #wasmtime_module_deserialize can load the
+ *   data with `engine`, if no error is returned
+ *
+ * Only a small header of `serialized` is read, so this is a cheap check to
+ * make before deserializing, for example to discard stale entries of an
+ * ahead-of-time compilation cache. An error is returned if `serialized` is too
+ * short to be serialized module data.
+ *
+ * This function does not take ownership of any of its arguments, but the
+ * returned error is owned by the caller.
+ */
+WASM_API_EXTERN own wasmtime_error_t *wasmtime_module_is_compatible(
+    wasm_engine_t *engine,
+    const wasm_byte_vec_t *serialized,
+    bool *ret
+);
+
+/**
+ * \brief Compiles a WebAssembly binary ahead of time.
+ *
+ * \param engine the engine whose configuration the binary is compiled with
+ * \param binary the WebAssembly binary, or text if the `wat` feature is enabled
+ * \param ret if compilation succeeds, this byte vector is filled in with the
+ *   compiled module, which can be loaded with #wasmtime_module_deserialize
+ *
+ * \return a non-null error if compilation fails, or `NULL`. If compilation
+ * fails then `ret` isn't touched.
+ *
+ * Unlike #wasmtime_module_new followed by #wasmtime_module_serialize, the
+ * compiled code is never loaded into executable memory, so this also works for
+ * engines configured to compile for another platform.
+ *
+ * This function does not take ownership of `engine` or `binary`, and the
+ * caller is expected to deallocate the returned #wasmtime_error_t and
+ * #wasm_byte_vec_t.
+ */
+WASM_API_EXTERN own wasmtime_error_t *wasmtime_engine_precompile(
+    wasm_engine_t *engine,
+    const wasm_byte_vec_t *binary,
+    own wasm_byte_vec_t *ret
+);
+
 /**
  * \struct wasm_instancetype_t
  * \brief An opaque object representing the type of a function.
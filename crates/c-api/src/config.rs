@@ -29,6 +29,7 @@ pub enum wasmtime_opt_level_t {
     WASMTIME_OPT_LEVEL_NONE,
     WASMTIME_OPT_LEVEL_SPEED,
     WASMTIME_OPT_LEVEL_SPEED_AND_SIZE,
+    WASMTIME_OPT_LEVEL_SIZE,
 }
 
 #[repr(u8)]
@@ -55,6 +56,11 @@ pub extern "C" fn wasmtime_config_interruptable_set(c: &mut wasm_config_t, enabl
     c.config.interruptable(enable);
 }
 
+#[no_mangle]
+pub extern "C" fn wasmtime_config_consume_fuel_set(c: &mut wasm_config_t, enable: bool) {
+    c.config.consume_fuel(enable);
+}
+
 #[no_mangle]
 pub extern "C" fn wasmtime_config_max_wasm_stack_set(c: &mut wasm_config_t, size: usize) {
     c.config.max_wasm_stack(size);
@@ -122,6 +128,7 @@ pub extern "C" fn wasmtime_config_cranelift_opt_level_set(
         WASMTIME_OPT_LEVEL_NONE => OptLevel::None,
         WASMTIME_OPT_LEVEL_SPEED => OptLevel::Speed,
         WASMTIME_OPT_LEVEL_SPEED_AND_SIZE => OptLevel::SpeedAndSize,
+        WASMTIME_OPT_LEVEL_SIZE => OptLevel::Size,
     });
 }
 
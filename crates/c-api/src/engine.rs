@@ -1,4 +1,4 @@
-use crate::wasm_config_t;
+use crate::{handle_result, wasm_byte_vec_t, wasm_config_t, wasmtime_error_t};
 use wasmtime::Engine;
 
 #[repr(C)]
@@ -33,3 +33,14 @@ pub extern "C" fn wasm_engine_new_with_config(c: Box<wasm_config_t>) -> Box<wasm
         engine: Engine::new(&config),
     })
 }
+
+#[no_mangle]
+pub extern "C" fn wasmtime_engine_precompile(
+    engine: &wasm_engine_t,
+    binary: &wasm_byte_vec_t,
+    ret: &mut wasm_byte_vec_t,
+) -> Option<Box<wasmtime_error_t>> {
+    handle_result(engine.engine.precompile_module(binary.as_slice()), |buf| {
+        ret.set_buffer(buf);
+    })
+}
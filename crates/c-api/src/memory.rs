@@ -1,4 +1,5 @@
-use crate::{wasm_extern_t, wasm_memorytype_t, wasm_store_t};
+use crate::{handle_result, wasm_extern_t, wasm_memorytype_t, wasm_store_t, wasmtime_error_t};
+use std::slice;
 use wasmtime::{Extern, Memory};
 
 #[derive(Clone)]
@@ -70,3 +71,46 @@ pub extern "C" fn wasm_memory_size(m: &wasm_memory_t) -> wasm_memory_pages_t {
 pub extern "C" fn wasm_memory_grow(m: &wasm_memory_t, delta: wasm_memory_pages_t) -> bool {
     m.memory().grow(delta).is_ok()
 }
+
+#[no_mangle]
+pub extern "C" fn wasmtime_memory_grow(
+    m: &wasm_memory_t,
+    delta: wasm_memory_pages_t,
+    prev_size: Option<&mut wasm_memory_pages_t>,
+) -> Option<Box<wasmtime_error_t>> {
+    handle_result(m.memory().grow(delta), |prev| {
+        if let Some(ptr) = prev_size {
+            *ptr = prev;
+        }
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn wasmtime_memory_read(
+    m: &wasm_memory_t,
+    offset: usize,
+    buffer: *mut u8,
+    len: usize,
+) -> Option<Box<wasmtime_error_t>> {
+    let buffer: &mut [u8] = if len == 0 {
+        &mut []
+    } else {
+        slice::from_raw_parts_mut(buffer, len)
+    };
+    handle_result(m.memory().read(offset, buffer), |()| {})
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn wasmtime_memory_write(
+    m: &wasm_memory_t,
+    offset: usize,
+    buffer: *const u8,
+    len: usize,
+) -> Option<Box<wasmtime_error_t>> {
+    let buffer: &[u8] = if len == 0 {
+        &[]
+    } else {
+        slice::from_raw_parts(buffer, len)
+    };
+    handle_result(m.memory().write(offset, buffer), |()| {})
+}
@@ -194,6 +194,18 @@ pub extern "C" fn wasmtime_module_deserialize(
     )
 }
 
+#[no_mangle]
+pub extern "C" fn wasmtime_module_is_compatible(
+    engine: &wasm_engine_t,
+    serialized: &wasm_byte_vec_t,
+    ret: &mut bool,
+) -> Option<Box<wasmtime_error_t>> {
+    handle_result(
+        Module::is_compatible(&engine.engine, serialized.as_slice()),
+        |compatible| *ret = compatible,
+    )
+}
+
 #[no_mangle]
 pub extern "C" fn wasm_module_type(f: &wasm_module_t) -> Box<wasm_moduletype_t> {
     Box::new(wasm_moduletype_t::new(f.module().ty()))
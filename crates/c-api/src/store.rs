@@ -1,4 +1,4 @@
-use crate::wasm_engine_t;
+use crate::{handle_result, wasm_engine_t, wasmtime_error_t};
 use wasmtime::{InterruptHandle, Store};
 
 #[repr(C)]
@@ -22,6 +22,25 @@ pub extern "C" fn wasmtime_store_gc(store: &wasm_store_t) {
     store.store.gc();
 }
 
+#[no_mangle]
+pub extern "C" fn wasmtime_store_add_fuel(
+    store: &wasm_store_t,
+    fuel: u64,
+) -> Option<Box<wasmtime_error_t>> {
+    handle_result(store.store.add_fuel(fuel), |()| {})
+}
+
+#[no_mangle]
+pub extern "C" fn wasmtime_store_fuel_consumed(store: &wasm_store_t, fuel: &mut u64) -> bool {
+    match store.store.fuel_consumed() {
+        Some(amt) => {
+            *fuel = amt;
+            true
+        }
+        None => false,
+    }
+}
+
 #[repr(C)]
 pub struct wasmtime_interrupt_handle_t {
     handle: InterruptHandle,
@@ -1,14 +1,19 @@
 //! The WASI embedding API definitions for Wasmtime.
-use crate::{wasm_extern_t, wasm_importtype_t, wasm_store_t, wasm_trap_t};
+use crate::{wasm_byte_vec_t, wasm_extern_t, wasm_importtype_t, wasm_store_t, wasm_trap_t};
 use anyhow::Result;
 use std::collections::HashMap;
+use std::convert::TryFrom;
 use std::ffi::CStr;
 use std::fs::File;
+use std::io::Cursor;
 use std::os::raw::{c_char, c_int};
 use std::path::{Path, PathBuf};
 use std::slice;
 use std::str;
-use wasi_common::{preopen_dir, WasiCtx, WasiCtxBuilder};
+use std::sync::{Arc, RwLock};
+use wasi_common::virtfs::pipe::{ReadPipe, WritePipe};
+use wasi_common::wasi::types::Rights;
+use wasi_common::{preopen_dir, HandleRights, WasiCtx, WasiCtxBuilder};
 use wasmtime::{Extern, Linker, Trap};
 use wasmtime_wasi::{old::snapshot_0::Wasi as WasiSnapshot0, Wasi as WasiPreview1};
 
@@ -24,6 +29,9 @@ unsafe fn create_file(path: *const c_char) -> Option<File> {
     File::create(cstr_to_path(path)?).ok()
 }
 
+/// An in-memory buffer that stdout or stderr is captured in.
+type OutputBuffer = Arc<RwLock<Cursor<Vec<u8>>>>;
+
 pub enum WasiModule {
     Snapshot0(WasiSnapshot0),
     Preview1(WasiPreview1),
@@ -37,9 +45,12 @@ pub struct wasi_config_t {
     args: Vec<Vec<u8>>,
     env: Vec<(Vec<u8>, Vec<u8>)>,
     stdin: Option<File>,
+    stdin_bytes: Option<Vec<u8>>,
     stdout: Option<File>,
+    stdout_buffer: Option<OutputBuffer>,
     stderr: Option<File>,
-    preopens: Vec<(File, PathBuf)>,
+    stderr_buffer: Option<OutputBuffer>,
+    preopens: Vec<(File, PathBuf, HandleRights)>,
     inherit_args: bool,
     inherit_env: bool,
     inherit_stdin: bool,
@@ -68,6 +79,12 @@ pub unsafe extern "C" fn wasi_config_set_argv(
     config.inherit_args = false;
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn wasi_config_push_arg(config: &mut wasi_config_t, arg: *const c_char) {
+    config.args.push(CStr::from_ptr(arg).to_bytes().to_owned());
+    config.inherit_args = false;
+}
+
 #[no_mangle]
 pub extern "C" fn wasi_config_inherit_argv(config: &mut wasi_config_t) {
     config.args.clear();
@@ -96,6 +113,19 @@ pub unsafe extern "C" fn wasi_config_set_env(
     config.inherit_env = false;
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn wasi_config_push_env(
+    config: &mut wasi_config_t,
+    name: *const c_char,
+    value: *const c_char,
+) {
+    config.env.push((
+        CStr::from_ptr(name).to_bytes().to_owned(),
+        CStr::from_ptr(value).to_bytes().to_owned(),
+    ));
+    config.inherit_env = false;
+}
+
 #[no_mangle]
 pub extern "C" fn wasi_config_inherit_env(config: &mut wasi_config_t) {
     config.env.clear();
@@ -113,14 +143,26 @@ pub unsafe extern "C" fn wasi_config_set_stdin_file(
     };
 
     config.stdin = Some(file);
+    config.stdin_bytes = None;
     config.inherit_stdin = false;
 
     true
 }
 
+#[no_mangle]
+pub extern "C" fn wasi_config_set_stdin_bytes(
+    config: &mut wasi_config_t,
+    binary: &mut wasm_byte_vec_t,
+) {
+    config.stdin = None;
+    config.stdin_bytes = Some(binary.take());
+    config.inherit_stdin = false;
+}
+
 #[no_mangle]
 pub extern "C" fn wasi_config_inherit_stdin(config: &mut wasi_config_t) {
     config.stdin = None;
+    config.stdin_bytes = None;
     config.inherit_stdin = true;
 }
 
@@ -135,14 +177,23 @@ pub unsafe extern "C" fn wasi_config_set_stdout_file(
     };
 
     config.stdout = Some(file);
+    config.stdout_buffer = None;
     config.inherit_stdout = false;
 
     true
 }
 
+#[no_mangle]
+pub extern "C" fn wasi_config_set_stdout_buffer(config: &mut wasi_config_t) {
+    config.stdout = None;
+    config.stdout_buffer = Some(OutputBuffer::default());
+    config.inherit_stdout = false;
+}
+
 #[no_mangle]
 pub extern "C" fn wasi_config_inherit_stdout(config: &mut wasi_config_t) {
     config.stdout = None;
+    config.stdout_buffer = None;
     config.inherit_stdout = true;
 }
 
@@ -157,14 +208,23 @@ pub unsafe extern "C" fn wasi_config_set_stderr_file(
     };
 
     (*config).stderr = Some(file);
+    (*config).stderr_buffer = None;
     (*config).inherit_stderr = false;
 
     true
 }
 
+#[no_mangle]
+pub extern "C" fn wasi_config_set_stderr_buffer(config: &mut wasi_config_t) {
+    config.stderr = None;
+    config.stderr_buffer = Some(OutputBuffer::default());
+    config.inherit_stderr = false;
+}
+
 #[no_mangle]
 pub extern "C" fn wasi_config_inherit_stderr(config: &mut wasi_config_t) {
     config.stderr = None;
+    config.stderr_buffer = None;
     config.inherit_stderr = true;
 }
 
@@ -174,6 +234,28 @@ pub unsafe extern "C" fn wasi_config_preopen_dir(
     path: *const c_char,
     guest_path: *const c_char,
 ) -> bool {
+    wasi_config_preopen_dir_with_rights(config, path, guest_path, u64::MAX, u64::MAX)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn wasi_config_preopen_dir_with_rights(
+    config: &mut wasi_config_t,
+    path: *const c_char,
+    guest_path: *const c_char,
+    rights_base: u64,
+    rights_inheriting: u64,
+) -> bool {
+    // Bits for rights that don't exist are ignored, so that `UINT64_MAX`
+    // grants every right.
+    let all = u64::from(Rights::all());
+    let rights = match (
+        Rights::try_from(rights_base & all),
+        Rights::try_from(rights_inheriting & all),
+    ) {
+        (Ok(base), Ok(inheriting)) => HandleRights::new(base, inheriting),
+        _ => return false,
+    };
+
     let guest_path = match cstr_to_path(guest_path) {
         Some(p) => p,
         None => return false,
@@ -187,7 +269,9 @@ pub unsafe extern "C" fn wasi_config_preopen_dir(
         None => return false,
     };
 
-    (*config).preopens.push((dir, guest_path.to_owned()));
+    (*config)
+        .preopens
+        .push((dir, guest_path.to_owned(), rights));
 
     true
 }
@@ -198,7 +282,6 @@ enum WasiInstance {
 }
 
 fn create_wasi_ctx(config: wasi_config_t) -> Result<WasiCtx> {
-    use std::convert::TryFrom;
     use wasi_common::OsFile;
     let mut builder = WasiCtxBuilder::new();
     if config.inherit_args {
@@ -215,19 +298,25 @@ fn create_wasi_ctx(config: wasi_config_t) -> Result<WasiCtx> {
         builder.inherit_stdin();
     } else if let Some(file) = config.stdin {
         builder.stdin(OsFile::try_from(file)?);
+    } else if let Some(bytes) = config.stdin_bytes {
+        builder.stdin(ReadPipe::from(bytes));
     }
     if config.inherit_stdout {
         builder.inherit_stdout();
     } else if let Some(file) = config.stdout {
         builder.stdout(OsFile::try_from(file)?);
+    } else if let Some(buffer) = config.stdout_buffer {
+        builder.stdout(WritePipe::from_shared(buffer));
     }
     if config.inherit_stderr {
         builder.inherit_stderr();
     } else if let Some(file) = config.stderr {
         builder.stderr(OsFile::try_from(file)?);
+    } else if let Some(buffer) = config.stderr_buffer {
+        builder.stderr(WritePipe::from_shared(buffer));
     }
-    for preopen in config.preopens {
-        builder.preopened_dir(preopen.0, preopen.1);
+    for (dir, guest_path, rights) in config.preopens {
+        builder.preopened_dir_with_rights(dir, guest_path, rights);
     }
     Ok(builder.build()?)
 }
@@ -236,6 +325,8 @@ fn create_wasi_ctx(config: wasi_config_t) -> Result<WasiCtx> {
 pub struct wasi_instance_t {
     wasi: WasiInstance,
     export_cache: HashMap<String, Box<wasm_extern_t>>,
+    stdout_buffer: Option<OutputBuffer>,
+    stderr_buffer: Option<OutputBuffer>,
 }
 
 impl wasi_instance_t {
@@ -255,6 +346,8 @@ pub unsafe extern "C" fn wasi_instance_new(
     trap: &mut *mut wasm_trap_t,
 ) -> Option<Box<wasi_instance_t>> {
     let store = &store.store;
+    let stdout_buffer = config.stdout_buffer.clone();
+    let stderr_buffer = config.stderr_buffer.clone();
 
     let result = match CStr::from_ptr(name).to_str().unwrap_or("") {
         "wasi_snapshot_preview1" => {
@@ -269,6 +362,8 @@ pub unsafe extern "C" fn wasi_instance_new(
         Ok(wasi) => Some(Box::new(wasi_instance_t {
             wasi,
             export_cache: HashMap::new(),
+            stdout_buffer,
+            stderr_buffer,
         })),
         Err(e) => {
             *trap = Box::into_raw(Box::new(wasm_trap_t {
@@ -283,6 +378,32 @@ pub unsafe extern "C" fn wasi_instance_new(
 #[no_mangle]
 pub extern "C" fn wasi_instance_delete(_instance: Box<wasi_instance_t>) {}
 
+fn copy_output_buffer(buffer: &Option<OutputBuffer>, out: &mut wasm_byte_vec_t) -> bool {
+    match buffer {
+        Some(buffer) => {
+            out.set_buffer(buffer.read().unwrap().get_ref().clone());
+            true
+        }
+        None => false,
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn wasi_instance_stdout_buffer(
+    instance: &wasi_instance_t,
+    out: &mut wasm_byte_vec_t,
+) -> bool {
+    copy_output_buffer(&instance.stdout_buffer, out)
+}
+
+#[no_mangle]
+pub extern "C" fn wasi_instance_stderr_buffer(
+    instance: &wasi_instance_t,
+    out: &mut wasm_byte_vec_t,
+) -> bool {
+    copy_output_buffer(&instance.stderr_buffer, out)
+}
+
 #[no_mangle]
 pub extern "C" fn wasi_instance_bind_import<'a>(
     instance: &'a mut wasi_instance_t,
@@ -6,6 +6,7 @@ use std::hash::Hash;
 use std::hash::Hasher;
 use std::io::Write;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 #[macro_use] // for tests
 mod config;
@@ -14,12 +15,105 @@ mod worker;
 pub use config::{create_new_config, CacheConfig};
 use worker::Worker;
 
+/// A storage backend for the compiled-code cache.
+///
+/// By default the cache is stored in a directory on the local filesystem, as
+/// configured by [`CacheConfig`]. Embedders can implement this trait to back
+/// the cache with a different store instead, for example a key/value service
+/// shared by many machines.
+///
+/// Keys are opaque strings made of characters which are safe to use in file
+/// names and URLs. Values are compressed, serialized compilation artifacts.
+/// Failures of a store are never fatal: a failed `get` is treated as a cache
+/// miss and a failed `put` only means that the result isn't reused later.
+pub trait CacheStore: Send + Sync {
+    /// Returns the value previously stored under `key`, if any.
+    fn get(&self, key: &str) -> Option<Vec<u8>>;
+
+    /// Stores `value` under `key`, returning whether it was stored
+    /// successfully.
+    fn put(&self, key: &str, value: &[u8]) -> bool;
+}
+
+impl<T: CacheStore + ?Sized> CacheStore for &T {
+    fn get(&self, key: &str) -> Option<Vec<u8>> {
+        (**self).get(key)
+    }
+
+    fn put(&self, key: &str, value: &[u8]) -> bool {
+        (**self).put(key, value)
+    }
+}
+
+/// The default [`CacheStore`], which keeps entries as files in the directory
+/// configured by a [`CacheConfig`].
+///
+/// The cache worker is told about every access, so that it can recompress
+/// frequently used entries and clean up the directory.
+struct DirectoryStore {
+    root_path: PathBuf,
+    cache_config: CacheConfig,
+    /// Whether accesses count as the cache hits and misses reported by
+    /// `CacheConfig`, which are only about whole modules.
+    count_accesses: bool,
+}
+
+impl DirectoryStore {
+    fn new(kind: &str, compiler_name: &str, cache_config: &CacheConfig) -> Self {
+        let compiler_dir = compiler_dir(compiler_name);
+        let root_path = cache_config.directory().join(kind).join(compiler_dir);
+
+        Self {
+            root_path,
+            cache_config: cache_config.clone(),
+            count_accesses: kind == "modules",
+        }
+    }
+}
+
+impl CacheStore for DirectoryStore {
+    fn get(&self, key: &str) -> Option<Vec<u8>> {
+        let mod_cache_path = self.root_path.join(key);
+        trace!("get_data() for path: {}", mod_cache_path.display());
+        let bytes = fs::read(&mod_cache_path).ok()?;
+        // call on success
+        if self.count_accesses {
+            self.cache_config.on_cache_get_async(&mod_cache_path);
+        } else {
+            self.cache_config
+                .worker()
+                .on_cache_get_async(&mod_cache_path);
+        }
+        Some(bytes)
+    }
+
+    fn put(&self, key: &str, value: &[u8]) -> bool {
+        let mod_cache_path = self.root_path.join(key);
+        trace!("update_data() for path: {}", mod_cache_path.display());
+        if !write_cache_file(&mod_cache_path, value) {
+            return false;
+        }
+        // call on success
+        if self.count_accesses {
+            self.cache_config.on_cache_update_async(&mod_cache_path);
+        } else {
+            self.cache_config
+                .worker()
+                .on_cache_update_async(&mod_cache_path);
+        }
+        true
+    }
+}
+
 /// Module level cache entry.
 pub struct ModuleCacheEntry<'config>(Option<ModuleCacheEntryInner<'config>>);
 
 struct ModuleCacheEntryInner<'config> {
-    root_path: PathBuf,
-    cache_config: &'config CacheConfig,
+    /// Prepended to the hash of each entry to form its key.
+    key_prefix: String,
+    store: Box<dyn CacheStore + 'config>,
+    /// The zstd compression level of newly stored entries.
+    compression_level: i32,
 }
 
 struct Sha256Hasher(Sha256);
@@ -28,18 +122,25 @@ impl<'config> ModuleCacheEntry<'config> {
     /// Create the cache entry.
     pub fn new<'data>(compiler_name: &str, cache_config: &'config CacheConfig) -> Self {
         if cache_config.enabled() {
-            Self(Some(ModuleCacheEntryInner::new(
-                compiler_name,
-                cache_config,
-            )))
+            // The compiler has a directory of its own, so it's not part of
+            // the keys.
+            Self(Some(ModuleCacheEntryInner {
+                key_prefix: String::new(),
+                store: Box::new(DirectoryStore::new("modules", compiler_name, cache_config)),
+                compression_level: cache_config.baseline_compression_level(),
+            }))
         } else {
             Self(None)
         }
     }
 
-    #[cfg(test)]
-    fn from_inner(inner: ModuleCacheEntryInner<'config>) -> Self {
-        Self(Some(inner))
+    /// Create the cache entry backed by a custom [`CacheStore`].
+    pub fn from_store(compiler_name: &str, store: &'config dyn CacheStore) -> Self {
+        Self(Some(ModuleCacheEntryInner {
+            key_prefix: format!("{}-", compiler_dir(compiler_name)),
+            store: Box::new(store),
+            compression_level: STORE_COMPRESSION_LEVEL,
+        }))
     }
 
     /// Gets cached data if state matches, otherwise calls the `compute`.
@@ -48,131 +149,244 @@ impl<'config> ModuleCacheEntry<'config> {
         T: Hash,
         U: Serialize + for<'a> Deserialize<'a>,
     {
+        let inner = match &self.0 {
+            Some(inner) => inner,
+            None => return compute(state),
+        };
+
         let mut hasher = Sha256Hasher(Sha256::new());
         state.hash(&mut hasher);
         let hash: [u8; 32] = hasher.0.finalize().into();
         // standard encoding uses '/' which can't be used for filename
         let hash = base64::encode_config(&hash, base64::URL_SAFE_NO_PAD);
 
-        let inner = match &self.0 {
-            Some(inner) => inner,
-            None => return compute(state),
-        };
+        let key = format!("{}{}", inner.key_prefix, hash);
+        get_or_compute(&*inner.store, &key, inner.compression_level, || {
+            compute(state)
+        })
+    }
+}
 
-        if let Some(cached_val) = inner.get_data(&hash) {
-            let mod_cache_path = inner.root_path.join(&hash);
-            inner.cache_config.on_cache_get_async(&mod_cache_path); // call on success
-            return Ok(cached_val);
+/// Returns the value stored under `key` in `store`, or else calls `compute`
+/// and stores its result under `key`.
+fn get_or_compute<U, E>(
+    store: &dyn CacheStore,
+    key: &str,
+    compression_level: i32,
+    compute: impl FnOnce() -> Result<U, E>,
+) -> Result<U, E>
+where
+    U: Serialize + for<'a> Deserialize<'a>,
+{
+    trace!("get_data() for key: {}", key);
+    if let Some(cached_val) = store.get(key).and_then(|bytes| decode(&bytes)) {
+        return Ok(cached_val);
+    }
+    let val_to_cache = compute()?;
+    trace!("update_data() for key: {}", key);
+    if let Some(bytes) = encode(&val_to_cache, compression_level) {
+        if !store.put(key, &bytes) {
+            warn!("Failed to store cached code, key: {}", key);
         }
-        let val_to_cache = compute(state)?;
-        if inner.update_data(&hash, &val_to_cache).is_some() {
-            let mod_cache_path = inner.root_path.join(&hash);
-            inner.cache_config.on_cache_update_async(&mod_cache_path); // call on success
+    }
+    Ok(val_to_cache)
+}
+
+/// Cache of individually compiled functions.
+///
+/// Where [`ModuleCacheEntry`] caches the compilation of a whole module, this
+/// caches every function on its own, so recompiling a module in which only a
+/// few functions were edited reuses the code of all the others.
+pub struct FunctionCache {
+    /// Prepended to the hash of each function to form its key.
+    key_prefix: String,
+    store: Arc<dyn CacheStore>,
+    /// The zstd compression level of newly stored functions.
+    compression_level: i32,
+}
+
+/// Functions of a single module in a [`FunctionCache`].
+///
+/// Created with [`FunctionCache::module`].
+pub struct ModuleFunctions<'cache> {
+    cache: &'cache FunctionCache,
+    module_hash: Option<[u8; 32]>,
+}
+
+impl FunctionCache {
+    /// Create a function cache in the directory configured by `cache_config`,
+    /// or `None` if the cache is disabled.
+    pub fn new(compiler_name: &str, cache_config: &CacheConfig) -> Option<Self> {
+        if !cache_config.enabled() {
+            return None;
         }
-        Ok(val_to_cache)
-    }
-}
-
-impl<'config> ModuleCacheEntryInner<'config> {
-    fn new<'data>(compiler_name: &str, cache_config: &'config CacheConfig) -> Self {
-        // If debug assertions are enabled then assume that we're some sort of
-        // local build. We don't want local builds to stomp over caches between
-        // builds, so just use a separate cache directory based on the mtime of
-        // our executable, which should roughly correlate with "you changed the
-        // source code so you get a different directory".
-        //
-        // Otherwise if this is a release build we use the `GIT_REV` env var
-        // which is either the git rev if installed from git or the crate
-        // version if installed from crates.io.
-        let compiler_dir = if cfg!(debug_assertions) {
-            fn self_mtime() -> Option<String> {
-                let path = std::env::current_exe().ok()?;
-                let metadata = path.metadata().ok()?;
-                let mtime = metadata.modified().ok()?;
-                Some(match mtime.duration_since(std::time::UNIX_EPOCH) {
-                    Ok(dur) => format!("{}", dur.as_millis()),
-                    Err(err) => format!("m{}", err.duration().as_millis()),
-                })
-            }
-            let self_mtime = self_mtime().unwrap_or("no-mtime".to_string());
-            format!(
-                "{comp_name}-{comp_ver}-{comp_mtime}",
-                comp_name = compiler_name,
-                comp_ver = env!("GIT_REV"),
-                comp_mtime = self_mtime,
-            )
-        } else {
-            format!(
-                "{comp_name}-{comp_ver}",
-                comp_name = compiler_name,
-                comp_ver = env!("GIT_REV"),
-            )
-        };
-        let root_path = cache_config.directory().join("modules").join(compiler_dir);
+        // Functions go through the worker like modules do, so they're subject
+        // to the same limits, but aren't counted as module cache hits and
+        // misses.
+        Some(Self {
+            key_prefix: String::new(),
+            store: Arc::new(DirectoryStore::new(
+                "functions",
+                compiler_name,
+                cache_config,
+            )),
+            compression_level: cache_config.baseline_compression_level(),
+        })
+    }
 
+    /// Create a function cache backed by a custom [`CacheStore`].
+    pub fn from_store(compiler_name: &str, store: Arc<dyn CacheStore>) -> Self {
         Self {
-            root_path,
-            cache_config,
+            key_prefix: format!("{}-fn-", compiler_dir(compiler_name)),
+            store,
+            compression_level: STORE_COMPRESSION_LEVEL,
         }
     }
 
-    fn get_data<T>(&self, hash: &str) -> Option<T>
+    /// Look up the functions of a module.
+    ///
+    /// The code of a function usually depends on more than its body, for
+    /// example on the types and indices of the functions it calls. `state`
+    /// and `module` describe everything shared by the module's functions
+    /// which may affect their code; `module` is hashed through its serialized
+    /// form, which is convenient for types that can't implement `Hash`.
+    pub fn module<T, M>(&self, state: T, module: &M) -> ModuleFunctions<'_>
     where
-        T: for<'a> Deserialize<'a>,
+        T: Hash,
+        M: Serialize,
     {
-        let mod_cache_path = self.root_path.join(hash);
-        trace!("get_data() for path: {}", mod_cache_path.display());
-        let compressed_cache_bytes = fs::read(&mod_cache_path).ok()?;
-        let cache_bytes = zstd::decode_all(&compressed_cache_bytes[..])
-            .map_err(|err| warn!("Failed to decompress cached code: {}", err))
-            .ok()?;
-        bincode::deserialize(&cache_bytes[..])
-            .map_err(|err| warn!("Failed to deserialize cached code: {}", err))
+        let module_hash = bincode::serialize(module)
+            .map_err(|err| warn!("Failed to serialize module for function cache: {}", err))
             .ok()
+            .map(|module| {
+                let mut hasher = Sha256Hasher(Sha256::new());
+                state.hash(&mut hasher);
+                hasher.0.update(&module);
+                hasher.0.finalize().into()
+            });
+        ModuleFunctions {
+            cache: self,
+            module_hash,
+        }
     }
+}
 
-    fn update_data<T: Serialize>(&self, hash: &str, data: &T) -> Option<()> {
-        let mod_cache_path = self.root_path.join(hash);
-        trace!("update_data() for path: {}", mod_cache_path.display());
-        let serialized_data = bincode::serialize(&data)
-            .map_err(|err| warn!("Failed to serialize cached code: {}", err))
-            .ok()?;
-        let compressed_data = zstd::encode_all(
-            &serialized_data[..],
-            self.cache_config.baseline_compression_level(),
+impl ModuleFunctions<'_> {
+    /// Gets the cached function if `state` matches, otherwise calls `compute`.
+    pub fn get_data<T, U, E>(
+        &self,
+        state: T,
+        compute: impl FnOnce() -> Result<U, E>,
+    ) -> Result<U, E>
+    where
+        T: Hash,
+        U: Serialize + for<'a> Deserialize<'a>,
+    {
+        let module_hash = match &self.module_hash {
+            Some(hash) => hash,
+            None => return compute(),
+        };
+        let mut hasher = Sha256Hasher(Sha256::new());
+        hasher.0.update(module_hash);
+        state.hash(&mut hasher);
+        let hash: [u8; 32] = hasher.0.finalize().into();
+        let hash = base64::encode_config(&hash, base64::URL_SAFE_NO_PAD);
+
+        let cache = self.cache;
+        let key = format!("{}{}", cache.key_prefix, hash);
+        get_or_compute(&*cache.store, &key, cache.compression_level, compute)
+    }
+}
+
+/// Compression level used for entries written to a custom [`CacheStore`].
+///
+/// Custom stores don't go through the cache worker, which recompresses
+/// frequently used entries, so pick a reasonable middle ground up front.
+const STORE_COMPRESSION_LEVEL: i32 = 7;
+
+fn compiler_dir(compiler_name: &str) -> String {
+    // If debug assertions are enabled then assume that we're some sort of
+    // local build. We don't want local builds to stomp over caches between
+    // builds, so just use a separate cache directory based on the mtime of
+    // our executable, which should roughly correlate with "you changed the
+    // source code so you get a different directory".
+    //
+    // Otherwise if this is a release build we use the `GIT_REV` env var
+    // which is either the git rev if installed from git or the crate
+    // version if installed from crates.io.
+    if cfg!(debug_assertions) {
+        fn self_mtime() -> Option<String> {
+            let path = std::env::current_exe().ok()?;
+            let metadata = path.metadata().ok()?;
+            let mtime = metadata.modified().ok()?;
+            Some(match mtime.duration_since(std::time::UNIX_EPOCH) {
+                Ok(dur) => format!("{}", dur.as_millis()),
+                Err(err) => format!("m{}", err.duration().as_millis()),
+            })
+        }
+        let self_mtime = self_mtime().unwrap_or("no-mtime".to_string());
+        format!(
+            "{comp_name}-{comp_ver}-{comp_mtime}",
+            comp_name = compiler_name,
+            comp_ver = env!("GIT_REV"),
+            comp_mtime = self_mtime,
         )
-        .map_err(|err| warn!("Failed to compress cached code: {}", err))
+    } else {
+        format!(
+            "{comp_name}-{comp_ver}",
+            comp_name = compiler_name,
+            comp_ver = env!("GIT_REV"),
+        )
+    }
+}
+
+fn encode<T: Serialize>(data: &T, compression_level: i32) -> Option<Vec<u8>> {
+    let serialized_data = bincode::serialize(&data)
+        .map_err(|err| warn!("Failed to serialize cached code: {}", err))
         .ok()?;
+    zstd::encode_all(&serialized_data[..], compression_level)
+        .map_err(|err| warn!("Failed to compress cached code: {}", err))
+        .ok()
+}
 
-        // Optimize syscalls: first, try writing to disk. It should succeed in most cases.
-        // Otherwise, try creating the cache directory and retry writing to the file.
-        if fs_write_atomic(&mod_cache_path, "mod", &compressed_data) {
-            return Some(());
-        }
+fn decode<T>(compressed_cache_bytes: &[u8]) -> Option<T>
+where
+    T: for<'a> Deserialize<'a>,
+{
+    let cache_bytes = zstd::decode_all(compressed_cache_bytes)
+        .map_err(|err| warn!("Failed to decompress cached code: {}", err))
+        .ok()?;
+    bincode::deserialize(&cache_bytes[..])
+        .map_err(|err| warn!("Failed to deserialize cached code: {}", err))
+        .ok()
+}
 
-        debug!(
-            "Attempting to create the cache directory, because \
-             failed to write cached code to disk, path: {}",
-            mod_cache_path.display(),
-        );
+/// Writes `contents` to the cache file at `path`, creating its directory if
+/// needed, and returns whether it succeeded.
+fn write_cache_file(path: &Path, contents: &[u8]) -> bool {
+    // Optimize syscalls: first, try writing to disk. It should succeed in most cases.
+    // Otherwise, try creating the cache directory and retry writing to the file.
+    if fs_write_atomic(path, "mod", contents) {
+        return true;
+    }
 
-        let cache_dir = mod_cache_path.parent().unwrap();
-        fs::create_dir_all(cache_dir)
-            .map_err(|err| {
-                warn!(
-                    "Failed to create cache directory, path: {}, message: {}",
-                    cache_dir.display(),
-                    err
-                )
-            })
-            .ok()?;
+    debug!(
+        "Attempting to create the cache directory, because \
+         failed to write cached code to disk, path: {}",
+        path.display(),
+    );
 
-        if fs_write_atomic(&mod_cache_path, "mod", &compressed_data) {
-            Some(())
-        } else {
-            None
-        }
+    let cache_dir = path.parent().unwrap();
+    if let Err(err) = fs::create_dir_all(cache_dir) {
+        warn!(
+            "Failed to create cache directory, path: {}, message: {}",
+            cache_dir.display(),
+            err
+        );
+        return false;
     }
+
+    fs_write_atomic(path, "mod", contents)
 }
 
 impl Hasher for Sha256Hasher {
@@ -1,6 +1,8 @@
 use super::config::tests::test_prolog;
 use super::*;
+use std::collections::HashMap;
 use std::fs;
+use std::sync::Mutex;
 
 // Since cache system is a global thing, each test needs to be run in seperate process.
 // So, init() tests are run as integration tests.
@@ -8,6 +10,24 @@ use std::fs;
 // from the inside of the module.
 // We test init() in exactly one test, rest of the tests doesn't rely on it.
 
+/// A `CacheStore` keeping its entries in memory.
+#[derive(Default)]
+struct MemoryStore(Mutex<HashMap<String, Vec<u8>>>);
+
+impl CacheStore for MemoryStore {
+    fn get(&self, key: &str) -> Option<Vec<u8>> {
+        self.0.lock().unwrap().get(key).cloned()
+    }
+
+    fn put(&self, key: &str, value: &[u8]) -> bool {
+        self.0
+            .lock()
+            .unwrap()
+            .insert(key.to_string(), value.to_vec());
+        true
+    }
+}
+
 #[test]
 fn test_cache_init() {
     let (_tempdir, cache_dir, config_path) = test_prolog();
@@ -62,8 +82,8 @@ fn test_write_read_cache() {
     let compiler1 = "test-1";
     let compiler2 = "test-2";
 
-    let entry1 = ModuleCacheEntry::from_inner(ModuleCacheEntryInner::new(compiler1, &cache_config));
-    let entry2 = ModuleCacheEntry::from_inner(ModuleCacheEntryInner::new(compiler2, &cache_config));
+    let entry1 = ModuleCacheEntry::new(compiler1, &cache_config);
+    let entry2 = ModuleCacheEntry::new(compiler2, &cache_config);
 
     entry1.get_data::<_, i32, i32>(1, |_| Ok(100)).unwrap();
     entry1.get_data::<_, i32, i32>(1, |_| panic!()).unwrap();
@@ -90,3 +110,47 @@ fn test_write_read_cache() {
     entry1.get_data::<_, i32, i32>(4, |_| panic!()).unwrap();
     entry2.get_data::<_, i32, i32>(1, |_| panic!()).unwrap();
 }
+
+#[test]
+fn test_write_read_custom_store() {
+    let store = MemoryStore::default();
+    let entry1 = ModuleCacheEntry::from_store("test-1", &store);
+    let entry2 = ModuleCacheEntry::from_store("test-2", &store);
+
+    entry1.get_data::<_, i32, i32>(1, |_| Ok(100)).unwrap();
+    assert_eq!(entry1.get_data::<_, i32, i32>(1, |_| panic!()), Ok(100));
+    entry1.get_data::<_, i32, i32>(2, |_| Ok(200)).unwrap();
+    assert_eq!(entry1.get_data::<_, i32, i32>(2, |_| panic!()), Ok(200));
+    assert_eq!(store.0.lock().unwrap().len(), 2);
+
+    // Entries are keyed by compiler as well as by state.
+    entry2.get_data::<_, i32, i32>(1, |_| Ok(300)).unwrap();
+    assert_eq!(entry2.get_data::<_, i32, i32>(1, |_| panic!()), Ok(300));
+    assert_eq!(entry1.get_data::<_, i32, i32>(1, |_| panic!()), Ok(100));
+    assert_eq!(store.0.lock().unwrap().len(), 3);
+}
+
+#[test]
+fn test_function_cache_custom_store() {
+    let store = Arc::new(MemoryStore::default());
+    let cache = FunctionCache::from_store("test", store.clone());
+
+    let module1 = cache.module(1, &"module");
+    module1.get_data::<_, i32, i32>("f", || Ok(100)).unwrap();
+    module1.get_data::<_, i32, i32>("g", || Ok(200)).unwrap();
+    assert_eq!(module1.get_data::<_, i32, i32>("f", || panic!()), Ok(100));
+    assert_eq!(store.0.lock().unwrap().len(), 2);
+
+    // Functions are keyed by the module-wide state as well as by their own.
+    let module2 = cache.module(1, &"other module");
+    module2.get_data::<_, i32, i32>("f", || Ok(300)).unwrap();
+    let module3 = cache.module(2, &"module");
+    module3.get_data::<_, i32, i32>("f", || Ok(400)).unwrap();
+    assert_eq!(store.0.lock().unwrap().len(), 4);
+    assert_eq!(
+        cache
+            .module(1, &"module")
+            .get_data::<_, i32, i32>("g", || panic!()),
+        Ok(200)
+    );
+}
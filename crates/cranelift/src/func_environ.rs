@@ -6,12 +6,15 @@ use cranelift_codegen::ir::types::*;
 use cranelift_codegen::ir::{AbiParam, ArgumentPurpose, Function, InstBuilder, Signature};
 use cranelift_codegen::isa::{self, TargetFrontendConfig};
 use cranelift_entity::{EntityRef, PrimaryMap};
-use cranelift_frontend::FunctionBuilder;
+use cranelift_frontend::{FunctionBuilder, Variable};
+use cranelift_wasm::wasmparser::Operator;
 use cranelift_wasm::{
-    self, FuncIndex, GlobalIndex, GlobalVariable, MemoryIndex, SignatureIndex, TableIndex,
-    TargetEnvironment, TypeIndex, WasmError, WasmResult, WasmType,
+    self, FuncIndex, FuncTranslationState, GlobalIndex, GlobalVariable, MemoryIndex,
+    SignatureIndex, TableIndex, TargetEnvironment, TypeIndex, WasmError, WasmResult, WasmType,
 };
+use std::collections::HashMap;
 use std::convert::TryFrom;
+use std::mem;
 use wasmtime_environ::{
     BuiltinFunctionIndex, MemoryPlan, MemoryStyle, Module, TableStyle, Tunables, VMOffsets,
     INTERRUPTED, WASM_PAGE_SIZE,
@@ -125,6 +128,26 @@ pub struct FuncEnvironment<'module_environment> {
     pub(crate) offsets: VMOffsets,
 
     tunables: &'module_environment Tunables,
+
+    /// Branch hints for this function, keyed by the source location (the
+    /// offset in the module) of the hinted `if` or `br_if` operator.
+    pub(crate) branch_hints: HashMap<u32, bool>,
+
+    /// A function-local variable which stores the cached value of the amount of
+    /// fuel remaining to execute. If used this is modified frequently so it's
+    /// stored locally as a variable instead of always referenced from the field
+    /// in `*const VMInterrupts`
+    fuel_var: Variable,
+
+    /// A function-local variable which caches the value of `*const
+    /// VMInterrupts` for this function's vmctx argument. This pointer is stored
+    /// in the vmctx itself, but never changes for the lifetime of the function,
+    /// so if we load it up front we can continue to use it throughout.
+    vminterrupts_ptr: Variable,
+
+    /// A cached amount of fuel consumed which hasn't yet been added to
+    /// `fuel_var`, used to batch up the updates of straight-line code.
+    fuel_consumed: i64,
 }
 
 impl<'module_environment> FuncEnvironment<'module_environment> {
@@ -151,6 +174,14 @@ impl<'module_environment> FuncEnvironment<'module_environment> {
             builtin_function_signatures,
             offsets: VMOffsets::new(target_config.pointer_bytes(), module),
             tunables,
+            branch_hints: HashMap::new(),
+            // Both of these are placeholders which are reassigned in
+            // `after_locals`, once the number of wasm locals is known.
+            fuel_var: Variable::new(0),
+            vminterrupts_ptr: Variable::new(0),
+            // Start with at least one fuel being consumed because even empty
+            // functions should consume at least some fuel.
+            fuel_consumed: 1,
         }
     }
 
@@ -363,6 +394,237 @@ impl<'module_environment> FuncEnvironment<'module_environment> {
         (base, func_addr)
     }
 
+    fn declare_vminterrupts_ptr(&mut self, builder: &mut FunctionBuilder<'_>) {
+        // We load the `*const VMInterrupts` value stored within vmctx at the
+        // head of the function and reuse the same value across the entire
+        // function. This is possible since we know that the pointer never
+        // changes for the lifetime of the function.
+        let pointer_type = self.pointer_type();
+        builder.declare_var(self.vminterrupts_ptr, pointer_type);
+        let vmctx = self.vmctx(builder.func);
+        let base = builder.ins().global_value(pointer_type, vmctx);
+        let offset = i32::try_from(self.offsets.vmctx_interrupts()).unwrap();
+        let interrupt_ptr = builder
+            .ins()
+            .load(pointer_type, ir::MemFlags::trusted(), base, offset);
+        builder.def_var(self.vminterrupts_ptr, interrupt_ptr);
+    }
+
+    fn fuel_function_entry(&mut self, builder: &mut FunctionBuilder<'_>) {
+        // On function entry we load the amount of fuel into a function-local
+        // `self.fuel_var` to make fuel modifications fast locally. This cache
+        // is then periodically flushed to the Store-defined location in
+        // `VMInterrupts` later.
+        builder.declare_var(self.fuel_var, I64);
+        self.fuel_load_into_var(builder);
+        self.fuel_check(builder);
+    }
+
+    fn fuel_function_exit(&mut self, builder: &mut FunctionBuilder<'_>) {
+        // On exiting the function we need to be sure to save the fuel we have
+        // cached locally in `self.fuel_var` back into the Store-defined
+        // location.
+        self.fuel_save_from_var(builder);
+    }
+
+    fn fuel_before_op(
+        &mut self,
+        op: &Operator<'_>,
+        builder: &mut FunctionBuilder<'_>,
+        reachable: bool,
+    ) {
+        if !reachable {
+            // In unreachable code we shouldn't have any leftover fuel we
+            // haven't accounted for since the reason for us to become
+            // unreachable should have already added it to `self.fuel_var`.
+            debug_assert_eq!(self.fuel_consumed, 0);
+            return;
+        }
+
+        self.fuel_consumed += match op {
+            // Nop and drop generate no code, so don't consume fuel for them.
+            Operator::Nop | Operator::Drop => 0,
+
+            // Control flow may create branches, but is generally cheap and
+            // free, so don't consume fuel. Note the lack of `if` since some
+            // cost is incurred with the conditional check.
+            Operator::Block { .. }
+            | Operator::Loop { .. }
+            | Operator::Unreachable
+            | Operator::Return
+            | Operator::Else
+            | Operator::End => 0,
+
+            // everything else, just call it one operation.
+            _ => 1,
+        };
+
+        match op {
+            // Exiting a function (via a return or unreachable) or otherwise
+            // entering a different function (via a call) means that we need to
+            // update the fuel consumption in `VMInterrupts` because we're
+            // about to move control out of this function itself and the fuel
+            // may need to be read.
+            //
+            // Before this we need to update the fuel counter from our own cost
+            // leading up to this function call, and then we can store
+            // `self.fuel_var` into `VMInterrupts`.
+            Operator::Unreachable
+            | Operator::Return
+            | Operator::CallIndirect { .. }
+            | Operator::Call { .. }
+            | Operator::ReturnCall { .. }
+            | Operator::ReturnCallIndirect { .. } => {
+                self.fuel_increment_var(builder);
+                self.fuel_save_from_var(builder);
+            }
+
+            // To ensure all code preceding a loop is only counted once we
+            // update the fuel variable on entry.
+            Operator::Loop { .. }
+
+            // Entering into an `if` block means that the edge we take isn't
+            // known until runtime, so we need to update our fuel consumption
+            // before we take the branch.
+            | Operator::If { .. }
+
+            // Control-flow instructions mean that we're moving to the end/exit
+            // of a block somewhere else. That means we need to update the fuel
+            // counter since we're effectively terminating our basic block.
+            | Operator::Br { .. }
+            | Operator::BrIf { .. }
+            | Operator::BrTable { .. }
+
+            // Exiting a scope means that we need to update the fuel
+            // consumption because there are multiple ways to exit a scope and
+            // this is the only time we have to account for instructions
+            // executed so far.
+            | Operator::End
+
+            // This is similar to `end`, except that it's only the terminator
+            // for an `if` block. The same reasoning applies though in that we
+            // are terminating a basic block and need to update the fuel
+            // variable.
+            | Operator::Else => self.fuel_increment_var(builder),
+
+            // This is a normal instruction where the fuel is buffered to later
+            // get added to `self.fuel_var`.
+            //
+            // Note that we generally ignore instructions which may trap and
+            // therefore result in exiting a block early. Current usage of fuel
+            // means that it's not too important to account for a precise amount
+            // of fuel consumed but rather "close to the actual amount" is good
+            // enough.
+            //
+            // Note that `Block` is specifically omitted from incrementing the
+            // fuel variable. Control flow entering a `block` is unconditional
+            // which means it's effectively executing straight-line code. We'll
+            // update the counter when exiting a block, but we shouldn't need to
+            // do so upon entering a block.
+            _ => {}
+        }
+    }
+
+    fn fuel_after_op(&mut self, op: &Operator<'_>, builder: &mut FunctionBuilder<'_>) {
+        match op {
+            // After a function call we need to reload our fuel value since the
+            // function may have changed it.
+            Operator::Call { .. } | Operator::CallIndirect { .. } => {
+                self.fuel_load_into_var(builder);
+            }
+
+            // The body of a loop is entered on every iteration, so this is
+            // where we check whether fuel has run out, which bounds the
+            // execution of long or infinite loops.
+            Operator::Loop { .. } => self.fuel_check(builder),
+
+            _ => {}
+        }
+    }
+
+    /// Adds `self.fuel_consumed` to the `fuel_var`, zero-ing out the amount of
+    /// fuel consumed at that point.
+    fn fuel_increment_var(&mut self, builder: &mut FunctionBuilder<'_>) {
+        let consumption = mem::replace(&mut self.fuel_consumed, 0);
+        if consumption == 0 {
+            return;
+        }
+
+        let fuel = builder.use_var(self.fuel_var);
+        let fuel = builder.ins().iadd_imm(fuel, consumption);
+        builder.def_var(self.fuel_var, fuel);
+    }
+
+    /// Loads the fuel consumption value from `VMInterrupts` into `self.fuel_var`
+    fn fuel_load_into_var(&mut self, builder: &mut FunctionBuilder<'_>) {
+        let (addr, offset) = self.fuel_addr_offset(builder);
+        let fuel = builder
+            .ins()
+            .load(I64, ir::MemFlags::trusted(), addr, offset);
+        builder.def_var(self.fuel_var, fuel);
+    }
+
+    /// Stores the fuel consumption value from `self.fuel_var` into
+    /// `VMInterrupts`.
+    fn fuel_save_from_var(&mut self, builder: &mut FunctionBuilder<'_>) {
+        let (addr, offset) = self.fuel_addr_offset(builder);
+        let fuel_consumed = builder.use_var(self.fuel_var);
+        builder
+            .ins()
+            .store(ir::MemFlags::trusted(), fuel_consumed, addr, offset);
+    }
+
+    /// Returns the `(address, offset)` of the fuel consumption within
+    /// `VMInterrupts`, used to perform loads/stores later.
+    fn fuel_addr_offset(&mut self, builder: &mut FunctionBuilder<'_>) -> (ir::Value, Offset32) {
+        (
+            builder.use_var(self.vminterrupts_ptr),
+            i32::from(self.offsets.vminterrupts_fuel_consumed()).into(),
+        )
+    }
+
+    /// Checks the amount of remaining, and if we've run out of fuel we call
+    /// the out-of-fuel function.
+    fn fuel_check(&mut self, builder: &mut FunctionBuilder<'_>) {
+        self.fuel_increment_var(builder);
+        let out_of_gas_block = builder.create_block();
+        builder.func.layout.set_cold(out_of_gas_block);
+        let continuation_block = builder.create_block();
+
+        // Note that our fuel is encoded as adding positive values to a
+        // negative number. Whenever the negative number goes positive that
+        // means we ran out of fuel.
+        //
+        // Compare to see if our fuel is positive, and if so we ran out of gas.
+        // Otherwise we can continue on like usual.
+        let fuel = builder.use_var(self.fuel_var);
+        let cmp = builder
+            .ins()
+            .icmp_imm(IntCC::SignedGreaterThanOrEqual, fuel, 0);
+        builder.ins().brnz(cmp, out_of_gas_block, &[]);
+        builder.ins().jump(continuation_block, &[]);
+        builder.seal_block(out_of_gas_block);
+
+        // If we ran out of gas then we call our out-of-gas intrinsic which
+        // raises a trap. Fuel is saved beforehand so the store observes the
+        // amount of fuel consumed up to this point.
+        builder.switch_to_block(out_of_gas_block);
+        self.fuel_save_from_var(builder);
+        let out_of_gas_sig = self.builtin_function_signatures.out_of_gas(builder.func);
+        let (vmctx, out_of_gas) = self.translate_load_builtin_function_address(
+            &mut builder.cursor(),
+            BuiltinFunctionIndex::out_of_gas(),
+        );
+        builder
+            .ins()
+            .call_indirect(out_of_gas_sig, out_of_gas, &[vmctx]);
+        self.fuel_load_into_var(builder);
+        builder.ins().jump(continuation_block, &[]);
+        builder.seal_block(continuation_block);
+
+        builder.switch_to_block(continuation_block);
+    }
+
     /// Generate code to increment or decrement the given `externref`'s
     /// reference count.
     ///
@@ -997,6 +1259,23 @@ impl<'module_environment> cranelift_wasm::FuncEnvironment for FuncEnvironment<'m
             }
         };
 
+        // 64-bit memories are indexed with `i64` addresses, which are bounds
+        // checked against the full pointer-sized `current_length`.
+        let memory64 = self.module.memory_plans[index].memory.memory64;
+        let (index_type, bound_type) = if memory64 {
+            if pointer_type != I64 {
+                return Err(WasmError::Unsupported(
+                    "64-bit memories are only supported on 64-bit targets".to_string(),
+                ));
+            }
+            (I64, I64)
+        } else {
+            (
+                I32,
+                self.offsets.type_of_vmmemory_definition_current_length(),
+            )
+        };
+
         // If we have a declared maximum, we can make this a "static" heap, which is
         // allocated up front and never moved.
         let (offset_guard_size, heap_style, readonly_base) = match self.module.memory_plans[index] {
@@ -1008,7 +1287,7 @@ impl<'module_environment> cranelift_wasm::FuncEnvironment for FuncEnvironment<'m
                 let heap_bound = func.create_global_value(ir::GlobalValueData::Load {
                     base: ptr,
                     offset: Offset32::new(current_length_offset),
-                    global_type: self.offsets.type_of_vmmemory_definition_current_length(),
+                    global_type: bound_type,
                     readonly: false,
                 });
                 (
@@ -1043,7 +1322,7 @@ impl<'module_environment> cranelift_wasm::FuncEnvironment for FuncEnvironment<'m
             min_size: 0.into(),
             offset_guard_size,
             style: heap_style,
-            index_type: I32,
+            index_type,
         }))
     }
 
@@ -1238,13 +1517,33 @@ impl<'module_environment> cranelift_wasm::FuncEnvironment for FuncEnvironment<'m
         _heap: ir::Heap,
         val: ir::Value,
     ) -> WasmResult<ir::Value> {
+        let memory64 = self.module.memory_plans[index].memory.memory64;
+        let val = if memory64 {
+            // Growing by more than `u32::MAX` pages always fails, and so does
+            // growing by exactly `u32::MAX` pages, so clamp the delta to that.
+            let too_large =
+                pos.ins()
+                    .icmp_imm(IntCC::UnsignedGreaterThan, val, i64::from(u32::MAX));
+            let clamped = pos.ins().iconst(I32, i64::from(u32::MAX));
+            let val = pos.ins().ireduce(I32, val);
+            pos.ins().select(too_large, clamped, val)
+        } else {
+            val
+        };
         let (func_sig, index_arg, func_idx) = self.get_memory_grow_func(&mut pos.func, index);
         let memory_index = pos.ins().iconst(I32, index_arg as i64);
         let (vmctx, func_addr) = self.translate_load_builtin_function_address(&mut pos, func_idx);
         let call_inst = pos
             .ins()
             .call_indirect(func_sig, func_addr, &[vmctx, val, memory_index]);
-        Ok(*pos.func.dfg.inst_results(call_inst).first().unwrap())
+        let result = *pos.func.dfg.inst_results(call_inst).first().unwrap();
+        if memory64 {
+            // The previous size always fits in 31 bits, so sign-extending
+            // only affects the `-1` returned on failure.
+            Ok(pos.ins().sextend(I64, result))
+        } else {
+            Ok(result)
+        }
     }
 
     fn translate_memory_size(
@@ -1253,26 +1552,67 @@ impl<'module_environment> cranelift_wasm::FuncEnvironment for FuncEnvironment<'m
         index: MemoryIndex,
         _heap: ir::Heap,
     ) -> WasmResult<ir::Value> {
+        let memory64 = self.module.memory_plans[index].memory.memory64;
         let (func_sig, index_arg, func_idx) = self.get_memory_size_func(&mut pos.func, index);
         let memory_index = pos.ins().iconst(I32, index_arg as i64);
         let (vmctx, func_addr) = self.translate_load_builtin_function_address(&mut pos, func_idx);
         let call_inst = pos
             .ins()
             .call_indirect(func_sig, func_addr, &[vmctx, memory_index]);
-        Ok(*pos.func.dfg.inst_results(call_inst).first().unwrap())
+        let result = *pos.func.dfg.inst_results(call_inst).first().unwrap();
+        if memory64 {
+            Ok(pos.ins().uextend(I64, result))
+        } else {
+            Ok(result)
+        }
     }
 
     fn translate_memory_copy(
         &mut self,
         mut pos: FuncCursor,
         src_index: MemoryIndex,
-        _src_heap: ir::Heap,
+        src_heap: ir::Heap,
         dst_index: MemoryIndex,
-        _dst_heap: ir::Heap,
+        dst_heap: ir::Heap,
         dst: ir::Value,
         src: ir::Value,
         len: ir::Value,
     ) -> WasmResult<()> {
+        if let Some(len) = inline_bulk_memory_len(&pos.func, len) {
+            let pointer_type = self.pointer_type();
+            let src_addr = pos.ins().heap_addr(pointer_type, src_heap, src, len);
+            let dst_addr = pos.ins().heap_addr(pointer_type, dst_heap, dst, len);
+            let chunks = bulk_memory_chunks(len);
+            // Load everything before storing anything, so that overlapping
+            // ranges are handled like `memmove` does.
+            let vals: Vec<_> = chunks
+                .iter()
+                .map(|&(offset, size)| {
+                    let flags = bulk_memory_flags();
+                    match size {
+                        8 => pos.ins().load(I64, flags, src_addr, offset),
+                        4 => pos.ins().load(I32, flags, src_addr, offset),
+                        2 => pos.ins().uload16(I32, flags, src_addr, offset),
+                        _ => pos.ins().uload8(I32, flags, src_addr, offset),
+                    }
+                })
+                .collect();
+            // Store the last chunk first: if the destination is partially out
+            // of bounds, it faults before any memory has been modified.
+            for (&(offset, size), &val) in chunks.iter().zip(&vals).rev() {
+                let flags = bulk_memory_flags();
+                match size {
+                    8 | 4 => pos.ins().store(flags, val, dst_addr, offset),
+                    2 => pos.ins().istore16(flags, val, dst_addr, offset),
+                    _ => pos.ins().istore8(flags, val, dst_addr, offset),
+                };
+            }
+            return Ok(());
+        }
+
+        let dst = narrow_memory_address(&mut pos, dst);
+        let src = narrow_memory_address(&mut pos, src);
+        let len = narrow_memory_operand(&mut pos, len);
         let src_index = pos.ins().iconst(I32, i64::from(src_index.as_u32()));
         let dst_index = pos.ins().iconst(I32, i64::from(dst_index.as_u32()));
 
@@ -1293,11 +1633,33 @@ impl<'module_environment> cranelift_wasm::FuncEnvironment for FuncEnvironment<'m
         &mut self,
         mut pos: FuncCursor,
         memory_index: MemoryIndex,
-        _heap: ir::Heap,
+        heap: ir::Heap,
         dst: ir::Value,
         val: ir::Value,
         len: ir::Value,
     ) -> WasmResult<()> {
+        if let Some(len) = inline_bulk_memory_len(&pos.func, len) {
+            let pointer_type = self.pointer_type();
+            let addr = pos.ins().heap_addr(pointer_type, heap, dst, len);
+            // Replicate the low byte of `val` across all 8 bytes of an i64.
+            let byte = pos.ins().band_imm(val, 0xff);
+            let byte = pos.ins().uextend(I64, byte);
+            let pattern = pos.ins().imul_imm(byte, 0x0101_0101_0101_0101);
+            // As for `memory.copy`, store the last chunk first.
+            for &(offset, size) in bulk_memory_chunks(len).iter().rev() {
+                let flags = bulk_memory_flags();
+                match size {
+                    8 => pos.ins().store(flags, pattern, addr, offset),
+                    4 => pos.ins().istore32(flags, pattern, addr, offset),
+                    2 => pos.ins().istore16(flags, pattern, addr, offset),
+                    _ => pos.ins().istore8(flags, pattern, addr, offset),
+                };
+            }
+            return Ok(());
+        }
+
+        let dst = narrow_memory_address(&mut pos, dst);
+        let len = narrow_memory_operand(&mut pos, len);
         let (func_sig, memory_index, func_idx) =
             self.get_memory_fill_func(&mut pos.func, memory_index);
 
@@ -1324,6 +1686,7 @@ impl<'module_environment> cranelift_wasm::FuncEnvironment for FuncEnvironment<'m
         src: ir::Value,
         len: ir::Value,
     ) -> WasmResult<()> {
+        let dst = narrow_memory_address(&mut pos, dst);
         let (func_sig, func_idx) = self.get_memory_init_func(&mut pos.func);
 
         let memory_index_arg = pos.ins().iconst(I32, memory_index.index() as i64);
@@ -1443,6 +1806,7 @@ impl<'module_environment> cranelift_wasm::FuncEnvironment for FuncEnvironment<'m
         expected: ir::Value,
         timeout: ir::Value,
     ) -> WasmResult<ir::Value> {
+        let addr = narrow_memory_operand(&mut pos, addr);
         let implied_ty = pos.func.dfg.value_type(expected);
         let (func_sig, memory_index, func_idx) =
             self.get_memory_atomic_wait(&mut pos.func, memory_index, implied_ty);
@@ -1468,6 +1832,7 @@ impl<'module_environment> cranelift_wasm::FuncEnvironment for FuncEnvironment<'m
         addr: ir::Value,
         count: ir::Value,
     ) -> WasmResult<ir::Value> {
+        let addr = narrow_memory_operand(&mut pos, addr);
         let (func_sig, memory_index, func_idx) =
             self.get_memory_atomic_notify(&mut pos.func, memory_index);
 
@@ -1482,6 +1847,10 @@ impl<'module_environment> cranelift_wasm::FuncEnvironment for FuncEnvironment<'m
         Ok(*pos.func.dfg.inst_results(call_inst).first().unwrap())
     }
 
+    fn branch_hint(&self, srcloc: ir::SourceLoc) -> Option<bool> {
+        self.branch_hints.get(&srcloc.bits()).cloned()
+    }
+
     fn translate_loop_header(&mut self, mut pos: FuncCursor) -> WasmResult<()> {
         if !self.tunables.interruptable {
             return Ok(());
@@ -1514,4 +1883,142 @@ impl<'module_environment> cranelift_wasm::FuncEnvironment for FuncEnvironment<'m
         pos.ins().trapnz(cmp, ir::TrapCode::Interrupt);
         Ok(())
     }
+
+    fn after_locals(&mut self, num_locals: usize) {
+        self.vminterrupts_ptr = Variable::new(num_locals);
+        self.fuel_var = Variable::new(num_locals + 1);
+    }
+
+    fn before_translate_function(
+        &mut self,
+        builder: &mut FunctionBuilder,
+        _state: &FuncTranslationState,
+    ) -> WasmResult<()> {
+        // If the `vminterrupts_ptr` variable will get used then we initialize
+        // it here.
+        if self.tunables.consume_fuel {
+            self.declare_vminterrupts_ptr(builder);
+            self.fuel_function_entry(builder);
+        }
+        Ok(())
+    }
+
+    fn before_translate_operator(
+        &mut self,
+        op: &Operator,
+        builder: &mut FunctionBuilder,
+        state: &FuncTranslationState,
+    ) -> WasmResult<()> {
+        if self.tunables.consume_fuel {
+            self.fuel_before_op(op, builder, state.reachable());
+        }
+        Ok(())
+    }
+
+    fn after_translate_operator(
+        &mut self,
+        op: &Operator,
+        builder: &mut FunctionBuilder,
+        state: &FuncTranslationState,
+    ) -> WasmResult<()> {
+        if self.tunables.consume_fuel && state.reachable() {
+            self.fuel_after_op(op, builder);
+        }
+        Ok(())
+    }
+
+    fn after_translate_function(
+        &mut self,
+        builder: &mut FunctionBuilder,
+        state: &FuncTranslationState,
+    ) -> WasmResult<()> {
+        if self.tunables.consume_fuel && state.reachable() {
+            self.fuel_function_exit(builder);
+        }
+        Ok(())
+    }
+}
+
+/// `memory.copy` and `memory.fill` with a constant length of at most this many
+/// bytes are expanded inline into loads and stores, rather than calling into
+/// the runtime.
+const INLINE_BULK_MEMORY_MAX_LEN: u32 = 32;
+
+/// Returns the length of a bulk memory operation if it is a nonzero constant
+/// small enough for the operation to be expanded inline.
+///
+/// Zero lengths are left to the runtime, since they still have to trap if the
+/// destination is beyond the end of memory.
+fn inline_bulk_memory_len(func: &Function, len: ir::Value) -> Option<u32> {
+    let inst = func.dfg.value_def(len).inst()?;
+    match func.dfg[inst] {
+        ir::InstructionData::UnaryImm {
+            opcode: ir::Opcode::Iconst,
+            imm,
+        } => {
+            let len = imm.bits() as u64;
+            if len > 0 && len <= u64::from(INLINE_BULK_MEMORY_MAX_LEN) {
+                Some(len as u32)
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Converts an `i64` length or atomic address operand of a 64-bit memory to
+/// the `i32` expected by the runtime's libcalls, trapping if it doesn't fit.
+///
+/// Memories are never larger than 4GiB, so any such operand is out of bounds,
+/// except for a length of exactly 4GiB on a 4GiB memory which the libcalls
+/// can't express. `i32` operands are returned unchanged.
+fn narrow_memory_operand(pos: &mut FuncCursor, val: ir::Value) -> ir::Value {
+    if pos.func.dfg.value_type(val) != I64 {
+        return val;
+    }
+    let too_large = pos
+        .ins()
+        .icmp_imm(IntCC::UnsignedGreaterThan, val, i64::from(u32::MAX));
+    pos.ins().trapnz(too_large, ir::TrapCode::HeapOutOfBounds);
+    pos.ins().ireduce(I32, val)
+}
+
+/// Converts an `i64` address operand of a 64-bit memory bulk operation to the
+/// `i32` expected by the runtime's libcalls, trapping if it can't be in bounds.
+///
+/// Unlike other operands, an address of exactly 4GiB is valid for a
+/// zero-length operation on a 4GiB memory. It's clamped to `u32::MAX`, which
+/// the runtime's bounds checks treat the same way: in bounds only for a zero
+/// length on a 4GiB memory.
+fn narrow_memory_address(pos: &mut FuncCursor, val: ir::Value) -> ir::Value {
+    if pos.func.dfg.value_type(val) != I64 {
+        return val;
+    }
+    let too_large = pos.ins().icmp_imm(IntCC::UnsignedGreaterThan, val, 1 << 32);
+    pos.ins().trapnz(too_large, ir::TrapCode::HeapOutOfBounds);
+    let at_end = pos.ins().icmp_imm(IntCC::Equal, val, 1 << 32);
+    let clamped = pos.ins().iconst(I32, i64::from(u32::MAX));
+    let val = pos.ins().ireduce(I32, val);
+    pos.ins().select(at_end, clamped, val)
+}
+
+/// Splits `len` bytes into `(offset, size)` chunks of 8, 4, 2 and 1 bytes.
+fn bulk_memory_chunks(len: u32) -> Vec<(i32, u32)> {
+    let mut chunks = Vec::new();
+    let mut offset = 0;
+    for &size in &[8, 4, 2, 1] {
+        while len - offset >= size {
+            chunks.push((offset as i32, size));
+            offset += size;
+        }
+    }
+    chunks
+}
+
+/// The flags for the loads and stores of an inlined bulk memory operation.
+fn bulk_memory_flags() -> ir::MemFlags {
+    let mut flags = ir::MemFlags::new();
+    flags.set_endianness(ir::Endianness::Little);
+    flags
 }
@@ -94,12 +94,13 @@ use cranelift_codegen::machinst::buffer::MachSrcLoc;
 use cranelift_codegen::print_errors::pretty_error;
 use cranelift_codegen::{binemit, isa, Context};
 use cranelift_wasm::{DefinedFuncIndex, FuncIndex, FuncTranslator};
+use std::collections::{HashMap, HashSet};
 use std::convert::TryFrom;
 use std::sync::Mutex;
 use wasmtime_environ::{
-    CompileError, CompiledFunction, Compiler, FunctionAddressMap, FunctionBodyData,
-    InstructionAddressMap, ModuleTranslation, Relocation, RelocationTarget, StackMapInformation,
-    TrapInformation, Tunables, TypeTables,
+    CompileError, CompiledFunction, Compiler, ExecutionProfile, FunctionAddressMap,
+    FunctionBodyData, InstructionAddressMap, ModuleTranslation, Relocation, RelocationTarget,
+    StackMapInformation, TrapInformation, Tunables, TypeTables,
 };
 
 mod func_environ;
@@ -217,6 +218,42 @@ impl StackMapSink {
     }
 }
 
+/// Mark the blocks of `func` that `profile` says are cold. Profile offsets are
+/// relative to the function body, which starts at `body_offset` in the module.
+fn mark_cold_blocks(
+    func: &mut ir::Function,
+    profile: &ExecutionProfile,
+    func_index: FuncIndex,
+    body_offset: u32,
+) {
+    let cold = profile.cold_blocks(func_index).collect::<HashSet<_>>();
+    if cold.is_empty() {
+        return;
+    }
+    // Map each profiled source location to whether it is cold.
+    let known = profile
+        .block_counts(func_index)
+        .map(|(offset, _)| (body_offset + offset, cold.contains(&offset)))
+        .collect::<HashMap<_, _>>();
+
+    let entry = func.layout.entry_block();
+    let blocks = func.layout.blocks().collect::<Vec<_>>();
+    for block in blocks {
+        if Some(block) == entry {
+            continue;
+        }
+        // A block is as hot as the first instruction in it that the profile
+        // knows about.
+        let is_cold = func
+            .layout
+            .block_insts(block)
+            .find_map(|inst| known.get(&func.srclocs[inst].bits()).cloned());
+        if is_cold == Some(true) {
+            func.layout.set_cold(block);
+        }
+    }
+}
+
 fn get_function_address_map<'data>(
     context: &Context,
     data: &FunctionBodyData<'data>,
@@ -345,11 +382,45 @@ impl Compiler for Cranelift {
         &self,
         translation: &ModuleTranslation<'_>,
         func_index: DefinedFuncIndex,
-        mut input: FunctionBodyData<'_>,
+        input: FunctionBodyData<'_>,
         isa: &dyn isa::TargetIsa,
         tunables: &Tunables,
         types: &TypeTables,
     ) -> Result<CompiledFunction, CompileError> {
+        let (func, _) =
+            self.compile(translation, func_index, input, isa, tunables, types, false)?;
+        Ok(func)
+    }
+
+    fn compile_function_with_ir(
+        &self,
+        translation: &ModuleTranslation<'_>,
+        func_index: DefinedFuncIndex,
+        input: FunctionBodyData<'_>,
+        isa: &dyn isa::TargetIsa,
+        tunables: &Tunables,
+        types: &TypeTables,
+    ) -> Result<(CompiledFunction, Option<String>), CompileError> {
+        self.compile(translation, func_index, input, isa, tunables, types, true)
+    }
+
+    fn purge(&self) {
+        *self.translators.lock().unwrap() = Vec::new();
+    }
+}
+
+impl Cranelift {
+    #[allow(clippy::too_many_arguments)]
+    fn compile(
+        &self,
+        translation: &ModuleTranslation<'_>,
+        func_index: DefinedFuncIndex,
+        mut input: FunctionBodyData<'_>,
+        isa: &dyn isa::TargetIsa,
+        tunables: &Tunables,
+        types: &TypeTables,
+        with_ir: bool,
+    ) -> Result<(CompiledFunction, Option<String>), CompileError> {
         let module = &translation.module;
         let func_index = module.func_index(func_index);
         let mut context = Context::new();
@@ -400,6 +471,17 @@ impl Compiler for Cranelift {
             readonly: false,
         });
         context.func.stack_limit = Some(stack_limit);
+
+        // Branch-hint offsets are relative to the start of the function body,
+        // while source locations are relative to the start of the module.
+        if let Some(hints) = translation.branch_hints.get(&func_index) {
+            let body_offset = input.body.get_binary_reader().original_position() as u32;
+            func_env.branch_hints = hints
+                .iter()
+                .map(|&(offset, likely)| (body_offset + offset, likely))
+                .collect();
+        }
+
         let mut func_translator = self.take_translator();
         let result = func_translator.translate_body(
             &mut input.validator,
@@ -412,6 +494,11 @@ impl Compiler for Cranelift {
         }
         result?;
 
+        if let Some(profile) = &translation.profile {
+            let body_offset = input.body.get_binary_reader().original_position() as u32;
+            mark_cold_blocks(&mut context.func, profile, func_index, body_offset);
+        }
+
         let mut code_buf: Vec<u8> = Vec::new();
         let mut reloc_sink = RelocSink::new(func_index);
         let mut trap_sink = TrapSink::new();
@@ -444,7 +531,15 @@ impl Compiler for Cranelift {
             None
         };
 
-        Ok(CompiledFunction {
+        // Render the IR before the pieces of the function needed below are
+        // moved out of it.
+        let ir = if with_ir {
+            Some(context.func.display(isa).to_string())
+        } else {
+            None
+        };
+
+        let func = CompiledFunction {
             body: code_buf,
             jt_offsets: context.func.jt_offsets,
             relocations: reloc_sink.func_relocs,
@@ -454,6 +549,7 @@ impl Compiler for Cranelift {
             traps: trap_sink.traps,
             unwind_info,
             stack_maps: stack_map_sink.finish(),
-        })
+        };
+        Ok((func, ir))
     }
 }
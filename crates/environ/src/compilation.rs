@@ -106,4 +106,26 @@ pub trait Compiler: Send + Sync {
         tunables: &Tunables,
         types: &TypeTables,
     ) -> Result<CompiledFunction, CompileError>;
+
+    /// Compile a function like `compile_function`, additionally returning a
+    /// textual rendering of its final intermediate representation.
+    ///
+    /// This is intended for inspecting codegen decisions. Compilers without a
+    /// textual intermediate representation return `None`.
+    fn compile_function_with_ir(
+        &self,
+        translation: &ModuleTranslation<'_>,
+        index: DefinedFuncIndex,
+        data: FunctionBodyData<'_>,
+        isa: &dyn isa::TargetIsa,
+        tunables: &Tunables,
+        types: &TypeTables,
+    ) -> Result<(CompiledFunction, Option<String>), CompileError> {
+        let func = self.compile_function(translation, index, data, isa, tunables, types)?;
+        Ok((func, None))
+    }
+
+    /// Releases any state this compiler caches between compilations to speed
+    /// up subsequent ones.
+    fn purge(&self) {}
 }
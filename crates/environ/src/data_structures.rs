@@ -6,7 +6,7 @@ pub mod ir {
         types, AbiParam, ArgumentPurpose, JumpTableOffsets, LibCall, Signature, SourceLoc,
         StackSlots, TrapCode, Type, ValueLabel, ValueLoc,
     };
-    pub use cranelift_codegen::{ValueLabelsRanges, ValueLocRange};
+    pub use cranelift_codegen::{LabelValueLoc, ValueLabelsRanges, ValueLocRange};
 }
 
 pub mod settings {
@@ -15,7 +15,7 @@ pub mod settings {
 
 pub mod isa {
     pub use cranelift_codegen::isa::{
-        unwind, Builder, CallConv, RegUnit, TargetFrontendConfig, TargetIsa,
+        unwind, BackendVariant, Builder, CallConv, RegUnit, TargetFrontendConfig, TargetIsa,
     };
 }
 
@@ -29,6 +29,7 @@ mod compilation;
 mod data_structures;
 mod module;
 mod module_environ;
+mod profile;
 mod tunables;
 mod vmoffsets;
 
@@ -38,6 +39,7 @@ pub use crate::compilation::*;
 pub use crate::data_structures::*;
 pub use crate::module::*;
 pub use crate::module_environ::*;
+pub use crate::profile::ExecutionProfile;
 pub use crate::tunables::Tunables;
 pub use crate::vmoffsets::{TargetSharedSignatureIndex, VMOffsets, INTERRUPTED};
 
@@ -39,6 +39,30 @@ pub enum MemoryStyle {
 impl MemoryStyle {
     /// Decide on an implementation style for the given `Memory`.
     pub fn for_memory(memory: Memory, tunables: &Tunables) -> (Self, u64) {
+        // Shared memories may be accessed concurrently by other threads while
+        // they're being grown, so they must never be moved. Reserve their full
+        // maximum size up front; the threads proposal requires shared memories
+        // to declare a maximum.
+        if memory.shared {
+            let maximum = memory.maximum.unwrap_or(WASM_MAX_PAGES);
+            if memory.memory64 {
+                return (Self::Static { bound: maximum }, 0);
+            }
+            return (
+                Self::Static {
+                    bound: maximum.max(tunables.static_memory_bound),
+                },
+                tunables.static_memory_offset_guard_size,
+            );
+        }
+
+        // 64-bit memories are always bounds checked explicitly. A guard
+        // region can't elide any checks of 64-bit addresses, so don't reserve
+        // one.
+        if memory.memory64 {
+            return (Self::Dynamic, 0);
+        }
+
         // A heap with a maximum that doesn't exceed the static memory bound specified by the
         // tunables make it static.
         //
@@ -164,6 +188,7 @@ pub struct Module {
     pub table_elements: Vec<TableElements>,
 
     /// WebAssembly passive elements.
+    #[serde(serialize_with = "serialize_sorted")]
     pub passive_elements: HashMap<ElemIndex, Box<[FuncIndex]>>,
 
     /// WebAssembly passive data segments.
@@ -171,6 +196,7 @@ pub struct Module {
     pub passive_data: HashMap<DataIndex, Arc<[u8]>>,
 
     /// WebAssembly table initializers.
+    #[serde(serialize_with = "serialize_sorted")]
     pub func_names: HashMap<FuncIndex, String>,
 
     /// Types declared in the wasm module.
@@ -404,6 +430,19 @@ pub struct InstanceSignature {
     pub exports: IndexMap<String, EntityType>,
 }
 
+/// Serialize `map` with its entries sorted by key, so that the serialized
+/// form of a module doesn't depend on hash map iteration order.
+fn serialize_sorted<K, V, S>(map: &HashMap<K, V>, ser: S) -> Result<S::Ok, S::Error>
+where
+    K: Ord + Serialize,
+    V: Serialize,
+    S: serde::Serializer,
+{
+    let mut entries = map.iter().collect::<Vec<_>>();
+    entries.sort_unstable_by_key(|(k, _)| *k);
+    ser.collect_map(entries)
+}
+
 mod passive_data_serde {
     use super::{Arc, DataIndex, HashMap};
     use serde::{de::MapAccess, de::Visitor, ser::SerializeMap, Deserializer, Serializer};
@@ -416,8 +455,10 @@ mod passive_data_serde {
     where
         S: Serializer,
     {
-        let mut map = ser.serialize_map(Some(data.len()))?;
-        for (k, v) in data {
+        let mut entries = data.iter().collect::<Vec<_>>();
+        entries.sort_unstable_by_key(|(k, _)| *k);
+        let mut map = ser.serialize_map(Some(entries.len()))?;
+        for (k, v) in entries {
             map.serialize_entry(k, v.as_ref())?;
         }
         map.end()
@@ -2,6 +2,7 @@ use crate::module::{
     Initializer, InstanceSignature, MemoryPlan, Module, ModuleSignature, ModuleType, TableElements,
     TablePlan, TypeTables,
 };
+use crate::profile::ExecutionProfile;
 use crate::tunables::Tunables;
 use cranelift_codegen::ir;
 use cranelift_codegen::ir::{AbiParam, ArgumentPurpose};
@@ -68,6 +69,15 @@ pub struct ModuleTranslation<'data> {
     /// configuration.
     pub has_unparsed_debuginfo: bool,
 
+    /// Branch hints from the `metadata.code.branch_hint` custom section: for
+    /// each function, a list of `(offset, likely)` pairs, where `offset` is
+    /// the offset of an `if` or `br_if` instruction from the start of the
+    /// function body and `likely` is whether the branch is likely taken.
+    pub branch_hints: HashMap<FuncIndex, Vec<(u32, bool)>>,
+
+    /// An embedder-supplied execution profile to guide code layout, if any.
+    pub profile: Option<ExecutionProfile>,
+
     /// When we're parsing the code section this will be incremented so we know
     /// which function is currently being defined.
     code_index: u32,
@@ -224,6 +234,30 @@ impl<'data> ModuleEnvironment<'data> {
         dwarf.ranges = gimli::RangeLists::new(info.debug_ranges, info.debug_rnglists);
         dwarf.locations = gimli::LocationLists::new(info.debug_loc, info.debug_loclists);
     }
+
+    /// Parse the branch-hinting proposal's `metadata.code.branch_hint` custom
+    /// section.
+    fn parse_branch_hints(&mut self, data: &[u8]) -> WasmResult<()> {
+        let mut reader = wasmparser::BinaryReader::new(data);
+        for _ in 0..reader.read_var_u32()? {
+            let func_index = FuncIndex::from_u32(reader.read_var_u32()?);
+            let mut hints = Vec::new();
+            for _ in 0..reader.read_var_u32()? {
+                let offset = reader.read_var_u32()?;
+                let size = reader.read_var_u32()?;
+                if size != 1 {
+                    return Err(WasmError::InvalidWebAssembly {
+                        message: format!("invalid branch hint size {}", size),
+                        offset: reader.original_position(),
+                    });
+                }
+                let likely = reader.read_u8()? != 0;
+                hints.push((offset, likely));
+            }
+            self.result.branch_hints.insert(func_index, hints);
+        }
+        Ok(())
+    }
 }
 
 impl<'data> TargetEnvironment for ModuleEnvironment<'data> {
@@ -719,6 +753,16 @@ impl<'data> cranelift_wasm::ModuleEnvironment<'data> for ModuleEnvironment<'data
         self.register_dwarf_section(name, data);
 
         match name {
+            "metadata.code.branch_hint" => {
+                // Hints only affect code layout, so a malformed section is
+                // ignored rather than failing the whole module.
+                if let Err(e) = self.parse_branch_hints(data) {
+                    log::warn!("failed to parse branch hints: {}", e);
+                    self.result.branch_hints.clear();
+                }
+                Ok(())
+            }
+
             "webidl-bindings" | "wasm-interface-types" => Err(WasmError::Unsupported(
                 "\
 Support for interface types has temporarily been removed from `wasmtime`.
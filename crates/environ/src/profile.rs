@@ -0,0 +1,85 @@
+//! Embedder-supplied execution profiles, used to guide code layout.
+
+use cranelift_wasm::FuncIndex;
+use std::collections::BTreeMap;
+
+/// A block whose execution count is below this fraction of its function's
+/// entry count is considered cold.
+const COLD_BLOCK_RATIO: u64 = 100;
+
+/// An execution profile for a module, recording how hot its functions and
+/// the blocks within them are.
+///
+/// Function hotness is the number of times a function was entered. Block
+/// hotness is the number of times the code starting at a given instruction
+/// was executed, where the instruction is identified by its byte offset from
+/// the start of the function body (the start of the local declarations), as
+/// in the branch-hinting proposal.
+///
+/// Functions are identified by their index in the module's function index
+/// space, imports included.
+///
+/// The counts only need to be meaningful relative to each other, so samples
+/// from a sampling profiler work as well as exact counts.
+#[derive(Clone, Debug, Default, Hash, PartialEq, Eq)]
+pub struct ExecutionProfile {
+    funcs: BTreeMap<u32, u64>,
+    blocks: BTreeMap<(u32, u32), u64>,
+}
+
+impl ExecutionProfile {
+    /// Creates a new, empty profile.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records that the function `func` was entered `count` times.
+    pub fn set_function_count(&mut self, func: u32, count: u64) -> &mut Self {
+        self.funcs.insert(func, count);
+        self
+    }
+
+    /// Records that the code starting at byte offset `offset` of the body of
+    /// function `func` was executed `count` times.
+    pub fn set_block_count(&mut self, func: u32, offset: u32, count: u64) -> &mut Self {
+        self.blocks.insert((func, offset), count);
+        self
+    }
+
+    /// Returns how many times `func` was entered, if known.
+    pub fn function_count(&self, func: FuncIndex) -> Option<u64> {
+        self.funcs.get(&func.as_u32()).cloned()
+    }
+
+    /// Returns the recorded `(offset, count)` pairs for the blocks of `func`,
+    /// in order of offset.
+    pub fn block_counts(&self, func: FuncIndex) -> impl Iterator<Item = (u32, u64)> + '_ {
+        self.blocks
+            .range((func.as_u32(), 0)..=(func.as_u32(), u32::max_value()))
+            .map(|(&(_, offset), &count)| (offset, count))
+    }
+
+    /// Returns the body offsets of the blocks of `func` that are cold: those
+    /// executed less than 1/100th as often as the function was entered. If the
+    /// function's entry count is not known, its hottest block's count is used
+    /// instead.
+    pub fn cold_blocks(&self, func: FuncIndex) -> impl Iterator<Item = u32> + '_ {
+        let reference = self
+            .function_count(func)
+            .or_else(|| self.block_counts(func).map(|(_, count)| count).max())
+            .unwrap_or(0);
+        self.block_counts(func)
+            .filter(move |&(_, count)| count.saturating_mul(COLD_BLOCK_RATIO) < reference)
+            .map(|(offset, _)| offset)
+    }
+
+    /// Orders `funcs` for layout: functions with a nonzero entry count come
+    /// first, hottest first, followed by the rest in their original order.
+    pub fn order_functions(&self, funcs: &mut [FuncIndex]) {
+        // `sort_by_key` is stable, so ties keep their original order.
+        funcs.sort_by_key(|&f| match self.function_count(f) {
+            Some(count) if count > 0 => (0, u64::max_value() - count),
+            _ => (1, 0),
+        });
+    }
+}
@@ -20,16 +20,30 @@ use arbitrary::{Arbitrary, Unstructured};
 pub struct DifferentialConfig {
     strategy: DifferentialStrategy,
     opt_level: OptLevel,
+    backend: Backend,
+    regalloc: Regalloc,
 }
 
 impl DifferentialConfig {
     /// Convert this differential fuzzing config into a `wasmtime::Config`.
+    ///
+    /// Fails if the config asks for something that was turned off at compile
+    /// time, such as Lightbeam or a Cranelift backend.
     pub fn to_wasmtime_config(&self) -> anyhow::Result<wasmtime::Config> {
         let mut config = crate::fuzz_default_config(match self.strategy {
             DifferentialStrategy::Cranelift => wasmtime::Strategy::Cranelift,
             DifferentialStrategy::Lightbeam => wasmtime::Strategy::Lightbeam,
         })?;
-        config.cranelift_opt_level(self.opt_level.to_wasmtime());
+        config
+            .cranelift_opt_level(self.opt_level.to_wasmtime())
+            .cranelift_regalloc(match self.regalloc {
+                Regalloc::Backtracking => wasmtime::RegallocAlgorithm::Backtracking,
+                Regalloc::LinearScan => wasmtime::RegallocAlgorithm::LinearScan,
+            })
+            .cranelift_backend(match self.backend {
+                Backend::Legacy => wasmtime::CraneliftBackend::Legacy,
+                Backend::MachInst => wasmtime::CraneliftBackend::MachInst,
+            })?;
         Ok(config)
     }
 }
@@ -40,11 +54,24 @@ enum DifferentialStrategy {
     Lightbeam,
 }
 
+#[derive(Arbitrary, Clone, Debug, PartialEq, Eq, Hash)]
+enum Backend {
+    Legacy,
+    MachInst,
+}
+
+#[derive(Arbitrary, Clone, Debug, PartialEq, Eq, Hash)]
+enum Regalloc {
+    Backtracking,
+    LinearScan,
+}
+
 #[derive(Arbitrary, Clone, Debug, PartialEq, Eq, Hash)]
 enum OptLevel {
     None,
     Speed,
     SpeedAndSize,
+    Size,
 }
 
 impl OptLevel {
@@ -53,6 +80,7 @@ impl OptLevel {
             OptLevel::None => wasmtime::OptLevel::None,
             OptLevel::Speed => wasmtime::OptLevel::Speed,
             OptLevel::SpeedAndSize => wasmtime::OptLevel::SpeedAndSize,
+            OptLevel::Size => wasmtime::OptLevel::Size,
         }
     }
 }
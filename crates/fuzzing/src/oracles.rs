@@ -147,6 +147,7 @@ pub fn differential_execution(
     };
 
     let mut export_func_results: HashMap<String, Result<Box<[Val]>, Trap>> = Default::default();
+    let mut export_states: Option<Vec<(String, ExportState)>> = None;
     let wasm = module.to_bytes();
     log_wasm(&wasm);
 
@@ -178,6 +179,7 @@ pub fn differential_execution(
             }
         };
 
+        let mut trapped = false;
         for (name, f) in instance.exports().filter_map(|e| {
             let name = e.name();
             e.into_func().map(|f| (name, f))
@@ -189,12 +191,25 @@ pub fn differential_execution(
             let ty = f.ty();
             let params = dummy::dummy_values(ty.params());
             let this_result = f.call(&params).map_err(|e| e.downcast::<Trap>().unwrap());
+            trapped |= this_result.is_err();
 
             let existing_result = export_func_results
                 .entry(name.to_string())
                 .or_insert_with(|| this_result.clone());
             assert_same_export_func_result(&existing_result, &this_result, name);
         }
+
+        // Miscompiled stores don't necessarily show up in return values, so
+        // also compare the final state of exported memories and globals. Skip
+        // this if anything trapped, since how far execution got before a
+        // stack overflow depends on the configuration.
+        if !trapped {
+            let this_states = export_states_of(&instance);
+            match &export_states {
+                None => export_states = Some(this_states),
+                Some(existing_states) => assert_same_export_states(existing_states, &this_states),
+            }
+        }
     }
 
     fn init_hang_limit(instance: &Instance) {
@@ -228,21 +243,72 @@ pub fn differential_execution(
                     fail();
                 }
                 for (lhs, rhs) in lhs.iter().zip(rhs.iter()) {
-                    match (lhs, rhs) {
-                        (Val::I32(lhs), Val::I32(rhs)) if lhs == rhs => continue,
-                        (Val::I64(lhs), Val::I64(rhs)) if lhs == rhs => continue,
-                        (Val::V128(lhs), Val::V128(rhs)) if lhs == rhs => continue,
-                        (Val::F32(lhs), Val::F32(rhs)) if f32_equal(*lhs, *rhs) => continue,
-                        (Val::F64(lhs), Val::F64(rhs)) if f64_equal(*lhs, *rhs) => continue,
-                        (Val::ExternRef(_), Val::ExternRef(_))
-                        | (Val::FuncRef(_), Val::FuncRef(_)) => continue,
-                        _ => fail(),
+                    if !same_val(lhs, rhs) {
+                        fail();
                     }
                 }
             }
             _ => fail(),
         }
     }
+
+    #[derive(Debug)]
+    enum ExportState {
+        Memory(Vec<u8>),
+        Global(Val),
+    }
+
+    fn export_states_of(instance: &Instance) -> Vec<(String, ExportState)> {
+        instance
+            .exports()
+            .filter_map(|e| {
+                let name = e.name().to_string();
+                match e.into_extern() {
+                    Extern::Memory(m) => {
+                        // Safe because nothing else is accessing the memory.
+                        let data = unsafe { m.data_unchecked().to_vec() };
+                        Some((name, ExportState::Memory(data)))
+                    }
+                    Extern::Global(g) => Some((name, ExportState::Global(g.get()))),
+                    _ => None,
+                }
+            })
+            .collect()
+    }
+
+    fn assert_same_export_states(lhs: &[(String, ExportState)], rhs: &[(String, ExportState)]) {
+        assert_eq!(lhs.len(), rhs.len());
+        for ((name, lhs), (_, rhs)) in lhs.iter().zip(rhs.iter()) {
+            match (lhs, rhs) {
+                (ExportState::Memory(lhs), ExportState::Memory(rhs)) if lhs != rhs => panic!(
+                    "differential fuzzing failed: exported memory {} has different \
+                     contents (sizes {} and {}, first difference at byte {:?})",
+                    name,
+                    lhs.len(),
+                    rhs.len(),
+                    lhs.iter().zip(rhs.iter()).position(|(a, b)| a != b),
+                ),
+                (ExportState::Memory(_), ExportState::Memory(_)) => {}
+                (ExportState::Global(lhs), ExportState::Global(rhs)) if same_val(lhs, rhs) => {}
+                _ => panic!(
+                    "differential fuzzing failed: exported {} differs: {:?} != {:?}",
+                    name, lhs, rhs
+                ),
+            }
+        }
+    }
+}
+
+fn same_val(lhs: &Val, rhs: &Val) -> bool {
+    match (lhs, rhs) {
+        (Val::I32(lhs), Val::I32(rhs)) => lhs == rhs,
+        (Val::I64(lhs), Val::I64(rhs)) => lhs == rhs,
+        (Val::V128(lhs), Val::V128(rhs)) => lhs == rhs,
+        (Val::F32(lhs), Val::F32(rhs)) => f32_equal(*lhs, *rhs),
+        (Val::F64(lhs), Val::F64(rhs)) => f64_equal(*lhs, *rhs),
+        (Val::ExternRef(_), Val::ExternRef(_)) | (Val::FuncRef(_), Val::FuncRef(_)) => true,
+        _ => false,
+    }
 }
 
 fn f32_equal(a: u32, b: u32) -> bool {
@@ -491,7 +557,8 @@ impl wasm_smith::Config for DifferentialWasmiModuleConfig {
 }
 
 /// Perform differential execution between Cranelift and wasmi, diffing the
-/// resulting memory image when execution terminates. This relies on the
+/// returned value or trap code and the resulting memory image when execution
+/// terminates. This relies on the
 /// module-under-test to be instrumented to bound the execution time. Invoke
 /// with a module generated by `wasm-smith` using the
 /// `DiferentialWasmiModuleConfig` configuration type for best results.
@@ -568,6 +635,20 @@ pub fn differential_wasmi_execution(wasm: &[u8], config: &crate::generators::Con
         }
     };
 
+    // The two engines have different limits on stack depth, so one may
+    // overflow where the other doesn't; nothing meaningful to compare then.
+    let wasmi_overflowed = match &wasmi_val {
+        Err(wasmi::Error::Trap(t)) => matches!(t.kind(), wasmi::TrapKind::StackOverflow),
+        _ => false,
+    };
+    let wasmtime_overflowed = match &wasmtime_val {
+        Err(e) => wasmtime_trap_code(e) == Some(TrapCode::StackOverflow),
+        _ => false,
+    };
+    if wasmi_overflowed || wasmtime_overflowed {
+        return None;
+    }
+
     match (&wasmi_val, &wasmtime_val) {
         (&Ok(Some(wasmi::RuntimeValue::I32(a))), &Ok(Some(Val::I32(b)))) if a == b => {}
         (&Ok(Some(wasmi::RuntimeValue::F32(a))), &Ok(Some(Val::F32(b))))
@@ -576,7 +657,8 @@ pub fn differential_wasmi_execution(wasm: &[u8], config: &crate::generators::Con
         (&Ok(Some(wasmi::RuntimeValue::F64(a))), &Ok(Some(Val::F64(b))))
             if f64_equal(a.to_bits(), b) => {}
         (&Ok(None), &Ok(None)) => {}
-        (&Err(_), &Err(_)) => {}
+        (Err(wasmi::Error::Trap(a)), Err(b))
+            if wasmtime_trap_code(b).map_or(false, |b| same_trap(a.kind(), b)) => {}
         _ => {
             show_wat();
             panic!(
@@ -615,6 +697,31 @@ pub fn differential_wasmi_execution(wasm: &[u8], config: &crate::generators::Con
     Some(())
 }
 
+fn wasmtime_trap_code(err: &anyhow::Error) -> Option<TrapCode> {
+    err.downcast_ref::<Trap>()?.trap_code()
+}
+
+/// Returns whether a wasmi trap and a Wasmtime trap describe the same
+/// condition.
+///
+/// wasmi reports signed division overflow (`i32.div_s` of `INT_MIN` by -1) as
+/// an invalid conversion rather than a dedicated overflow trap, so that pairing
+/// is accepted as well.
+fn same_trap(wasmi: &wasmi::TrapKind, wasmtime: TrapCode) -> bool {
+    use wasmi::TrapKind;
+    match (wasmi, wasmtime) {
+        (TrapKind::Unreachable, TrapCode::UnreachableCodeReached)
+        | (TrapKind::MemoryAccessOutOfBounds, TrapCode::MemoryOutOfBounds)
+        | (TrapKind::TableAccessOutOfBounds, TrapCode::TableOutOfBounds)
+        | (TrapKind::ElemUninitialized, TrapCode::IndirectCallToNull)
+        | (TrapKind::UnexpectedSignature, TrapCode::BadSignature)
+        | (TrapKind::DivisionByZero, TrapCode::IntegerDivisionByZero)
+        | (TrapKind::InvalidConversionToInt, TrapCode::BadConversionToInteger)
+        | (TrapKind::InvalidConversionToInt, TrapCode::IntegerOverflow) => true,
+        _ => false,
+    }
+}
+
 #[derive(Default)]
 struct SignalOnDrop {
     state: Arc<(Mutex<bool>, Condvar)>,
@@ -2,20 +2,29 @@
 
 use crate::instantiate::SetupError;
 use crate::object::{build_object, ObjectUnwindInfo};
+use cranelift_codegen::timing;
 use object::write::Object;
 #[cfg(feature = "parallel-compilation")]
 use rayon::prelude::*;
 use std::hash::{Hash, Hasher};
 use std::mem;
+use std::time::{Duration, Instant};
 use wasmparser::WasmFeatures;
+#[cfg(feature = "cache")]
+use wasmtime_cache::{FunctionCache, ModuleFunctions};
 use wasmtime_debug::{emit_dwarf, DwarfSection};
-use wasmtime_environ::entity::EntityRef;
+use wasmtime_environ::entity::{EntityRef, PrimaryMap};
+#[cfg(feature = "cache")]
+use wasmtime_environ::ir;
 use wasmtime_environ::isa::{TargetFrontendConfig, TargetIsa};
-use wasmtime_environ::wasm::{DefinedMemoryIndex, MemoryIndex};
+use wasmtime_environ::wasm::{DefinedFuncIndex, DefinedMemoryIndex, MemoryIndex};
+#[cfg(feature = "cache")]
+use wasmtime_environ::{wasm::WasmError, FunctionBodyData};
 use wasmtime_environ::{
-    CompiledFunctions, Compiler as EnvCompiler, DebugInfoData, Module, ModuleMemoryOffset,
-    ModuleTranslation, Tunables, TypeTables, VMOffsets,
+    CompileError, CompiledFunction, CompiledFunctions, Compiler as EnvCompiler, DebugInfoData,
+    Module, ModuleMemoryOffset, ModuleTranslation, Tunables, TypeTables, VMOffsets,
 };
+use wasmtime_runtime::InstantiationError;
 
 /// Select which kind of compilation to use.
 #[derive(Copy, Clone, Debug, Hash)]
@@ -45,6 +54,10 @@ pub struct Compiler {
     strategy: CompilationStrategy,
     tunables: Tunables,
     features: WasmFeatures,
+    verify_determinism: bool,
+    compile_stats: bool,
+    #[cfg(feature = "cache")]
+    function_cache: Option<FunctionCache>,
 }
 
 impl Compiler {
@@ -67,8 +80,36 @@ impl Compiler {
             },
             tunables,
             features,
+            verify_determinism: false,
+            compile_stats: false,
+            #[cfg(feature = "cache")]
+            function_cache: None,
         }
     }
+
+    /// Measure the time spent compiling each function, and in each Cranelift
+    /// pass while doing so, reporting it in `Compilation::stats`.
+    pub fn set_compile_stats(&mut self, enable: bool) {
+        self.compile_stats = enable;
+    }
+
+    /// Compile every module twice and check that both compilations produce
+    /// identical code.
+    ///
+    /// See `CompilationArtifacts::build` for where this is checked.
+    pub fn set_verify_determinism(&mut self, enable: bool) {
+        self.verify_determinism = enable;
+    }
+
+    /// Look up and store individual functions in `cache` when compiling.
+    ///
+    /// Functions are keyed by their body, their index and everything in the
+    /// module they're part of which affects their code, so that recompiling
+    /// a module in which only some functions changed reuses the others.
+    #[cfg(feature = "cache")]
+    pub fn set_function_cache(&mut self, cache: FunctionCache) {
+        self.function_cache = Some(cache);
+    }
 }
 
 fn _assert_compiler_send_sync() {
@@ -100,6 +141,18 @@ pub struct Compilation {
     pub obj: Object,
     pub unwind_info: Vec<ObjectUnwindInfo>,
     pub funcs: CompiledFunctions,
+    pub stats: Option<PrimaryMap<DefinedFuncIndex, FunctionCompileStats>>,
+}
+
+/// The time spent compiling a single function, collected when enabled with
+/// `Compiler::set_compile_stats`.
+#[derive(Debug, Clone)]
+pub struct FunctionCompileStats {
+    /// The total time spent compiling the function.
+    pub total: Duration,
+    /// The description of each Cranelift pass that ran while compiling the
+    /// function and the time spent in it, excluding nested passes.
+    pub passes: Vec<(&'static str, Duration)>,
 }
 
 impl Compiler {
@@ -123,28 +176,92 @@ impl Compiler {
         &self.features
     }
 
-    /// Compile the given function bodies.
-    pub fn compile<'data>(
+    /// Returns whether compilations are checked for determinism.
+    pub fn verify_determinism(&self) -> bool {
+        self.verify_determinism
+    }
+
+    /// Releases state cached by the underlying compiler between compilations.
+    pub fn purge(&self) {
+        self.compiler.purge();
+    }
+
+    /// Compile the given function bodies, returning each function's machine
+    /// code alongside its final IR, if the underlying compiler can render it.
+    ///
+    /// Unlike `compile` this doesn't produce anything which can be linked and
+    /// executed; it's intended for inspecting generated code.
+    pub fn compile_with_ir(
         &self,
         translation: &mut ModuleTranslation,
         types: &TypeTables,
-    ) -> Result<Compilation, SetupError> {
+    ) -> Result<Vec<(DefinedFuncIndex, CompiledFunction, Option<String>)>, SetupError> {
         let functions = mem::take(&mut translation.function_body_inputs);
         let functions = functions.into_iter().collect::<Vec<_>>();
         let funcs = maybe_parallel!(functions.(into_iter | into_par_iter))
             .map(|(index, func)| {
-                self.compiler.compile_function(
+                let (func, ir) = self.compiler.compile_function_with_ir(
                     translation,
                     index,
                     func,
                     &*self.isa,
                     &self.tunables,
                     types,
-                )
+                )?;
+                Ok((index, func, ir))
             })
-            .collect::<Result<Vec<_>, _>>()?
-            .into_iter()
-            .collect::<CompiledFunctions>();
+            .collect::<Result<Vec<_>, CompileError>>()?;
+        Ok(funcs)
+    }
+
+    /// Compile the given function bodies.
+    pub fn compile<'data>(
+        &self,
+        translation: &mut ModuleTranslation,
+        types: &TypeTables,
+    ) -> Result<Compilation, SetupError> {
+        #[cfg(feature = "cache")]
+        let function_cache = self
+            .function_cache
+            .as_ref()
+            .map(|cache| cache.module(self, &(&translation.module, types)));
+
+        let functions = mem::take(&mut translation.function_body_inputs);
+        let functions = functions.into_iter().collect::<Vec<_>>();
+        let results = maybe_parallel!(functions.(into_iter | into_par_iter))
+            .map(|(index, func)| {
+                let (func, stats) = self.measure(|| {
+                    #[cfg(feature = "cache")]
+                    {
+                        if let Some(cache) = &function_cache {
+                            return self.compile_function_cached(
+                                cache,
+                                translation,
+                                index,
+                                func,
+                                types,
+                            );
+                        }
+                    }
+                    self.compiler.compile_function(
+                        translation,
+                        index,
+                        func,
+                        &*self.isa,
+                        &self.tunables,
+                        types,
+                    )
+                });
+                Ok((func?, stats))
+            })
+            .collect::<Result<Vec<_>, CompileError>>()?;
+        let (funcs, stats): (Vec<_>, Vec<_>) = results.into_iter().unzip();
+        let funcs = funcs.into_iter().collect::<CompiledFunctions>();
+        let stats = if self.compile_stats {
+            Some(stats.into_iter().map(Option::unwrap).collect())
+        } else {
+            None
+        };
 
         let dwarf_sections = if self.tunables.generate_native_debuginfo && !funcs.is_empty() {
             transform_dwarf_data(
@@ -164,8 +281,158 @@ impl Compiler {
             obj,
             unwind_info,
             funcs,
+            stats,
         })
     }
+
+    /// Run `f`, which compiles a single function on the current thread,
+    /// measuring the time it takes if compile stats are enabled.
+    fn measure<T>(&self, f: impl FnOnce() -> T) -> (T, Option<FunctionCompileStats>) {
+        if !self.compile_stats {
+            return (f(), None);
+        }
+
+        // Cranelift accumulates pass timings per thread, so set aside
+        // whatever was accumulated before compiling this function and
+        // restore it afterwards.
+        let outer = timing::take_current();
+        let start = Instant::now();
+        let result = f();
+        let total = start.elapsed();
+        let times = timing::take_current();
+        timing::add_to_current(&outer);
+        timing::add_to_current(&times);
+
+        let passes = times
+            .passes()
+            .map(|(desc, _total, own)| (desc, own))
+            .collect();
+        (result, Some(FunctionCompileStats { total, passes }))
+    }
+
+    /// Compile `translation` and `reference`, two translations of the same
+    /// module, and check that they produce identical code.
+    ///
+    /// With parallel compilation enabled the two compilations run
+    /// concurrently, so functions are generally compiled on different
+    /// threads each time.
+    pub fn compile_verified(
+        &self,
+        translation: &mut ModuleTranslation,
+        reference: &mut ModuleTranslation,
+        types: &TypeTables,
+    ) -> Result<(Compilation, Vec<u8>), SetupError> {
+        #[cfg(feature = "parallel-compilation")]
+        let (compilation, expected) = rayon::join(
+            || self.compile(translation, types),
+            || self.compile(reference, types),
+        );
+        #[cfg(not(feature = "parallel-compilation"))]
+        let (compilation, expected) = (
+            self.compile(translation, types),
+            self.compile(reference, types),
+        );
+        let (compilation, expected) = (compilation?, expected?);
+
+        for (index, func) in compilation.funcs.iter() {
+            if *func != expected.funcs[index] {
+                return Err(nondeterminism(format!(
+                    "function {} of module {:?} compiled to different code",
+                    translation.module.func_index(index).index(),
+                    translation.module.name.as_deref().unwrap_or("<unnamed>"),
+                )));
+            }
+        }
+
+        let obj = write_object(&compilation.obj)?;
+        if obj != write_object(&expected.obj)? {
+            return Err(nondeterminism(format!(
+                "module {:?} produced different object files",
+                translation.module.name.as_deref().unwrap_or("<unnamed>"),
+            )));
+        }
+
+        Ok((compilation, obj))
+    }
+}
+
+/// Write out the image of a compiled module.
+pub(crate) fn write_object(obj: &Object) -> Result<Vec<u8>, SetupError> {
+    obj.write().map_err(|_| {
+        SetupError::Instantiate(InstantiationError::Resource(
+            "failed to create image memory".to_string(),
+        ))
+    })
+}
+
+fn nondeterminism(msg: String) -> SetupError {
+    SetupError::Compile(CompileError::Codegen(format!(
+        "nondeterministic compilation: {}",
+        msg
+    )))
+}
+
+#[cfg(feature = "cache")]
+impl Compiler {
+    fn compile_function_cached(
+        &self,
+        cache: &ModuleFunctions<'_>,
+        translation: &ModuleTranslation<'_>,
+        index: DefinedFuncIndex,
+        func: FunctionBodyData<'_>,
+        types: &TypeTables,
+    ) -> Result<CompiledFunction, CompileError> {
+        let mut reader = func.body.get_binary_reader();
+        let start = reader.original_position() as u32;
+        let body = reader
+            .read_bytes(reader.bytes_remaining())
+            .map_err(|e| CompileError::Wasm(WasmError::from(e)))?;
+
+        // Source locations are offsets into the whole wasm binary, so they
+        // change whenever an earlier function is edited. Cache them relative
+        // to the start of the function body instead.
+        //
+        // Note that a cache hit also skips validating the body; that's fine
+        // since the key covers everything validation depends on. Branch hints
+        // and profile data are part of the key too since they affect code
+        // layout.
+        let func_index = translation.module.func_index(index);
+        let hints = translation.branch_hints.get(&func_index);
+        let profile = translation.profile.as_ref().map(|p| {
+            let blocks = p.block_counts(func_index).collect::<Vec<_>>();
+            (p.function_count(func_index), blocks)
+        });
+        let mut compiled = cache.get_data((index, body, hints, profile), || {
+            let mut compiled = self.compiler.compile_function(
+                translation,
+                index,
+                func,
+                &*self.isa,
+                &self.tunables,
+                types,
+            )?;
+            map_srclocs(&mut compiled, |offset| offset.wrapping_sub(start));
+            Ok::<_, CompileError>(compiled)
+        })?;
+        map_srclocs(&mut compiled, |offset| offset.wrapping_add(start));
+        Ok(compiled)
+    }
+}
+
+/// Replace the offset of every source location in `func` with `f(offset)`.
+#[cfg(feature = "cache")]
+fn map_srclocs(func: &mut CompiledFunction, f: impl Fn(u32) -> u32) {
+    let map = |srcloc: &mut ir::SourceLoc| {
+        if !srcloc.is_default() {
+            *srcloc = ir::SourceLoc::new(f(srcloc.bits()));
+        }
+    };
+    let address_map = &mut func.address_map;
+    for inst in address_map.instructions.iter_mut() {
+        map(&mut inst.srcloc);
+    }
+    map(&mut address_map.start_srcloc);
+    map(&mut address_map.end_srcloc);
 }
 
 impl Hash for Compiler {
@@ -176,6 +443,10 @@ impl Hash for Compiler {
             isa,
             tunables,
             features,
+            verify_determinism: _,
+            compile_stats: _,
+            #[cfg(feature = "cache")]
+                function_cache: _,
         } = self;
 
         // Hash compiler's flags: compilation strategy, isa, frontend config,
@@ -187,7 +458,10 @@ impl Hash for Compiler {
         // a native hashing ability of flags into cranelift itself, but
         // compilation and/or cache loading is relatively expensive so seems
         // unlikely.
-        isa.flags().to_string().hash(hasher);
+        //
+        // The `Display` of an ISA includes both shared and ISA-specific flags,
+        // so code compiled for different CPU features is never mixed up.
+        isa.to_string().hash(hasher);
         isa.frontend_config().hash(hasher);
         tunables.hash(hasher);
 
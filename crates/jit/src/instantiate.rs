@@ -4,7 +4,7 @@
 //! steps.
 
 use crate::code_memory::CodeMemory;
-use crate::compiler::{Compilation, Compiler};
+use crate::compiler::{write_object, Compilation, Compiler, FunctionCompileStats};
 use crate::link::link_module;
 use crate::object::ObjectUnwindInfo;
 use object::File as ObjectFile;
@@ -22,15 +22,15 @@ use wasmtime_environ::wasm::{
     DefinedFuncIndex, InstanceTypeIndex, ModuleTypeIndex, SignatureIndex, WasmFuncType,
 };
 use wasmtime_environ::{
-    CompileError, DataInitializer, DataInitializerLocation, DebugInfoData, FunctionAddressMap,
-    InstanceSignature, Module, ModuleEnvironment, ModuleSignature, ModuleTranslation,
-    StackMapInformation, TrapInformation,
+    CompileError, DataInitializer, DataInitializerLocation, DebugInfoData, ExecutionProfile,
+    FunctionAddressMap, InstanceSignature, Module, ModuleEnvironment, ModuleSignature,
+    ModuleTranslation, StackMapInformation, TrapInformation,
 };
 use wasmtime_profiling::ProfilingAgent;
 use wasmtime_runtime::{
-    GdbJitImageRegistration, Imports, InstanceHandle, InstantiationError, RuntimeMemoryCreator,
-    StackMapRegistry, VMExternRefActivationsTable, VMFunctionBody, VMInterrupts,
-    VMSharedSignatureIndex, VMTrampoline,
+    GdbJitImageRegistration, Imports, InstanceHandle, InstanceLimits, InstantiationError,
+    RuntimeMemoryCreator, StackMapRegistry, VMExternRefActivationsTable, VMFunctionBody,
+    VMInterrupts, VMSharedSignatureIndex, VMTrampoline,
 };
 
 /// An error condition while setting up a wasm instance, be it validation,
@@ -83,6 +83,12 @@ pub struct CompilationArtifacts {
     /// Debug information found in the wasm file, used for symbolicating
     /// backtraces.
     debug_info: Option<DebugInfo>,
+
+    /// The time spent compiling each function, if measured. This isn't
+    /// serialized since it's only meaningful for the compilation that
+    /// produced these artifacts.
+    #[serde(skip)]
+    compile_stats: Option<PrimaryMap<DefinedFuncIndex, FunctionCompileStats>>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -106,21 +112,59 @@ impl CompilationArtifacts {
         compiler: &Compiler,
         data: &[u8],
     ) -> Result<(Vec<CompilationArtifacts>, TypeTables), SetupError> {
-        let (translations, types) = ModuleEnvironment::new(
-            compiler.frontend_config(),
-            compiler.tunables(),
-            compiler.features(),
-        )
-        .translate(data)
-        .map_err(|error| SetupError::Compile(CompileError::Wasm(error)))?;
+        CompilationArtifacts::build_with_profile(compiler, data, None)
+    }
+
+    /// Like `build`, but uses `profile`, if given, to guide the code layout
+    /// of the outermost module.
+    pub fn build_with_profile(
+        compiler: &Compiler,
+        data: &[u8],
+        profile: Option<&ExecutionProfile>,
+    ) -> Result<(Vec<CompilationArtifacts>, TypeTables), SetupError> {
+        let translate = || {
+            let (mut translations, types) = ModuleEnvironment::new(
+                compiler.frontend_config(),
+                compiler.tunables(),
+                compiler.features(),
+            )
+            .translate(data)
+            .map_err(|error| SetupError::Compile(CompileError::Wasm(error)))?;
+
+            if let Some(translation) = translations.first_mut() {
+                translation.profile = profile.cloned();
+            }
+            Ok::<_, SetupError>((translations, types))
+        };
+        let (translations, types) = translate()?;
+
+        // Compiling a function consumes its validator, so checking
+        // determinism requires translating the whole module a second time.
+        let references = if compiler.verify_determinism() {
+            translate()?.0.into_iter().map(Some).collect::<Vec<_>>()
+        } else {
+            translations.iter().map(|_| None).collect()
+        };
+        let translations = translations.into_iter().zip(references).collect::<Vec<_>>();
 
         let list = maybe_parallel!(translations.(into_iter | into_par_iter))
-            .map(|mut translation| {
+            .map(|(mut translation, reference)| {
+                let (compilation, obj) = match reference {
+                    Some(mut reference) => {
+                        compiler.compile_verified(&mut translation, &mut reference, &types)?
+                    }
+                    None => {
+                        let compilation = compiler.compile(&mut translation, &types)?;
+                        let obj = write_object(&compilation.obj)?;
+                        (compilation, obj)
+                    }
+                };
                 let Compilation {
-                    obj,
                     unwind_info,
                     funcs,
-                } = compiler.compile(&mut translation, &types)?;
+                    stats,
+                    ..
+                } = compilation;
 
                 let ModuleTranslation {
                     module,
@@ -136,12 +180,6 @@ impl CompilationArtifacts {
                     .collect::<Vec<_>>()
                     .into_boxed_slice();
 
-                let obj = obj.write().map_err(|_| {
-                    SetupError::Instantiate(InstantiationError::Resource(
-                        "failed to create image memory".to_string(),
-                    ))
-                })?;
-
                 Ok(CompilationArtifacts {
                     module,
                     obj: obj.into_boxed_slice(),
@@ -162,6 +200,7 @@ impl CompilationArtifacts {
                         None
                     },
                     has_unparsed_debuginfo,
+                    compile_stats: stats,
                 })
             })
             .collect::<Result<Vec<_>, SetupError>>()?;
@@ -291,6 +330,7 @@ impl CompiledModule {
         imports: Imports<'_>,
         lookup_shared_signature: &dyn Fn(SignatureIndex) -> VMSharedSignatureIndex,
         mem_creator: Option<&dyn RuntimeMemoryCreator>,
+        limits: &InstanceLimits,
         interrupts: *const VMInterrupts,
         host_state: Box<dyn Any>,
         externref_activations_table: *mut VMExternRefActivationsTable,
@@ -301,6 +341,7 @@ impl CompiledModule {
             &self.finished_functions.0,
             imports,
             mem_creator,
+            limits,
             lookup_shared_signature,
             host_state,
             interrupts,
@@ -433,6 +474,12 @@ impl CompiledModule {
     pub fn has_unparsed_debuginfo(&self) -> bool {
         self.artifacts.has_unparsed_debuginfo
     }
+
+    /// Returns the time spent compiling each function of this module, if it
+    /// was compiled with compile stats enabled.
+    pub fn compile_stats(&self) -> Option<&PrimaryMap<DefinedFuncIndex, FunctionCompileStats>> {
+        self.artifacts.compile_stats.as_ref()
+    }
 }
 
 type Addr2LineContext<'a> = addr2line::Context<gimli::EndianSlice<'a, gimli::LittleEndian>>;
@@ -45,7 +45,7 @@ pub mod native;
 pub mod trampoline;
 
 pub use crate::code_memory::CodeMemory;
-pub use crate::compiler::{Compilation, CompilationStrategy, Compiler};
+pub use crate::compiler::{Compilation, CompilationStrategy, Compiler, FunctionCompileStats};
 pub use crate::instantiate::{
     CompilationArtifacts, CompiledModule, ModuleCode, SetupError, SymbolizeContext, TypeTables,
 };
@@ -6,9 +6,17 @@ pub fn builder() -> cranelift_codegen::isa::Builder {
     cranelift_native::builder().expect("host machine is not a supported target")
 }
 
+/// Returns a builder for the host target using the given backend variant, or
+/// an error if that variant isn't available for the host.
+pub fn builder_with_backend_variant(
+    variant: cranelift_codegen::isa::BackendVariant,
+) -> Result<cranelift_codegen::isa::Builder, &'static str> {
+    cranelift_native::builder_with_backend_variant(variant)
+}
+
 pub fn call_conv() -> cranelift_codegen::isa::CallConv {
     use target_lexicon::HOST;
     cranelift_codegen::isa::CallConv::triple_default(&HOST)
 }
 
-pub use cranelift_codegen::isa::lookup;
+pub use cranelift_codegen::isa::{lookup, lookup_variant};
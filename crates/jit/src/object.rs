@@ -63,6 +63,20 @@ pub(crate) fn build_object(
         .set_code_alignment(CODE_SECTION_ALIGNMENT)
         .set_trampolines(trampolines)
         .set_dwarf_sections(dwarf_sections);
+    // Lay out the hottest functions first, next to each other.
+    if let Some(profile) = &translation.profile {
+        let module = &translation.module;
+        let mut order = funcs
+            .keys()
+            .map(|i| module.func_index(i))
+            .collect::<Vec<_>>();
+        profile.order_functions(&mut order);
+        let order = order
+            .into_iter()
+            .map(|i| module.defined_func_index(i).unwrap())
+            .collect();
+        builder.set_function_order(order);
+    }
     let obj = builder.build()?;
 
     Ok((obj, unwind_info))
@@ -27,7 +27,7 @@ use object::{
 use std::collections::HashMap;
 use target_lexicon::Triple;
 use wasmtime_debug::{DwarfSection, DwarfSectionRelocTarget};
-use wasmtime_environ::entity::{EntityRef, PrimaryMap};
+use wasmtime_environ::entity::{EntityRef, PrimaryMap, SecondaryMap};
 use wasmtime_environ::ir::{LibCall, Reloc};
 use wasmtime_environ::isa::unwind::UnwindInfo;
 use wasmtime_environ::wasm::{DefinedFuncIndex, FuncIndex, SignatureIndex};
@@ -259,6 +259,7 @@ pub struct ObjectBuilder<'a> {
     compilation: &'a CompiledFunctions,
     trampolines: PrimaryMap<SignatureIndex, CompiledFunction>,
     dwarf_sections: Vec<DwarfSection>,
+    function_order: Option<Vec<DefinedFuncIndex>>,
 }
 
 impl<'a> ObjectBuilder<'a> {
@@ -273,6 +274,7 @@ impl<'a> ObjectBuilder<'a> {
             code_alignment: 1,
             trampolines: PrimaryMap::new(),
             dwarf_sections: vec![],
+            function_order: None,
             compilation,
         }
     }
@@ -295,6 +297,15 @@ impl<'a> ObjectBuilder<'a> {
         self
     }
 
+    /// Sets the order in which the compiled functions are laid out in the
+    /// text section. `order` must list every compiled function exactly once;
+    /// by default, functions are laid out in index order.
+    pub fn set_function_order(&mut self, order: Vec<DefinedFuncIndex>) -> &mut Self {
+        assert_eq!(order.len(), self.compilation.len());
+        self.function_order = Some(order);
+        self
+    }
+
     pub fn build(self) -> Result<Object, anyhow::Error> {
         let mut obj = Object::new(
             self.target.binary_format,
@@ -349,13 +360,22 @@ impl<'a> ObjectBuilder<'a> {
             symbol_id
         };
 
-        // Create symbols and section data for the compiled functions.
-        for (index, func) in self.compilation.iter() {
+        // Create symbols and section data for the compiled functions, in the
+        // requested layout order, and then record the symbols in index order.
+        let order = match &self.function_order {
+            Some(order) => order.clone(),
+            None => self.compilation.keys().collect(),
+        };
+        let mut defined_symbols = SecondaryMap::new();
+        for index in order {
             let name = utils::func_symbol_name(module.func_index(index))
                 .as_bytes()
                 .to_vec();
-            let symbol_id = append_func(name, func);
-            func_symbols.push(symbol_id);
+            let symbol_id = append_func(name, &self.compilation[index]);
+            defined_symbols[index] = Some(symbol_id);
+        }
+        for index in self.compilation.keys() {
+            func_symbols.push(defined_symbols[index].unwrap());
         }
         let mut trampolines = Vec::new();
         for (i, func) in self.trampolines.iter() {
@@ -31,7 +31,9 @@ use wasmtime_environ::wasm::{
     ElemIndex, EntityIndex, FuncIndex, GlobalIndex, GlobalInit, InstanceIndex, MemoryIndex,
     ModuleIndex, SignatureIndex, TableElementType, TableIndex, WasmType,
 };
-use wasmtime_environ::{ir, DataInitializer, Module, ModuleType, TableElements, VMOffsets};
+use wasmtime_environ::{
+    ir, DataInitializer, Module, ModuleType, TableElements, VMOffsets, WASM_PAGE_SIZE,
+};
 
 /// A WebAssembly instance.
 ///
@@ -785,6 +787,19 @@ impl Instance {
     }
 }
 
+/// Limits on the memories and tables defined by an instance.
+///
+/// The maximum size of each memory and table is clamped to these limits when
+/// the instance is created, so growing beyond them fails in the same way as
+/// growing beyond a declared maximum.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct InstanceLimits {
+    /// The maximum size of each linear memory, in wasm pages.
+    pub memory_pages: Option<u32>,
+    /// The maximum number of elements in each table.
+    pub table_elements: Option<u32>,
+}
+
 /// A handle holding an `Instance` of a WebAssembly module.
 #[derive(Hash, PartialEq, Eq)]
 pub struct InstanceHandle {
@@ -811,11 +826,15 @@ impl InstanceHandle {
     /// It is your responsibility to ensure that the given raw
     /// `externref_activations_table` and `stack_map_registry` outlive this
     /// instance.
+    ///
+    /// Fails with `InstantiationError::Resource` if the minimum size of a
+    /// memory or table defined by the module exceeds `limits`.
     pub unsafe fn new(
         module: Arc<Module>,
         finished_functions: &PrimaryMap<DefinedFuncIndex, *mut [VMFunctionBody]>,
         imports: Imports,
         mem_creator: Option<&dyn RuntimeMemoryCreator>,
+        limits: &InstanceLimits,
         lookup_shared_signature: &dyn Fn(SignatureIndex) -> VMSharedSignatureIndex,
         host_state: Box<dyn Any>,
         interrupts: *const VMInterrupts,
@@ -825,8 +844,12 @@ impl InstanceHandle {
         debug_assert!(!externref_activations_table.is_null());
         debug_assert!(!stack_map_registry.is_null());
 
-        let tables = create_tables(&module);
-        let memories = create_memories(&module, mem_creator.unwrap_or(&DefaultMemoryCreator {}))?;
+        let tables = create_tables(&module, limits)?;
+        let memories = create_memories(
+            &module,
+            mem_creator.unwrap_or(&DefaultMemoryCreator {}),
+            limits,
+        )?;
 
         let vmctx_tables = tables
             .values()
@@ -1052,6 +1075,16 @@ impl InstanceHandle {
         self.instance().memory_grow(memory_index, delta)
     }
 
+    /// Returns the total size, in bytes, of the memories defined by this
+    /// instance. Imported memories aren't included.
+    pub fn defined_memory_bytes(&self) -> usize {
+        self.instance()
+            .memories
+            .values()
+            .map(|m| m.size() as usize * WASM_PAGE_SIZE as usize)
+            .sum()
+    }
+
     /// Return the table index for the given `VMTableDefinition` in this instance.
     pub fn table_index(&self, table: &VMTableDefinition) -> DefinedTableIndex {
         self.instance().table_index(table)
@@ -1180,14 +1213,25 @@ fn get_memory_init_start(init: &DataInitializer<'_>, instance: &Instance) -> usi
     let mut start = init.location.offset;
 
     if let Some(base) = init.location.base {
-        let val = unsafe {
+        let global = unsafe {
             if let Some(def_index) = instance.module.defined_global_index(base) {
-                *instance.global(def_index).as_u32()
+                instance.global(def_index)
             } else {
-                *(*instance.imported_global(base).from).as_u32()
+                *instance.imported_global(base).from
             }
         };
-        start += usize::try_from(val).unwrap();
+        // Offsets into 64-bit memories are `i64` globals.
+        let val = unsafe {
+            if instance.module.memory_plans[init.location.memory_index]
+                .memory
+                .memory64
+            {
+                *global.as_u64()
+            } else {
+                u64::from(*global.as_u32())
+            }
+        };
+        start = start.saturating_add(usize::try_from(val).unwrap_or(usize::MAX));
     }
 
     start
@@ -1221,7 +1265,8 @@ fn check_memory_init_bounds(
         let start = get_memory_init_start(init, instance);
         unsafe {
             let mem_slice = get_memory_slice(init, instance);
-            if mem_slice.get_mut(start..start + init.data.len()).is_none() {
+            let end = start.checked_add(init.data.len());
+            if end.and_then(|end| mem_slice.get_mut(start..end)).is_none() {
                 return Err(InstantiationError::Link(LinkError(
                     "memory out of bounds: data segment does not fit".into(),
                 )));
@@ -1233,14 +1278,32 @@ fn check_memory_init_bounds(
 }
 
 /// Allocate memory for just the tables of the current module.
-fn create_tables(module: &Module) -> BoxedSlice<DefinedTableIndex, Table> {
+fn create_tables(
+    module: &Module,
+    limits: &InstanceLimits,
+) -> Result<BoxedSlice<DefinedTableIndex, Table>, InstantiationError> {
     let num_imports = module.num_imported_tables;
     let mut tables: PrimaryMap<DefinedTableIndex, _> =
         PrimaryMap::with_capacity(module.table_plans.len() - num_imports);
-    for table in &module.table_plans.values().as_slice()[num_imports..] {
-        tables.push(Table::new(table));
+    for plan in &module.table_plans.values().as_slice()[num_imports..] {
+        match limits.table_elements {
+            Some(limit) if plan.table.minimum > limit => {
+                return Err(InstantiationError::Resource(format!(
+                    "table minimum size of {} elements exceeds the limit of {}",
+                    plan.table.minimum, limit
+                )));
+            }
+            Some(limit) => {
+                let mut plan = plan.clone();
+                plan.table.maximum = Some(plan.table.maximum.map_or(limit, |max| max.min(limit)));
+                tables.push(Table::new(&plan));
+            }
+            None => {
+                tables.push(Table::new(plan));
+            }
+        }
     }
-    tables.into_boxed_slice()
+    Ok(tables.into_boxed_slice())
 }
 
 /// Compute the offset for a table element initializer.
@@ -1335,16 +1398,27 @@ fn initialize_passive_elements(instance: &Instance) {
 fn create_memories(
     module: &Module,
     mem_creator: &dyn RuntimeMemoryCreator,
+    limits: &InstanceLimits,
 ) -> Result<BoxedSlice<DefinedMemoryIndex, Box<dyn RuntimeLinearMemory>>, InstantiationError> {
     let num_imports = module.num_imported_memories;
     let mut memories: PrimaryMap<DefinedMemoryIndex, _> =
         PrimaryMap::with_capacity(module.memory_plans.len() - num_imports);
     for plan in &module.memory_plans.values().as_slice()[num_imports..] {
-        memories.push(
-            mem_creator
-                .new_memory(plan)
-                .map_err(InstantiationError::Resource)?,
-        );
+        let memory = match limits.memory_pages {
+            Some(limit) if plan.memory.minimum > limit => {
+                return Err(InstantiationError::Resource(format!(
+                    "memory minimum size of {} pages exceeds the limit of {}",
+                    plan.memory.minimum, limit
+                )));
+            }
+            Some(limit) => {
+                let mut plan = plan.clone();
+                plan.memory.maximum = Some(plan.memory.maximum.map_or(limit, |max| max.min(limit)));
+                mem_creator.new_memory(&plan)
+            }
+            None => mem_creator.new_memory(plan),
+        };
+        memories.push(memory.map_err(InstantiationError::Resource)?);
     }
     Ok(memories.into_boxed_slice())
 }
@@ -186,20 +186,39 @@ impl Table {
             return Err(Trap::wasm(ir::TrapCode::TableOutOfBounds));
         }
 
-        let srcs = src_index..src_index + len;
-        let dsts = dst_index..dst_index + len;
+        let src = src_index as usize..(src_index + len) as usize;
+        let dst = dst_index as usize..(dst_index + len) as usize;
 
-        // Note on the unwraps: the bounds check above means that these will
-        // never panic.
-        //
-        // TODO(#983): investigate replacing this get/set loop with a `memcpy`.
-        if dst_index <= src_index {
-            for (s, d) in (srcs).zip(dsts) {
-                dst_table.set(d, src_table.get(s).unwrap()).unwrap();
+        if ptr::eq(dst_table, src_table) {
+            match &mut *dst_table.elements.borrow_mut() {
+                TableElements::FuncRefs(x) => x.copy_within(src, dst.start),
+                // `VMExternRef`s are reference counted, so they have to be
+                // cloned one at a time, in an order that reads each element
+                // before it's overwritten.
+                TableElements::ExternRefs(x) => {
+                    if dst.start <= src.start {
+                        for (s, d) in src.zip(dst) {
+                            x[d] = x[s].clone();
+                        }
+                    } else {
+                        for (s, d) in src.rev().zip(dst.rev()) {
+                            x[d] = x[s].clone();
+                        }
+                    }
+                }
             }
         } else {
-            for (s, d) in srcs.rev().zip(dsts.rev()) {
-                dst_table.set(d, src_table.get(s).unwrap()).unwrap();
+            match (
+                &mut *dst_table.elements.borrow_mut(),
+                &*src_table.elements.borrow(),
+            ) {
+                (TableElements::FuncRefs(d), TableElements::FuncRefs(s)) => {
+                    d[dst].copy_from_slice(&s[src])
+                }
+                (TableElements::ExternRefs(d), TableElements::ExternRefs(s)) => {
+                    d[dst].clone_from_slice(&s[src])
+                }
+                _ => panic!("table.copy between tables of different element types"),
             }
         }
 
@@ -284,6 +284,17 @@ pub unsafe fn raise_user_trap(data: Box<dyn Error + Send + Sync>) -> ! {
     tls::with(|info| info.unwrap().unwind_with(UnwindReason::UserTrap(data)))
 }
 
+/// Invokes `TrapInfo::out_of_gas` for the store of the wasm code currently
+/// executing.
+///
+/// # Safety
+///
+/// Only safe to call when wasm code is on the stack, aka `catch_traps` must
+/// have been previously called.
+pub unsafe fn out_of_gas() {
+    tls::with(|state| state.unwrap().trap_info.out_of_gas())
+}
+
 /// Raises a trap from inside library code immediately.
 ///
 /// This function performs as-if a wasm trap was just executed. This trap
@@ -436,6 +447,13 @@ pub unsafe trait TrapInfo {
     /// Returns the maximum size, in bytes, the wasm native stack is allowed to
     /// grow to.
     fn max_wasm_stack(&self) -> usize;
+
+    /// Callback invoked whenever WebAssembly has entirely consumed the fuel
+    /// that it was allotted.
+    ///
+    /// This function may return to continue executing wasm, typically after
+    /// adding more fuel, or it may raise a trap with `raise_user_trap`.
+    fn out_of_gas(&self);
 }
 
 enum UnwindReason {
@@ -530,7 +548,11 @@ impl<'a> CallThreadState<'a> {
         // to it). In any case it's expected to be at most a few hundred bytes
         // of slop one way or another. When wasm is typically given a MB or so
         // (a million bytes) the slop shouldn't matter too much.
-        let wasm_stack_limit = psm::stack_pointer() as usize - self.trap_info.max_wasm_stack();
+        //
+        // Saturate rather than wrapping around for absurdly large limits, in
+        // which case wasm is bounded only by the native stack's guard page.
+        let wasm_stack_limit =
+            (psm::stack_pointer() as usize).saturating_sub(self.trap_info.max_wasm_stack());
 
         let interrupts = unsafe { &**(&*self.vmctx).instance().interrupts() };
         let reset_stack_limit = match interrupts.stack_limit.compare_exchange(
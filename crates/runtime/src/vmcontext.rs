@@ -4,6 +4,7 @@
 use crate::externref::VMExternRef;
 use crate::instance::Instance;
 use std::any::Any;
+use std::cell::UnsafeCell;
 use std::ptr::NonNull;
 use std::sync::atomic::{AtomicUsize, Ordering::SeqCst};
 use std::u32;
@@ -612,6 +613,7 @@ impl VMBuiltinFunctionsArray {
             wasmtime_memory_atomic_wait64 as usize;
         ptrs[BuiltinFunctionIndex::imported_memory_atomic_wait64().index() as usize] =
             wasmtime_imported_memory_atomic_wait64 as usize;
+        ptrs[BuiltinFunctionIndex::out_of_gas().index() as usize] = wasmtime_out_of_gas as usize;
 
         if cfg!(debug_assertions) {
             for i in 0..ptrs.len() {
@@ -658,8 +660,7 @@ impl VMInvokeArgument {
     }
 }
 
-/// Structure used to control interrupting wasm code, currently with only one
-/// atomic flag internally used.
+/// Structure used to control interrupting wasm code.
 #[derive(Debug)]
 #[repr(C)]
 pub struct VMInterrupts {
@@ -668,8 +669,23 @@ pub struct VMInterrupts {
     /// This is used to control both stack overflow as well as interrupting wasm
     /// modules. For more information see `crates/environ/src/cranelift.rs`.
     pub stack_limit: AtomicUsize,
+
+    /// Indicator of how much fuel has been consumed and is remaining to
+    /// WebAssembly.
+    ///
+    /// This field is typically negative and increments towards positive. Upon
+    /// turning positive a wasm trap will be generated. This field is only
+    /// modified if wasm is configured to consume fuel.
+    pub fuel_consumed: UnsafeCell<i64>,
 }
 
+// The `VMInterrupts` type is a pod-type with no destructor, and we only access
+// `stack_limit` from other threads, so add in these trait impls which are
+// otherwise not available due to the `fuel_consumed` variable in
+// `VMInterrupts`.
+unsafe impl Send for VMInterrupts {}
+unsafe impl Sync for VMInterrupts {}
+
 impl VMInterrupts {
     /// Flag that an interrupt should occur
     pub fn interrupt(&self) {
@@ -682,6 +698,7 @@ impl Default for VMInterrupts {
     fn default() -> VMInterrupts {
         VMInterrupts {
             stack_limit: AtomicUsize::new(usize::max_value()),
+            fuel_consumed: UnsafeCell::new(0),
         }
     }
 }
@@ -701,6 +718,10 @@ mod test_vminterrupts {
             offset_of!(VMInterrupts, stack_limit),
             usize::from(offsets.vminterrupts_stack_limit())
         );
+        assert_eq!(
+            offset_of!(VMInterrupts, fuel_consumed),
+            usize::from(offsets.vminterrupts_fuel_consumed())
+        );
     }
 }
 
@@ -1,10 +1,11 @@
 use crate::entry::{Entry, EntryHandle};
 use crate::fdpool::FdPool;
-use crate::handle::Handle;
+use crate::handle::{Handle, HandleRights, Rights};
 use crate::string_array::{PendingString, StringArray, StringArrayError};
 use crate::sys::osdir::OsDir;
+use crate::sys::osother::OsOther;
 use crate::sys::stdio::NullDevice;
-use crate::sys::stdio::{Stderr, StderrExt, Stdin, StdinExt, Stdout, StdoutExt};
+use crate::sys::stdio::{Stderr, StderrExt, Stdin, StdinExt, StdioMode, Stdout, StdoutExt};
 use crate::virtfs::{VirtualDir, VirtualDirEntry};
 use crate::wasi::types::Fd;
 use crate::Error;
@@ -41,7 +42,7 @@ pub enum WasiCtxBuilderError {
 type WasiCtxBuilderResult<T> = std::result::Result<T, WasiCtxBuilderError>;
 
 enum PendingEntry {
-    Thunk(fn() -> io::Result<Box<dyn Handle>>),
+    Thunk(fn(StdioMode) -> io::Result<Box<dyn Handle>>),
     Handle(Box<dyn Handle>),
 }
 
@@ -51,7 +52,7 @@ impl std::fmt::Debug for PendingEntry {
             Self::Thunk(f) => write!(
                 fmt,
                 "PendingEntry::Thunk({:p})",
-                f as *const fn() -> io::Result<Box<dyn Handle>>
+                f as *const fn(StdioMode) -> io::Result<Box<dyn Handle>>
             ),
             Self::Handle(handle) => write!(fmt, "PendingEntry::Handle({:p})", handle),
         }
@@ -78,6 +79,8 @@ pub struct WasiCtxBuilder {
     stdin: Option<PendingEntry>,
     stdout: Option<PendingEntry>,
     stderr: Option<PendingEntry>,
+    stdio_mode: StdioMode,
+    sockets: Option<Vec<File>>,
     preopens: Option<Vec<(PathBuf, PendingPreopen)>>,
     args: Option<Vec<PendingString>>,
     env: Option<HashMap<PendingString, PendingString>>,
@@ -94,6 +97,8 @@ impl WasiCtxBuilder {
             stdin,
             stdout,
             stderr,
+            stdio_mode: StdioMode::default(),
+            sockets: Some(Vec::new()),
             preopens: Some(Vec::new()),
             args: Some(Vec::new()),
             env: Some(HashMap::new()),
@@ -159,6 +164,15 @@ impl WasiCtxBuilder {
         self
     }
 
+    /// Set how inherited stdio streams are presented to the guest.
+    ///
+    /// This only affects streams inherited from the host process, and
+    /// defaults to `StdioMode::Text`.
+    pub fn stdio_mode(&mut self, mode: StdioMode) -> &mut Self {
+        self.stdio_mode = mode;
+        self
+    }
+
     /// Inherit the environment variables from the host process.
     ///
     /// If any environment variables from the host process contain invalid Unicode (UTF-16 for
@@ -215,10 +229,41 @@ impl WasiCtxBuilder {
         self
     }
 
+    /// Add a preopened socket, such as a listening TCP socket.
+    ///
+    /// Sockets are given the file descriptors following those of stdio and
+    /// any preopened directories, in the order they're added. Placing them
+    /// last keeps the preopened directories contiguous from file descriptor
+    /// 3, which is where guests start scanning for them.
+    pub fn preopened_socket(&mut self, socket: File) -> &mut Self {
+        self.sockets.as_mut().unwrap().push(socket);
+        self
+    }
+
     /// Add a preopened directory.
     pub fn preopened_dir<P: AsRef<Path>>(&mut self, dir: File, guest_path: P) -> &mut Self {
+        let rights = HandleRights::new(Rights::all(), Rights::all());
+        self.preopened_dir_with_rights(dir, guest_path, rights)
+    }
+
+    /// Add a preopened directory with restricted rights.
+    ///
+    /// The directory gets the intersection of `rights` and the rights it would
+    /// otherwise have, so this can only take rights away, for example to make
+    /// the directory and everything opened through it read-only.
+    pub fn preopened_dir_with_rights<P: AsRef<Path>>(
+        &mut self,
+        dir: File,
+        guest_path: P,
+        rights: HandleRights,
+    ) -> &mut Self {
         let preopen = PendingPreopen::new(move || {
             let dir = OsDir::try_from(dir).map_err(WasiCtxBuilderError::from)?;
+            let current = dir.get_rights();
+            dir.set_rights(HandleRights::new(
+                current.base() & rights.base(),
+                current.inheriting() & rights.inheriting(),
+            ));
             Ok(Box::new(dir))
         });
         self.preopens
@@ -294,7 +339,7 @@ impl WasiCtxBuilder {
             );
             let fd = match pending {
                 PendingEntry::Thunk(f) => {
-                    let handle = EntryHandle::from(f()?);
+                    let handle = EntryHandle::from(f(self.stdio_mode)?);
                     let entry = Entry::new(handle);
                     entries
                         .insert(entry)
@@ -320,6 +365,15 @@ impl WasiCtxBuilder {
                 .ok_or(WasiCtxBuilderError::TooManyFilesOpen)?;
             tracing::debug!(fd = tracing::field::debug(fd), "WasiCtx inserted",);
         }
+        // And finally the preopened sockets.
+        for socket in self.sockets.take().unwrap() {
+            let handle: Box<dyn Handle> = Box::new(OsOther::try_from(socket)?);
+            let entry = Entry::new(EntryHandle::from(handle));
+            let fd = entries
+                .insert(entry)
+                .ok_or(WasiCtxBuilderError::TooManyFilesOpen)?;
+            tracing::debug!(fd = tracing::field::debug(fd), "WasiCtx inserted socket");
+        }
 
         Ok(WasiCtx {
             args,
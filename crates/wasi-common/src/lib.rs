@@ -42,4 +42,5 @@ pub use sys::osdir::OsDir;
 pub use sys::osfile::OsFile;
 pub use sys::osother::OsOther;
 pub use sys::preopen_dir;
+pub use sys::stdio::StdioMode;
 pub use virtfs::{FileContents, VirtualDirEntry};
@@ -25,15 +25,36 @@ use std::cell::Cell;
 use std::convert::TryInto;
 use std::io::{self, Read, Write};
 
+/// How the host's standard streams are presented to the guest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StdioMode {
+    /// Output written to a terminal is sanitized, so that the guest can't
+    /// emit control sequences or invalid UTF-8 to it. Output written to a
+    /// pipe or file is passed through unmodified. This is the default.
+    Text,
+    /// The streams are raw byte streams, even when connected to a
+    /// terminal. Nothing is sanitized or translated, and the bytes are
+    /// passed straight to the underlying OS handle, bypassing any console
+    /// transcoding of the host's standard library.
+    Raw,
+}
+
+impl Default for StdioMode {
+    fn default() -> Self {
+        Self::Text
+    }
+}
+
 pub(crate) trait StdinExt: Sized {
     /// Create `Stdin` from `io::stdin`.
-    fn stdin() -> io::Result<Box<dyn Handle>>;
+    fn stdin(mode: StdioMode) -> io::Result<Box<dyn Handle>>;
 }
 
 #[derive(Debug, Clone)]
 pub(crate) struct Stdin {
     pub(crate) file_type: Filetype,
     pub(crate) rights: Cell<HandleRights>,
+    pub(crate) mode: StdioMode,
 }
 
 impl Handle for Stdin {
@@ -69,20 +90,30 @@ impl Handle for Stdin {
         fd::filestat_get(&*self.as_file()?)
     }
     fn read_vectored(&self, iovs: &mut [io::IoSliceMut]) -> Result<usize> {
-        let nread = io::stdin().read_vectored(iovs)?;
+        let nread = match self.mode {
+            StdioMode::Text => io::stdin().read_vectored(iovs)?,
+            // Read from the OS handle directly; on Windows, `io::stdin`
+            // transcodes console input from UTF-16 and rejects anything else.
+            StdioMode::Raw => (&*self.as_file()?).read_vectored(iovs)?,
+        };
         Ok(nread)
     }
 }
 
 pub(crate) trait StdoutExt: Sized {
     /// Create `Stdout` from `io::stdout`.
-    fn stdout() -> io::Result<Box<dyn Handle>>;
+    fn stdout(mode: StdioMode) -> io::Result<Box<dyn Handle>>;
 }
 
 #[derive(Debug, Clone)]
 pub(crate) struct Stdout {
     pub(crate) file_type: Filetype,
     pub(crate) rights: Cell<HandleRights>,
+    pub(crate) mode: StdioMode,
+    /// The console output code page to restore once the last clone of this
+    /// handle is dropped, if it was switched to UTF-8 for raw output.
+    #[cfg(windows)]
+    pub(crate) code_page: Option<std::rc::Rc<super::windows::stdio::ConsoleOutputCodePage>>,
 }
 
 impl Handle for Stdout {
@@ -121,10 +152,18 @@ impl Handle for Stdout {
         // lock for the duration of the scope
         let stdout = io::stdout();
         let mut stdout = stdout.lock();
-        let nwritten = if self.is_tty() {
-            SandboxedTTYWriter::new(&mut stdout).write_vectored(&iovs)?
-        } else {
-            stdout.write_vectored(iovs)?
+        let nwritten = match self.mode {
+            StdioMode::Text if self.is_tty() => {
+                SandboxedTTYWriter::new(&mut stdout).write_vectored(&iovs)?
+            }
+            StdioMode::Text => stdout.write_vectored(iovs)?,
+            StdioMode::Raw => {
+                // Write to the OS handle directly; on Windows, `io::stdout`
+                // fails on invalid UTF-8 when connected to a console. Flush
+                // first so that anything the host buffered stays in order.
+                stdout.flush()?;
+                (&*self.as_file()?).write_vectored(iovs)?
+            }
         };
         stdout.flush()?;
         Ok(nwritten)
@@ -133,7 +172,9 @@ impl Handle for Stdout {
 
 pub(crate) trait StderrExt: Sized {
     /// Create `Stderr` from `io::stderr`.
-    fn stderr() -> io::Result<Box<dyn Handle>>;
+    ///
+    /// Stderr is always sanitized, so `mode` is ignored.
+    fn stderr(mode: StdioMode) -> io::Result<Box<dyn Handle>>;
 }
 
 #[derive(Debug, Clone)]
@@ -1,6 +1,6 @@
 use super::{get_file_type, get_rights};
 use crate::handle::Handle;
-use crate::sys::stdio::{Stderr, StderrExt, Stdin, StdinExt, Stdout, StdoutExt};
+use crate::sys::stdio::{Stderr, StderrExt, Stdin, StdinExt, StdioMode, Stdout, StdoutExt};
 use std::cell::Cell;
 use std::fs::File;
 use std::io;
@@ -26,29 +26,37 @@ impl AsRawFd for Stderr {
 }
 
 impl StdinExt for Stdin {
-    fn stdin() -> io::Result<Box<dyn Handle>> {
+    fn stdin(mode: StdioMode) -> io::Result<Box<dyn Handle>> {
         let file = unsafe { File::from_raw_fd(io::stdin().as_raw_fd()) };
         let file = ManuallyDrop::new(file);
         let file_type = get_file_type(&file)?;
         let rights = get_rights(&file, &file_type)?;
         let rights = Cell::new(rights);
-        Ok(Box::new(Self { file_type, rights }))
+        Ok(Box::new(Self {
+            file_type,
+            rights,
+            mode,
+        }))
     }
 }
 
 impl StdoutExt for Stdout {
-    fn stdout() -> io::Result<Box<dyn Handle>> {
+    fn stdout(mode: StdioMode) -> io::Result<Box<dyn Handle>> {
         let file = unsafe { File::from_raw_fd(io::stdout().as_raw_fd()) };
         let file = ManuallyDrop::new(file);
         let file_type = get_file_type(&file)?;
         let rights = get_rights(&file, &file_type)?;
         let rights = Cell::new(rights);
-        Ok(Box::new(Self { file_type, rights }))
+        Ok(Box::new(Self {
+            file_type,
+            rights,
+            mode,
+        }))
     }
 }
 
 impl StderrExt for Stderr {
-    fn stderr() -> io::Result<Box<dyn Handle>> {
+    fn stderr(_mode: StdioMode) -> io::Result<Box<dyn Handle>> {
         let file = unsafe { File::from_raw_fd(io::stderr().as_raw_fd()) };
         let file = ManuallyDrop::new(file);
         let file_type = get_file_type(&file)?;
@@ -1,11 +1,39 @@
 use super::{get_file_type, get_rights};
+use crate::handle::Filetype;
 use crate::handle::Handle;
-use crate::sys::stdio::{Stderr, StderrExt, Stdin, StdinExt, Stdout, StdoutExt};
+use crate::sys::stdio::{Stderr, StderrExt, Stdin, StdinExt, StdioMode, Stdout, StdoutExt};
 use std::cell::Cell;
 use std::fs::File;
 use std::io;
 use std::mem::ManuallyDrop;
 use std::os::windows::prelude::{AsRawHandle, FromRawHandle, RawHandle};
+use std::rc::Rc;
+use winapi::um::{wincon, winnls};
+
+/// The console output code page in effect before it was switched to UTF-8,
+/// which is restored when this is dropped.
+#[derive(Debug)]
+pub(crate) struct ConsoleOutputCodePage {
+    previous: u32,
+}
+
+impl ConsoleOutputCodePage {
+    /// Switch the console output code page to UTF-8, returning `None` if
+    /// there is no console or the code page can't be changed.
+    fn set_utf8() -> Option<Self> {
+        let previous = unsafe { wincon::GetConsoleOutputCP() };
+        if previous == 0 || unsafe { wincon::SetConsoleOutputCP(winnls::CP_UTF8) } == 0 {
+            return None;
+        }
+        Some(Self { previous })
+    }
+}
+
+impl Drop for ConsoleOutputCodePage {
+    fn drop(&mut self) {
+        unsafe { wincon::SetConsoleOutputCP(self.previous) };
+    }
+}
 
 impl AsRawHandle for Stdin {
     fn as_raw_handle(&self) -> RawHandle {
@@ -26,30 +54,46 @@ impl AsRawHandle for Stderr {
 }
 
 impl StdinExt for Stdin {
-    fn stdin() -> io::Result<Box<dyn Handle>> {
+    fn stdin(mode: StdioMode) -> io::Result<Box<dyn Handle>> {
         let file = unsafe { File::from_raw_handle(io::stdin().as_raw_handle()) };
         let file = ManuallyDrop::new(file);
         let file_type = get_file_type(&file)?;
         let rights = get_rights(&file_type)?;
         let rights = Cell::new(rights);
-        Ok(Box::new(Self { file_type, rights }))
+        Ok(Box::new(Self {
+            file_type,
+            rights,
+            mode,
+        }))
     }
 }
 
 impl StdoutExt for Stdout {
-    fn stdout() -> io::Result<Box<dyn Handle>> {
-        let file = unsafe { File::from_raw_handle(io::stdin().as_raw_handle()) };
+    fn stdout(mode: StdioMode) -> io::Result<Box<dyn Handle>> {
+        let file = unsafe { File::from_raw_handle(io::stdout().as_raw_handle()) };
         let file = ManuallyDrop::new(file);
         let file_type = get_file_type(&file)?;
         let rights = get_rights(&file_type)?;
         let rights = Cell::new(rights);
-        Ok(Box::new(Self { file_type, rights }))
+        // Raw output bypasses the UTF-16 transcoding of `io::stdout`, so the
+        // console itself has to interpret the bytes as UTF-8.
+        let code_page = if mode == StdioMode::Raw && file_type == Filetype::CharacterDevice {
+            ConsoleOutputCodePage::set_utf8().map(Rc::new)
+        } else {
+            None
+        };
+        Ok(Box::new(Self {
+            file_type,
+            rights,
+            mode,
+            code_page,
+        }))
     }
 }
 
 impl StderrExt for Stderr {
-    fn stderr() -> io::Result<Box<dyn Handle>> {
-        let file = unsafe { File::from_raw_handle(io::stdin().as_raw_handle()) };
+    fn stderr(_mode: StdioMode) -> io::Result<Box<dyn Handle>> {
+        let file = unsafe { File::from_raw_handle(io::stderr().as_raw_handle()) };
         let file = ManuallyDrop::new(file);
         let file_type = get_file_type(&file)?;
         let rights = get_rights(&file_type)?;
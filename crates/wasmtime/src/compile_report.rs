@@ -0,0 +1,136 @@
+//! Reporting of the time spent compiling a module, see
+//! [`Module::compile_report`](crate::Module::compile_report).
+
+use std::fmt;
+use std::time::Duration;
+use wasmtime_environ::entity::EntityRef;
+use wasmtime_jit::CompiledModule;
+
+/// The time spent compiling each function of a module.
+///
+/// This is returned by
+/// [`Module::compile_report`](crate::Module::compile_report) and is intended
+/// for tracking down functions which take unusually long to compile. Its
+/// `Display` implementation prints the functions from slowest to fastest
+/// along with the passes they spent the most time in.
+#[derive(Debug, Clone)]
+pub struct CompileReport {
+    functions: Vec<FunctionCompileReport>,
+}
+
+/// The time spent compiling a single function, as part of a
+/// [`CompileReport`].
+#[derive(Debug, Clone)]
+pub struct FunctionCompileReport {
+    module_index: usize,
+    index: u32,
+    name: Option<String>,
+    total: Duration,
+    passes: Vec<(&'static str, Duration)>,
+}
+
+impl CompileReport {
+    pub(crate) fn new(modules: &[CompiledModule]) -> Option<CompileReport> {
+        let mut functions = Vec::new();
+        for (module_index, compiled) in modules.iter().enumerate() {
+            let module = compiled.module();
+            for (defined, stats) in compiled.compile_stats()?.iter() {
+                let index = module.func_index(defined);
+                functions.push(FunctionCompileReport {
+                    module_index,
+                    index: index.index() as u32,
+                    name: module.func_names.get(&index).cloned(),
+                    total: stats.total,
+                    passes: stats.passes.clone(),
+                });
+            }
+        }
+        Some(CompileReport { functions })
+    }
+
+    /// Returns the functions that were compiled, ordered by module and then
+    /// by function index.
+    pub fn functions(&self) -> &[FunctionCompileReport] {
+        &self.functions
+    }
+
+    /// Returns the total time spent compiling functions.
+    ///
+    /// Functions may have been compiled in parallel, so this may be more than
+    /// the time it took to compile the module.
+    pub fn total(&self) -> Duration {
+        self.functions.iter().map(|f| f.total).sum()
+    }
+}
+
+impl fmt::Display for CompileReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut functions = self.functions.iter().collect::<Vec<_>>();
+        functions.sort_by(|a, b| b.total.cmp(&a.total));
+
+        writeln!(
+            f,
+            "compiled {} functions in {:.3?}",
+            functions.len(),
+            self.total()
+        )?;
+        for func in functions {
+            write!(f, "{:>12}  ", format!("{:.3?}", func.total))?;
+            if func.module_index != 0 {
+                write!(f, "module {} ", func.module_index)?;
+            }
+            write!(f, "function {}", func.index)?;
+            if let Some(name) = &func.name {
+                write!(f, " ({})", name)?;
+            }
+            writeln!(f)?;
+
+            let mut passes = func.passes.iter().collect::<Vec<_>>();
+            passes.sort_by(|a, b| b.1.cmp(&a.1));
+            for (desc, time) in passes {
+                writeln!(f, "{:>26}  {}", format!("{:.3?}", time), desc)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FunctionCompileReport {
+    /// Returns the index of the module this function is defined in.
+    ///
+    /// This is always 0 unless the module linking proposal is enabled, in
+    /// which case modules nested in the original binary are numbered in the
+    /// order in which they're translated.
+    pub fn module_index(&self) -> usize {
+        self.module_index
+    }
+
+    /// Returns the index of this function in its module's function index
+    /// space, which includes imported functions.
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    /// Returns the name of this function from the `name` custom section, if
+    /// present.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Returns the total time spent compiling this function.
+    pub fn total(&self) -> Duration {
+        self.total
+    }
+
+    /// Returns the description of each Cranelift pass that ran while
+    /// compiling this function and the time spent in it, excluding nested
+    /// passes, in the order the passes are defined in.
+    ///
+    /// Passes that didn't run aren't included, and this is empty for
+    /// compilation strategies other than Cranelift. Time not spent in any
+    /// pass, such as setting up compilation, is only included in
+    /// [`FunctionCompileReport::total`].
+    pub fn passes(&self) -> &[(&'static str, Duration)] {
+        &self.passes
+    }
+}
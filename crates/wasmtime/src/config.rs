@@ -1,6 +1,6 @@
 use crate::externals::MemoryCreator;
 use crate::trampoline::MemoryCreatorProxy;
-use anyhow::{bail, Result};
+use anyhow::{anyhow, bail, Result};
 use std::cmp;
 use std::convert::TryFrom;
 use std::fmt;
@@ -9,7 +9,7 @@ use std::path::Path;
 use std::sync::Arc;
 use wasmparser::WasmFeatures;
 #[cfg(feature = "cache")]
-use wasmtime_cache::CacheConfig;
+use wasmtime_cache::{CacheConfig, CacheStore, FunctionCache};
 use wasmtime_environ::settings::{self, Configurable, SetError};
 use wasmtime_environ::{isa, isa::TargetIsa, Tunables};
 use wasmtime_jit::{native, CompilationStrategy, Compiler};
@@ -24,13 +24,23 @@ use wasmtime_profiling::{JitDumpAgent, NullProfilerAgent, ProfilingAgent, VTuneA
 pub struct Config {
     pub(crate) flags: settings::Builder,
     pub(crate) isa_flags: isa::Builder,
+    pub(crate) backend_variant: isa::BackendVariant,
     pub(crate) tunables: Tunables,
     pub(crate) strategy: CompilationStrategy,
     #[cfg(feature = "cache")]
     pub(crate) cache_config: CacheConfig,
+    #[cfg(feature = "cache")]
+    pub(crate) cache_store: Option<Arc<dyn CacheStore>>,
+    #[cfg(feature = "cache")]
+    pub(crate) cache_functions: bool,
     pub(crate) profiler: Arc<dyn ProfilingAgent>,
     pub(crate) memory_creator: Option<MemoryCreatorProxy>,
     pub(crate) max_wasm_stack: usize,
+    pub(crate) runtime_stats: bool,
+    pub(crate) verify_determinism: bool,
+    pub(crate) compile_stats: bool,
+    pub(crate) coredump_on_trap: bool,
+    pub(crate) module_limits: ModuleLimits,
     pub(crate) features: WasmFeatures,
     pub(crate) wasm_backtrace_details_env_used: bool,
 }
@@ -66,12 +76,22 @@ impl Config {
             tunables: Tunables::default(),
             flags,
             isa_flags: native::builder(),
+            backend_variant: isa::BackendVariant::Any,
             strategy: CompilationStrategy::Auto,
             #[cfg(feature = "cache")]
             cache_config: CacheConfig::new_cache_disabled(),
+            #[cfg(feature = "cache")]
+            cache_store: None,
+            #[cfg(feature = "cache")]
+            cache_functions: false,
             profiler: Arc::new(NullProfilerAgent),
             memory_creator: None,
             max_wasm_stack: 1 << 20,
+            runtime_stats: false,
+            verify_determinism: false,
+            compile_stats: false,
+            coredump_on_trap: false,
+            module_limits: ModuleLimits::default(),
             wasm_backtrace_details_env_used: false,
             features: WasmFeatures {
                 reference_types: true,
@@ -131,6 +151,76 @@ impl Config {
         self
     }
 
+    /// Configures whether execution of WebAssembly will "consume fuel" to
+    /// either halt or yield execution as desired.
+    ///
+    /// This option is similar in purpose to [`Config::interruptable`] where
+    /// you can prevent infinitely-executing WebAssembly code. The difference
+    /// is that this option allows deterministic execution of WebAssembly code
+    /// by instrumenting generated code consume fuel as it executes. When fuel
+    /// runs out the execution of WebAssembly traps.
+    ///
+    /// Note that a [`Store`](crate::Store) starts with no fuel, so if you
+    /// enable this option you'll have to be sure to pour some fuel into
+    /// [`Store::add_fuel`](crate::Store::add_fuel) before executing some code.
+    ///
+    /// By default this option is `false`.
+    pub fn consume_fuel(&mut self, enable: bool) -> &mut Self {
+        self.tunables.consume_fuel = enable;
+        self
+    }
+
+    /// Configures whether stores keep track of the time spent executing
+    /// WebAssembly and the host functions it calls.
+    ///
+    /// When enabled the totals are available from
+    /// [`Store::runtime_stats`](crate::Store::runtime_stats). This requires
+    /// reading a clock on every call into WebAssembly and on every call from
+    /// WebAssembly to the host, which is measurable overhead for code making
+    /// many short host calls.
+    ///
+    /// By default this option is `false`.
+    pub fn runtime_stats(&mut self, enable: bool) -> &mut Self {
+        self.runtime_stats = enable;
+        self
+    }
+
+    /// Configures whether the time spent compiling each function is measured.
+    ///
+    /// When enabled, modules compiled with this configuration record how long
+    /// each of their functions took to compile, broken down by compilation
+    /// pass, which is available from
+    /// [`Module::compile_report`](crate::Module::compile_report). This is
+    /// intended for tracking down functions which take unusually long to
+    /// compile.
+    ///
+    /// Compilation caches are bypassed while this is enabled, since a cached
+    /// module or function wouldn't be compiled at all.
+    ///
+    /// By default this option is `false`.
+    pub fn compile_stats(&mut self, enable: bool) -> &mut Self {
+        self.compile_stats = enable;
+        self
+    }
+
+    /// Configures whether traps capture a coredump of their store.
+    ///
+    /// When enabled, traps raised while executing WebAssembly record the wasm
+    /// stack along with the contents of the memories and globals of the
+    /// store's instances, which is available from
+    /// [`Trap::coredump`](crate::Trap::coredump) and can be written out for
+    /// offline debugging. Explicit exits, such as through WASI's
+    /// `proc_exit`, don't capture a coredump.
+    ///
+    /// Since this copies every memory in the store whenever a trap is
+    /// created, it's intended for debugging rather than production use.
+    ///
+    /// By default this option is `false`.
+    pub fn coredump_on_trap(&mut self, enable: bool) -> &mut Self {
+        self.coredump_on_trap = enable;
+        self
+    }
+
     /// Configures the maximum amount of native stack space available to
     /// executing WebAssembly code.
     ///
@@ -144,12 +234,68 @@ impl Config {
     /// If a wasm call (or series of nested wasm calls) take more stack space
     /// than the `size` specified then a stack overflow trap will be raised.
     ///
+    /// The limit is measured from the native stack pointer at the point wasm
+    /// is first entered on a thread, so it only yields a reliable trap if the
+    /// thread has at least `size` bytes of stack left at that point. Threads
+    /// calling into wasm with a large `size` should be spawned with a
+    /// correspondingly large stack, e.g. with
+    /// [`std::thread::Builder::stack_size`].
+    ///
     /// By default this option is 1 MB.
     pub fn max_wasm_stack(&mut self, size: usize) -> &mut Self {
         self.max_wasm_stack = size;
         self
     }
 
+    /// Configures the maximum size, in bytes, of WebAssembly binaries accepted
+    /// by [`Module::new`](crate::Module::new) and
+    /// [`Module::validate`](crate::Module::validate).
+    ///
+    /// This, along with the other static complexity limits such as
+    /// [`Config::max_module_functions`], allows services accepting untrusted
+    /// modules to reject pathological inputs before spending any time
+    /// compiling them. Modules exceeding a limit fail validation with a
+    /// [`ValidationError`](crate::ValidationError).
+    ///
+    /// By default there is no limit.
+    pub fn max_module_size(&mut self, size: usize) -> &mut Self {
+        self.module_limits.size = Some(size);
+        self
+    }
+
+    /// Configures the maximum number of functions, both imported and defined,
+    /// that a WebAssembly module may contain.
+    ///
+    /// For more information see [`Config::max_module_size`].
+    ///
+    /// By default there is no limit.
+    pub fn max_module_functions(&mut self, functions: u32) -> &mut Self {
+        self.module_limits.functions = Some(functions);
+        self
+    }
+
+    /// Configures the maximum number of locals, not including parameters,
+    /// that any single function of a WebAssembly module may declare.
+    ///
+    /// For more information see [`Config::max_module_size`].
+    ///
+    /// By default there is no limit other than the one imposed by validation.
+    pub fn max_function_locals(&mut self, locals: u32) -> &mut Self {
+        self.module_limits.locals = Some(locals);
+        self
+    }
+
+    /// Configures the maximum number of entries in the type section of a
+    /// WebAssembly module.
+    ///
+    /// For more information see [`Config::max_module_size`].
+    ///
+    /// By default there is no limit.
+    pub fn max_module_types(&mut self, types: u32) -> &mut Self {
+        self.module_limits.types = Some(types);
+        self
+    }
+
     /// Configures whether the WebAssembly threads proposal will be enabled for
     /// compilation.
     ///
@@ -162,6 +308,10 @@ impl Config {
     /// instructions. Note that enabling the threads feature will
     /// also enable the bulk memory feature.
     ///
+    /// Atomic instructions are only supported by the new backends, so on
+    /// x86_64 this requires the `experimental_x64` feature; otherwise
+    /// compiling a module which uses them will return an error.
+    ///
     /// This is `false` by default.
     ///
     /// > **Note**: Wasmtime does not implement everything for the wasm threads
@@ -278,6 +428,22 @@ impl Config {
         self
     }
 
+    /// Configures whether the WebAssembly memory64 [proposal] will
+    /// be enabled for compilation.
+    ///
+    /// This feature gates memories which are indexed with `i64` addresses.
+    /// Accesses to such memories are always bounds checked explicitly, so
+    /// they're slower than accesses to 32-bit memories, and they're only
+    /// supported on 64-bit hosts.
+    ///
+    /// This is `false` by default.
+    ///
+    /// [proposal]: https://github.com/webassembly/memory64
+    pub fn wasm_memory64(&mut self, enable: bool) -> &mut Self {
+        self.features.memory64 = enable;
+        self
+    }
+
     /// Configures whether the WebAssembly module linking [proposal] will
     /// be enabled for compilation.
     ///
@@ -348,18 +514,43 @@ impl Config {
         self
     }
 
+    /// Configures whether compilation is checked to be deterministic.
+    ///
+    /// When enabled every module is compiled twice, concurrently if the
+    /// `parallel-compilation` feature is enabled, and compiling it fails
+    /// unless both compilations produce byte-identical machine code and
+    /// object images. This is intended for users relying on reproducible
+    /// builds of precompiled modules, who can use it to check that Wasmtime's
+    /// output only depends on its input and configuration.
+    ///
+    /// Compilation caches are bypassed while this is enabled, since a cached
+    /// module or function wouldn't be compiled at all.
+    ///
+    /// This doubles compile times, and the default value is `false`.
+    pub fn verify_determinism(&mut self, enable: bool) -> &mut Self {
+        self.verify_determinism = enable;
+        self
+    }
+
     /// Configures the Cranelift code generator optimization level.
     ///
     /// When the Cranelift code generator is used you can configure the
     /// optimization level used for generated code in a few various ways. For
     /// more information see the documentation of [`OptLevel`].
     ///
-    /// The default value for this is `OptLevel::None`.
+    /// Individual optimization passes can be further toggled with
+    /// [`Config::cranelift_other_flag`] through the `enable_preopt`,
+    /// `enable_licm`, `enable_gvn` and `enable_redundant_load_elim` flags,
+    /// which all default to `true` and only take effect when the optimization
+    /// level isn't `OptLevel::None`.
+    ///
+    /// The default value for this is `OptLevel::Speed`.
     pub fn cranelift_opt_level(&mut self, level: OptLevel) -> &mut Self {
         let val = match level {
             OptLevel::None => "none",
             OptLevel::Speed => "speed",
             OptLevel::SpeedAndSize => "speed_and_size",
+            OptLevel::Size => "size",
         };
         self.flags
             .set("opt_level", val)
@@ -367,11 +558,66 @@ impl Config {
         self
     }
 
+    /// Configures the register allocator used by Cranelift.
+    ///
+    /// Register allocation is often the most expensive part of compiling a
+    /// large module, and this allows trading the quality of the generated code
+    /// for compile time. For more information see the documentation of
+    /// [`RegallocAlgorithm`].
+    ///
+    /// Note that this only affects backends built on Cranelift's new `MachInst`
+    /// framework, such as AArch64 and the `experimental_x64` backend. The
+    /// default x86_64 backend ignores this setting.
+    ///
+    /// The default value for this is `RegallocAlgorithm::Backtracking`.
+    pub fn cranelift_regalloc(&mut self, algorithm: RegallocAlgorithm) -> &mut Self {
+        let val = match algorithm {
+            RegallocAlgorithm::Backtracking => "backtracking",
+            RegallocAlgorithm::LinearScan => "experimental_linear_scan",
+        };
+        self.flags
+            .set("regalloc", val)
+            .expect("should be valid flag");
+        self
+    }
+
+    /// Selects which of Cranelift's code generators to use, on targets that
+    /// have more than one.
+    ///
+    /// On x86_64 Cranelift has both a legacy backend, which is the default,
+    /// and a backend built on its new `MachInst` framework, which is only
+    /// available when Wasmtime is built with the `experimental_x64` feature.
+    /// Selecting between them at runtime is mostly useful for comparing the
+    /// two, for example when fuzzing.
+    ///
+    /// Note that this resets any CPU features configured with
+    /// [`Config::cranelift_cpu_feature`] to those detected on the host.
+    ///
+    /// # Errors
+    ///
+    /// This method fails if the requested backend was not compiled in or
+    /// doesn't support the host architecture.
+    pub fn cranelift_backend(&mut self, backend: CraneliftBackend) -> Result<&mut Self> {
+        let variant = match backend {
+            CraneliftBackend::Default => isa::BackendVariant::Any,
+            CraneliftBackend::Legacy => isa::BackendVariant::Legacy,
+            CraneliftBackend::MachInst => isa::BackendVariant::MachInst,
+        };
+        self.isa_flags = match native::builder_with_backend_variant(variant) {
+            Ok(builder) => builder,
+            Err(msg) => bail!("cannot use the {:?} backend: {}", backend, msg),
+        };
+        self.backend_variant = variant;
+        Ok(self)
+    }
+
     /// Configures whether Cranelift should perform a NaN-canonicalization pass.
     ///
     /// When Cranelift is used as a code generation backend this will configure
     /// it to replace NaNs with a single canonical value. This is useful for users
-    /// requiring entirely deterministic WebAssembly computation.
+    /// requiring entirely deterministic WebAssembly computation, such as
+    /// identical NaN bit patterns on x86_64 and aarch64. Results of both scalar
+    /// and SIMD floating-point arithmetic are canonicalized.
     /// This is not required by the WebAssembly spec, so it is not enabled by default.
     ///
     /// The default value for this is `false`
@@ -383,6 +629,138 @@ impl Config {
         self
     }
 
+    /// Configures how Cranelift mitigates Spectre attacks on the bounds checks
+    /// of linear memories and tables.
+    ///
+    /// A mispredicted bounds check can otherwise let WebAssembly code
+    /// speculatively read outside of its sandbox. For more information see the
+    /// documentation of [`SpectreMitigation`].
+    ///
+    /// The default value for this is `SpectreMitigation::ConditionalMasking`.
+    pub fn cranelift_spectre_mitigation(&mut self, mitigation: SpectreMitigation) -> &mut Self {
+        let val = match mitigation {
+            SpectreMitigation::ConditionalMasking => "true",
+            SpectreMitigation::None => "false",
+        };
+        self.flags
+            .set("enable_heap_access_spectre_mitigation", val)
+            .expect("should be valid flag");
+        self.flags
+            .set("enable_table_access_spectre_mitigation", val)
+            .expect("should be valid flag");
+        self
+    }
+
+    /// Configures the target platform of this [`Config`].
+    ///
+    /// This method is used to configure the output of compilation in an
+    /// [`Engine`](crate::Engine), so that modules can be compiled ahead of time
+    /// for a different platform with
+    /// [`Engine::precompile_module`](crate::Engine::precompile_module). Code
+    /// compiled for a foreign target can't be run on the host.
+    ///
+    /// Only the baseline CPU features of the target's architecture are
+    /// enabled; further features can be enabled with
+    /// [`Config::cranelift_cpu_feature`]. Note that this resets any CPU
+    /// features configured previously.
+    ///
+    /// The default target is the host's.
+    ///
+    /// # Errors
+    ///
+    /// This method fails if the target triple can't be parsed or isn't
+    /// supported by Cranelift.
+    pub fn target(&mut self, target: &str) -> Result<&mut Self> {
+        use std::str::FromStr;
+        let triple = target_lexicon::Triple::from_str(target).map_err(|e| anyhow!(e))?;
+        self.isa_flags = native::lookup_variant(triple, self.backend_variant)?;
+        Ok(self)
+    }
+
+    /// Restricts generated code to the baseline CPU features of the target's
+    /// architecture, such as SSE2 on x86_64.
+    ///
+    /// By default Wasmtime detects the features of the host CPU and lets
+    /// Cranelift use all of them, so code compiled on one machine may not be
+    /// usable on another. Calling this method makes the generated code, and
+    /// artifacts produced by [`Module::serialize`](crate::Module::serialize),
+    /// independent of the CPU it was compiled on. Individual features can then
+    /// be enabled again with [`Config::cranelift_cpu_feature`].
+    pub fn cranelift_baseline_cpu_features(&mut self) -> &mut Self {
+        let triple = self.target_isa().triple().clone();
+        self.isa_flags = native::lookup_variant(triple, self.backend_variant)
+            .expect("configured target should be supported");
+        self
+    }
+
+    /// Enables or disables the use of a CPU feature in generated code.
+    ///
+    /// Features are named as in Rust's `#[target_feature]` attribute. The
+    /// following features are currently supported when targeting x86_64:
+    /// `sse3`, `ssse3`, `sse4.1`, `sse4.2`, `popcnt`, `avx`, `avx2`,
+    /// `avx512f`, `avx512dq`, `avx512vl`, `bmi1`, `bmi2` and `lzcnt`.
+    ///
+    /// Together with [`Config::cranelift_baseline_cpu_features`] this allows
+    /// producing the same code on every machine supporting a chosen set of
+    /// features.
+    ///
+    /// # Errors
+    ///
+    /// This method fails if the feature is unknown for the configured target
+    /// (see [`Config::target`]) and backend. When compiling for the host, it
+    /// also fails if the feature is being enabled but isn't supported by the
+    /// host CPU, as generated code using it would fault at runtime.
+    pub fn cranelift_cpu_feature(&mut self, feature: &str, enable: bool) -> Result<&mut Self> {
+        let triple = self.target_isa().triple().clone();
+        let flag = match cpu_feature_flag(&triple, feature) {
+            Some(flag) => flag,
+            None => bail!("unknown CPU feature `{}` for target `{}`", feature, triple),
+        };
+        if enable && triple == target_lexicon::Triple::host() && !host_has_cpu_feature(feature) {
+            bail!("CPU feature `{}` is not supported by the host", feature);
+        }
+        let val = if enable { "true" } else { "false" };
+        self.isa_flags.set(flag, val)?;
+        Ok(self)
+    }
+
+    /// Returns the CPU features that can be passed to
+    /// [`Config::cranelift_cpu_feature`], along with whether the host CPU
+    /// supports each of them.
+    pub fn host_cpu_features() -> Vec<(&'static str, bool)> {
+        cpu_features(&target_lexicon::Triple::host())
+            .iter()
+            .map(|&(feature, _)| (feature, host_has_cpu_feature(feature)))
+            .collect()
+    }
+
+    /// Returns the target triple that code is compiled for.
+    pub fn target_triple(&self) -> String {
+        self.target_isa().triple().to_string()
+    }
+
+    /// Returns the Cranelift settings that code is compiled with, both the
+    /// shared ones and those specific to the target, as `name = value` lines
+    /// under a `[group]` header for each group.
+    pub fn compiler_settings(&self) -> String {
+        self.target_isa().to_string()
+    }
+
+    /// Returns the name of each WebAssembly proposal that Wasmtime supports,
+    /// along with whether it's enabled in this configuration.
+    pub fn wasm_proposals(&self) -> Vec<(&'static str, bool)> {
+        vec![
+            ("threads", self.features.threads),
+            ("reference-types", self.features.reference_types),
+            ("simd", self.features.simd),
+            ("bulk-memory", self.features.bulk_memory),
+            ("multi-value", self.features.multi_value),
+            ("multi-memory", self.features.multi_memory),
+            ("memory64", self.features.memory64),
+            ("module-linking", self.features.module_linking),
+        ]
+    }
+
     /// Allows settings another Cranelift flag defined by a flag name and value. This allows
     /// fine-tuning of Cranelift settings.
     ///
@@ -460,6 +838,53 @@ impl Config {
         Ok(self)
     }
 
+    /// Configures a custom storage backend for the compilation cache.
+    ///
+    /// When set, compiled modules are looked up in and saved to `store`
+    /// instead of the directory configured with
+    /// [`Config::cache_config_load`]. This allows, for example, sharing
+    /// compiled code across a fleet of machines through a network store.
+    ///
+    /// Entries are keyed by a hash of the wasm module, the compiler
+    /// configuration and the version of Wasmtime, so a single store can be
+    /// shared between differently configured engines. Errors from the store
+    /// are not fatal and are treated as cache misses.
+    ///
+    /// By default no custom store is configured.
+    ///
+    /// This method is only available when the `cache` feature of this crate is
+    /// enabled.
+    #[cfg(feature = "cache")]
+    pub fn with_cache_store(&mut self, store: Arc<dyn CacheStore>) -> &mut Self {
+        self.cache_store = Some(store);
+        self
+    }
+
+    /// Configures whether functions are also cached individually.
+    ///
+    /// Normally the cache holds whole compiled modules, so changing a single
+    /// function of a module means recompiling all of it. With this option
+    /// enabled each function is additionally cached on its own, keyed by its
+    /// body, the compiler configuration and the parts of the module it
+    /// depends on, such as its type and the types of the functions it calls.
+    /// Recompiling a slightly edited module then only compiles the functions
+    /// which actually changed, which is mostly useful during development.
+    ///
+    /// Functions are stored in the same place as modules: the directory
+    /// configured with [`Config::cache_config_load`], or the store configured
+    /// with [`Config::with_cache_store`]. Nothing is cached if neither is
+    /// enabled.
+    ///
+    /// By default this is `false`.
+    ///
+    /// This method is only available when the `cache` feature of this crate is
+    /// enabled.
+    #[cfg(feature = "cache")]
+    pub fn cache_functions(&mut self, enable: bool) -> &mut Self {
+        self.cache_functions = enable;
+        self
+    }
+
     /// Sets a custom memory creator
     pub fn with_host_memory(&mut self, mem_creator: Arc<dyn MemoryCreator>) -> &mut Self {
         self.memory_creator = Some(MemoryCreatorProxy { mem_creator });
@@ -647,12 +1072,113 @@ impl Config {
         self.isa_flags.clone().finish(settings::Flags::new(flags))
     }
 
+    /// Whether compilation caches must be bypassed because compiling is what
+    /// is being checked or measured.
+    #[cfg(feature = "cache")]
+    pub(crate) fn bypass_caches(&self) -> bool {
+        self.verify_determinism || self.compile_stats
+    }
+
     pub(crate) fn build_compiler(&self) -> Compiler {
         let isa = self.target_isa();
-        Compiler::new(isa, self.strategy, self.tunables.clone(), self.features)
+        let mut compiler = Compiler::new(isa, self.strategy, self.tunables.clone(), self.features);
+        compiler.set_verify_determinism(self.verify_determinism);
+        compiler.set_compile_stats(self.compile_stats);
+        #[cfg(feature = "cache")]
+        if self.cache_functions && !self.bypass_caches() {
+            let cache = match &self.cache_store {
+                Some(store) => Some(FunctionCache::from_store("wasmtime", store.clone())),
+                None => FunctionCache::new("wasmtime", &self.cache_config),
+            };
+            if let Some(cache) = cache {
+                compiler.set_function_cache(cache);
+            }
+        }
+        compiler
+    }
+}
+
+/// Static complexity limits on modules, enforced during validation.
+#[derive(Clone, Default, Debug)]
+pub(crate) struct ModuleLimits {
+    pub(crate) size: Option<usize>,
+    pub(crate) functions: Option<u32>,
+    pub(crate) locals: Option<u32>,
+    pub(crate) types: Option<u32>,
+}
+
+impl ModuleLimits {
+    pub(crate) fn is_unlimited(&self) -> bool {
+        self.size.is_none()
+            && self.functions.is_none()
+            && self.locals.is_none()
+            && self.types.is_none()
+    }
+}
+
+/// The CPU features that can be configured when targeting x86_64, along with
+/// the Cranelift ISA flag each one maps to.
+const X86_64_CPU_FEATURES: &[(&str, &str)] = &[
+    ("sse3", "has_sse3"),
+    ("ssse3", "has_ssse3"),
+    ("sse4.1", "has_sse41"),
+    ("sse4.2", "has_sse42"),
+    ("popcnt", "has_popcnt"),
+    ("avx", "has_avx"),
+    ("avx2", "has_avx2"),
+    ("avx512f", "has_avx512f"),
+    ("avx512dq", "has_avx512dq"),
+    ("avx512vl", "has_avx512vl"),
+    ("bmi1", "has_bmi1"),
+    ("bmi2", "has_bmi2"),
+    ("lzcnt", "has_lzcnt"),
+];
+
+/// The CPU features that can be configured for `triple`, along with the
+/// Cranelift ISA flag each one maps to.
+fn cpu_features(triple: &target_lexicon::Triple) -> &'static [(&'static str, &'static str)] {
+    match triple.architecture {
+        target_lexicon::Architecture::X86_64 => X86_64_CPU_FEATURES,
+        _ => &[],
+    }
+}
+
+/// Maps a Rust target feature name to the corresponding Cranelift ISA flag
+/// for `triple`.
+fn cpu_feature_flag(triple: &target_lexicon::Triple, feature: &str) -> Option<&'static str> {
+    cpu_features(triple)
+        .iter()
+        .find(|&&(name, _)| name == feature)
+        .map(|&(_, flag)| flag)
+}
+
+/// Whether the host CPU supports the feature with the given Rust target
+/// feature name.
+#[cfg(target_arch = "x86_64")]
+fn host_has_cpu_feature(feature: &str) -> bool {
+    match feature {
+        "sse3" => is_x86_feature_detected!("sse3"),
+        "ssse3" => is_x86_feature_detected!("ssse3"),
+        "sse4.1" => is_x86_feature_detected!("sse4.1"),
+        "sse4.2" => is_x86_feature_detected!("sse4.2"),
+        "popcnt" => is_x86_feature_detected!("popcnt"),
+        "avx" => is_x86_feature_detected!("avx"),
+        "avx2" => is_x86_feature_detected!("avx2"),
+        "avx512f" => is_x86_feature_detected!("avx512f"),
+        "avx512dq" => is_x86_feature_detected!("avx512dq"),
+        "avx512vl" => is_x86_feature_detected!("avx512vl"),
+        "bmi1" => is_x86_feature_detected!("bmi1"),
+        "bmi2" => is_x86_feature_detected!("bmi2"),
+        "lzcnt" => is_x86_feature_detected!("lzcnt"),
+        _ => false,
     }
 }
 
+#[cfg(not(target_arch = "x86_64"))]
+fn host_has_cpu_feature(_feature: &str) -> bool {
+    false
+}
+
 fn round_up_to_pages(val: u64) -> u64 {
     let page_size = region::page::size() as u64;
     debug_assert!(page_size.is_power_of_two());
@@ -679,6 +1205,11 @@ impl fmt::Debug for Config {
             .field("wasm_simd", &self.features.simd)
             .field("wasm_multi_value", &self.features.multi_value)
             .field("wasm_module_linking", &self.features.module_linking)
+            .field("runtime_stats", &self.runtime_stats)
+            .field("verify_determinism", &self.verify_determinism)
+            .field("compile_stats", &self.compile_stats)
+            .field("coredump_on_trap", &self.coredump_on_trap)
+            .field("module_limits", &self.module_limits)
             .field(
                 "flags",
                 &settings::Flags::new(self.flags.clone()).to_string(),
@@ -728,6 +1259,64 @@ pub enum OptLevel {
     /// Similar to `speed`, but also performs transformations aimed at reducing
     /// code size.
     SpeedAndSize,
+    /// Favors smaller code over faster code, skipping optimizations which tend
+    /// to grow the generated code. This is intended for deployments where
+    /// instruction cache pressure and artifact size matter more than peak
+    /// throughput.
+    Size,
+}
+
+/// Possible register allocators for the Cranelift codegen backend.
+///
+/// This is used as an argument to the [`Config::cranelift_regalloc`] method.
+#[non_exhaustive]
+#[derive(Clone, Debug)]
+pub enum RegallocAlgorithm {
+    /// A greedy, backtracking allocator which generally produces the best
+    /// code, but may take longer to run.
+    Backtracking,
+    /// A linear scan allocator which usually takes less time to allocate
+    /// registers, at the cost of more spills and moves in the generated code.
+    ///
+    /// This allocator is still experimental in Cranelift and should not yet
+    /// be used in production settings.
+    LinearScan,
+}
+
+/// Possible Cranelift code generators, for targets that have more than one.
+///
+/// This is used as an argument to the [`Config::cranelift_backend`] method.
+#[non_exhaustive]
+#[derive(Clone, Debug)]
+pub enum CraneliftBackend {
+    /// The default code generator for the target.
+    Default,
+    /// The legacy code generator, based on instruction encodings and
+    /// legalization.
+    Legacy,
+    /// The code generator built on Cranelift's new `MachInst` framework.
+    MachInst,
+}
+
+/// Possible Spectre mitigation strategies for bounds checks in generated code.
+///
+/// This is used as an argument to the [`Config::cranelift_spectre_mitigation`]
+/// method.
+#[non_exhaustive]
+#[derive(Clone, Debug)]
+pub enum SpectreMitigation {
+    /// Out-of-bounds addresses are replaced with a null pointer by a
+    /// conditional move after each bounds check, which is not subject to
+    /// branch prediction.
+    ///
+    /// Note that bounds checks which are elided entirely, such as accesses to
+    /// a static memory covered by its guard region, need no mitigation.
+    ConditionalMasking,
+    /// No mitigation is performed.
+    ///
+    /// This makes bounds-checked accesses slightly faster, but should only be
+    /// used when all code running in the process is trusted.
+    None,
 }
 
 /// Select which profiling technique to support.
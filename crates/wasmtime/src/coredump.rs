@@ -0,0 +1,293 @@
+//! Capturing the state of a `Store` when a trap happens as a wasm coredump.
+//!
+//! The format written by [`WasmCoreDump::serialize`] is the one described in
+//! the WebAssembly tool-conventions `Coredump.md`: a wasm module whose memory,
+//! global, and data sections hold the state of the trapping instances, along
+//! with custom sections describing the process, modules, instances, and the
+//! stack of the trapping thread.
+
+use crate::snapshot::{
+    global, memory, nonzero_regions, val_type, write_section, write_var_i64, write_var_u32,
+};
+use crate::{FrameInfo, Store, Val};
+use std::fmt;
+use wasmtime_environ::wasm::WasmType;
+
+const CUSTOM_SECTION: u8 = 0;
+const MEMORY_SECTION: u8 = 5;
+const GLOBAL_SECTION: u8 = 6;
+const DATA_SECTION: u8 = 11;
+
+/// The state of a [`Store`] at the time a [`Trap`](crate::Trap) was raised.
+///
+/// Coredumps are captured when they're enabled with
+/// [`Config::coredump_on_trap`](crate::Config::coredump_on_trap) and are
+/// available through [`Trap::coredump`](crate::Trap::coredump). They include
+/// the wasm stack of the trap along with the contents of the memories and
+/// globals of every instance in the store defining either.
+pub struct WasmCoreDump {
+    modules: Vec<String>,
+    instances: Vec<CoreDumpInstance>,
+    frames: Vec<CoreDumpFrame>,
+}
+
+struct CoreDumpInstance {
+    module: u32,
+    memories: Vec<CoreDumpMemory>,
+    /// Encoded entries of the global section, including the type, mutability
+    /// and initializer of each global.
+    globals: Vec<Vec<u8>>,
+}
+
+struct CoreDumpMemory {
+    maximum: Option<u32>,
+    data: Vec<u8>,
+}
+
+struct CoreDumpFrame {
+    instance: u32,
+    func_index: u32,
+    offset: u32,
+}
+
+impl WasmCoreDump {
+    pub(crate) fn capture(store: &Store, trace: &[FrameInfo]) -> WasmCoreDump {
+        let mut modules: Vec<String> = Vec::new();
+        let mut instances = Vec::new();
+        for instance in store.instances() {
+            let module = instance.handle.module();
+            let defined_memories = module
+                .memory_plans
+                .iter()
+                .skip(module.num_imported_memories);
+            let defined_globals = module.globals.iter().skip(module.num_imported_globals);
+            if defined_memories.len() == 0 && defined_globals.len() == 0 {
+                continue;
+            }
+
+            let name = module.name.as_deref().unwrap_or("<unknown>");
+            let module_index = match modules.iter().position(|m| m == name) {
+                Some(i) => i,
+                None => {
+                    modules.push(name.to_string());
+                    modules.len() - 1
+                }
+            };
+
+            let memories = defined_memories
+                .map(|(index, plan)| {
+                    let memory = memory(&instance, index);
+                    // Safety: wasm has been unwound, or is stopped at the
+                    // trap, and `Store`s are confined to one thread.
+                    let data = unsafe { memory.data_unchecked() }.to_vec();
+                    CoreDumpMemory {
+                        maximum: plan.memory.maximum,
+                        data,
+                    }
+                })
+                .collect();
+            let globals = defined_globals
+                .map(|(index, ty)| encode_global(ty.wasm_ty, global(&instance, index).get()))
+                .collect();
+
+            instances.push(CoreDumpInstance {
+                module: module_index as u32,
+                memories,
+                globals,
+            });
+        }
+
+        // Frames don't record which instance they were running in, so they're
+        // attributed to the first instance of a module with the same name.
+        let frames = trace
+            .iter()
+            .map(|frame| {
+                let name = frame.module_name().unwrap_or("<unknown>");
+                let instance = instances
+                    .iter()
+                    .position(|i| modules[i.module as usize] == name)
+                    .unwrap_or(0);
+                CoreDumpFrame {
+                    instance: instance as u32,
+                    func_index: frame.func_index(),
+                    offset: frame.module_offset() as u32,
+                }
+            })
+            .collect();
+
+        WasmCoreDump {
+            modules,
+            instances,
+            frames,
+        }
+    }
+
+    /// Returns the names of the modules of the instances in this coredump.
+    ///
+    /// Modules without a name in their name section are listed as
+    /// `<unknown>`.
+    pub fn modules(&self) -> &[String] {
+        &self.modules
+    }
+
+    /// Returns the contents of all memories in this coredump, in the order
+    /// they're written to the serialized coredump.
+    pub fn memories(&self) -> impl Iterator<Item = &[u8]> {
+        self.instances
+            .iter()
+            .flat_map(|i| i.memories.iter())
+            .map(|m| &m.data[..])
+    }
+
+    /// Serializes this coredump into the wasm coredump format, recording
+    /// `name` as the name of the process that trapped.
+    pub fn serialize(&self, name: &str) -> Vec<u8> {
+        let mut ret = b"\0asm\x01\0\0\0".to_vec();
+
+        let mut core = vec![0x00];
+        write_name(&mut core, name);
+        write_custom_section(&mut ret, "core", &core);
+
+        let mut coremodules = Vec::new();
+        write_var_u32(&mut coremodules, self.modules.len() as u32);
+        for module in self.modules.iter() {
+            coremodules.push(0x00);
+            write_name(&mut coremodules, module);
+        }
+        write_custom_section(&mut ret, "coremodules", &coremodules);
+
+        let mut coreinstances = Vec::new();
+        write_var_u32(&mut coreinstances, self.instances.len() as u32);
+        let mut memories = 0;
+        let mut globals = 0;
+        for instance in self.instances.iter() {
+            coreinstances.push(0x00);
+            write_var_u32(&mut coreinstances, instance.module);
+            write_var_u32(&mut coreinstances, instance.memories.len() as u32);
+            for _ in instance.memories.iter() {
+                write_var_u32(&mut coreinstances, memories);
+                memories += 1;
+            }
+            write_var_u32(&mut coreinstances, instance.globals.len() as u32);
+            for _ in instance.globals.iter() {
+                write_var_u32(&mut coreinstances, globals);
+                globals += 1;
+            }
+        }
+        write_custom_section(&mut ret, "coreinstances", &coreinstances);
+
+        // Locals and operand stack values aren't available, so each frame
+        // records empty lists of them.
+        let mut corestack = vec![0x00];
+        write_name(&mut corestack, "main");
+        write_var_u32(&mut corestack, self.frames.len() as u32);
+        for frame in self.frames.iter() {
+            corestack.push(0x00);
+            write_var_u32(&mut corestack, frame.instance);
+            write_var_u32(&mut corestack, frame.func_index);
+            write_var_u32(&mut corestack, frame.offset);
+            write_var_u32(&mut corestack, 0);
+            write_var_u32(&mut corestack, 0);
+        }
+        write_custom_section(&mut ret, "corestack", &corestack);
+
+        let mut memory_section = Vec::new();
+        write_var_u32(&mut memory_section, memories);
+        for memory in self.instances.iter().flat_map(|i| i.memories.iter()) {
+            memory_section.push(if memory.maximum.is_some() { 0x01 } else { 0x00 });
+            write_var_u32(&mut memory_section, (memory.data.len() / 65536) as u32);
+            if let Some(maximum) = memory.maximum {
+                write_var_u32(&mut memory_section, maximum);
+            }
+        }
+        write_section(&mut ret, MEMORY_SECTION, &memory_section);
+
+        let mut global_section = Vec::new();
+        write_var_u32(&mut global_section, globals);
+        for global in self.instances.iter().flat_map(|i| i.globals.iter()) {
+            global_section.extend_from_slice(global);
+        }
+        write_section(&mut ret, GLOBAL_SECTION, &global_section);
+
+        let mut segments = Vec::new();
+        let mut count = 0;
+        let all_memories = self.instances.iter().flat_map(|i| i.memories.iter());
+        for (index, memory) in all_memories.enumerate() {
+            for (start, end) in nonzero_regions(&memory.data) {
+                segments.push(0x02);
+                write_var_u32(&mut segments, index as u32);
+                segments.push(0x41);
+                write_var_i64(&mut segments, i64::from(start as u32 as i32));
+                segments.push(0x0b);
+                write_var_u32(&mut segments, (end - start) as u32);
+                segments.extend_from_slice(&memory.data[start..end]);
+                count += 1;
+            }
+        }
+        let mut data_section = Vec::new();
+        write_var_u32(&mut data_section, count);
+        data_section.extend_from_slice(&segments);
+        write_section(&mut ret, DATA_SECTION, &data_section);
+
+        ret
+    }
+}
+
+impl fmt::Debug for WasmCoreDump {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WasmCoreDump")
+            .field("modules", &self.modules)
+            .field("instances", &self.instances.len())
+            .field("frames", &self.frames.len())
+            .finish()
+    }
+}
+
+/// Encodes an entry of the global section for a mutable global of type `ty`
+/// initialized to `val`.
+///
+/// Non-null references can't be expressed in a constant expression, so
+/// they're recorded as null.
+fn encode_global(ty: WasmType, val: Val) -> Vec<u8> {
+    let mut ret = Vec::new();
+    ret.push(val_type(ty).unwrap_or(0x70));
+    ret.push(0x01);
+    match val {
+        Val::I32(i) => {
+            ret.push(0x41);
+            write_var_i64(&mut ret, i64::from(i));
+        }
+        Val::I64(i) => {
+            ret.push(0x42);
+            write_var_i64(&mut ret, i);
+        }
+        Val::F32(bits) => {
+            ret.push(0x43);
+            ret.extend_from_slice(&bits.to_le_bytes());
+        }
+        Val::F64(bits) => {
+            ret.push(0x44);
+            ret.extend_from_slice(&bits.to_le_bytes());
+        }
+        Val::V128(bits) => {
+            ret.extend_from_slice(&[0xfd, 0x0c]);
+            ret.extend_from_slice(&bits.to_le_bytes());
+        }
+        Val::ExternRef(_) => ret.extend_from_slice(&[0xd0, 0x6f]),
+        Val::FuncRef(_) => ret.extend_from_slice(&[0xd0, 0x70]),
+    }
+    ret.push(0x0b);
+    ret
+}
+
+fn write_name(dst: &mut Vec<u8>, name: &str) {
+    write_var_u32(dst, name.len() as u32);
+    dst.extend_from_slice(name.as_bytes());
+}
+
+fn write_custom_section(dst: &mut Vec<u8>, name: &str, contents: &[u8]) {
+    let mut section = Vec::new();
+    write_name(&mut section, name);
+    section.extend_from_slice(contents);
+    write_section(dst, CUSTOM_SECTION, &section);
+}
@@ -1,4 +1,8 @@
-use crate::Config;
+use crate::metrics::MetricsCounters;
+use crate::{Config, EngineMetrics, MetricsEvent, Module};
+use anyhow::Result;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicUsize, Ordering::SeqCst};
 use std::sync::Arc;
 #[cfg(feature = "cache")]
 use wasmtime_cache::CacheConfig;
@@ -35,6 +39,51 @@ pub struct Engine {
 struct EngineInner {
     config: Config,
     compiler: Compiler,
+    resources: Arc<ResourceCounters>,
+    metrics: MetricsCounters,
+}
+
+/// Counters of the live resources associated with an [`Engine`].
+#[derive(Default)]
+pub(crate) struct ResourceCounters {
+    pub(crate) modules: AtomicUsize,
+    pub(crate) code_bytes: AtomicUsize,
+    pub(crate) stores: AtomicUsize,
+    pub(crate) instances: AtomicUsize,
+}
+
+/// Accounts for a compiled module in its engine's [`ResourceCounters`] for as
+/// long as it's alive.
+pub(crate) struct ModuleResources {
+    counters: Arc<ResourceCounters>,
+    code_bytes: usize,
+}
+
+impl Drop for ModuleResources {
+    fn drop(&mut self) {
+        self.counters.modules.fetch_sub(1, SeqCst);
+        self.counters.code_bytes.fetch_sub(self.code_bytes, SeqCst);
+    }
+}
+
+/// A snapshot of the resources currently held on behalf of an [`Engine`].
+///
+/// This is returned by [`Engine::resources`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct EngineResources {
+    /// The number of compiled modules which are still alive, either through
+    /// a [`Module`](crate::Module) or through an instance of one.
+    pub modules: usize,
+    /// The number of bytes of executable memory mapped for the code of those
+    /// modules.
+    pub code_bytes: usize,
+    /// The number of [`Store`](crate::Store)s which are still alive.
+    pub stores: usize,
+    /// The number of instances held by those stores. This includes the
+    /// instances created internally for host-defined functions, memories,
+    /// tables and globals.
+    pub instances: usize,
 }
 
 impl Engine {
@@ -46,6 +95,8 @@ impl Engine {
             inner: Arc::new(EngineInner {
                 config: config.clone(),
                 compiler: config.build_compiler(),
+                resources: Default::default(),
+                metrics: Default::default(),
             }),
         }
     }
@@ -64,10 +115,196 @@ impl Engine {
         &self.config().cache_config
     }
 
+    pub(crate) fn resource_counters(&self) -> &ResourceCounters {
+        &self.inner.resources
+    }
+
+    pub(crate) fn register_module_resources(&self, code_bytes: usize) -> ModuleResources {
+        let counters = self.inner.resources.clone();
+        counters.modules.fetch_add(1, SeqCst);
+        counters.code_bytes.fetch_add(code_bytes, SeqCst);
+        ModuleResources {
+            counters,
+            code_bytes,
+        }
+    }
+
+    /// Returns a snapshot of the resources currently held on behalf of this
+    /// engine.
+    ///
+    /// Compiled code is unmapped as soon as the last [`Module`](crate::Module)
+    /// referencing it is dropped and no live [`Store`](crate::Store) holds an
+    /// instance of it, and instances are deallocated when their `Store` is
+    /// dropped. These counters can be used by long-running embeddings to
+    /// verify that resources are released as modules and stores churn.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmtime::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// let engine = Engine::default();
+    /// let module = Module::new(&engine, "(module (func (export \"f\")))")?;
+    /// let store = Store::new(&engine);
+    /// Instance::new(&store, &module, &[])?;
+    /// let resources = engine.resources();
+    /// assert_eq!(resources.modules, 1);
+    /// assert_eq!(resources.stores, 1);
+    /// assert_eq!(resources.instances, 1);
+    ///
+    /// drop((module, store));
+    /// assert_eq!(engine.resources(), EngineResources::default());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn resources(&self) -> EngineResources {
+        let counters = &self.inner.resources;
+        EngineResources {
+            modules: counters.modules.load(SeqCst),
+            code_bytes: counters.code_bytes.load(SeqCst),
+            stores: counters.stores.load(SeqCst),
+            instances: counters.instances.load(SeqCst),
+        }
+    }
+
+    pub(crate) fn metrics_counters(&self) -> &MetricsCounters {
+        &self.inner.metrics
+    }
+
+    /// Returns a snapshot of the metrics this engine has collected so far.
+    ///
+    /// This is intended to be polled periodically by embeddings which export
+    /// their internals to a monitoring system. See [`EngineMetrics`] for what
+    /// is measured, and [`Engine::on_metrics_event`] to be notified of each
+    /// update instead.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmtime::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// let engine = Engine::default();
+    /// let module = Module::new(&engine, "(module (func (export \"f\") unreachable))")?;
+    /// let store = Store::new(&engine);
+    /// let instance = Instance::new(&store, &module, &[])?;
+    /// assert!(instance.get_func("f").unwrap().call(&[]).is_err());
+    ///
+    /// let metrics = engine.metrics();
+    /// assert_eq!(metrics.compilations, 1);
+    /// assert_eq!(metrics.instantiations, 1);
+    /// assert_eq!(metrics.traps[&TrapCode::UnreachableCodeReached], 1);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn metrics(&self) -> EngineMetrics {
+        self.inner.metrics.snapshot()
+    }
+
+    /// Registers a callback which is invoked with every update to this
+    /// engine's metrics.
+    ///
+    /// The callback runs synchronously on the thread which did the work being
+    /// measured, so it should be cheap, for example incrementing a counter of
+    /// a StatsD client. It must not register further callbacks on this engine.
+    pub fn on_metrics_event(&self, callback: impl Fn(MetricsEvent) + Send + Sync + 'static) {
+        self.inner.metrics.add_callback(Box::new(callback));
+    }
+
+    /// Releases memory that this engine caches to speed up future
+    /// compilations.
+    ///
+    /// Compilation reuses scratch state between functions and modules, which
+    /// is retained by the engine after compilation finishes. Hosts that
+    /// compile modules only occasionally can call this method afterwards to
+    /// return that memory. This doesn't affect any compiled
+    /// [`Module`](crate::Module) or the on-disk compilation cache.
+    pub fn purge(&self) {
+        self.inner.compiler.purge();
+    }
+
     /// Returns whether the engine `a` and `b` refer to the same configuration.
     pub fn same(a: &Engine, b: &Engine) -> bool {
         Arc::ptr_eq(&a.inner, &b.inner)
     }
+
+    /// Returns a [`Hash`] that can be used to check precompiled WebAssembly
+    /// compatibility.
+    ///
+    /// The output of [`Module::serialize`](crate::Module::serialize) can only
+    /// be deserialized by an engine with a compatible configuration: the same
+    /// Wasmtime version, compilation target, compiler flags and enabled wasm
+    /// features. The value returned here hashes all of those settings, so two
+    /// engines which produce equal hashes can share serialized modules.
+    ///
+    /// This is intended for use as part of a cache key for precompiled
+    /// modules, allowing caches and deployment pipelines to detect stale
+    /// artifacts without attempting to deserialize them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use wasmtime::*;
+    /// use std::collections::hash_map::DefaultHasher;
+    /// use std::hash::{Hash, Hasher};
+    ///
+    /// fn hash_of(engine: &Engine) -> u64 {
+    ///     let mut hasher = DefaultHasher::new();
+    ///     engine.precompile_compatibility_hash().hash(&mut hasher);
+    ///     hasher.finish()
+    /// }
+    ///
+    /// let mut config = Config::new();
+    /// config.cranelift_opt_level(OptLevel::None);
+    /// assert_ne!(hash_of(&Engine::default()), hash_of(&Engine::new(&config)));
+    /// assert_eq!(hash_of(&Engine::default()), hash_of(&Engine::default()));
+    /// ```
+    pub fn precompile_compatibility_hash(&self) -> impl Hash + '_ {
+        HashedEngineCompileEnv(self)
+    }
+
+    /// Ahead-of-time (AOT) compiles a WebAssembly module.
+    ///
+    /// The `bytes` provided must be in one of two formats:
+    ///
+    /// * A [binary-encoded][binary] WebAssembly module. This is always supported.
+    /// * A [text-encoded][text] instance of the WebAssembly text format.
+    ///   This is only supported when the `wat` feature of this crate is enabled.
+    ///   If this is supplied then the text format will be parsed before validation.
+    ///   Note that the `wat` feature is enabled by default.
+    ///
+    /// The result is in the same format as [`Module::serialize`], and can be
+    /// loaded with [`Module::deserialize`] by an engine with a compatible
+    /// configuration. Unlike compiling a [`Module`] and serializing it, the
+    /// compiled code is never loaded into executable memory, so this also
+    /// works for engines configured with [`Config::target`] to compile for a
+    /// different platform.
+    ///
+    /// [binary]: https://webassembly.github.io/spec/core/binary/index.html
+    /// [text]: https://webassembly.github.io/spec/core/text/index.html
+    /// [`Module`]: crate::Module
+    /// [`Module::serialize`]: crate::Module::serialize
+    /// [`Module::deserialize`]: crate::Module::deserialize
+    /// [`Config::target`]: crate::Config::target
+    pub fn precompile_module(&self, bytes: &[u8]) -> Result<Vec<u8>> {
+        #[cfg(feature = "wat")]
+        let bytes = wat::parse_bytes(bytes)?;
+        Module::precompile(self, &bytes)
+    }
+}
+
+/// The result of [`Engine::precompile_compatibility_hash`], hashing all
+/// settings of an engine which affect the compatibility of serialized modules.
+struct HashedEngineCompileEnv<'a>(&'a Engine);
+
+impl Hash for HashedEngineCompileEnv<'_> {
+    fn hash<H: Hasher>(&self, hasher: &mut H) {
+        // The compiler's hash covers the target, compiler flags, tunables and
+        // enabled wasm features, along with the version of the compiler.
+        self.0.compiler().hash(hasher);
+
+        // Catch accidental reuse of artifacts across versions of this crate.
+        env!("CARGO_PKG_VERSION").hash(hasher);
+    }
 }
 
 impl Default for Engine {
@@ -130,6 +367,17 @@ mod tests {
         assert_eq!(engine.config().cache_config.cache_hits(), 1);
         assert_eq!(engine.config().cache_config.cache_misses(), 1);
 
+        let mut cfg = Config::new();
+        cfg.cranelift_opt_level(OptLevel::Size)
+            .cache_config_load(&config_path)?;
+        let engine = Engine::new(&cfg);
+        Module::new(&engine, "(module (func))")?;
+        assert_eq!(engine.config().cache_config.cache_hits(), 0);
+        assert_eq!(engine.config().cache_config.cache_misses(), 1);
+        Module::new(&engine, "(module (func))")?;
+        assert_eq!(engine.config().cache_config.cache_hits(), 1);
+        assert_eq!(engine.config().cache_config.cache_misses(), 1);
+
         // FIXME(#1523) need debuginfo on aarch64 before we run this test there
         if !cfg!(target_arch = "aarch64") {
             let mut cfg = Config::new();
@@ -145,4 +393,120 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn custom_cache_store() -> Result<()> {
+        use crate::CacheStore;
+        use std::collections::HashMap;
+        use std::sync::{Arc, Mutex};
+
+        #[derive(Default)]
+        struct MemoryStore {
+            entries: Mutex<HashMap<String, Vec<u8>>>,
+            gets: Mutex<Vec<bool>>,
+        }
+
+        impl CacheStore for MemoryStore {
+            fn get(&self, key: &str) -> Option<Vec<u8>> {
+                let ret = self.entries.lock().unwrap().get(key).cloned();
+                self.gets.lock().unwrap().push(ret.is_some());
+                ret
+            }
+
+            fn put(&self, key: &str, value: &[u8]) -> bool {
+                let mut entries = self.entries.lock().unwrap();
+                entries.insert(key.to_string(), value.to_vec());
+                true
+            }
+        }
+
+        let store = Arc::new(MemoryStore::default());
+        let mut cfg = Config::new();
+        cfg.with_cache_store(store.clone());
+        let engine = Engine::new(&cfg);
+        Module::new(&engine, "(module (func))")?;
+        Module::new(&engine, "(module (func))")?;
+        assert_eq!(*store.gets.lock().unwrap(), [false, true]);
+        assert_eq!(store.entries.lock().unwrap().len(), 1);
+
+        // A differently configured engine doesn't reuse the entry.
+        let mut cfg = Config::new();
+        cfg.cranelift_opt_level(OptLevel::None)
+            .with_cache_store(store.clone());
+        let engine = Engine::new(&cfg);
+        Module::new(&engine, "(module (func))")?;
+        assert_eq!(*store.gets.lock().unwrap(), [false, true, false]);
+        assert_eq!(store.entries.lock().unwrap().len(), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn function_cache() -> Result<()> {
+        use crate::{CacheStore, Instance, Store, Trap};
+        use std::collections::HashMap;
+        use std::sync::{Arc, Mutex};
+
+        #[derive(Default)]
+        struct MemoryStore {
+            entries: Mutex<HashMap<String, Vec<u8>>>,
+            hits: Mutex<usize>,
+        }
+
+        impl CacheStore for MemoryStore {
+            fn get(&self, key: &str) -> Option<Vec<u8>> {
+                let ret = self.entries.lock().unwrap().get(key).cloned();
+                if ret.is_some() {
+                    *self.hits.lock().unwrap() += 1;
+                }
+                ret
+            }
+
+            fn put(&self, key: &str, value: &[u8]) -> bool {
+                let mut entries = self.entries.lock().unwrap();
+                entries.insert(key.to_string(), value.to_vec());
+                true
+            }
+        }
+
+        let store = Arc::new(MemoryStore::default());
+        let mut cfg = Config::new();
+        cfg.with_cache_store(store.clone()).cache_functions(true);
+        let engine = Engine::new(&cfg);
+
+        // One entry for the module and one for each function.
+        Module::new(
+            &engine,
+            r#"(module
+                (func (result i32) i32.const 1)
+                (func (export "f") unreachable))"#,
+        )?;
+        assert_eq!(*store.hits.lock().unwrap(), 0);
+        assert_eq!(store.entries.lock().unwrap().len(), 3);
+
+        // After editing the first function the second one is reused, even
+        // though its offset in the binary changed.
+        let wat = r#"(module
+            (func (result i32) i32.const 1000)
+            (func (export "f") unreachable))"#;
+        let trap_offset = |engine: &Engine| -> Result<usize> {
+            let module = Module::new(engine, wat)?;
+            let store = Store::new(engine);
+            let instance = Instance::new(&store, &module, &[])?;
+            let trap = instance
+                .get_func("f")
+                .unwrap()
+                .call(&[])
+                .err()
+                .expect("error calling function")
+                .downcast::<Trap>()?;
+            Ok(trap.trace()[0].module_offset())
+        };
+        let offset = trap_offset(&engine)?;
+        assert_eq!(*store.hits.lock().unwrap(), 1);
+        assert_eq!(store.entries.lock().unwrap().len(), 5);
+        assert_eq!(offset, trap_offset(&Engine::new(&Config::new()))?);
+
+        Ok(())
+    }
 }
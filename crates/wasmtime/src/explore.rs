@@ -0,0 +1,219 @@
+//! Support for inspecting the code generated for a wasm module, see
+//! [`Module::compiled_functions`](crate::Module::compiled_functions).
+
+use crate::Engine;
+use anyhow::Result;
+use std::fmt;
+use wasmtime_environ::entity::EntityRef;
+use wasmtime_environ::ModuleEnvironment;
+use wasmtime_jit::CompiledModule;
+
+/// The code generated for a single WebAssembly function.
+///
+/// This is returned by
+/// [`Module::compiled_functions`](crate::Module::compiled_functions) and is
+/// intended for inspecting the decisions made by the code generator.
+pub struct FunctionCode {
+    module_index: usize,
+    index: u32,
+    name: Option<String>,
+    ir: Option<String>,
+    code: Vec<u8>,
+    #[cfg(feature = "disas")]
+    arch: target_lexicon::Architecture,
+}
+
+impl FunctionCode {
+    /// Returns the index of the module this function is defined in.
+    ///
+    /// This is always 0 unless the module linking proposal is enabled, in
+    /// which case modules nested in the original binary are numbered in the
+    /// order in which they're translated.
+    pub fn module_index(&self) -> usize {
+        self.module_index
+    }
+
+    /// Returns the index of this function in its module's function index
+    /// space, which includes imported functions.
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    /// Returns the name of this function from the `name` custom section, if
+    /// present.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Returns the compiler's intermediate representation of this function
+    /// after optimization and lowering, just before machine code was emitted.
+    ///
+    /// For Cranelift this is the CLIF of the function. Returns `None` for
+    /// compilation strategies which have no textual intermediate
+    /// representation.
+    pub fn ir(&self) -> Option<&str> {
+        self.ir.as_deref()
+    }
+
+    /// Returns the machine code generated for this function, followed by any
+    /// read-only data such as jump tables and constants.
+    ///
+    /// Relocations in this code have not been applied.
+    pub fn code(&self) -> &[u8] {
+        &self.code
+    }
+
+    /// Returns a disassembly of [`FunctionCode::code`].
+    ///
+    /// This method is only available when the `disas` feature of this crate
+    /// is enabled.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there's no disassembler for the target
+    /// architecture.
+    #[cfg(feature = "disas")]
+    pub fn disassembly(&self) -> Result<String> {
+        use capstone::prelude::*;
+        use std::fmt::Write;
+        use target_lexicon::Architecture;
+
+        fn map_caperr(err: capstone::Error) -> anyhow::Error {
+            anyhow::format_err!("{}", err)
+        }
+
+        let cs = match self.arch {
+            Architecture::X86_64 => Capstone::new()
+                .x86()
+                .mode(arch::x86::ArchMode::Mode64)
+                .build()
+                .map_err(map_caperr)?,
+            Architecture::Aarch64 { .. } => {
+                let mut cs = Capstone::new()
+                    .arm64()
+                    .mode(arch::arm64::ArchMode::Arm)
+                    .build()
+                    .map_err(map_caperr)?;
+                // Constants are inlined in the instruction stream on AArch64,
+                // so keep going when encountering one.
+                cs.set_skipdata(true).map_err(map_caperr)?;
+                cs
+            }
+            arch => anyhow::bail!("no disassembler available for {}", arch),
+        };
+
+        let insns = cs.disasm_all(&self.code, 0).map_err(map_caperr)?;
+        let mut ret = String::new();
+        for i in insns.iter() {
+            write!(ret, "{:6x}:\t", i.address()).unwrap();
+            if let Some(s) = i.mnemonic() {
+                write!(ret, "{}\t", s).unwrap();
+            }
+            if let Some(s) = i.op_str() {
+                write!(ret, "{}", s).unwrap();
+            }
+            ret.push('\n');
+        }
+        Ok(ret)
+    }
+}
+
+impl fmt::Debug for FunctionCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("FunctionCode")
+            .field("module_index", &self.module_index)
+            .field("index", &self.index)
+            .field("name", &self.name)
+            .field("code_size", &self.code.len())
+            .finish()
+    }
+}
+
+pub(crate) fn compiled_functions(engine: &Engine, binary: &[u8]) -> Result<Vec<FunctionCode>> {
+    let compiler = engine.compiler();
+    let (translations, types) = ModuleEnvironment::new(
+        compiler.frontend_config(),
+        compiler.tunables(),
+        compiler.features(),
+    )
+    .translate(binary)?;
+
+    let mut ret = Vec::new();
+    for (module_index, mut translation) in translations.into_iter().enumerate() {
+        let funcs = compiler.compile_with_ir(&mut translation, &types)?;
+        let module = &translation.module;
+        for (defined, func, ir) in funcs {
+            let index = module.func_index(defined);
+            ret.push(FunctionCode {
+                module_index,
+                index: index.index() as u32,
+                name: module.func_names.get(&index).cloned(),
+                ir,
+                code: func.body,
+                #[cfg(feature = "disas")]
+                arch: compiler.isa().triple().architecture,
+            });
+        }
+    }
+    ret.sort_by_key(|f| (f.module_index, f.index));
+    Ok(ret)
+}
+
+/// The size of the native code of a single function in a compiled module.
+///
+/// This is returned by
+/// [`Module::function_sizes`](crate::Module::function_sizes) and, unlike
+/// [`FunctionCode`], is available for modules created with
+/// [`Module::deserialize`](crate::Module::deserialize).
+#[derive(Debug, Clone)]
+pub struct FunctionSize {
+    module_index: usize,
+    index: u32,
+    name: Option<String>,
+    size: usize,
+}
+
+impl FunctionSize {
+    /// Returns the index of the module this function is defined in.
+    ///
+    /// This is always 0 unless the module linking proposal is enabled, in
+    /// which case modules nested in the original binary are numbered in the
+    /// order in which they're translated.
+    pub fn module_index(&self) -> usize {
+        self.module_index
+    }
+
+    /// Returns the index of this function in its module's function index
+    /// space, which includes imported functions.
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    /// Returns the name of this function from the `name` custom section, if
+    /// present.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Returns the size in bytes of this function's machine code.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+}
+
+pub(crate) fn function_sizes(modules: &[CompiledModule]) -> Vec<FunctionSize> {
+    let mut ret = Vec::new();
+    for (module_index, compiled) in modules.iter().enumerate() {
+        let module = compiled.module();
+        for (defined, body) in compiled.finished_functions() {
+            let index = module.func_index(defined);
+            ret.push(FunctionSize {
+                module_index,
+                index: index.index() as u32,
+                name: module.func_names.get(&index).cloned(),
+                size: unsafe { (**body).len() },
+            });
+        }
+    }
+    ret
+}
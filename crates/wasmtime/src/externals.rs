@@ -278,6 +278,7 @@ impl Global {
                 ValType::I64 => Val::from(*definition.as_i64()),
                 ValType::F32 => Val::F32(*definition.as_u32()),
                 ValType::F64 => Val::F64(*definition.as_u64()),
+                ValType::V128 => Val::V128(*definition.as_u128()),
                 ValType::ExternRef => Val::ExternRef(
                     definition
                         .as_externref()
@@ -287,7 +288,6 @@ impl Global {
                 ValType::FuncRef => {
                     from_checked_anyfunc(definition.as_anyfunc() as *mut _, &self.instance.store)
                 }
-                ty => unimplemented!("Global::get for {:?}", ty),
             }
         }
     }
@@ -316,6 +316,7 @@ impl Global {
                 Val::I64(i) => *definition.as_i64_mut() = i,
                 Val::F32(f) => *definition.as_u32_mut() = f,
                 Val::F64(f) => *definition.as_u64_mut() = f,
+                Val::V128(v) => *definition.as_u128_mut() = v,
                 Val::FuncRef(f) => {
                     *definition.as_anyfunc_mut() = f.map_or(ptr::null(), |f| {
                         f.caller_checked_anyfunc().as_ptr() as *const _
@@ -329,7 +330,6 @@ impl Global {
                     let old = mem::replace(definition.as_externref_mut(), x.map(|x| x.inner));
                     drop(old);
                 }
-                _ => unimplemented!("Global::set for {:?}", val.ty()),
             }
         }
         Ok(())
@@ -915,6 +915,55 @@ impl Memory {
         slice::from_raw_parts_mut(definition.base, definition.current_length)
     }
 
+    /// Copies the contents of this memory starting at `offset` into
+    /// `buffer`.
+    ///
+    /// Unlike [`Memory::data_unchecked`] this is safe to call at any time,
+    /// since the bytes are copied out before any other code can observe or
+    /// modify the memory.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the range `offset..offset + buffer.len()` is out
+    /// of bounds of this memory, in which case `buffer` isn't modified.
+    pub fn read(&self, offset: usize, buffer: &mut [u8]) -> Result<()> {
+        let data = unsafe { self.data_unchecked() };
+        match offset
+            .checked_add(buffer.len())
+            .and_then(|end| data.get(offset..end))
+        {
+            Some(src) => {
+                buffer.copy_from_slice(src);
+                Ok(())
+            }
+            None => bail!("out of bounds memory access"),
+        }
+    }
+
+    /// Copies `buffer` into this memory starting at `offset`.
+    ///
+    /// Unlike [`Memory::data_unchecked_mut`] this is safe to call at any
+    /// time, since the bytes are copied in before any other code can observe
+    /// or modify the memory.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the range `offset..offset + buffer.len()` is out
+    /// of bounds of this memory, in which case the memory isn't modified.
+    pub fn write(&self, offset: usize, buffer: &[u8]) -> Result<()> {
+        let data = unsafe { self.data_unchecked_mut() };
+        match offset
+            .checked_add(buffer.len())
+            .and_then(|end| data.get_mut(offset..end))
+        {
+            Some(dst) => {
+                dst.copy_from_slice(buffer);
+                Ok(())
+            }
+            None => bail!("out of bounds memory access"),
+        }
+    }
+
     /// Returns the base pointer, in the host's address space, that the memory
     /// is located at.
     ///
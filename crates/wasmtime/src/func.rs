@@ -284,14 +284,17 @@ impl Func {
             let mut returns: SmallVec<[Val; STACK_RETURNS]> =
                 smallvec![Val::null(); ty_clone.results().len()];
 
-            func(
+            store.enter_host();
+            let result = func(
                 Caller {
                     store: &store_weak,
                     caller_vmctx,
                 },
                 &args,
                 &mut returns,
-            )?;
+            );
+            store.exit_host();
+            result?;
 
             // Unlike our arguments we need to dynamically check that the return
             // values produced are correct. There could be a bug in `func` that
@@ -537,6 +540,62 @@ impl Func {
         func.into_func(store)
     }
 
+    /// Creates a new `Func` from the given Rust closure, like [`Func::wrap`],
+    /// which is cheaper to call from WebAssembly.
+    ///
+    /// This is intended for tiny host functions which are called very
+    /// frequently, such as reading a clock or generating random numbers,
+    /// where the bookkeeping done around calls to functions created with
+    /// [`Func::wrap`] is a significant part of the cost of the call. To make
+    /// that possible intrinsics are more restricted:
+    ///
+    /// * Their parameters and result can only be the plain number types
+    ///   implementing [`IntrinsicTy`], so they never need to be checked
+    ///   against the store.
+    /// * They can't take a [`Caller`] parameter.
+    /// * They can't trap, so they can't return a `Result`.
+    ///
+    /// Additionally time spent in intrinsics isn't accounted for as host time
+    /// by [`Store::runtime_stats`](crate::Store::runtime_stats), and calls to
+    /// them aren't counted. Panics are still propagated to the caller as they
+    /// are for [`Func::wrap`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use wasmtime::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// # let store = Store::default();
+    /// use std::time::{SystemTime, UNIX_EPOCH};
+    ///
+    /// let now = Func::wrap_intrinsic(&store, || {
+    ///     SystemTime::now()
+    ///         .duration_since(UNIX_EPOCH)
+    ///         .map(|d| d.as_nanos() as u64)
+    ///         .unwrap_or(0)
+    /// });
+    /// let module = Module::new(
+    ///     store.engine(),
+    ///     r#"
+    ///         (module
+    ///             (import "" "now" (func $now (result i64)))
+    ///             (func (export "foo") (result i64)
+    ///                 call $now))
+    ///     "#,
+    /// )?;
+    /// let instance = Instance::new(&store, &module, &[now.into()])?;
+    /// let foo = instance.get_func("foo").unwrap().get0::<u64>()?;
+    /// assert!(foo()? > 0);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn wrap_intrinsic<Params, Results>(
+        store: &Store,
+        func: impl IntoIntrinsic<Params, Results>,
+    ) -> Func {
+        func.into_intrinsic(store)
+    }
+
     pub(crate) fn sig_index(&self) -> VMSharedSignatureIndex {
         unsafe { self.export.anyfunc.as_ref().type_index }
     }
@@ -826,8 +885,10 @@ pub(crate) fn invoke_wasm_and_catch_traps(
             .externref_activations_table()
             .set_stack_canary(&canary);
 
-        wasmtime_runtime::catch_traps(vmctx, store, closure)
-            .map_err(|e| Trap::from_runtime(store, e))
+        let in_host = store.enter_wasm();
+        let result = wasmtime_runtime::catch_traps(vmctx, store, closure);
+        store.exit_wasm(in_host);
+        result.map_err(|e| Trap::from_runtime(store, e))
     }
 }
 
@@ -1419,6 +1480,34 @@ where
     }
 }
 
+/// A trait implemented for the types which can be passed to and returned from
+/// closures passed to [`Func::wrap_intrinsic`].
+///
+/// These are `i32`, `u32`, `i64`, `u64`, `f32` and `f64`, as well as `()` as
+/// a result. None of them can refer to a [`Store`], which is what allows
+/// intrinsics to skip checking them.
+///
+/// This trait should not be implemented by user types.
+pub unsafe trait IntrinsicTy: WasmTy {}
+
+unsafe impl IntrinsicTy for () {}
+unsafe impl IntrinsicTy for i32 {}
+unsafe impl IntrinsicTy for u32 {}
+unsafe impl IntrinsicTy for i64 {}
+unsafe impl IntrinsicTy for u64 {}
+unsafe impl IntrinsicTy for f32 {}
+unsafe impl IntrinsicTy for f64 {}
+
+/// Internal trait implemented for all arguments that can be passed to
+/// [`Func::wrap_intrinsic`].
+///
+/// This trait should not be implemented by external users, it's only intended
+/// as an implementation detail of this crate.
+pub trait IntoIntrinsic<Params, Results> {
+    #[doc(hidden)]
+    fn into_intrinsic(self, store: &Store) -> Func;
+}
+
 /// Internal trait implemented for all arguments that can be passed to
 /// [`Func::wrap`].
 ///
@@ -1597,6 +1686,9 @@ macro_rules! impl_into_func {
                     let (func, store) = &*(state as *const _ as *const (F, Weak<StoreInner>));
                     let weak_store = WeakStore(store);
 
+                    if let Some(store) = Store::upgrade(store) {
+                        store.enter_host();
+                    }
                     let ret = {
                         panic::catch_unwind(AssertUnwindSafe(|| {
                             func(
@@ -1605,6 +1697,9 @@ macro_rules! impl_into_func {
                             )
                         }))
                     };
+                    if let Some(store) = Store::upgrade(store) {
+                        store.exit_host();
+                    }
 
                     // Note that we need to be careful when dealing with traps
                     // here. Traps are implemented with longjmp/setjmp meaning
@@ -1691,6 +1786,100 @@ macro_rules! impl_into_func {
                 }
             }
         }
+
+        #[allow(non_snake_case)]
+        impl<F, $($args,)* R> IntoIntrinsic<($($args,)*), R> for F
+        where
+            F: Fn($($args),*) -> R + 'static,
+            $($args: IntrinsicTy,)*
+            R: IntrinsicTy,
+        {
+            fn into_intrinsic(self, store: &Store) -> Func {
+                /// Like `wasm_to_host_shim` above, but for intrinsics. Their
+                /// arguments and result can't refer to a store, and they
+                /// can't trap or take a `Caller`, so all that's left to do
+                /// is to call the function and to keep panics from unwinding
+                /// into Wasm.
+                unsafe extern "C" fn intrinsic_shim<F, $($args,)* R>(
+                    vmctx: *mut VMContext,
+                    _caller_vmctx: *mut VMContext,
+                    $( $args: $args::Abi, )*
+                ) -> R::Abi
+                where
+                    F: Fn($( $args ),*) -> R + 'static,
+                    $( $args: IntrinsicTy, )*
+                    R: IntrinsicTy,
+                {
+                    let state = (*vmctx).host_state();
+                    debug_assert!(state.is::<(F, Weak<StoreInner>)>());
+                    let (func, store) = &*(state as *const _ as *const (F, Weak<StoreInner>));
+                    let weak_store = WeakStore(store);
+
+                    let ret = panic::catch_unwind(AssertUnwindSafe(|| {
+                        func($( $args::from_abi($args, weak_store), )*)
+                    }));
+                    match ret {
+                        Err(panic) => wasmtime_runtime::resume_panic(panic),
+                        Ok(ret) => ret.into_abi_for_arg(weak_store),
+                    }
+                }
+
+                /// Same as `host_trampoline` above, for `intrinsic_shim`.
+                unsafe extern "C" fn intrinsic_trampoline<$($args,)* R>(
+                    callee_vmctx: *mut VMContext,
+                    caller_vmctx: *mut VMContext,
+                    ptr: *const VMFunctionBody,
+                    args: *mut u128,
+                )
+                where
+                    $($args: IntrinsicTy,)*
+                    R: IntrinsicTy,
+                {
+                    let ptr = mem::transmute::<
+                        *const VMFunctionBody,
+                        unsafe extern "C" fn(
+                            *mut VMContext,
+                            *mut VMContext,
+                            $( $args::Abi, )*
+                        ) -> R::Abi,
+                    >(ptr);
+
+                    let mut _next = args as *const u128;
+                    $( let $args = $args::load_from_args(&mut _next); )*
+                    let ret = ptr(callee_vmctx, caller_vmctx, $( $args ),*);
+                    R::store_to_args(ret, args);
+                }
+
+                let ty = FuncType::new(
+                    None::<ValType>.into_iter()
+                        $(.chain($args::valtype()))*
+                    ,
+                    R::valtype(),
+                );
+
+                let store_weak = store.weak();
+                let trampoline = intrinsic_trampoline::<$($args,)* R>;
+                let (instance, export) = unsafe {
+                    crate::trampoline::generate_raw_func_export(
+                        &ty,
+                        std::slice::from_raw_parts_mut(
+                            intrinsic_shim::<F, $($args,)* R> as *mut _,
+                            0,
+                        ),
+                        trampoline,
+                        store,
+                        Box::new((self, store_weak)),
+                    )
+                    .expect("failed to generate export")
+                };
+
+                Func {
+                    instance,
+                    export,
+                    trampoline,
+                }
+            }
+        }
     )*)
 }
 
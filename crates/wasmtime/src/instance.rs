@@ -173,12 +173,14 @@ fn instantiate(
     // compiled JIT code within the `Store`.
     store.register_module(module);
 
+    store.reserve_instance()?;
     let config = store.engine().config();
     let instance = unsafe {
         let instance = compiled_module.instantiate(
             imports,
             &store.lookup_shared_signature(module.types()),
             config.memory_creator.as_ref().map(|a| a as _),
+            &store.instance_limits(),
             store.interrupts(),
             Box::new(module.types().clone()),
             store.externref_activations_table() as *const VMExternRefActivationsTable as *mut _,
@@ -334,6 +336,8 @@ impl Instance {
             builder.define_extern(idx, import)
         })?;
 
+        store.engine().metrics_counters().instantiated();
+        store.report_metrics();
         Ok(Instance { handle })
     }
 
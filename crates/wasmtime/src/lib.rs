@@ -234,36 +234,51 @@
 #![doc(test(attr(deny(warnings))))]
 #![doc(test(attr(allow(dead_code, unused_variables, unused_mut))))]
 
+mod compile_report;
 mod config;
+mod coredump;
 mod engine;
+mod explore;
 mod externals;
 mod frame_info;
 mod func;
 mod instance;
 mod linker;
+mod metrics;
 mod module;
+mod producers;
 mod r#ref;
 mod sig_registry;
+mod snapshot;
 mod store;
 mod trampoline;
 mod trap;
 mod types;
 mod values;
 
+pub use crate::compile_report::{CompileReport, FunctionCompileReport};
 pub use crate::config::*;
+pub use crate::coredump::WasmCoreDump;
 pub use crate::engine::*;
+pub use crate::explore::{FunctionCode, FunctionSize};
 pub use crate::externals::*;
 pub use crate::frame_info::{FrameInfo, FrameSymbol};
 pub use crate::func::*;
 pub use crate::instance::Instance;
 pub use crate::linker::*;
-pub use crate::module::Module;
+pub use crate::metrics::{EngineMetrics, MetricsEvent};
+pub use crate::module::{Module, ValidationError};
+pub use crate::producers::{Producers, ProducersField, ProducersValue};
 pub use crate::r#ref::ExternRef;
 pub use crate::store::*;
 pub use crate::trap::*;
 pub use crate::types::*;
 pub use crate::values::*;
 
+#[cfg(feature = "cache")]
+pub use wasmtime_cache::CacheStore;
+pub use wasmtime_environ::ExecutionProfile;
+
 cfg_if::cfg_if! {
     if #[cfg(unix)] {
         pub mod unix;
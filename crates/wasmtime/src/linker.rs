@@ -1,6 +1,6 @@
 use crate::{
-    Extern, ExternType, Func, FuncType, GlobalType, ImportType, Instance, IntoFunc, Module, Store,
-    Trap,
+    Extern, ExternType, Func, FuncType, GlobalType, ImportType, Instance, IntoFunc, IntoIntrinsic,
+    Module, Store, Trap,
 };
 use anyhow::{anyhow, bail, Context, Error, Result};
 use log::warn;
@@ -43,6 +43,7 @@ pub struct Linker {
     strings: Vec<Rc<str>>,
     map: HashMap<ImportKey, Extern>,
     allow_shadowing: bool,
+    unknown_imports: Option<Rc<dyn Fn(&ImportType) -> Result<Extern>>>,
 }
 
 #[derive(Hash, PartialEq, Eq)]
@@ -86,6 +87,7 @@ impl Linker {
             string2idx: HashMap::new(),
             strings: Vec::new(),
             allow_shadowing: false,
+            unknown_imports: None,
         }
     }
 
@@ -119,6 +121,61 @@ impl Linker {
         self
     }
 
+    /// Configures a fallback handler used to define imports which aren't
+    /// otherwise defined in this [`Linker`].
+    ///
+    /// When instantiating a module, any import which can't be resolved by
+    /// name and type in this linker is passed to `handler`, which may
+    /// synthesize an item for it on the fly, for example a [`Func`] calling
+    /// into a dynamic dispatch layer or a fresh [`Global`](crate::Global) or
+    /// [`Memory`](crate::Memory). The [`ImportType`] provides the module
+    /// name, field name, and type of the import.
+    ///
+    /// Items returned by `handler` must come from this linker's [`Store`]
+    /// and match the type of the import, or instantiation fails. Returning
+    /// an error from `handler` fails instantiation with that error. Items
+    /// aren't recorded in this linker, so `handler` is called again for each
+    /// instantiation.
+    ///
+    /// Configuring a new handler replaces the previous one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use wasmtime::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// # let store = Store::default();
+    /// let mut linker = Linker::new(&store);
+    /// let store2 = store.clone();
+    /// linker.define_unknown_imports_with(move |import| match import.ty() {
+    ///     ExternType::Func(ty) => {
+    ///         let name = format!("{}::{}", import.module(), import.name().unwrap_or(""));
+    ///         let func = Func::new(&store2, ty, move |_, _, _| {
+    ///             Err(Trap::new(format!("`{}` is not implemented", name)))
+    ///         });
+    ///         Ok(func.into())
+    ///     }
+    ///     _ => anyhow::bail!("only functions may be left undefined"),
+    /// });
+    ///
+    /// let wat = r#"
+    ///     (module
+    ///         (import "host" "missing" (func))
+    ///     )
+    /// "#;
+    /// let module = Module::new(store.engine(), wat)?;
+    /// linker.instantiate(&module)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn define_unknown_imports_with(
+        &mut self,
+        handler: impl Fn(&ImportType) -> Result<Extern> + 'static,
+    ) -> &mut Self {
+        self.unknown_imports = Some(Rc::new(handler));
+        self
+    }
+
     /// Defines a new item in this [`Linker`].
     ///
     /// This method will add a new definition, by name, to this instance of
@@ -219,6 +276,46 @@ impl Linker {
         self._define(module, name, Func::wrap(&self.store, func).into())
     }
 
+    /// Convenience wrapper to define an intrinsic function import.
+    ///
+    /// This method is like [`Linker::func`] but internally delegates to
+    /// [`Func::wrap_intrinsic`], which makes calls to the function cheaper at
+    /// the cost of some restrictions on it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the `module` and `name` already identify an item
+    /// of the same type as the `item` provided and if shadowing is disallowed.
+    /// For more information see the documentation on [`Linker`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use wasmtime::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// # let store = Store::default();
+    /// let mut linker = Linker::new(&store);
+    /// linker.intrinsic("host", "double", |x: i32| x * 2)?;
+    ///
+    /// let wat = r#"
+    ///     (module
+    ///         (import "host" "double" (func (param i32) (result i32)))
+    ///     )
+    /// "#;
+    /// let module = Module::new(store.engine(), wat)?;
+    /// linker.instantiate(&module)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn intrinsic<Params, Args>(
+        &mut self,
+        module: &str,
+        name: &str,
+        func: impl IntoIntrinsic<Params, Args>,
+    ) -> Result<&mut Self> {
+        self._define(module, name, Func::wrap_intrinsic(&self.store, func).into())
+    }
+
     /// Convenience wrapper to define an entire [`Instance`] in this linker.
     ///
     /// This function is a convenience wrapper around [`Linker::define`] which
@@ -581,10 +678,82 @@ impl Linker {
         Instance::new(&self.store, module, &imports)
     }
 
+    /// Pre-initializes the WebAssembly binary `wasm`, returning a new binary
+    /// which starts out in the state `wasm` is in after initialization.
+    ///
+    /// This instantiates `wasm` with this linker's definitions and then calls
+    /// its `init_func` export, which must take no arguments and return no
+    /// results. The contents of the instance's memories and the values of its
+    /// mutable globals are then snapshotted into the returned binary, whose
+    /// data segments and global initializers reflect the initialized state.
+    /// Instantiating the returned binary thus skips any expensive startup
+    /// work done by `init_func`, similarly to the [`wizer`] tool.
+    ///
+    /// The start function, if any, is removed from the returned binary since
+    /// its effects are part of the snapshot. Tables aren't snapshotted, so
+    /// modules which import tables or could modify a table are rejected. Note
+    /// that changes made to imported items or by `data.drop` during
+    /// initialization are not part of the snapshot.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `wasm` fails to compile or instantiate, if
+    /// `init_func` doesn't exist or traps, or if the instance can't be
+    /// snapshotted. Modules which import memories or tables, use module
+    /// linking, or contain instructions such as `table.set` or `elem.drop`
+    /// can't be snapshotted, nor can non-null references held in mutable
+    /// globals.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use wasmtime::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// # let store = Store::default();
+    /// let linker = Linker::new(&store);
+    /// let wasm = wat::parse_str(r#"
+    ///     (module
+    ///         (global $g (mut i32) (i32.const 0))
+    ///         (func (export "init") (global.set $g (i32.const 42)))
+    ///         (func (export "get") (result i32) (global.get $g))
+    ///     )
+    /// "#)?;
+    /// let wasm = linker.preinitialize(&wasm, "init")?;
+    ///
+    /// let module = Module::new(store.engine(), &wasm)?;
+    /// let instance = linker.instantiate(&module)?;
+    /// let get = instance.get_func("get").unwrap().get0::<i32>()?;
+    /// assert_eq!(get()?, 42);
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`wizer`]: https://github.com/bytecodealliance/wizer
+    pub fn preinitialize(&self, wasm: &[u8], init_func: &str) -> Result<Vec<u8>> {
+        let module = Module::from_binary(self.store.engine(), wasm)?;
+        let instance = self.instantiate(&module)?;
+        let init = instance
+            .get_func(init_func)
+            .ok_or_else(|| anyhow!("no function export named `{}`", init_func))?
+            .get0::<()>()?;
+        init()?;
+        crate::snapshot::snapshot(&instance, wasm)
+    }
+
     fn compute_imports(&self, module: &Module) -> Result<Vec<Extern>> {
         module
             .imports()
-            .map(|import| self.get(&import).ok_or_else(|| self.link_error(&import)))
+            .map(|import| {
+                if let Some(item) = self.get(&import) {
+                    return Ok(item);
+                }
+                match &self.unknown_imports {
+                    Some(handler) => handler(&import).with_context(|| {
+                        format!("failed to define unknown import `{}`", import_desc(&import))
+                    }),
+                    None => Err(self.link_error(&import)),
+                }
+            })
             .collect()
     }
 
@@ -598,10 +767,7 @@ impl Linker {
             }
             options.push(format!("  * {:?}\n", i.kind));
         }
-        let desc = match import.name() {
-            Some(name) => format!("{}::{}", import.module(), name),
-            None => import.module().to_string(),
-        };
+        let desc = import_desc(import);
         if options.is_empty() {
             return anyhow!("unknown import: `{}` has not been defined", desc);
         }
@@ -733,6 +899,13 @@ impl Linker {
     }
 }
 
+fn import_desc(import: &ImportType) -> String {
+    match import.name() {
+        Some(name) => format!("{}::{}", import.module(), name),
+        None => import.module().to_string(),
+    }
+}
+
 /// Modules can be interpreted either as Commands or Reactors.
 enum ModuleKind {
     /// The instance is a Command, meaning an instance is created for each
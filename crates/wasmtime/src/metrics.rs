@@ -0,0 +1,138 @@
+use crate::TrapCode;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering::SeqCst};
+use std::sync::{Mutex, RwLock};
+
+/// A snapshot of the work an [`Engine`](crate::Engine) has done since it was
+/// created.
+///
+/// This is returned by [`Engine::metrics`](crate::Engine::metrics). All fields
+/// but `memory_bytes` are counters which only ever increase, which makes them
+/// suitable for exporting to systems like Prometheus as-is.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct EngineMetrics {
+    /// The number of modules compiled.
+    ///
+    /// Modules loaded from the compilation cache aren't counted here, but in
+    /// `cache_hits` instead.
+    pub compilations: u64,
+    /// The number of modules loaded from the compilation cache instead of
+    /// being compiled.
+    pub cache_hits: u64,
+    /// The number of modules instantiated with
+    /// [`Instance::new`](crate::Instance::new).
+    pub instantiations: u64,
+    /// The number of traps raised by WebAssembly instructions, by trap code.
+    ///
+    /// Errors returned from host functions and calls to `proc_exit` aren't
+    /// counted.
+    pub traps: HashMap<TrapCode, u64>,
+    /// The total fuel consumed by WebAssembly running in stores of this
+    /// engine. This is updated every time a call into WebAssembly returns.
+    pub fuel_consumed: u64,
+    /// The number of bytes of linear memory currently in use by stores of
+    /// this engine. This is updated on instantiation and every time a call
+    /// into WebAssembly returns.
+    pub memory_bytes: usize,
+}
+
+/// An individual update to an [`Engine`](crate::Engine)'s metrics, delivered
+/// to callbacks registered with
+/// [`Engine::on_metrics_event`](crate::Engine::on_metrics_event).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum MetricsEvent {
+    /// A module was compiled.
+    Compilation,
+    /// A module was loaded from the compilation cache.
+    CacheHit,
+    /// A module was instantiated.
+    Instantiation,
+    /// WebAssembly trapped with the given code.
+    Trap(TrapCode),
+    /// WebAssembly consumed the given amount of fuel.
+    FuelConsumed(u64),
+    /// The number of bytes of linear memory in use by stores of the engine
+    /// changed to the given value.
+    MemoryInUse(usize),
+}
+
+pub(crate) type MetricsCallback = dyn Fn(MetricsEvent) + Send + Sync;
+
+/// The counters behind [`EngineMetrics`], shared by everything created from
+/// an engine.
+#[derive(Default)]
+pub(crate) struct MetricsCounters {
+    compilations: AtomicU64,
+    cache_hits: AtomicU64,
+    instantiations: AtomicU64,
+    fuel_consumed: AtomicU64,
+    memory_bytes: AtomicUsize,
+    traps: Mutex<HashMap<TrapCode, u64>>,
+    callbacks: RwLock<Vec<Box<MetricsCallback>>>,
+}
+
+impl MetricsCounters {
+    pub(crate) fn snapshot(&self) -> EngineMetrics {
+        EngineMetrics {
+            compilations: self.compilations.load(SeqCst),
+            cache_hits: self.cache_hits.load(SeqCst),
+            instantiations: self.instantiations.load(SeqCst),
+            traps: self.traps.lock().unwrap().clone(),
+            fuel_consumed: self.fuel_consumed.load(SeqCst),
+            memory_bytes: self.memory_bytes.load(SeqCst),
+        }
+    }
+
+    pub(crate) fn add_callback(&self, callback: Box<MetricsCallback>) {
+        self.callbacks.write().unwrap().push(callback);
+    }
+
+    pub(crate) fn compiled(&self, cache_hit: bool) {
+        if cache_hit {
+            self.cache_hits.fetch_add(1, SeqCst);
+            self.emit(MetricsEvent::CacheHit);
+        } else {
+            self.compilations.fetch_add(1, SeqCst);
+            self.emit(MetricsEvent::Compilation);
+        }
+    }
+
+    pub(crate) fn instantiated(&self) {
+        self.instantiations.fetch_add(1, SeqCst);
+        self.emit(MetricsEvent::Instantiation);
+    }
+
+    pub(crate) fn trapped(&self, code: TrapCode) {
+        *self.traps.lock().unwrap().entry(code).or_insert(0) += 1;
+        self.emit(MetricsEvent::Trap(code));
+    }
+
+    pub(crate) fn fuel_consumed(&self, fuel: u64) {
+        if fuel == 0 {
+            return;
+        }
+        self.fuel_consumed.fetch_add(fuel, SeqCst);
+        self.emit(MetricsEvent::FuelConsumed(fuel));
+    }
+
+    /// Records that a store's linear memory changed size from `old` to `new`
+    /// bytes.
+    pub(crate) fn memory_resized(&self, old: usize, new: usize) {
+        let total = if new > old {
+            self.memory_bytes.fetch_add(new - old, SeqCst) + (new - old)
+        } else if new < old {
+            self.memory_bytes.fetch_sub(old - new, SeqCst) - (old - new)
+        } else {
+            return;
+        };
+        self.emit(MetricsEvent::MemoryInUse(total));
+    }
+
+    fn emit(&self, event: MetricsEvent) {
+        for callback in self.callbacks.read().unwrap().iter() {
+            callback(event);
+        }
+    }
+}
@@ -1,13 +1,26 @@
+use crate::compile_report::CompileReport;
+use crate::engine::ModuleResources;
+use crate::explore::{self, FunctionCode, FunctionSize};
+use crate::producers::Producers;
 use crate::types::{ExportType, ExternType, ImportType};
 use crate::{Engine, ModuleType};
 use anyhow::{bail, Context, Result};
 use bincode::Options;
-use std::hash::Hash;
+#[cfg(feature = "cache")]
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::mem;
 use std::path::Path;
 use std::sync::Arc;
-use wasmparser::Validator;
+use wasmparser::{
+    BinaryReaderError, Chunk, ImportSectionEntryType, NameSectionReader, Naming, Parser, Payload,
+    Validator,
+};
 #[cfg(feature = "cache")]
 use wasmtime_cache::ModuleCacheEntry;
+use wasmtime_environ::ExecutionProfile;
 use wasmtime_jit::{CompilationArtifacts, CompiledModule, TypeTables};
 
 /// A compiled WebAssembly module, ready to be instantiated.
@@ -88,6 +101,22 @@ pub struct Module {
 pub(crate) struct ModuleData {
     pub(crate) types: Arc<TypeTables>,
     pub(crate) modules: Vec<CompiledModule>,
+    resources: Arc<ModuleResources>,
+}
+
+impl ModuleData {
+    fn new(engine: &Engine, types: Arc<TypeTables>, modules: Vec<CompiledModule>) -> ModuleData {
+        let code_bytes = modules
+            .iter()
+            .flat_map(|m| m.jit_code_ranges())
+            .map(|(start, end)| end - start)
+            .sum();
+        ModuleData {
+            types,
+            modules,
+            resources: Arc::new(engine.register_module_resources(code_bytes)),
+        }
+    }
 }
 
 impl Module {
@@ -244,13 +273,77 @@ impl Module {
     /// # }
     /// ```
     pub fn from_binary(engine: &Engine, binary: &[u8]) -> Result<Module> {
+        Module::compile(engine, binary, None)
+    }
+
+    /// Creates a new WebAssembly `Module` from the given in-memory `binary`
+    /// data, using `profile` to guide the layout of the generated code.
+    ///
+    /// The profile records how often functions, and blocks of code within
+    /// them, were executed in earlier runs of the module (see
+    /// [`ExecutionProfile`]). Rarely executed blocks are moved out of the way
+    /// of the hot path, and the most frequently entered functions are laid
+    /// out first, next to each other. This can noticeably reduce instruction
+    /// cache misses for large modules. The profile only affects performance,
+    /// never behavior.
+    ///
+    /// Otherwise this is the same as [`Module::from_binary`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use wasmtime::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// # let engine = Engine::default();
+    /// let wasm = wat::parse_str("(module (func (export \"f\")))")?;
+    /// let mut profile = ExecutionProfile::new();
+    /// profile.set_function_count(0, 1000);
+    /// let module = Module::from_binary_with_profile(&engine, &wasm, &profile)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_binary_with_profile(
+        engine: &Engine,
+        binary: &[u8],
+        profile: &ExecutionProfile,
+    ) -> Result<Module> {
+        Module::compile(engine, binary, Some(profile))
+    }
+
+    fn compile(
+        engine: &Engine,
+        binary: &[u8],
+        profile: Option<&ExecutionProfile>,
+    ) -> Result<Module> {
+        check_limits(engine, binary)?;
+
         #[cfg(feature = "cache")]
-        let (artifacts, types) = ModuleCacheEntry::new("wasmtime", engine.cache_config())
-            .get_data((engine.compiler(), binary), |(compiler, binary)| {
-                CompilationArtifacts::build(compiler, binary)
-            })?;
+        let (artifacts, types) = if engine.config().bypass_caches() {
+            let ret = CompilationArtifacts::build_with_profile(engine.compiler(), binary, profile)?;
+            engine.metrics_counters().compiled(false);
+            ret
+        } else {
+            let compiled = Cell::new(false);
+            let ret = match &engine.config().cache_store {
+                Some(store) => ModuleCacheEntry::from_store("wasmtime", &**store),
+                None => ModuleCacheEntry::new("wasmtime", engine.cache_config()),
+            }
+            .get_data(
+                (engine.compiler(), binary, profile, NotHashed(&compiled)),
+                |(compiler, binary, profile, compiled)| {
+                    compiled.0.set(true);
+                    CompilationArtifacts::build_with_profile(compiler, binary, profile)
+                },
+            )?;
+            engine.metrics_counters().compiled(!compiled.get());
+            ret
+        };
         #[cfg(not(feature = "cache"))]
-        let (artifacts, types) = CompilationArtifacts::build(engine.compiler(), binary)?;
+        let (artifacts, types) = {
+            let ret = CompilationArtifacts::build_with_profile(engine.compiler(), binary, profile)?;
+            engine.metrics_counters().compiled(false);
+            ret
+        };
 
         let modules = CompiledModule::from_artifacts_list(
             artifacts,
@@ -262,7 +355,7 @@ impl Module {
         Ok(Module {
             engine: engine.clone(),
             index: 0,
-            data: Arc::new(ModuleData { types, modules }),
+            data: Arc::new(ModuleData::new(engine, types, modules)),
         })
     }
 
@@ -282,16 +375,246 @@ impl Module {
     ///
     /// If validation fails for any reason (type check error, usage of a feature
     /// that wasn't enabled, etc) then an error with a description of the
-    /// validation issue will be returned.
+    /// validation issue will be returned. The error can be downcast to a
+    /// [`ValidationError`] to inspect the byte offset of the issue and the
+    /// function it was found in.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use wasmtime::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// # let engine = Engine::default();
+    /// let wasm = wat::parse_str("(module (func $foo (result i32) i64.const 0))")?;
+    /// let err = Module::validate(&engine, &wasm).unwrap_err();
+    /// let err = err.downcast::<ValidationError>()?;
+    /// assert_eq!(err.func_index(), Some(0));
+    /// assert_eq!(err.func_name(), Some("foo"));
+    /// # Ok(())
+    /// # }
+    /// ```
     ///
     /// [binary]: https://webassembly.github.io/spec/core/binary/index.html
     pub fn validate(engine: &Engine, binary: &[u8]) -> Result<()> {
+        check_limits(engine, binary)?;
         let mut validator = Validator::new();
         validator.wasm_features(engine.config().features);
-        validator.validate_all(binary)?;
+        validator
+            .validate_all(binary)
+            .map_err(|e| ValidationError::new(binary, e))?;
         Ok(())
     }
 
+    /// Returns the custom sections of the WebAssembly `binary`, in the order
+    /// that they appear, as pairs of section name and section contents.
+    ///
+    /// Custom sections carry tool-specific metadata such as the `name`
+    /// section, DWARF debugging information, or the `producers` section.
+    /// Wasmtime ignores most of them, but embedders may wish to consume them.
+    ///
+    /// Only the top-level sections of `binary` are inspected, so custom
+    /// sections of nested modules are not returned. The text format is not
+    /// accepted by this function, and `binary` is not validated beyond what's
+    /// needed to delimit its sections.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `binary` is not a well-formed sequence of
+    /// WebAssembly sections.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use wasmtime::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// let wasm = b"\0asm\x01\0\0\0\0\x08\x03foobar!";
+    /// let sections = Module::custom_sections(wasm)?;
+    /// assert_eq!(sections, [("foo", &b"bar!"[..])]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn custom_sections(binary: &[u8]) -> Result<Vec<(&str, &[u8])>> {
+        Ok(sections(binary)?
+            .into_iter()
+            .filter_map(|section| section.custom())
+            .collect())
+    }
+
+    /// Parses the `producers` custom section of the WebAssembly `binary`,
+    /// which describes the toolchain that produced it.
+    ///
+    /// Returns `None` if `binary` has no `producers` section. Like
+    /// [`Module::custom_sections`] only the top-level sections of `binary`
+    /// are inspected and the text format isn't accepted.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `binary` is not a well-formed sequence of
+    /// WebAssembly sections, or if its `producers` section is malformed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use wasmtime::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// let wasm = b"\0asm\x01\0\0\0\0\x1b\x09producers\x01\x08language\x01\x04Rust\x00";
+    /// let producers = Module::producers(wasm)?.unwrap();
+    /// let language = producers.field("language").unwrap();
+    /// assert_eq!(language.values()[0].name(), "Rust");
+    /// assert_eq!(language.values()[0].version(), "");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn producers(binary: &[u8]) -> Result<Option<Producers>> {
+        match sections(binary)?
+            .into_iter()
+            .filter_map(|section| section.custom())
+            .find(|(name, _)| *name == "producers")
+        {
+            Some((_, data)) => Ok(Some(
+                Producers::parse(data).context("failed to parse the producers section")?,
+            )),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns a copy of the WebAssembly `binary` with custom sections
+    /// removed.
+    ///
+    /// The `keep` predicate is called with the name of each top-level custom
+    /// section, and the section is retained only if it returns `true`. All
+    /// other sections are copied verbatim, so the result is valid if `binary`
+    /// is. Passing `|_| false` strips all custom sections, which can
+    /// substantially reduce the size of binaries with debugging information.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `binary` is not a well-formed sequence of
+    /// WebAssembly sections.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use wasmtime::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// let wasm = b"\0asm\x01\0\0\0\0\x08\x03foobar!";
+    /// let stripped = Module::strip_custom_sections(wasm, |_| false)?;
+    /// assert_eq!(stripped, b"\0asm\x01\0\0\0");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn strip_custom_sections(
+        binary: &[u8],
+        mut keep: impl FnMut(&str) -> bool,
+    ) -> Result<Vec<u8>> {
+        let sections = sections(binary)?;
+        let mut ret = binary[..8].to_vec();
+        for section in sections {
+            if let Some((name, _)) = section.custom() {
+                if !keep(name) {
+                    continue;
+                }
+            }
+            ret.extend_from_slice(section.bytes);
+        }
+        Ok(ret)
+    }
+
+    /// Compiles `binary` and returns the code generated for each function
+    /// defined in it, in function index order.
+    ///
+    /// This is intended for performance work: each [`FunctionCode`] exposes
+    /// the compiler's final intermediate representation of the function and
+    /// its machine code (and, with the `disas` feature, a disassembly of it)
+    /// as produced with the settings of `engine`. The compiled code can't be
+    /// executed; use [`Module::from_binary`] for that.
+    ///
+    /// Functions are compiled from scratch, bypassing the compilation cache.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `binary` is not a valid WebAssembly module or if
+    /// it fails to compile.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmtime::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// # let engine = Engine::default();
+    /// let wasm = wat::parse_str(r#"
+    ///     (module
+    ///         (func $add (param i32 i32) (result i32)
+    ///             local.get 0
+    ///             local.get 1
+    ///             i32.add))
+    /// "#)?;
+    /// let funcs = Module::compiled_functions(&engine, &wasm)?;
+    /// assert_eq!(funcs.len(), 1);
+    /// assert_eq!(funcs[0].name(), Some("add"));
+    /// assert!(funcs[0].ir().unwrap().contains("iadd"));
+    /// assert!(!funcs[0].code().is_empty());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn compiled_functions(engine: &Engine, binary: &[u8]) -> Result<Vec<FunctionCode>> {
+        check_limits(engine, binary)?;
+        explore::compiled_functions(engine, binary)
+    }
+
+    /// Returns the size of the machine code of each function defined in this
+    /// module, ordered by module and then by function index.
+    ///
+    /// Unlike [`Module::compiled_functions`] this doesn't compile anything,
+    /// so it also works for modules created with [`Module::deserialize`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use wasmtime::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// let engine = Engine::default();
+    /// let module = Module::new(&engine, "(module (func $f))")?;
+    /// let sizes = module.function_sizes();
+    /// assert_eq!(sizes.len(), 1);
+    /// assert_eq!(sizes[0].name(), Some("f"));
+    /// assert!(sizes[0].size() > 0);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn function_sizes(&self) -> Vec<FunctionSize> {
+        explore::function_sizes(&self.data.modules)
+    }
+
+    /// Returns the time spent compiling each function of this module.
+    ///
+    /// This is only measured if this module was compiled with
+    /// [`Config::compile_stats`](crate::Config::compile_stats) enabled, and
+    /// returns `None` otherwise. It also returns `None` for modules created
+    /// with [`Module::deserialize`], which weren't compiled at all.
+    ///
+    /// If the module linking proposal is enabled the report covers all
+    /// modules compiled from the same binary as this one.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmtime::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut config = Config::new();
+    /// config.compile_stats(true);
+    /// let engine = Engine::new(&config);
+    /// let module = Module::new(&engine, "(module (func))")?;
+    /// let report = module.compile_report().unwrap();
+    /// assert_eq!(report.functions().len(), 1);
+    /// println!("{}", report);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn compile_report(&self) -> Option<CompileReport> {
+        CompileReport::new(&self.data.modules)
+    }
+
     /// Returns the type signature of this module.
     pub fn ty(&self) -> ModuleType {
         let mut sig = ModuleType::new();
@@ -311,19 +634,25 @@ impl Module {
 
     /// Serialize compilation artifacts to the buffer. See also `deseriaize`.
     pub fn serialize(&self) -> Result<Vec<u8>> {
-        let artifacts = (
-            compiler_fingerprint(&self.engine),
+        serialize_artifacts(
+            &self.engine,
             self.data
                 .modules
                 .iter()
                 .map(|i| i.compilation_artifacts())
-                .collect::<Vec<_>>(),
-            &*self.data.types,
+                .collect(),
+            &self.data.types,
             self.index,
-        );
+        )
+    }
 
-        let buffer = bincode_options().serialize(&artifacts)?;
-        Ok(buffer)
+    /// Compiles `binary` and serializes the result like [`Module::serialize`],
+    /// without loading the compiled code into executable memory.
+    pub(crate) fn precompile(engine: &Engine, binary: &[u8]) -> Result<Vec<u8>> {
+        check_limits(engine, binary)?;
+        let (artifacts, types) =
+            CompilationArtifacts::build_with_profile(engine.compiler(), binary, None)?;
+        serialize_artifacts(engine, artifacts.iter().collect(), &types, 0)
     }
 
     /// Deserializes and creates a module from the compilation artifacts.
@@ -335,6 +664,9 @@ impl Module {
     /// one are different. The method does not verify the serialized artifacts
     /// for modifications or curruptions. All responsibily of signing and its
     /// verification falls on the embedder.
+    ///
+    /// To check whether serialized artifacts are compatible with an engine
+    /// without deserializing them see [`Module::is_compatible`].
     pub fn deserialize(engine: &Engine, serialized: &[u8]) -> Result<Module> {
         let expected_fingerprint = compiler_fingerprint(engine);
 
@@ -342,7 +674,10 @@ impl Module {
             .deserialize::<(u64, _, _, _)>(serialized)
             .context("Deserialize compilation artifacts")?;
         if fingerprint != expected_fingerprint {
-            bail!("Incompatible compilation artifact");
+            bail!(
+                "Incompatible compilation artifact: the module was compiled \
+                 with a different Wasmtime version, target, or configuration"
+            );
         }
 
         let modules = CompiledModule::from_artifacts_list(
@@ -355,10 +690,29 @@ impl Module {
         Ok(Module {
             engine: engine.clone(),
             index,
-            data: Arc::new(ModuleData { modules, types }),
+            data: Arc::new(ModuleData::new(engine, types, modules)),
         })
     }
 
+    /// Returns whether the output of [`Module::serialize`] in `serialized` was
+    /// produced by an engine compatible with `engine`.
+    ///
+    /// Only the compatibility fingerprint at the start of `serialized` is
+    /// read, which makes this a cheap check to run before
+    /// [`Module::deserialize`]. Its result is consistent with
+    /// [`Engine::precompile_compatibility_hash`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `serialized` is too short to contain a fingerprint.
+    pub fn is_compatible(engine: &Engine, serialized: &[u8]) -> Result<bool> {
+        let fingerprint = bincode_options()
+            .allow_trailing_bytes()
+            .deserialize::<u64>(serialized)
+            .context("Deserialize compilation artifact fingerprint")?;
+        Ok(fingerprint == compiler_fingerprint(engine))
+    }
+
     pub(crate) fn compiled_module(&self) -> &CompiledModule {
         &self.data.modules[self.index]
     }
@@ -376,6 +730,10 @@ impl Module {
         &self.data.types
     }
 
+    pub(crate) fn resources(&self) -> &Arc<ModuleResources> {
+        &self.data.resources
+    }
+
     /// Returns identifier/name that this [`Module`] has. This name
     /// is used in traps/backtrace details.
     ///
@@ -598,10 +956,320 @@ fn bincode_options() -> impl Options {
     bincode::DefaultOptions::new().with_varint_encoding()
 }
 
+/// An error describing why a WebAssembly binary failed validation.
+///
+/// This is the error returned by [`Module::validate`], and it can be
+/// extracted from the returned [`anyhow::Error`] with `downcast`.
+#[derive(Debug, Clone)]
+pub struct ValidationError {
+    message: String,
+    offset: usize,
+    func_index: Option<u32>,
+    func_name: Option<String>,
+}
+
+impl ValidationError {
+    fn new(binary: &[u8], err: BinaryReaderError) -> ValidationError {
+        ValidationError::at(binary, err.message().to_string(), err.offset())
+    }
+
+    fn at(binary: &[u8], message: String, offset: usize) -> ValidationError {
+        let (func_index, func_name) = match locate_function(binary, offset) {
+            Some((index, name)) => (Some(index), name),
+            None => (None, None),
+        };
+        ValidationError {
+            message,
+            offset,
+            func_index,
+            func_name,
+        }
+    }
+
+    /// Returns a description of the validation error, without location
+    /// information.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Returns the byte offset in the binary at which the error was found.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Returns the index of the function whose body contains the error, if
+    /// the error was found in a function body.
+    ///
+    /// Like other function indices this counts imported functions first.
+    pub fn func_index(&self) -> Option<u32> {
+        self.func_index
+    }
+
+    /// Returns the name of the function whose body contains the error, if the
+    /// error was found in a function body and the `name` custom section names
+    /// the function.
+    pub fn func_name(&self) -> Option<&str> {
+        self.func_name.as_deref()
+    }
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} (at offset {:#x})", self.message, self.offset)?;
+        match (&self.func_name, self.func_index) {
+            (Some(name), _) => write!(f, " in function `{}`", name),
+            (None, Some(index)) => write!(f, " in function {}", index),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+/// Checks `binary` against the static complexity limits configured for
+/// `engine`, returning a `ValidationError` for the first limit exceeded.
+///
+/// Malformed input is ignored here since it's reported by validation itself.
+fn check_limits(engine: &Engine, binary: &[u8]) -> Result<()> {
+    let limits = &engine.config().module_limits;
+    if limits.is_unlimited() {
+        return Ok(());
+    }
+    let exceeded = |what: &str, count: u64, max: u32, offset: usize| -> Result<()> {
+        if count <= u64::from(max) {
+            return Ok(());
+        }
+        let message = format!("{} {} exceeds the configured limit of {}", count, what, max);
+        Err(ValidationError::at(binary, message, offset).into())
+    };
+
+    if let Some(max) = limits.size {
+        if binary.len() > max {
+            let message = format!(
+                "module size of {} bytes exceeds the configured limit of {} bytes",
+                binary.len(),
+                max
+            );
+            return Err(ValidationError::at(binary, message, 0).into());
+        }
+    }
+
+    // Each nested module, if any, is subject to the same limits, so keep a
+    // stack of function counts.
+    let mut funcs = 0u64;
+    let mut stack = Vec::new();
+    for payload in Parser::new(0).parse_all(binary) {
+        let payload = match payload {
+            Ok(payload) => payload,
+            Err(_) => return Ok(()),
+        };
+        match payload {
+            Payload::ModuleCodeSectionEntry { .. } => stack.push(mem::replace(&mut funcs, 0)),
+            Payload::End => funcs = stack.pop().unwrap_or(0),
+            Payload::TypeSection(types) => {
+                if let Some(max) = limits.types {
+                    let offset = types.original_position();
+                    exceeded("types", u64::from(types.get_count()), max, offset)?;
+                }
+            }
+            Payload::ImportSection(imports) => {
+                let offset = imports.original_position();
+                for import in imports {
+                    match import {
+                        Ok(import) => {
+                            if let ImportSectionEntryType::Function(_) = import.ty {
+                                funcs += 1;
+                            }
+                        }
+                        Err(_) => return Ok(()),
+                    }
+                }
+                if let Some(max) = limits.functions {
+                    exceeded("functions", funcs, max, offset)?;
+                }
+            }
+            Payload::FunctionSection(functions) => {
+                funcs += u64::from(functions.get_count());
+                if let Some(max) = limits.functions {
+                    exceeded("functions", funcs, max, functions.original_position())?;
+                }
+            }
+            Payload::CodeSectionEntry(body) => {
+                let max = match limits.locals {
+                    Some(max) => max,
+                    None => continue,
+                };
+                let mut reader = match body.get_locals_reader() {
+                    Ok(reader) => reader,
+                    Err(_) => return Ok(()),
+                };
+                let mut locals = 0u64;
+                for _ in 0..reader.get_count() {
+                    match reader.read() {
+                        Ok((count, _ty)) => locals += u64::from(count),
+                        Err(_) => return Ok(()),
+                    }
+                }
+                exceeded("locals", locals, max, body.range().start)?;
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Finds the index, and name if known, of the function in `binary` whose body
+/// contains `offset`.
+///
+/// This is best-effort: parsing stops at the first malformed section, and
+/// nested modules are skipped.
+fn locate_function(binary: &[u8], offset: usize) -> Option<(u32, Option<String>)> {
+    let mut imported_funcs = 0;
+    let mut defined_funcs = 0;
+    let mut found = None;
+    let mut names = HashMap::new();
+    let mut depth = 0;
+    for payload in Parser::new(0).parse_all(binary) {
+        let payload = match payload {
+            Ok(payload) => payload,
+            Err(_) => break,
+        };
+        match payload {
+            Payload::ModuleCodeSectionEntry { .. } => depth += 1,
+            Payload::End if depth > 0 => depth -= 1,
+            _ if depth > 0 => {}
+            Payload::ImportSection(imports) => {
+                for import in imports {
+                    match import {
+                        Ok(import) => {
+                            if let ImportSectionEntryType::Function(_) = import.ty {
+                                imported_funcs += 1;
+                            }
+                        }
+                        Err(_) => break,
+                    }
+                }
+            }
+            Payload::CodeSectionEntry(body) => {
+                let range = body.range();
+                if range.start <= offset && offset < range.end {
+                    found = Some(imported_funcs + defined_funcs);
+                }
+                defined_funcs += 1;
+            }
+            Payload::CustomSection {
+                name: "name",
+                data,
+                data_offset,
+            } => {
+                let reader = match NameSectionReader::new(data, data_offset) {
+                    Ok(reader) => reader,
+                    Err(_) => continue,
+                };
+                for subsection in reader {
+                    if let Ok(wasmparser::Name::Function(f)) = subsection {
+                        if let Ok(mut map) = f.get_map() {
+                            for _ in 0..map.get_count() {
+                                match map.read() {
+                                    Ok(Naming { index, name }) => {
+                                        names.insert(index, name);
+                                    }
+                                    Err(_) => break,
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    let index = found?;
+    Some((index, names.get(&index).map(|name| name.to_string())))
+}
+
+/// A top-level section of a WebAssembly binary, as returned by `sections`.
+pub(crate) struct RawSection<'a> {
+    /// The id of this section.
+    pub(crate) id: u8,
+    /// The section as parsed by `wasmparser`. The entries of code sections
+    /// aren't parsed.
+    pub(crate) payload: Payload<'a>,
+    /// The full encoding of this section, including its id and size.
+    pub(crate) bytes: &'a [u8],
+}
+
+impl<'a> RawSection<'a> {
+    /// For custom sections, returns the name and contents of the section.
+    pub(crate) fn custom(&self) -> Option<(&'a str, &'a [u8])> {
+        match self.payload {
+            Payload::CustomSection { name, data, .. } => Some((name, data)),
+            _ => None,
+        }
+    }
+}
+
+/// Splits `binary` into its top-level sections without validating them.
+pub(crate) fn sections(binary: &[u8]) -> Result<Vec<RawSection<'_>>> {
+    let mut parser = Parser::new(0);
+    let mut ret = Vec::new();
+    let mut pos = 0;
+    loop {
+        let (consumed, payload) = match parser.parse(&binary[pos..], true)? {
+            Chunk::Parsed { consumed, payload } => (consumed, payload),
+            // With `eof` set the parser reports truncated input as an error.
+            Chunk::NeedMoreData(_) => unreachable!(),
+        };
+        let start = pos;
+        pos += consumed;
+        match payload {
+            Payload::Version { .. } => continue,
+            Payload::End => return Ok(ret),
+            // Only the header of code sections has been consumed; skip over
+            // their entries, including any nested modules.
+            Payload::CodeSectionStart { ref range, .. }
+            | Payload::ModuleCodeSectionStart { ref range, .. } => {
+                if range.end > binary.len() {
+                    bail!("section at offset {} is out of bounds", start);
+                }
+                parser.skip_section();
+                pos = range.end;
+            }
+            _ => {}
+        }
+        ret.push(RawSection {
+            id: binary[start],
+            payload,
+            bytes: &binary[start..pos],
+        });
+    }
+}
+
+fn serialize_artifacts(
+    engine: &Engine,
+    artifacts: Vec<&CompilationArtifacts>,
+    types: &TypeTables,
+    index: usize,
+) -> Result<Vec<u8>> {
+    let artifacts = (compiler_fingerprint(engine), artifacts, types, index);
+    let buffer = bincode_options().serialize(&artifacts)?;
+    Ok(buffer)
+}
+
+/// Wrapper for state passed to a cache's compute function which isn't part of
+/// the cache key. Hashing it is a no-op, so appending it to a tuple doesn't
+/// change the tuple's hash.
+#[cfg(feature = "cache")]
+struct NotHashed<T>(T);
+
+#[cfg(feature = "cache")]
+impl<T> Hash for NotHashed<T> {
+    fn hash<H: Hasher>(&self, _hasher: &mut H) {}
+}
+
 fn compiler_fingerprint(engine: &Engine) -> u64 {
-    use std::hash::Hasher;
     let mut hasher = std::collections::hash_map::DefaultHasher::new();
-    engine.compiler().hash(&mut hasher);
+    engine.precompile_compatibility_hash().hash(&mut hasher);
     hasher.finish()
 }
 
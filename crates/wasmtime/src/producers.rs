@@ -0,0 +1,95 @@
+//! Parsing of the `producers` custom section, see
+//! [`Module::producers`](crate::Module::producers).
+
+use anyhow::{bail, Result};
+use wasmparser::BinaryReader;
+
+/// The contents of a module's `producers` custom section.
+///
+/// This section records the toolchain used to produce a module, organized
+/// into fields such as `language`, `processed-by` and `sdk`, each listing
+/// tool names with versions. See the [tool conventions][conventions] for
+/// details.
+///
+/// This is returned by [`Module::producers`](crate::Module::producers).
+///
+/// [conventions]: https://github.com/WebAssembly/tool-conventions/blob/master/ProducersSection.md
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Producers {
+    fields: Vec<ProducersField>,
+}
+
+impl Producers {
+    /// Returns the fields of this section in the order they're encoded.
+    pub fn fields(&self) -> &[ProducersField] {
+        &self.fields
+    }
+
+    /// Returns the field named `name`, if present.
+    pub fn field(&self, name: &str) -> Option<&ProducersField> {
+        self.fields.iter().find(|f| f.name == name)
+    }
+
+    pub(crate) fn parse(data: &[u8]) -> Result<Producers> {
+        let mut reader = BinaryReader::new(data);
+        let count = reader.read_var_u32()?;
+        let mut fields = Vec::new();
+        for _ in 0..count {
+            let name = reader.read_string()?.to_string();
+            let count = reader.read_var_u32()?;
+            let mut values = Vec::new();
+            for _ in 0..count {
+                values.push(ProducersValue {
+                    name: reader.read_string()?.to_string(),
+                    version: reader.read_string()?.to_string(),
+                });
+            }
+            fields.push(ProducersField { name, values });
+        }
+        if !reader.eof() {
+            bail!(
+                "trailing bytes at offset {} of the producers section",
+                reader.original_position()
+            );
+        }
+        Ok(Producers { fields })
+    }
+}
+
+/// A field of the `producers` custom section, see [`Producers`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProducersField {
+    name: String,
+    values: Vec<ProducersValue>,
+}
+
+impl ProducersField {
+    /// Returns the name of this field, e.g. `language` or `processed-by`.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the tools listed in this field.
+    pub fn values(&self) -> &[ProducersValue] {
+        &self.values
+    }
+}
+
+/// A tool listed in a [`ProducersField`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProducersValue {
+    name: String,
+    version: String,
+}
+
+impl ProducersValue {
+    /// Returns the name of this tool, e.g. `rustc` or `Rust`.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the version of this tool, which may be empty.
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+}
@@ -0,0 +1,424 @@
+//! Snapshotting of initialized instances back into WebAssembly binaries.
+//!
+//! This is the implementation of [`Linker::preinitialize`](crate::Linker::preinitialize)
+//! which, like the `wizer` tool, takes an instance which has run its
+//! initialization and rewrites the original binary so its memories and
+//! globals start out in that initialized state.
+
+use crate::module::sections;
+use crate::{Extern, Global, Instance, Memory, Val};
+use anyhow::{bail, Result};
+use wasmparser::{Operator, Parser, Payload};
+use wasmtime_environ::entity::EntityRef;
+use wasmtime_environ::wasm::{
+    DataIndex, EntityIndex, GlobalIndex, GlobalInit, MemoryIndex, WasmType,
+};
+
+const MEMORY_SECTION: u8 = 5;
+const GLOBAL_SECTION: u8 = 6;
+const START_SECTION: u8 = 8;
+const CODE_SECTION: u8 = 10;
+const DATA_SECTION: u8 = 11;
+const DATA_COUNT_SECTION: u8 = 12;
+
+/// Runs of up to this many zero bytes between non-zero bytes of a memory are
+/// included in a data segment rather than splitting it in two, since each
+/// segment has a few bytes of overhead.
+const MAX_ZERO_RUN: usize = 16;
+
+/// Rewrites `wasm`, the binary `instance` was instantiated from, such that
+/// the initial contents of its memories and values of its mutable globals are
+/// those currently in `instance`.
+///
+/// The start function is removed since its effects are part of the snapshot.
+/// Tables aren't snapshotted, so modules which import tables or contain any
+/// instruction mutating a table or element segment are rejected.
+/// Passive data segments are retained at their original indices so that
+/// `memory.init` instructions remain valid, while active segments are
+/// replaced with the contents of memory.
+pub(crate) fn snapshot(instance: &Instance, wasm: &[u8]) -> Result<Vec<u8>> {
+    let module = instance.handle.module();
+    if !module.modules.is_empty() || !module.instances.is_empty() {
+        bail!("cannot snapshot modules that use module linking");
+    }
+    if module.num_imported_memories > 0 {
+        bail!("cannot snapshot modules that import memories");
+    }
+    if module.num_imported_tables > 0 {
+        bail!("cannot snapshot modules that import tables");
+    }
+    check_tables_immutable(wasm)?;
+
+    let sections = sections(wasm)?;
+    let has_data_section = sections.iter().any(|s| s.id == DATA_SECTION);
+    let memories = encode_memories(instance);
+    let globals = encode_globals(instance)?;
+
+    let original_segments = sections
+        .iter()
+        .find_map(|s| match &s.payload {
+            Payload::DataSection(data) => Some(data.get_count()),
+            _ => None,
+        })
+        .unwrap_or(0);
+    let (segments, data) = encode_data(instance, original_segments);
+
+    let mut ret = wasm[..8].to_vec();
+    let mut data_written = false;
+    for section in sections.iter() {
+        match section.id {
+            MEMORY_SECTION => write_section(&mut ret, MEMORY_SECTION, &memories),
+            GLOBAL_SECTION => write_section(&mut ret, GLOBAL_SECTION, &globals),
+            START_SECTION => {}
+            DATA_COUNT_SECTION => {
+                let mut count = Vec::new();
+                write_var_u32(&mut count, segments);
+                write_section(&mut ret, DATA_COUNT_SECTION, &count);
+            }
+            DATA_SECTION => {
+                write_section(&mut ret, DATA_SECTION, &data);
+                data_written = true;
+            }
+            _ => ret.extend_from_slice(section.bytes),
+        }
+
+        // The data section, if we need to add one, immediately follows the
+        // code section.
+        if section.id == CODE_SECTION && !has_data_section && segments > 0 {
+            write_section(&mut ret, DATA_SECTION, &data);
+            data_written = true;
+        }
+    }
+    if !data_written && segments > 0 {
+        write_section(&mut ret, DATA_SECTION, &data);
+    }
+    Ok(ret)
+}
+
+/// Fails if any function in `wasm` may modify a table or drop an element
+/// segment, since that state wouldn't be captured by the snapshot.
+fn check_tables_immutable(wasm: &[u8]) -> Result<()> {
+    for payload in Parser::new(0).parse_all(wasm) {
+        let body = match payload? {
+            Payload::CodeSectionEntry(body) => body,
+            _ => continue,
+        };
+        let mut operators = body.get_operators_reader()?;
+        while !operators.eof() {
+            let offset = operators.original_position();
+            match operators.read()? {
+                Operator::TableSet { .. }
+                | Operator::TableGrow { .. }
+                | Operator::TableFill { .. }
+                | Operator::TableCopy { .. }
+                | Operator::TableInit { .. }
+                | Operator::ElemDrop { .. } => bail!(
+                    "cannot snapshot modules that modify tables (found at offset {:#x})",
+                    offset
+                ),
+                _ => {}
+            }
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn memory(instance: &Instance, index: MemoryIndex) -> Memory {
+    let export = instance
+        .handle
+        .lookup_by_declaration(&EntityIndex::Memory(index));
+    match Extern::from_wasmtime_export(export, instance.handle.clone()) {
+        Extern::Memory(memory) => memory,
+        _ => unreachable!(),
+    }
+}
+
+pub(crate) fn global(instance: &Instance, index: GlobalIndex) -> Global {
+    let export = instance
+        .handle
+        .lookup_by_declaration(&EntityIndex::Global(index));
+    match Extern::from_wasmtime_export(export, instance.handle.clone()) {
+        Extern::Global(global) => global,
+        _ => unreachable!(),
+    }
+}
+
+/// Encodes the memory section, with each memory's minimum size raised to its
+/// current size.
+fn encode_memories(instance: &Instance) -> Vec<u8> {
+    let module = instance.handle.module();
+    let defined = module
+        .memory_plans
+        .iter()
+        .skip(module.num_imported_memories);
+    let mut ret = Vec::new();
+    write_var_u32(&mut ret, defined.len() as u32);
+    for (index, plan) in defined {
+        let ty = &plan.memory;
+        let mut flags = 0x00;
+        if ty.maximum.is_some() {
+            flags |= 0x01;
+        }
+        if ty.shared {
+            flags |= 0x02;
+        }
+        ret.push(flags);
+        write_var_u32(&mut ret, memory(instance, index).size());
+        if let Some(maximum) = ty.maximum {
+            write_var_u32(&mut ret, maximum);
+        }
+    }
+    ret
+}
+
+/// Encodes the global section, with mutable globals initialized to their
+/// current values.
+fn encode_globals(instance: &Instance) -> Result<Vec<u8>> {
+    let module = instance.handle.module();
+    let defined = module.globals.iter().skip(module.num_imported_globals);
+    let mut ret = Vec::new();
+    write_var_u32(&mut ret, defined.len() as u32);
+    for (index, ty) in defined {
+        ret.push(val_type(ty.wasm_ty)?);
+        ret.push(ty.mutability as u8);
+        if !ty.mutability {
+            encode_init(&mut ret, &ty.initializer, ty.wasm_ty);
+        } else {
+            match global(instance, index).get() {
+                Val::I32(i) => {
+                    ret.push(0x41);
+                    write_var_i64(&mut ret, i64::from(i));
+                }
+                Val::I64(i) => {
+                    ret.push(0x42);
+                    write_var_i64(&mut ret, i);
+                }
+                Val::F32(bits) => {
+                    ret.push(0x43);
+                    ret.extend_from_slice(&bits.to_le_bytes());
+                }
+                Val::F64(bits) => {
+                    ret.push(0x44);
+                    ret.extend_from_slice(&bits.to_le_bytes());
+                }
+                Val::V128(bits) => {
+                    ret.extend_from_slice(&[0xfd, 0x0c]);
+                    ret.extend_from_slice(&bits.to_le_bytes());
+                }
+                Val::ExternRef(None) | Val::FuncRef(None) => {
+                    ret.push(0xd0);
+                    ret.push(val_type(ty.wasm_ty)?);
+                }
+                Val::ExternRef(Some(_)) | Val::FuncRef(Some(_)) => bail!(
+                    "cannot snapshot the non-null reference in global {}",
+                    index.index()
+                ),
+            }
+        }
+        ret.push(0x0b);
+    }
+    Ok(ret)
+}
+
+/// Encodes the constant expression initializing an immutable global, without
+/// its trailing `end`.
+fn encode_init(ret: &mut Vec<u8>, init: &GlobalInit, ty: WasmType) {
+    match *init {
+        GlobalInit::I32Const(i) => {
+            ret.push(0x41);
+            write_var_i64(ret, i64::from(i));
+        }
+        GlobalInit::I64Const(i) => {
+            ret.push(0x42);
+            write_var_i64(ret, i);
+        }
+        GlobalInit::F32Const(bits) => {
+            ret.push(0x43);
+            ret.extend_from_slice(&bits.to_le_bytes());
+        }
+        GlobalInit::F64Const(bits) => {
+            ret.push(0x44);
+            ret.extend_from_slice(&bits.to_le_bytes());
+        }
+        GlobalInit::V128Const(imm) => {
+            ret.extend_from_slice(&[0xfd, 0x0c]);
+            ret.extend_from_slice(&imm.0);
+        }
+        GlobalInit::GetGlobal(index) => {
+            ret.push(0x23);
+            write_var_u32(ret, index.as_u32());
+        }
+        GlobalInit::RefNullConst => {
+            ret.push(0xd0);
+            ret.push(if ty == WasmType::ExternRef {
+                0x6f
+            } else {
+                0x70
+            });
+        }
+        GlobalInit::RefFunc(index) => {
+            ret.push(0xd2);
+            write_var_u32(ret, index.as_u32());
+        }
+        GlobalInit::Import => unreachable!("defined globals are not imported"),
+    }
+}
+
+/// Encodes the data section, returning the number of segments within it.
+///
+/// The first `original_segments` segments correspond to those of the original
+/// binary: passive segments are retained and active segments are replaced
+/// with empty ones. The segments following them hold the non-zero contents of
+/// each memory.
+fn encode_data(instance: &Instance, original_segments: u32) -> (u32, Vec<u8>) {
+    let module = instance.handle.module();
+    let mut segments = Vec::new();
+    for i in 0..original_segments {
+        segments.push(match module.passive_data.get(&DataIndex::from_u32(i)) {
+            Some(data) => {
+                let mut segment = vec![0x01];
+                write_var_u32(&mut segment, data.len() as u32);
+                segment.extend_from_slice(data);
+                segment
+            }
+            // An empty active segment at offset 0 of memory 0.
+            None => vec![0x00, 0x41, 0x00, 0x0b, 0x00],
+        });
+    }
+
+    for (index, _) in module
+        .memory_plans
+        .iter()
+        .skip(module.num_imported_memories)
+    {
+        let memory = memory(instance, index);
+        // Safety: no wasm is running, and `memory` can't be accessed
+        // concurrently since `Store`s are confined to one thread.
+        let data = unsafe { memory.data_unchecked() };
+        for (start, end) in nonzero_regions(data) {
+            let mut segment = Vec::new();
+            if index.as_u32() == 0 {
+                segment.push(0x00);
+            } else {
+                segment.push(0x02);
+                write_var_u32(&mut segment, index.as_u32());
+            }
+            // Offsets are interpreted as unsigned, so reinterpret large ones
+            // as negative `i32.const` immediates.
+            segment.push(0x41);
+            write_var_i64(&mut segment, i64::from(start as u32 as i32));
+            segment.push(0x0b);
+            write_var_u32(&mut segment, (end - start) as u32);
+            segment.extend_from_slice(&data[start..end]);
+            segments.push(segment);
+        }
+    }
+
+    let mut ret = Vec::new();
+    write_var_u32(&mut ret, segments.len() as u32);
+    for segment in segments.iter() {
+        ret.extend_from_slice(segment);
+    }
+    (segments.len() as u32, ret)
+}
+
+/// Returns the `start..end` ranges of `data` containing non-zero bytes,
+/// merging ranges separated by short runs of zeros.
+pub(crate) fn nonzero_regions(data: &[u8]) -> Vec<(usize, usize)> {
+    let mut regions: Vec<(usize, usize)> = Vec::new();
+    let mut i = 0;
+    while i < data.len() {
+        if data[i] == 0 {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < data.len() && data[i] != 0 {
+            i += 1;
+        }
+        match regions.last_mut() {
+            Some((_, end)) if start - *end <= MAX_ZERO_RUN => *end = i,
+            _ => regions.push((start, i)),
+        }
+    }
+    regions
+}
+
+pub(crate) fn val_type(ty: WasmType) -> Result<u8> {
+    Ok(match ty {
+        WasmType::I32 => 0x7f,
+        WasmType::I64 => 0x7e,
+        WasmType::F32 => 0x7d,
+        WasmType::F64 => 0x7c,
+        WasmType::V128 => 0x7b,
+        WasmType::FuncRef => 0x70,
+        WasmType::ExternRef => 0x6f,
+        WasmType::ExnRef => bail!("cannot snapshot globals of type exnref"),
+    })
+}
+
+pub(crate) fn write_section(dst: &mut Vec<u8>, id: u8, contents: &[u8]) {
+    dst.push(id);
+    write_var_u32(dst, contents.len() as u32);
+    dst.extend_from_slice(contents);
+}
+
+pub(crate) fn write_var_u32(dst: &mut Vec<u8>, mut val: u32) {
+    loop {
+        let byte = (val & 0x7f) as u8;
+        val >>= 7;
+        if val == 0 {
+            dst.push(byte);
+            return;
+        }
+        dst.push(byte | 0x80);
+    }
+}
+
+pub(crate) fn write_var_i64(dst: &mut Vec<u8>, mut val: i64) {
+    loop {
+        let byte = (val & 0x7f) as u8;
+        val >>= 7;
+        let done = (val == 0 && byte & 0x40 == 0) || (val == -1 && byte & 0x40 != 0);
+        if done {
+            dst.push(byte);
+            return;
+        }
+        dst.push(byte | 0x80);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn leb128() {
+        let encode = |val| {
+            let mut ret = Vec::new();
+            write_var_i64(&mut ret, val);
+            ret
+        };
+        assert_eq!(encode(0), [0x00]);
+        assert_eq!(encode(63), [0x3f]);
+        assert_eq!(encode(64), [0xc0, 0x00]);
+        assert_eq!(encode(-1), [0x7f]);
+        assert_eq!(encode(-65), [0xbf, 0x7f]);
+
+        let mut ret = Vec::new();
+        write_var_u32(&mut ret, 624485);
+        assert_eq!(ret, [0xe5, 0x8e, 0x26]);
+    }
+
+    #[test]
+    fn regions() {
+        assert!(nonzero_regions(&[0; 100]).is_empty());
+        assert_eq!(nonzero_regions(&[0, 1, 1, 0]), [(1, 3)]);
+
+        let mut data = vec![0; 100];
+        data[10] = 1;
+        data[20] = 1;
+        data[80] = 1;
+        assert_eq!(nonzero_regions(&data), [(10, 21), (80, 81)]);
+    }
+}
@@ -1,20 +1,27 @@
+use crate::engine::ModuleResources;
 use crate::frame_info::StoreFrameInfo;
 use crate::sig_registry::SignatureRegistry;
 use crate::trampoline::StoreInstanceHandle;
-use crate::{Engine, Module};
+use crate::{Engine, FrameInfo, Instance, Module, Trap};
 use anyhow::{bail, Result};
 use std::any::Any;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::collections::HashSet;
+use std::convert::TryFrom;
 use std::fmt;
 use std::hash::{Hash, Hasher};
+use std::mem;
+use std::panic::{self, AssertUnwindSafe};
 use std::rc::{Rc, Weak};
-use std::sync::Arc;
-use wasmtime_environ::wasm;
+use std::sync::atomic::{AtomicBool, Ordering::SeqCst};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use wasmtime_environ::{wasm, WASM_PAGE_SIZE};
 use wasmtime_jit::{CompiledModule, ModuleCode, TypeTables};
 use wasmtime_runtime::{
-    InstanceHandle, RuntimeMemoryCreator, SignalHandler, StackMapRegistry, TrapInfo, VMExternRef,
-    VMExternRefActivationsTable, VMInterrupts, VMSharedSignatureIndex,
+    InstanceHandle, InstanceLimits, RuntimeMemoryCreator, SignalHandler, StackMapRegistry,
+    TrapInfo, VMExternRef, VMExternRefActivationsTable, VMInterrupts, VMSharedSignatureIndex,
 };
 
 /// A `Store` is a collection of WebAssembly instances and host-defined items.
@@ -67,6 +74,114 @@ pub(crate) struct StoreInner {
     /// Set of all compiled modules that we're holding a strong reference to
     /// the module's code for. This includes JIT functions, trampolines, etc.
     modules: RefCell<HashSet<ArcModuleCode>>,
+    /// Time accounting for `Store::runtime_stats`, if enabled.
+    runtime_stats: Option<RefCell<RuntimeStatsState>>,
+    /// Total fuel added to this store through `Store::add_fuel`, used to
+    /// translate the negative counter in `VMInterrupts` back into the amount
+    /// of fuel consumed.
+    fuel_adj: Cell<i64>,
+    /// Callback configured with `Store::out_of_fuel_handler`.
+    out_of_fuel_handler: RefCell<Option<Box<OutOfFuelHandler>>>,
+    /// Limits configured with `Store::limit`.
+    limits: Cell<StoreLimits>,
+    /// Number of module instances counted against `StoreLimits::instances`.
+    module_instances: Cell<usize>,
+    /// Fuel consumed and bytes of linear memory as last reported to the
+    /// engine's metrics.
+    reported_fuel: Cell<u64>,
+    reported_memory: Cell<usize>,
+    /// Number of calls into wasm currently on the stack, so that metrics are
+    /// only reported when the outermost one returns.
+    wasm_calls: Cell<usize>,
+    /// Timer started by `Store::set_deadline`, if any.
+    deadline: RefCell<Option<Deadline>>,
+}
+
+type OutOfFuelHandler = dyn FnMut(&[FrameInfo]) -> Result<u64, Trap>;
+
+/// Statistics about the time spent executing code on behalf of a [`Store`].
+///
+/// This is returned by [`Store::runtime_stats`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct RuntimeStats {
+    /// Total time spent executing WebAssembly code.
+    pub wasm_time: Duration,
+    /// Total time spent in host functions called from WebAssembly.
+    pub host_time: Duration,
+    /// Number of calls from WebAssembly to host functions.
+    pub host_calls: u64,
+}
+
+/// Limits on the resources that WebAssembly in a [`Store`] may use.
+///
+/// Limits are applied to a store with [`Store::limit`]. By default nothing is
+/// limited.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StoreLimits {
+    memory_size: Option<usize>,
+    table_elements: Option<u32>,
+    instances: Option<usize>,
+}
+
+impl StoreLimits {
+    /// Creates a new set of limits that doesn't limit anything.
+    pub fn new() -> StoreLimits {
+        StoreLimits::default()
+    }
+
+    /// Limits the size of each linear memory to `bytes`, rounded down to a
+    /// whole number of wasm pages.
+    ///
+    /// Instantiating a module with a memory whose minimum size exceeds the
+    /// limit fails, and growing a memory beyond the limit fails as if the
+    /// memory had reached its declared maximum, so `memory.grow` returns -1.
+    pub fn memory_size(&mut self, bytes: usize) -> &mut Self {
+        self.memory_size = Some(bytes);
+        self
+    }
+
+    /// Limits the number of elements in each table to `elements`.
+    ///
+    /// This is enforced in the same way as [`StoreLimits::memory_size`].
+    pub fn table_elements(&mut self, elements: u32) -> &mut Self {
+        self.table_elements = Some(elements);
+        self
+    }
+
+    /// Limits the number of modules that may be instantiated in the store.
+    ///
+    /// Instances created for host-defined items, like functions defined with
+    /// [`Func::wrap`](crate::Func::wrap), aren't counted.
+    pub fn instances(&mut self, instances: usize) -> &mut Self {
+        self.instances = Some(instances);
+        self
+    }
+}
+
+#[derive(Default)]
+struct RuntimeStatsState {
+    stats: RuntimeStats,
+    /// Whether a host function called from wasm is currently executing.
+    in_host: bool,
+    /// When execution last switched between wasm and host code, or `None` if
+    /// neither is currently executing.
+    last_switch: Option<Instant>,
+}
+
+impl RuntimeStatsState {
+    /// Attributes the time since the last switch to whichever side was
+    /// executing, and starts a new period at `now`.
+    fn switch(&mut self, now: Instant) {
+        if let Some(last) = self.last_switch {
+            if self.in_host {
+                self.stats.host_time += now - last;
+            } else {
+                self.stats.wasm_time += now - last;
+            }
+        }
+        self.last_switch = Some(now);
+    }
 }
 
 struct HostInfoKey(VMExternRef);
@@ -98,6 +213,7 @@ impl Store {
         // each one that's not relevant just won't do anything.
         wasmtime_runtime::init_traps();
 
+        engine.resource_counters().stores.fetch_add(1, SeqCst);
         Store {
             inner: Rc::new(StoreInner {
                 engine: engine.clone(),
@@ -109,6 +225,19 @@ impl Store {
                 stack_map_registry: StackMapRegistry::default(),
                 frame_info: Default::default(),
                 modules: Default::default(),
+                runtime_stats: if engine.config().runtime_stats {
+                    Some(Default::default())
+                } else {
+                    None
+                },
+                fuel_adj: Cell::new(0),
+                out_of_fuel_handler: RefCell::new(None),
+                limits: Cell::new(StoreLimits::default()),
+                module_instances: Cell::new(0),
+                reported_fuel: Cell::new(0),
+                reported_memory: Cell::new(0),
+                wasm_calls: Cell::new(0),
+                deadline: RefCell::new(None),
             }),
         }
     }
@@ -175,10 +304,10 @@ impl Store {
         // need to preserve its jit-code. References to this module's code and
         // trampolines are not owning-references so it's our responsibility to
         // keep it all alive within the `Store`.
-        self.inner
-            .modules
-            .borrow_mut()
-            .insert(ArcModuleCode(module.compiled_module().code().clone()));
+        self.inner.modules.borrow_mut().insert(ArcModuleCode(
+            module.compiled_module().code().clone(),
+            module.resources().clone(),
+        ));
     }
 
     fn register_jit_code(&self, module: &CompiledModule) {
@@ -215,12 +344,32 @@ impl Store {
 
     pub(crate) unsafe fn add_instance(&self, handle: InstanceHandle) -> StoreInstanceHandle {
         self.inner.instances.borrow_mut().push(handle.clone());
+        self.engine()
+            .resource_counters()
+            .instances
+            .fetch_add(1, SeqCst);
         StoreInstanceHandle {
             store: self.clone(),
             handle,
         }
     }
 
+    /// Returns all instances within this store, including those created for
+    /// host-defined items.
+    pub(crate) fn instances(&self) -> Vec<Instance> {
+        self.inner
+            .instances
+            .borrow()
+            .iter()
+            .map(|handle| {
+                // Safety: the returned `Instance` keeps this store, and with it
+                // `handle`, alive.
+                let handle = unsafe { handle.clone() };
+                Instance::from_wasmtime(self.existing_instance_handle(handle))
+            })
+            .collect()
+    }
+
     pub(crate) fn existing_instance_handle(&self, handle: InstanceHandle) -> StoreInstanceHandle {
         debug_assert!(self
             .inner
@@ -353,6 +502,191 @@ impl Store {
         }
     }
 
+    /// Interrupts WebAssembly executing in this store once `timeout` has
+    /// elapsed from now.
+    ///
+    /// This is a convenience over [`Store::interrupt_handle`] for enforcing a
+    /// wall-clock time limit, such as one per request. A timer thread
+    /// interrupts this store when the deadline passes, so WebAssembly that
+    /// is running at that point, or the next call into WebAssembly if none
+    /// is, traps with [`TrapCode::DeadlineExceeded`](crate::TrapCode). This
+    /// is independent of any fuel limit.
+    ///
+    /// Setting a new deadline replaces the previous one, and
+    /// [`Store::clear_deadline`] removes it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if interrupts aren't enabled with
+    /// [`Config::interruptable`](crate::Config::interruptable).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use anyhow::Result;
+    /// # use std::time::Duration;
+    /// # use wasmtime::*;
+    /// # fn main() -> Result<()> {
+    /// let engine = Engine::new(Config::new().interruptable(true));
+    /// let store = Store::new(&engine);
+    /// let module = Module::new(&engine, r#"(func (export "run") (loop br 0))"#)?;
+    /// let instance = Instance::new(&store, &module, &[])?;
+    /// let run = instance.get_func("run").unwrap().get0::<()>()?;
+    ///
+    /// store.set_deadline(Duration::from_millis(10))?;
+    /// let trap = run().unwrap_err();
+    /// assert_eq!(trap.trap_code(), Some(TrapCode::DeadlineExceeded));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_deadline(&self, timeout: Duration) -> Result<()> {
+        if !self.engine().config().tunables.interruptable {
+            bail!("interrupts aren't enabled for this `Store`");
+        }
+        // Drop the previous deadline first so its timer can't fire after
+        // the new one is in place.
+        self.clear_deadline();
+        let deadline = Deadline::start(self.inner.interrupts.clone(), timeout);
+        *self.inner.deadline.borrow_mut() = Some(deadline);
+        Ok(())
+    }
+
+    /// Removes the deadline set with [`Store::set_deadline`], if any.
+    ///
+    /// If the deadline has already passed, execution may still be
+    /// interrupted once.
+    pub fn clear_deadline(&self) {
+        self.inner.deadline.borrow_mut().take();
+    }
+
+    /// Returns whether this store's deadline has passed, and resets that
+    /// state so that only the first interrupt after it is attributed to the
+    /// deadline.
+    pub(crate) fn take_deadline_exceeded(&self) -> bool {
+        match &*self.inner.deadline.borrow() {
+            Some(deadline) => deadline.state.exceeded.swap(false, SeqCst),
+            None => false,
+        }
+    }
+
+    /// Returns the amount of fuel consumed by this store's execution so far.
+    ///
+    /// If fuel consumption is not enabled via
+    /// [`Config::consume_fuel`](crate::Config::consume_fuel) then this
+    /// function will return `None`. Also note that fuel, if enabled, must be
+    /// originally configured via [`Store::add_fuel`].
+    pub fn fuel_consumed(&self) -> Option<u64> {
+        if !self.engine().config().tunables.consume_fuel {
+            return None;
+        }
+        let consumed = unsafe { *self.inner.interrupts.fuel_consumed.get() };
+        Some(u64::try_from(self.inner.fuel_adj.get() + consumed).unwrap())
+    }
+
+    /// Adds fuel to this [`Store`] for wasm to consume while executing.
+    ///
+    /// For this method to work fuel consumption must be enabled via
+    /// [`Config::consume_fuel`](crate::Config::consume_fuel). By default a
+    /// [`Store`] starts with 0 fuel for wasm to execute with (meaning it will
+    /// immediately trap). This function must be called for the store to have
+    /// some fuel to allow WebAssembly to execute.
+    ///
+    /// Most WebAssembly instructions consume 1 unit of fuel. Some
+    /// instructions, such as `nop`, `drop`, `block`, and `loop`, consume 0
+    /// units, as any execution cost associated with them involves other
+    /// instructions which do consume fuel.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if fuel consumption is not enabled
+    /// via [`Config::consume_fuel`](crate::Config::consume_fuel).
+    pub fn add_fuel(&self, fuel: u64) -> Result<()> {
+        if !self.engine().config().tunables.consume_fuel {
+            bail!("fuel is not configured in this store");
+        }
+
+        // Fuel is stored as an i64, so we need to cast it. If the provided fuel
+        // value overflows that just assume that i64::max will suffice. Wasm
+        // execution isn't fast enough to burn through i64::max fuel in any
+        // reasonable amount of time anyway.
+        let fuel = i64::try_from(fuel).unwrap_or(i64::max_value());
+        let adj = self.inner.fuel_adj.get();
+        let consumed_ptr = unsafe { &mut *self.inner.interrupts.fuel_consumed.get() };
+
+        match (consumed_ptr.checked_sub(fuel), adj.checked_add(fuel)) {
+            // If we succesfully did arithmetic without overflowing then we can
+            // just update our fields.
+            (Some(consumed), Some(adj)) => {
+                self.inner.fuel_adj.set(adj);
+                *consumed_ptr = consumed;
+            }
+
+            // Otherwise something overflowed. Make sure that we preserve the
+            // amount of fuel that's already consumed, but otherwise assume that
+            // we were given infinite fuel.
+            _ => {
+                self.inner.fuel_adj.set(i64::max_value());
+                *consumed_ptr = (*consumed_ptr + adj) - i64::max_value();
+            }
+        }
+        Ok(())
+    }
+
+    /// Limits the resources that WebAssembly in this [`Store`] may use.
+    ///
+    /// The limits apply to instances, memories, and tables created after
+    /// this call; existing ones aren't affected. Instances created before the
+    /// call still count against [`StoreLimits::instances`].
+    pub fn limit(&self, limits: StoreLimits) {
+        self.inner.limits.set(limits);
+    }
+
+    /// Returns the limits on memories and tables to create new instances with.
+    pub(crate) fn instance_limits(&self) -> InstanceLimits {
+        let limits = self.inner.limits.get();
+        InstanceLimits {
+            memory_pages: limits.memory_size.map(|bytes| {
+                u32::try_from(bytes / WASM_PAGE_SIZE as usize).unwrap_or(u32::max_value())
+            }),
+            table_elements: limits.table_elements,
+        }
+    }
+
+    /// Counts a new module instance against [`StoreLimits::instances`],
+    /// failing if the limit has been reached.
+    pub(crate) fn reserve_instance(&self) -> Result<()> {
+        let count = self.inner.module_instances.get();
+        if let Some(max) = self.inner.limits.get().instances {
+            if count >= max {
+                bail!("instance limit of {} exceeded", max);
+            }
+        }
+        self.inner.module_instances.set(count + 1);
+        Ok(())
+    }
+
+    /// Configures a callback to invoke whenever wasm in this [`Store`] runs
+    /// out of fuel.
+    ///
+    /// By default running out of fuel raises a trap. When a handler is
+    /// configured it's instead called with the wasm stack at the point fuel
+    /// ran out, youngest frame first. If the handler returns `Ok(fuel)` then
+    /// `fuel` is added to this store (as if by [`Store::add_fuel`]) and
+    /// execution resumes; if it returns `Err(trap)` then `trap` is raised.
+    ///
+    /// Combined with small amounts of fuel this can be used to periodically
+    /// regain control from long-running wasm, for example to implement a
+    /// sampling profiler.
+    ///
+    /// This has no effect unless fuel consumption is enabled via
+    /// [`Config::consume_fuel`](crate::Config::consume_fuel).
+    pub fn out_of_fuel_handler(
+        &self,
+        handler: impl FnMut(&[FrameInfo]) -> Result<u64, Trap> + 'static,
+    ) {
+        *self.inner.out_of_fuel_handler.borrow_mut() = Some(Box::new(handler));
+    }
+
     pub(crate) fn externref_activations_table(&self) -> &VMExternRefActivationsTable {
         &self.inner.externref_activations_table
     }
@@ -365,6 +699,97 @@ impl Store {
         &self.inner.frame_info
     }
 
+    /// Returns the time spent executing WebAssembly and host functions on
+    /// behalf of this store so far.
+    ///
+    /// Time is only accounted for while WebAssembly is executing, including
+    /// time spent in host functions it calls, which makes this suitable for
+    /// billing or limiting the compute used by guests. Time the embedder
+    /// spends outside of calls into WebAssembly isn't included.
+    ///
+    /// Accounting must be enabled with
+    /// [`Config::runtime_stats`](crate::Config::runtime_stats), otherwise
+    /// this returns all zeros.
+    pub fn runtime_stats(&self) -> RuntimeStats {
+        match &self.inner.runtime_stats {
+            Some(state) => state.borrow().stats,
+            None => RuntimeStats::default(),
+        }
+    }
+
+    /// Brings the engine's metrics up to date with the fuel consumed and the
+    /// linear memory used by this store.
+    pub(crate) fn report_metrics(&self) {
+        let metrics = self.engine().metrics_counters();
+        if let Some(consumed) = self.fuel_consumed() {
+            let reported = self.inner.reported_fuel.replace(consumed);
+            metrics.fuel_consumed(consumed.saturating_sub(reported));
+        }
+        let memory = self
+            .inner
+            .instances
+            .borrow()
+            .iter()
+            .map(|i| i.defined_memory_bytes())
+            .sum();
+        let reported = self.inner.reported_memory.replace(memory);
+        metrics.memory_resized(reported, memory);
+    }
+
+    /// Records that execution is switching to wasm, returning state to pass to
+    /// `exit_wasm` when it returns.
+    pub(crate) fn enter_wasm(&self) -> bool {
+        self.inner.wasm_calls.set(self.inner.wasm_calls.get() + 1);
+        let mut state = match &self.inner.runtime_stats {
+            Some(state) => state.borrow_mut(),
+            None => return false,
+        };
+        state.switch(Instant::now());
+        mem::replace(&mut state.in_host, false)
+    }
+
+    /// Records that a call into wasm has returned, either normally or with a
+    /// trap. `in_host` is the value returned by the matching `enter_wasm`.
+    ///
+    /// Once the outermost call has returned, the store's fuel and memory use
+    /// are reported to the engine's metrics.
+    pub(crate) fn exit_wasm(&self, in_host: bool) {
+        let wasm_calls = self.inner.wasm_calls.get() - 1;
+        self.inner.wasm_calls.set(wasm_calls);
+        if wasm_calls == 0 {
+            self.report_metrics();
+        }
+        if let Some(state) = &self.inner.runtime_stats {
+            let mut state = state.borrow_mut();
+            // If a trap unwound past a host function before its `exit_host`
+            // the time since then is attributed to that host function.
+            state.switch(Instant::now());
+            state.in_host = in_host;
+            if !in_host {
+                state.last_switch = None;
+            }
+        }
+    }
+
+    /// Records that wasm is calling a host function.
+    pub(crate) fn enter_host(&self) {
+        if let Some(state) = &self.inner.runtime_stats {
+            let mut state = state.borrow_mut();
+            state.switch(Instant::now());
+            state.in_host = true;
+            state.stats.host_calls += 1;
+        }
+    }
+
+    /// Records that a host function is returning to wasm.
+    pub(crate) fn exit_host(&self) {
+        if let Some(state) = &self.inner.runtime_stats {
+            let mut state = state.borrow_mut();
+            state.switch(Instant::now());
+            state.in_host = false;
+        }
+    }
+
     /// Perform garbage collection of `ExternRef`s.
     pub fn gc(&self) {
         // For this crate's API, we ensure that `set_stack_canary` invariants
@@ -398,6 +823,45 @@ unsafe impl TrapInfo for Store {
     fn max_wasm_stack(&self) -> usize {
         self.engine().config().max_wasm_stack
     }
+
+    fn out_of_gas(&self) {
+        let handler = self.inner.out_of_fuel_handler.borrow_mut().take();
+        let trap = match handler {
+            Some(mut handler) => {
+                // The handler runs with wasm frames on the stack, so a panic
+                // must not unwind out of it directly.
+                let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                    handler(Trap::out_of_fuel(self, false).trace())
+                }));
+                // The handler may have installed a replacement for itself, in
+                // which case that one takes precedence and this one is dropped
+                // now, before any trap is raised below.
+                let mut slot = self.inner.out_of_fuel_handler.borrow_mut();
+                if slot.is_none() {
+                    *slot = Some(handler);
+                } else {
+                    drop(handler);
+                }
+                drop(slot);
+                match result {
+                    Ok(Ok(fuel)) => {
+                        // Fuel consumption is necessarily enabled to get here,
+                        // so this can't fail.
+                        self.add_fuel(fuel).unwrap();
+                        return;
+                    }
+                    Ok(Err(trap)) => trap,
+                    // As with host functions, the panic is carried past the
+                    // wasm frames and resumed once `catch_traps` returns.
+                    Err(panic) => unsafe { wasmtime_runtime::resume_panic(panic) },
+                }
+            }
+            None => Trap::out_of_fuel(self, true),
+        };
+        // Raising a trap unwinds past this frame without running destructors,
+        // so make sure everything else has already been dropped.
+        unsafe { wasmtime_runtime::raise_user_trap(Box::new(trap)) }
+    }
 }
 
 impl Default for Store {
@@ -420,6 +884,14 @@ impl Drop for StoreInner {
                 instance.dealloc();
             }
         }
+        let counters = self.engine.resource_counters();
+        counters
+            .instances
+            .fetch_sub(self.instances.get_mut().len(), SeqCst);
+        counters.stores.fetch_sub(1, SeqCst);
+        self.engine
+            .metrics_counters()
+            .memory_resized(self.reported_memory.get(), 0);
     }
 }
 
@@ -444,9 +916,69 @@ impl InterruptHandle {
     }
 }
 
+/// A timer thread started by [`Store::set_deadline`], which interrupts the
+/// store once the deadline passes unless it's dropped first.
+struct Deadline {
+    state: Arc<DeadlineState>,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+#[derive(Default)]
+struct DeadlineState {
+    cancelled: Mutex<bool>,
+    cond: Condvar,
+    exceeded: AtomicBool,
+}
+
+impl Deadline {
+    fn start(interrupts: Arc<VMInterrupts>, timeout: Duration) -> Deadline {
+        let state = Arc::new(DeadlineState::default());
+        let deadline = Instant::now() + timeout;
+        let thread = {
+            let state = state.clone();
+            thread::spawn(move || {
+                let mut cancelled = state.cancelled.lock().unwrap();
+                loop {
+                    if *cancelled {
+                        return;
+                    }
+                    let now = Instant::now();
+                    if now >= deadline {
+                        break;
+                    }
+                    cancelled = state
+                        .cond
+                        .wait_timeout(cancelled, deadline - now)
+                        .unwrap()
+                        .0;
+                }
+                // Flag the deadline before interrupting so the resulting trap
+                // is attributed to it.
+                state.exceeded.store(true, SeqCst);
+                interrupts.interrupt();
+            })
+        };
+        Deadline {
+            state,
+            thread: Some(thread),
+        }
+    }
+}
+
+impl Drop for Deadline {
+    fn drop(&mut self) {
+        *self.state.cancelled.lock().unwrap() = true;
+        self.state.cond.notify_one();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
 // Wrapper struct to implement hash/equality based on the pointer value of the
-// `Arc` in question.
-struct ArcModuleCode(Arc<ModuleCode>);
+// `Arc` in question. This also keeps the module's code accounted for in its
+// engine's resource counters for as long as the store holds on to the code.
+struct ArcModuleCode(Arc<ModuleCode>, Arc<ModuleResources>);
 
 impl PartialEq for ArcModuleCode {
     fn eq(&self, other: &ArcModuleCode) -> bool {
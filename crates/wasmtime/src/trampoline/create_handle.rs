@@ -31,6 +31,7 @@ pub(crate) fn create_handle(
             &finished_functions,
             imports,
             store.memory_creator(),
+            &store.instance_limits(),
             &|_| shared_signature_id.unwrap(),
             state,
             store.interrupts(),
@@ -3,6 +3,7 @@ use crate::trampoline::StoreInstanceHandle;
 use crate::{GlobalType, Mutability, Store, Val};
 use anyhow::Result;
 use wasmtime_environ::entity::PrimaryMap;
+use wasmtime_environ::ir::immediates::V128Imm;
 use wasmtime_environ::{
     wasm::{self, SignatureIndex},
     Module, ModuleType,
@@ -27,6 +28,7 @@ pub fn create_global(store: &Store, gt: &GlobalType, val: Val) -> Result<StoreIn
             Val::I64(i) => wasm::GlobalInit::I64Const(i),
             Val::F32(f) => wasm::GlobalInit::F32Const(f),
             Val::F64(f) => wasm::GlobalInit::F64Const(f),
+            Val::V128(v) => wasm::GlobalInit::V128Const(V128Imm(v.to_le_bytes())),
             Val::ExternRef(None) | Val::FuncRef(None) => wasm::GlobalInit::RefNullConst,
             Val::ExternRef(Some(x)) => {
                 // There is no `GlobalInit` variant for using an existing
@@ -62,7 +64,6 @@ pub fn create_global(store: &Store, gt: &GlobalType, val: Val) -> Result<StoreIn
 
                 wasm::GlobalInit::RefFunc(func_index)
             }
-            _ => unimplemented!("create_global for {:?}", gt),
         },
     };
 
@@ -20,6 +20,7 @@ pub fn create_handle_with_memory(
         minimum: memory.limits().min(),
         maximum: memory.limits().max(),
         shared: false, // TODO
+        memory64: memory.is_64(),
     };
 
     let memory_plan =
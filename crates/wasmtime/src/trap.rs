@@ -1,4 +1,4 @@
-use crate::{FrameInfo, Store};
+use crate::{FrameInfo, Store, WasmCoreDump};
 use backtrace::Backtrace;
 use std::fmt;
 use std::sync::Arc;
@@ -76,6 +76,10 @@ pub enum TrapCode {
 
     /// Execution has potentially run too long and may be interrupted.
     Interrupt,
+
+    /// Execution was interrupted because the deadline set with
+    /// [`Store::set_deadline`](crate::Store::set_deadline) passed.
+    DeadlineExceeded,
 }
 
 impl TrapCode {
@@ -113,6 +117,7 @@ impl fmt::Display for TrapCode {
             BadConversionToInteger => "invalid conversion to integer",
             UnreachableCodeReached => "unreachable",
             Interrupt => "interrupt",
+            DeadlineExceeded => "deadline exceeded",
         };
         write!(f, "{}", desc)
     }
@@ -123,6 +128,7 @@ struct TrapInner {
     wasm_trace: Vec<FrameInfo>,
     native_trace: Backtrace,
     hint_wasm_backtrace_details_env: bool,
+    coredump: Option<WasmCoreDump>,
 }
 
 fn _assert_trap_is_sync_and_send(t: &Trap) -> (&dyn Sync, &dyn Send) {
@@ -152,6 +158,23 @@ impl Trap {
         )
     }
 
+    /// Creates the trap raised when wasm running in `store` has consumed all
+    /// of its fuel, capturing the current wasm stack.
+    ///
+    /// A coredump is only captured, if enabled, when `capture_coredump` is
+    /// set, since this is also used to inspect the stack without raising the
+    /// trap.
+    pub(crate) fn out_of_fuel(store: &Store, capture_coredump: bool) -> Self {
+        let reason = TrapReason::Message("all fuel consumed by WebAssembly".to_string());
+        Trap::new_with_trace_and_coredump(
+            Some(store),
+            None,
+            reason,
+            Backtrace::new_unresolved(),
+            capture_coredump,
+        )
+    }
+
     pub(crate) fn from_runtime(store: &Store, runtime_trap: wasmtime_runtime::Trap) -> Self {
         match runtime_trap {
             wasmtime_runtime::Trap::User(error) => Trap::from(error),
@@ -188,7 +211,13 @@ impl Trap {
         code: ir::TrapCode,
         backtrace: Backtrace,
     ) -> Self {
-        let code = TrapCode::from_non_user(code);
+        let mut code = TrapCode::from_non_user(code);
+        if let Some(store) = store {
+            if code == TrapCode::Interrupt && store.take_deadline_exceeded() {
+                code = TrapCode::DeadlineExceeded;
+            }
+            store.engine().metrics_counters().trapped(code);
+        }
         Trap::new_with_trace(store, trap_pc, TrapReason::InstructionTrap(code), backtrace)
     }
 
@@ -208,14 +237,32 @@ impl Trap {
     /// * `native_trace` - this is a captured backtrace from when the trap
     ///   occurred, and this will iterate over the frames to find frames that
     ///   lie in wasm jit code.
+    ///
+    /// If the store has `Config::coredump_on_trap` enabled then a coredump is
+    /// captured as well, unless this is an explicit program exit.
     fn new_with_trace(
         store: Option<&Store>,
         trap_pc: Option<usize>,
         reason: TrapReason,
         native_trace: Backtrace,
+    ) -> Self {
+        let capture_coredump = match reason {
+            TrapReason::I32Exit(_) => false,
+            _ => true,
+        };
+        Trap::new_with_trace_and_coredump(store, trap_pc, reason, native_trace, capture_coredump)
+    }
+
+    fn new_with_trace_and_coredump(
+        store: Option<&Store>,
+        trap_pc: Option<usize>,
+        reason: TrapReason,
+        native_trace: Backtrace,
+        capture_coredump: bool,
     ) -> Self {
         let mut wasm_trace = Vec::new();
         let mut hint_wasm_backtrace_details_env = false;
+        let mut coredump = None;
         wasmtime_runtime::with_last_info(|last| {
             // If the `store` passed in is `None` then we look at the `last`
             // store configured to call wasm, and if that's a `Store` we use
@@ -256,6 +303,9 @@ impl Trap {
                         }
                     }
                 }
+                if capture_coredump && store.engine().config().coredump_on_trap {
+                    coredump = Some(WasmCoreDump::capture(store, &wasm_trace));
+                }
             }
         });
         Trap {
@@ -264,6 +314,7 @@ impl Trap {
                 wasm_trace,
                 native_trace,
                 hint_wasm_backtrace_details_env,
+                coredump,
             }),
         }
     }
@@ -277,12 +328,51 @@ impl Trap {
         }
     }
 
+    /// Returns the conventional process exit code for a program terminated by
+    /// this trap.
+    ///
+    /// For an explicit program exit (see [`Trap::i32_exit_status`]) this is
+    /// the exit status itself. Any other trap is reported the way the
+    /// platform reports an abnormal termination: `128 + SIGABRT` on Unix and
+    /// `3` on Windows, which is what `abort()` produces there. Since `3` is
+    /// ambiguous on Windows, explicit exit statuses of `3` or more are mapped
+    /// to `1` there.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmtime::Trap;
+    /// assert_eq!(Trap::i32_exit(2).exit_code(), 2);
+    /// assert_ne!(Trap::new("unexpected error").exit_code(), 0);
+    /// ```
+    pub fn exit_code(&self) -> i32 {
+        match self.i32_exit_status() {
+            Some(status) if cfg!(windows) && status >= 3 => 1,
+            Some(status) => status,
+            None if cfg!(windows) => 3,
+            None => 128 + libc::SIGABRT,
+        }
+    }
+
+    /// Returns the message describing this trap, without the backtrace that
+    /// its `Display` implementation includes.
+    pub fn message(&self) -> String {
+        self.inner.reason.to_string()
+    }
+
     /// Returns a list of function frames in WebAssembly code that led to this
     /// trap happening.
     pub fn trace(&self) -> &[FrameInfo] {
         &self.inner.wasm_trace
     }
 
+    /// Returns the coredump captured when this trap was raised, if
+    /// [`Config::coredump_on_trap`](crate::Config::coredump_on_trap) was
+    /// enabled.
+    pub fn coredump(&self) -> Option<&WasmCoreDump> {
+        self.inner.coredump.as_ref()
+    }
+
     /// Code of a trap that happened while executing a WASM instruction.
     /// If the trap was triggered by a host export this will be `None`.
     pub fn trap_code(&self) -> Option<TrapCode> {
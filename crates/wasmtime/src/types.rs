@@ -424,13 +424,32 @@ impl TableType {
 #[derive(Debug, Clone, Hash, Eq, PartialEq)]
 pub struct MemoryType {
     limits: Limits,
+    memory64: bool,
 }
 
 impl MemoryType {
     /// Creates a new descriptor for a WebAssembly memory given the specified
     /// limits of the memory.
     pub fn new(limits: Limits) -> MemoryType {
-        MemoryType { limits }
+        MemoryType {
+            limits,
+            memory64: false,
+        }
+    }
+
+    /// Creates a new descriptor for a 64-bit WebAssembly memory given the
+    /// specified limits of the memory.
+    ///
+    /// 64-bit memories are part of the [memory64 proposal] and are indexed
+    /// with `i64` addresses. Their limits are still measured in pages, so
+    /// they're limited to the same size as 32-bit memories.
+    ///
+    /// [memory64 proposal]: https://github.com/WebAssembly/memory64
+    pub fn new64(limits: Limits) -> MemoryType {
+        MemoryType {
+            limits,
+            memory64: true,
+        }
     }
 
     /// Returns the limits (in pages) that are configured for this memory.
@@ -438,8 +457,16 @@ impl MemoryType {
         &self.limits
     }
 
+    /// Returns whether this is a 64-bit memory, indexed with `i64` addresses.
+    pub fn is_64(&self) -> bool {
+        self.memory64
+    }
+
     pub(crate) fn from_wasmtime_memory(memory: &wasm::Memory) -> MemoryType {
-        MemoryType::new(Limits::new(memory.minimum, memory.maximum))
+        MemoryType {
+            limits: Limits::new(memory.minimum, memory.maximum),
+            memory64: memory.memory64,
+        }
     }
 }
 
@@ -1,5 +1,5 @@
 use proptest::prelude::*;
-use std::cell::UnsafeCell;
+use std::cell::{Cell, UnsafeCell};
 use std::marker;
 use wiggle::{BorrowHandle, GuestMemory, Region};
 
@@ -52,6 +52,7 @@ struct HostBuffer {
 pub struct HostMemory {
     buffer: HostBuffer,
     bc: BorrowChecker,
+    init: Option<InitTracker>,
 }
 impl HostMemory {
     pub fn new() -> Self {
@@ -60,6 +61,36 @@ impl HostMemory {
                 cell: UnsafeCell::new([0; 4096]),
             },
             bc: BorrowChecker::new(),
+            init: None,
+        }
+    }
+
+    /// Creates a `HostMemory` whose bytes all start out uninitialized. The
+    /// memory is filled with [`POISON`], and any read of a byte that hasn't
+    /// been written yet panics, so marshaling code that reads padding or
+    /// unwritten out-params is caught by the test that exercises it.
+    ///
+    /// Writes are observed through the borrow checking calls that `GuestPtr`
+    /// makes: a primitive write asks `is_shared_borrowed` and then
+    /// `is_mut_borrowed` of the same region, whereas a primitive read only
+    /// asks `is_mut_borrowed`. Mutable slices are assumed to be written in
+    /// full, and shared slices and strings must be entirely initialized.
+    pub fn new_uninit() -> Self {
+        HostMemory {
+            buffer: HostBuffer {
+                cell: UnsafeCell::new([POISON; 4096]),
+            },
+            bc: BorrowChecker::new(),
+            init: Some(InitTracker::new()),
+        }
+    }
+
+    /// Marks a region as initialized, for tests that set up memory other than
+    /// through `GuestPtr`. Has no effect unless created with
+    /// [`HostMemory::new_uninit`].
+    pub fn mark_init(&self, r: Region) {
+        if let Some(init) = &self.init {
+            init.mark(r);
         }
     }
 
@@ -126,16 +157,39 @@ unsafe impl GuestMemory for HostMemory {
         self.bc.has_outstanding_borrows()
     }
     fn is_shared_borrowed(&self, r: Region) -> bool {
-        self.bc.is_shared_borrowed(r)
+        let borrowed = self.bc.is_shared_borrowed(r);
+        if let Some(init) = &self.init {
+            // A write only goes on to ask `is_mut_borrowed` if this is false.
+            init.pending_write
+                .set(if borrowed { None } else { Some(r) });
+        }
+        borrowed
     }
     fn is_mut_borrowed(&self, r: Region) -> bool {
-        self.bc.is_mut_borrowed(r)
+        let borrowed = self.bc.is_mut_borrowed(r);
+        if let Some(init) = &self.init {
+            let is_write = init.pending_write.take() == Some(r);
+            if !borrowed {
+                if is_write {
+                    init.mark(r);
+                } else {
+                    init.check(r);
+                }
+            }
+        }
+        borrowed
     }
     fn mut_borrow(&self, r: Region) -> Result<BorrowHandle, GuestError> {
-        self.bc.mut_borrow(r)
+        let h = self.bc.mut_borrow(r)?;
+        self.mark_init(r);
+        Ok(h)
     }
     fn shared_borrow(&self, r: Region) -> Result<BorrowHandle, GuestError> {
-        self.bc.shared_borrow(r)
+        let h = self.bc.shared_borrow(r)?;
+        if let Some(init) = &self.init {
+            init.check(r);
+        }
+        Ok(h)
     }
     fn shared_unborrow(&self, h: BorrowHandle) {
         self.bc.shared_unborrow(h)
@@ -145,6 +199,48 @@ unsafe impl GuestMemory for HostMemory {
     }
 }
 
+/// The byte that uninitialized memory in [`HostMemory::new_uninit`] is filled
+/// with.
+pub const POISON: u8 = 0xa5;
+
+/// Tracks which bytes of a `HostMemory` have been written.
+struct InitTracker {
+    init: Vec<Cell<bool>>,
+    /// Region of the most recent `is_shared_borrowed` query, which is how a
+    /// primitive write announces itself.
+    pending_write: Cell<Option<Region>>,
+}
+
+impl InitTracker {
+    fn new() -> Self {
+        InitTracker {
+            init: (0..4096).map(|_| Cell::new(false)).collect(),
+            pending_write: Cell::new(None),
+        }
+    }
+
+    fn bytes(&self, r: Region) -> &[Cell<bool>] {
+        let start = r.start as usize;
+        &self.init[start..start + r.len as usize]
+    }
+
+    fn mark(&self, r: Region) {
+        for b in self.bytes(r) {
+            b.set(true);
+        }
+    }
+
+    fn check(&self, r: Region) {
+        if let Some(ix) = self.bytes(r).iter().position(|b| !b.get()) {
+            panic!(
+                "read of uninitialized guest memory at {:#x} (in {:?})",
+                r.start as usize + ix,
+                r
+            );
+        }
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct MemArea {
     pub ptr: u32,
@@ -221,6 +317,37 @@ mod test {
         assert_eq!(h.base().0 as usize % 4096, 0);
     }
 
+    #[test]
+    fn uninit_tracking() {
+        let h = HostMemory::new_uninit();
+        assert_eq!(unsafe { *h.base().0 }, POISON, "filled with poison");
+        h.ptr::<u32>(8).write(1).unwrap();
+        assert_eq!(h.ptr::<u32>(8).read().unwrap(), 1);
+        assert_eq!(h.ptr::<[u8]>((8, 4)).as_slice().unwrap().len(), 4);
+        h.ptr::<[u8]>((16, 4))
+            .copy_from_slice(&[1, 2, 3, 4])
+            .unwrap();
+        assert_eq!(&*h.ptr::<[u8]>((16, 4)).as_slice().unwrap(), &[1, 2, 3, 4]);
+        h.mark_init(Region::new(32, 2));
+        h.ptr::<u16>(32).read().unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "uninitialized guest memory at 0xc")]
+    fn uninit_read() {
+        let h = HostMemory::new_uninit();
+        h.ptr::<u32>(8).write(1).unwrap();
+        h.ptr::<u64>(8).read().unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "uninitialized guest memory at 0x2")]
+    fn uninit_slice() {
+        let h = HostMemory::new_uninit();
+        h.ptr::<u16>(0).write(1).unwrap();
+        h.ptr::<[u8]>((0, 4)).as_slice().unwrap();
+    }
+
     #[test]
     fn invert() {
         fn invert_equality(input: &[MemArea], expected: &[MemArea]) {
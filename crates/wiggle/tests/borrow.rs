@@ -0,0 +1,234 @@
+use proptest::prelude::*;
+use wiggle::{GuestError, GuestMemory, GuestPtr, GuestSlice, GuestSliceMut};
+use wiggle_test::HostMemory;
+
+// Size of the memory backing `HostMemory`.
+const MEM_SIZE: u32 = 4096;
+
+#[derive(Debug, Clone)]
+enum Op {
+    SharedSlice { start: u32, len: u32 },
+    MutSlice { start: u32, len: u32, fill: u8 },
+    Read { ptr: u32 },
+    Write { ptr: u32, val: u8 },
+    Release { ix: usize },
+}
+
+impl Op {
+    pub fn strat() -> BoxedStrategy<Self> {
+        // Most offsets are clustered near the start of memory so that borrows
+        // overlap often, with a few near (and past) the end of memory to
+        // exercise the bounds checks, and a few huge ones to exercise
+        // overflow.
+        let start = prop_oneof![
+            8 => 0..128u32,
+            2 => (MEM_SIZE - 64)..(MEM_SIZE + 64),
+            1 => any::<u32>(),
+        ];
+        let len = prop_oneof![
+            8 => 0..32u32,
+            1 => any::<u32>(),
+        ];
+        prop_oneof![
+            (start.clone(), len.clone()).prop_map(|(start, len)| Op::SharedSlice { start, len }),
+            (start.clone(), len, any::<u8>()).prop_map(|(start, len, fill)| Op::MutSlice {
+                start,
+                len,
+                fill
+            }),
+            start.clone().prop_map(|ptr| Op::Read { ptr }),
+            (start, any::<u8>()).prop_map(|(ptr, val)| Op::Write { ptr, val }),
+            any::<usize>().prop_map(|ix| Op::Release { ix }),
+        ]
+        .boxed()
+    }
+}
+
+enum Borrow<'a> {
+    Shared(GuestSlice<'a, u8>, Area),
+    Mut(GuestSliceMut<'a, u8>, Area),
+}
+
+impl Borrow<'_> {
+    fn area(&self) -> Area {
+        match self {
+            Borrow::Shared(_, a) | Borrow::Mut(_, a) => *a,
+        }
+    }
+}
+
+// A half-open range of guest memory. This is kept separate from
+// `wiggle::Region` so the overlap check under test isn't reused by the model.
+#[derive(Debug, Copy, Clone)]
+struct Area {
+    start: u64,
+    end: u64,
+}
+
+impl Area {
+    fn new(start: u32, len: u32) -> Self {
+        Area {
+            start: start as u64,
+            end: start as u64 + len as u64,
+        }
+    }
+    fn in_bounds(&self) -> bool {
+        self.end <= MEM_SIZE as u64
+    }
+    fn conflicts(&self, other: &Area) -> bool {
+        self.start < self.end
+            && other.start < other.end
+            && self.start < other.end
+            && other.start < self.end
+    }
+}
+
+#[derive(Debug)]
+struct BorrowExercise {
+    ops: Vec<Op>,
+}
+
+impl BorrowExercise {
+    pub fn strat() -> BoxedStrategy<Self> {
+        prop::collection::vec(Op::strat(), 1..64)
+            .prop_map(|ops| BorrowExercise { ops })
+            .boxed()
+    }
+
+    pub fn test(&self) {
+        let host_memory = HostMemory::new();
+        // Shadow copy of what guest memory should contain.
+        let mut shadow = vec![0u8; MEM_SIZE as usize];
+        let mut borrows: Vec<Borrow> = Vec::new();
+
+        for op in self.ops.iter() {
+            match *op {
+                Op::SharedSlice { start, len } => {
+                    let area = Area::new(start, len);
+                    let res = GuestPtr::<[u8]>::new(&host_memory, (start, len)).as_slice();
+                    let allowed = area.in_bounds()
+                        && !borrows.iter().any(|b| match b {
+                            Borrow::Mut(_, a) => a.conflicts(&area),
+                            Borrow::Shared(..) => false,
+                        });
+                    match res {
+                        Ok(slice) => {
+                            assert!(allowed, "shared borrow of {:?} should fail", area);
+                            assert_eq!(&*slice, &shadow[area.start as usize..area.end as usize]);
+                            borrows.push(Borrow::Shared(slice, area));
+                        }
+                        Err(e) => {
+                            assert!(!allowed, "shared borrow of {:?} failed: {}", area, e);
+                            assert_error(e, &area);
+                        }
+                    }
+                }
+                Op::MutSlice { start, len, fill } => {
+                    let area = Area::new(start, len);
+                    let res = GuestPtr::<[u8]>::new(&host_memory, (start, len)).as_slice_mut();
+                    let allowed =
+                        area.in_bounds() && !borrows.iter().any(|b| b.area().conflicts(&area));
+                    match res {
+                        Ok(mut slice) => {
+                            assert!(allowed, "mut borrow of {:?} should fail", area);
+                            assert_eq!(&*slice, &shadow[area.start as usize..area.end as usize]);
+                            for byte in slice.iter_mut() {
+                                *byte = fill;
+                            }
+                            for byte in shadow[area.start as usize..area.end as usize].iter_mut() {
+                                *byte = fill;
+                            }
+                            borrows.push(Borrow::Mut(slice, area));
+                        }
+                        Err(e) => {
+                            assert!(!allowed, "mut borrow of {:?} failed: {}", area, e);
+                            assert_error(e, &area);
+                        }
+                    }
+                }
+                Op::Read { ptr } => {
+                    let area = Area::new(ptr, 1);
+                    let res = GuestPtr::<u8>::new(&host_memory, ptr).read();
+                    let allowed = area.in_bounds()
+                        && !borrows.iter().any(|b| match b {
+                            Borrow::Mut(_, a) => a.conflicts(&area),
+                            Borrow::Shared(..) => false,
+                        });
+                    match res {
+                        Ok(val) => {
+                            assert!(allowed, "read of {:?} should fail", area);
+                            assert_eq!(val, shadow[ptr as usize]);
+                        }
+                        Err(e) => {
+                            assert!(!allowed, "read of {:?} failed: {}", area, e);
+                            assert_error(e, &area);
+                        }
+                    }
+                }
+                Op::Write { ptr, val } => {
+                    let area = Area::new(ptr, 1);
+                    let res = GuestPtr::<u8>::new(&host_memory, ptr).write(val);
+                    let allowed =
+                        area.in_bounds() && !borrows.iter().any(|b| b.area().conflicts(&area));
+                    match res {
+                        Ok(()) => {
+                            assert!(allowed, "write of {:?} should fail", area);
+                            shadow[ptr as usize] = val;
+                        }
+                        Err(e) => {
+                            assert!(!allowed, "write of {:?} failed: {}", area, e);
+                            assert_error(e, &area);
+                        }
+                    }
+                }
+                Op::Release { ix } => {
+                    if !borrows.is_empty() {
+                        let ix = ix % borrows.len();
+                        drop(borrows.remove(ix));
+                    }
+                }
+            }
+
+            assert_eq!(
+                host_memory.has_outstanding_borrows(),
+                !borrows.is_empty(),
+                "outstanding borrows are tracked"
+            );
+        }
+
+        drop(borrows);
+        assert!(!host_memory.has_outstanding_borrows());
+
+        // Everything written through guest pointers and mutable slices should
+        // have landed in memory, and nothing else.
+        let all = GuestPtr::<[u8]>::new(&host_memory, (0, MEM_SIZE))
+            .as_slice()
+            .expect("all of memory can be borrowed once borrows are released");
+        assert_eq!(&*all, &shadow[..]);
+    }
+}
+
+// Checks that a rejected access was rejected for the right reason.
+fn assert_error(e: GuestError, area: &Area) {
+    match e {
+        GuestError::PtrOutOfBounds(_) | GuestError::PtrOverflow => {
+            assert!(!area.in_bounds(), "{:?} is in bounds but got {}", area, e)
+        }
+        GuestError::PtrBorrowed(_) => {
+            assert!(
+                area.in_bounds(),
+                "{:?} is out of bounds but got {}",
+                area,
+                e
+            )
+        }
+        e => panic!("unexpected error for {:?}: {}", area, e),
+    }
+}
+
+proptest! {
+    #[test]
+    fn borrows(e in BorrowExercise::strat()) {
+        e.test()
+    }
+}
@@ -101,7 +101,7 @@ impl SumOfPairExercise {
 
     pub fn test(&self) {
         let ctx = WasiCtx::new();
-        let host_memory = HostMemory::new();
+        let host_memory = HostMemory::new_uninit();
 
         host_memory
             .ptr(self.input_loc.ptr)
@@ -189,7 +189,7 @@ impl SumPairPtrsExercise {
     }
     pub fn test(&self) {
         let ctx = WasiCtx::new();
-        let host_memory = HostMemory::new();
+        let host_memory = HostMemory::new_uninit();
 
         host_memory
             .ptr(self.input_first_loc.ptr)
@@ -277,7 +277,7 @@ impl SumIntAndPtrExercise {
     }
     pub fn test(&self) {
         let ctx = WasiCtx::new();
-        let host_memory = HostMemory::new();
+        let host_memory = HostMemory::new_uninit();
 
         host_memory
             .ptr(self.input_first_loc.ptr)
@@ -334,7 +334,7 @@ impl ReturnPairInts {
 
     pub fn test(&self) {
         let ctx = WasiCtx::new();
-        let host_memory = HostMemory::new();
+        let host_memory = HostMemory::new_uninit();
 
         let err = structs::return_pair_ints(&ctx, &host_memory, self.return_loc.ptr as i32);
 
@@ -399,7 +399,7 @@ impl ReturnPairPtrsExercise {
     }
     pub fn test(&self) {
         let ctx = WasiCtx::new();
-        let host_memory = HostMemory::new();
+        let host_memory = HostMemory::new_uninit();
 
         host_memory
             .ptr(self.input_first_loc.ptr)
@@ -500,7 +500,7 @@ impl SumArrayExercise {
     }
     pub fn test(&self) {
         let ctx = WasiCtx::new();
-        let host_memory = HostMemory::new();
+        let host_memory = HostMemory::new_uninit();
 
         // Write inputs to memory as an array
         for (ix, val) in self.inputs.iter().enumerate() {
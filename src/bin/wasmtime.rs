@@ -6,8 +6,12 @@
 use anyhow::Result;
 use structopt::{clap::AppSettings, clap::ErrorKind, StructOpt};
 use wasmtime_cli::commands::{
-    ConfigCommand, RunCommand, WasmToObjCommand, WastCommand, WASM2OBJ_AFTER_HELP,
+    BenchCommand, CompileCommand, CompletionCommand, ConfigCommand, ObjdumpCommand, ReplCommand,
+    RunCommand, ServeCommand, SettingsCommand, StripCommand, WasmToObjCommand, WastCommand,
+    BENCH_AFTER_HELP, COMPILE_AFTER_HELP, COMPLETION_AFTER_HELP, OBJDUMP_AFTER_HELP,
+    REPL_AFTER_HELP, SERVE_AFTER_HELP, SETTINGS_AFTER_HELP, STRIP_AFTER_HELP, WASM2OBJ_AFTER_HELP,
 };
+use wasmtime_cli::expand_flags_files;
 
 /// Wasmtime WebAssembly Runtime
 #[derive(StructOpt)]
@@ -32,14 +36,42 @@ use wasmtime_cli::commands::{
                   \n\
                   Invoking a specific function (e.g. `add`) in a WebAssembly module:\n\
                   \n  \
-                  wasmtime example.wasm --invoke add 1 2\n"
+                  wasmtime example.wasm --invoke add 1 2\n\
+                  \n\
+                  Reading arguments from a file, one per line:\n\
+                  \n  \
+                  wasmtime run @flags.txt example.wasm\n"
 )]
 enum WasmtimeApp {
     // !!! IMPORTANT: if subcommands are added or removed, update `parse_module` in `src/commands/run.rs`. !!!
+    /// Measures the compilation, instantiation, and call times of a WebAssembly module
+    #[structopt(name = "bench", after_help = BENCH_AFTER_HELP)]
+    Bench(BenchCommand),
+    /// Compiles a WebAssembly module ahead of time
+    #[structopt(name = "compile", after_help = COMPILE_AFTER_HELP)]
+    Compile(CompileCommand),
+    /// Generates shell completions for the `wasmtime` command
+    #[structopt(name = "completion", after_help = COMPLETION_AFTER_HELP)]
+    Completion(CompletionCommand),
     /// Controls Wasmtime configuration settings
     Config(ConfigCommand),
+    /// Prints the contents of a WebAssembly module or precompiled module
+    #[structopt(name = "objdump", after_help = OBJDUMP_AFTER_HELP)]
+    Objdump(ObjdumpCommand),
+    /// Interactively invokes the exports of a WebAssembly module
+    #[structopt(name = "repl", after_help = REPL_AFTER_HELP)]
+    Repl(ReplCommand),
     /// Runs a WebAssembly module
     Run(RunCommand),
+    /// Serves HTTP requests with a WebAssembly module
+    #[structopt(name = "serve", after_help = SERVE_AFTER_HELP)]
+    Serve(ServeCommand),
+    /// Prints the compiler settings, host CPU features, and WebAssembly proposals
+    #[structopt(name = "settings", after_help = SETTINGS_AFTER_HELP)]
+    Settings(SettingsCommand),
+    /// Removes custom sections from a WebAssembly module
+    #[structopt(name = "strip", after_help = STRIP_AFTER_HELP)]
+    Strip(StripCommand),
     /// Translates a WebAssembly module to native object file
     #[structopt(name = "wasm2obj", after_help = WASM2OBJ_AFTER_HELP)]
     WasmToObj(WasmToObjCommand),
@@ -51,8 +83,16 @@ impl WasmtimeApp {
     /// Executes the command.
     pub fn execute(&self) -> Result<()> {
         match self {
+            Self::Bench(c) => c.execute(),
+            Self::Compile(c) => c.execute(),
+            Self::Completion(c) => c.execute(Self::clap()),
             Self::Config(c) => c.execute(),
+            Self::Objdump(c) => c.execute(),
+            Self::Repl(c) => c.execute(),
             Self::Run(c) => c.execute(),
+            Self::Serve(c) => c.execute(),
+            Self::Settings(c) => c.execute(),
+            Self::Strip(c) => c.execute(),
             Self::WasmToObj(c) => c.execute(),
             Self::Wast(c) => c.execute(),
         }
@@ -60,14 +100,13 @@ impl WasmtimeApp {
 }
 
 fn main() -> Result<()> {
-    WasmtimeApp::from_iter_safe(std::env::args())
+    let args = expand_flags_files(&WasmtimeApp::clap(), std::env::args())?;
+    WasmtimeApp::from_iter_safe(&args)
         .unwrap_or_else(|e| match e.kind {
             ErrorKind::HelpDisplayed
             | ErrorKind::VersionDisplayed
             | ErrorKind::MissingArgumentOrSubcommand => e.exit(),
-            _ => WasmtimeApp::Run(
-                RunCommand::from_iter_safe(std::env::args()).unwrap_or_else(|_| e.exit()),
-            ),
+            _ => WasmtimeApp::Run(RunCommand::from_iter_safe(&args).unwrap_or_else(|_| e.exit())),
         })
         .execute()
 }
@@ -1,8 +1,19 @@
 //! The module for the Wasmtime CLI commands.
 
+mod bench;
+mod compile;
+mod completion;
 mod config;
+mod objdump;
+mod repl;
 mod run;
+mod serve;
+mod settings;
+mod strip;
 mod wasm2obj;
 mod wast;
 
-pub use self::{config::*, run::*, wasm2obj::*, wast::*};
+pub use self::{
+    bench::*, compile::*, completion::*, config::*, objdump::*, repl::*, run::*, serve::*,
+    settings::*, strip::*, wasm2obj::*, wast::*,
+};
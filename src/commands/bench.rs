@@ -0,0 +1,191 @@
+//! The module that implements the `wasmtime bench` command.
+
+use super::run::{parse_invoke_arg, populate_with_wasi};
+use crate::{init_file_per_thread_logger, CommonOptions};
+use anyhow::{bail, Context as _, Result};
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use structopt::{clap::AppSettings, StructOpt};
+use wasi_common::StdioMode;
+use wasmtime::{Engine, Func, Linker, Module, Store, Trap, Val};
+
+/// The after help text for the `bench` command.
+pub const BENCH_AFTER_HELP: &str =
+    "The module is compiled once, and then each iteration instantiates it in \
+     a fresh store and calls the function. The compilation time, and the \
+     mean, median, and 99th percentile of the instantiation and call times \
+     are reported. Warmup iterations run first and aren't measured.\n\
+     \n\
+     Pass `--disable-cache` to measure the compilation time without the \
+     compilation cache.\n\
+     \n\
+     Usage examples:\n\
+     \n\
+     Benchmarking a command module's `_start` function:\n\
+     \n  \
+     wasmtime bench example.wasm\n\
+     \n\
+     Benchmarking 1000 calls of a function `fib` with an argument:\n\
+     \n  \
+     wasmtime bench --invoke fib --iters 1000 example.wasm 20\n";
+
+/// Measures the compilation, instantiation, and call times of a WebAssembly module
+#[derive(StructOpt)]
+#[structopt(
+    name = "bench",
+    version = env!("CARGO_PKG_VERSION"),
+    setting = AppSettings::ColoredHelp,
+    setting = AppSettings::TrailingVarArg,
+    after_help = BENCH_AFTER_HELP,
+)]
+pub struct BenchCommand {
+    #[structopt(flatten)]
+    common: CommonOptions,
+
+    /// The name of the function to call; defaults to `_start`
+    #[structopt(long, value_name = "FUNCTION")]
+    invoke: Option<String>,
+
+    /// The number of measured iterations
+    #[structopt(long, value_name = "N", default_value = "10")]
+    iters: usize,
+
+    /// The number of unmeasured iterations to run first
+    #[structopt(long, value_name = "N", default_value = "1")]
+    warmup: usize,
+
+    /// The path of the WebAssembly module to benchmark
+    #[structopt(index = 1, value_name = "MODULE", parse(from_os_str))]
+    module: PathBuf,
+
+    // NOTE: this must come last for trailing varargs
+    /// The arguments to the function, or to the module for `_start`
+    #[structopt(value_name = "ARGS")]
+    args: Vec<String>,
+}
+
+impl BenchCommand {
+    /// Executes the command.
+    pub fn execute(&self) -> Result<()> {
+        if self.common.log_to_files {
+            let prefix = "wasmtime.dbg.";
+            init_file_per_thread_logger(prefix);
+        } else {
+            pretty_env_logger::init();
+        }
+
+        if self.iters == 0 {
+            bail!("`--iters` must be at least 1");
+        }
+
+        let config = self.common.config(None)?;
+        let engine = Engine::new(&config);
+
+        let bytes = fs::read(&self.module)
+            .with_context(|| format!("failed to read `{}`", self.module.display()))?;
+        let wasm = wat::parse_bytes(&bytes)?;
+        let start = Instant::now();
+        let module = Module::new(&engine, &wasm)
+            .with_context(|| format!("failed to compile `{}`", self.module.display()))?;
+        let compilation = start.elapsed();
+
+        for _ in 0..self.warmup {
+            self.iteration(&module)?;
+        }
+        let mut instantiations = Vec::with_capacity(self.iters);
+        let mut calls = Vec::with_capacity(self.iters);
+        for _ in 0..self.iters {
+            let (instantiation, call) = self.iteration(&module)?;
+            instantiations.push(instantiation);
+            calls.push(call);
+        }
+
+        let name = self.invoke.as_deref().unwrap_or("_start");
+        println!("{:<16} {:.2?}", "compilation:", compilation);
+        print_stats("instantiation:", &mut instantiations);
+        print_stats(&format!("call `{}`:", name), &mut calls);
+        Ok(())
+    }
+
+    /// Instantiates the module in a fresh store and calls the function,
+    /// returning how long each of these took.
+    fn iteration(&self, module: &Module) -> Result<(Duration, Duration)> {
+        let store = Store::new(module.engine());
+        let mut linker = Linker::new(&store);
+        let mut argv = vec![self.module.display().to_string()];
+        if self.invoke.is_none() {
+            argv.extend(self.args.iter().cloned());
+        }
+        populate_with_wasi(&mut linker, &[], &[], &argv, &[], StdioMode::Text)?;
+
+        let start = Instant::now();
+        let instance = linker.instantiate(module)?;
+        if self.invoke.is_some() {
+            // Initialize reactors, as `wasmtime run` does.
+            if let Some(init) = instance.get_func("_initialize") {
+                call_func(&init, &[])?;
+            }
+        }
+        let instantiation = start.elapsed();
+
+        let name = self.invoke.as_deref().unwrap_or("_start");
+        let func = match instance.get_func(name) {
+            Some(func) => func,
+            None => bail!("module has no function export `{}`", name),
+        };
+        let mut values = Vec::new();
+        if self.invoke.is_some() {
+            let ty = func.ty();
+            if self.args.len() != ty.params().len() {
+                bail!(
+                    "`{}` takes {} arguments, but {} were given",
+                    name,
+                    ty.params().len(),
+                    self.args.len()
+                );
+            }
+            for (ty, arg) in ty.params().zip(self.args.iter()) {
+                values.push(parse_invoke_arg(&ty, arg)?);
+            }
+        }
+
+        let start = Instant::now();
+        call_func(&func, &values).with_context(|| format!("failed to invoke `{}`", name))?;
+        let call = start.elapsed();
+
+        Ok((instantiation, call))
+    }
+}
+
+/// Calls `func`, treating a WASI exit with status 0 as a normal return.
+fn call_func(func: &Func, values: &[Val]) -> Result<()> {
+    match func.call(values) {
+        Ok(_) => Ok(()),
+        Err(e) => match e.downcast_ref::<Trap>().and_then(Trap::i32_exit_status) {
+            Some(0) => Ok(()),
+            Some(status) => bail!("exited with status {}", status),
+            None => Err(e),
+        },
+    }
+}
+
+fn print_stats(label: &str, samples: &mut [Duration]) {
+    samples.sort();
+    let total: Duration = samples.iter().sum();
+    let mean = total / samples.len() as u32;
+    println!(
+        "{:<16} mean {:.2?}, p50 {:.2?}, p99 {:.2?} ({} iterations)",
+        label,
+        mean,
+        percentile(samples, 50),
+        percentile(samples, 99),
+        samples.len()
+    );
+}
+
+/// Returns the nearest-rank percentile of the sorted `samples`.
+fn percentile(samples: &[Duration], p: usize) -> Duration {
+    let rank = (p * samples.len() + 99) / 100;
+    samples[rank.max(1) - 1]
+}
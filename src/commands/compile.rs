@@ -0,0 +1,91 @@
+//! The module that implements the `wasmtime compile` command.
+
+use crate::{emit::EmitOptions, init_file_per_thread_logger, CommonOptions};
+use anyhow::{Context as _, Result};
+use std::fs;
+use std::path::PathBuf;
+use structopt::{clap::AppSettings, StructOpt};
+use wasmtime::Engine;
+
+/// The after help text for the `compile` command.
+pub const COMPILE_AFTER_HELP: &str =
+    "By default, no CPU features or presets will be enabled for the compilation.\n\
+     \n\
+     Usage examples:\n\
+     \n\
+     Compiling a WebAssembly module for the current platform:\n\
+     \n  \
+     wasmtime compile example.wasm\n\
+     \n\
+     Specifying the output file:\n\
+     \n  \
+     wasmtime compile -o output.cwasm input.wasm\n\
+     \n\
+     Compiling for a specific platform (Linux) and CPU preset (Skylake):\n\
+     \n  \
+     wasmtime compile --target x86_64-unknown-linux --cranelift-flags skylake=true foo.wasm\n\
+     \n\
+     Writing the Cranelift IR of the function named `add` to the `clif` directory:\n\
+     \n  \
+     wasmtime compile --emit-clif clif --emit-func add foo.wasm\n";
+
+/// Compiles a WebAssembly module
+#[derive(StructOpt)]
+#[structopt(
+    name = "compile",
+    version = env!("CARGO_PKG_VERSION"),
+    setting = AppSettings::ColoredHelp,
+    after_help = COMPILE_AFTER_HELP,
+)]
+pub struct CompileCommand {
+    #[structopt(flatten)]
+    common: CommonOptions,
+
+    #[structopt(flatten)]
+    emit: EmitOptions,
+
+    /// The target triple; default is the host triple
+    #[structopt(long, value_name = "TARGET")]
+    target: Option<String>,
+
+    /// The path of the output compiled module; defaults to <MODULE>.cwasm
+    #[structopt(short = "o", long, value_name = "OUTPUT", parse(from_os_str))]
+    output: Option<PathBuf>,
+
+    /// The path of the WebAssembly to compile
+    #[structopt(index = 1, value_name = "MODULE", parse(from_os_str))]
+    module: PathBuf,
+}
+
+impl CompileCommand {
+    /// Executes the command.
+    pub fn execute(&self) -> Result<()> {
+        if self.common.log_to_files {
+            let prefix = "wasmtime.dbg.";
+            init_file_per_thread_logger(prefix);
+        } else {
+            pretty_env_logger::init();
+        }
+
+        let config = self.common.config(self.target.as_deref())?;
+        let engine = Engine::new(&config);
+        self.emit.emit(&engine, &self.module)?;
+
+        let input = fs::read(&self.module)
+            .with_context(|| format!("failed to read input file '{}'", self.module.display()))?;
+
+        let output = self.output.clone().unwrap_or_else(|| {
+            let mut output: PathBuf = self.module.file_name().unwrap().into();
+            output.set_extension("cwasm");
+            output
+        });
+
+        let compiled = engine
+            .precompile_module(&input)
+            .with_context(|| format!("failed to compile '{}'", self.module.display()))?;
+        fs::write(&output, compiled)
+            .with_context(|| format!("failed to write output file '{}'", output.display()))?;
+
+        Ok(())
+    }
+}
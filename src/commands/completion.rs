@@ -0,0 +1,49 @@
+//! The module that implements the `wasmtime completion` command.
+
+use anyhow::Result;
+use std::io;
+use structopt::{
+    clap::{App, AppSettings, Shell},
+    StructOpt,
+};
+
+/// The after help text for the `completion` command.
+pub const COMPLETION_AFTER_HELP: &str = "The completions are written to standard output.\n\
+     \n\
+     Usage examples:\n\
+     \n\
+     Enabling completions in the current bash session:\n\
+     \n  \
+     source <(wasmtime completion bash)\n\
+     \n\
+     Installing completions for zsh:\n\
+     \n  \
+     wasmtime completion zsh > ~/.zfunc/_wasmtime\n";
+
+/// Generates shell completions for the `wasmtime` command
+#[derive(StructOpt)]
+#[structopt(
+    name = "completion",
+    version = env!("CARGO_PKG_VERSION"),
+    setting = AppSettings::ColoredHelp,
+    after_help = COMPLETION_AFTER_HELP,
+)]
+pub struct CompletionCommand {
+    /// The shell to generate completions for
+    #[structopt(
+        index = 1,
+        value_name = "SHELL",
+        possible_values = &Shell::variants(),
+        case_insensitive = true,
+    )]
+    shell: Shell,
+}
+
+impl CompletionCommand {
+    /// Executes the command, generating completions for `app`, the top-level
+    /// `wasmtime` command.
+    pub fn execute(&self, mut app: App) -> Result<()> {
+        app.gen_completions_to("wasmtime", self.shell, &mut io::stdout());
+        Ok(())
+    }
+}
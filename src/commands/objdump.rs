@@ -0,0 +1,275 @@
+//! The module that implements the `wasmtime objdump` command.
+
+use super::repl::format_extern_type;
+use crate::{init_file_per_thread_logger, CommonOptions};
+use anyhow::{bail, Context as _, Result};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use structopt::{clap::AppSettings, StructOpt};
+use wasmparser::{BinaryReader, Chunk, Name, NameSectionReader, Naming, Parser, Payload};
+use wasmtime::{Engine, ExternType, Module};
+
+/// The after help text for the `objdump` command.
+pub const OBJDUMP_AFTER_HELP: &str =
+    "The input may be a WebAssembly module, in binary or text format, or a \
+     module precompiled with `wasmtime compile`. For WebAssembly modules the \
+     sizes of the function bodies in the binary are shown, and for \
+     precompiled modules the sizes of the native code of each function.\n\
+     \n\
+     Precompiled modules are loaded with the configuration given on the \
+     command line, which must match the one they were compiled with.\n\
+     \n\
+     Usage examples:\n\
+     \n  \
+     wasmtime objdump example.wasm\n\
+     \n  \
+     wasmtime objdump example.cwasm\n";
+
+/// Prints the contents of a WebAssembly module or precompiled module
+#[derive(StructOpt)]
+#[structopt(
+    name = "objdump",
+    version = env!("CARGO_PKG_VERSION"),
+    setting = AppSettings::ColoredHelp,
+    after_help = OBJDUMP_AFTER_HELP,
+)]
+pub struct ObjdumpCommand {
+    #[structopt(flatten)]
+    common: CommonOptions,
+
+    /// The path of the module to inspect
+    #[structopt(index = 1, value_name = "MODULE", parse(from_os_str))]
+    module: PathBuf,
+}
+
+impl ObjdumpCommand {
+    /// Executes the command.
+    pub fn execute(&self) -> Result<()> {
+        if self.common.log_to_files {
+            let prefix = "wasmtime.dbg.";
+            init_file_per_thread_logger(prefix);
+        } else {
+            pretty_env_logger::init();
+        }
+
+        let config = self.common.config(None)?;
+        let engine = Engine::new(&config);
+
+        let bytes = fs::read(&self.module)
+            .with_context(|| format!("failed to read `{}`", self.module.display()))?;
+        let precompiled = !bytes.starts_with(b"\0asm") && std::str::from_utf8(&bytes).is_err();
+
+        if precompiled {
+            if !Module::is_compatible(&engine, &bytes)? {
+                bail!(
+                    "`{}` was precompiled with a different Wasmtime version, target, \
+                     or configuration",
+                    self.module.display()
+                );
+            }
+            let module = Module::deserialize(&engine, &bytes)?;
+            println!(
+                "{}: precompiled module, {} bytes",
+                self.module.display(),
+                bytes.len()
+            );
+            print_imports_exports(&module);
+            print_native_sizes(&module);
+        } else {
+            let wasm = wat::parse_bytes(&bytes)?;
+            let module = Module::new(&engine, &wasm)
+                .with_context(|| format!("failed to compile `{}`", self.module.display()))?;
+            println!(
+                "{}: WebAssembly module, {} bytes",
+                self.module.display(),
+                wasm.len()
+            );
+            let sections = sections(&wasm)?;
+            print_sections(&sections);
+            print_imports_exports(&module);
+            print_body_sizes(&module, &wasm)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A top-level section of a WebAssembly binary.
+struct Section<'a> {
+    id: u8,
+    /// The offset of the section's contents in the binary.
+    offset: usize,
+    /// The size of the section's contents, excluding its id and size.
+    size: usize,
+    /// For custom sections, the name of the section.
+    custom: Option<&'a str>,
+}
+
+fn sections(wasm: &[u8]) -> Result<Vec<Section<'_>>> {
+    let mut parser = Parser::new(0);
+    let mut ret = Vec::new();
+    let mut pos = 0;
+    loop {
+        let (consumed, payload) = match parser.parse(&wasm[pos..], true)? {
+            Chunk::Parsed { consumed, payload } => (consumed, payload),
+            Chunk::NeedMoreData(_) => unreachable!(),
+        };
+        let start = pos;
+        pos += consumed;
+        let custom = match payload {
+            Payload::Version { .. } => continue,
+            Payload::End => return Ok(ret),
+            // Skip over the entries of code sections, including any nested
+            // modules.
+            Payload::CodeSectionStart { range, .. }
+            | Payload::ModuleCodeSectionStart { range, .. } => {
+                parser.skip_section();
+                pos = range.end;
+                None
+            }
+            Payload::CustomSection { name, .. } => Some(name),
+            _ => None,
+        };
+        // The section's id is followed by the size of its contents.
+        let mut header = BinaryReader::new_with_offset(&wasm[start + 1..pos], start + 1);
+        let size = header.read_var_u32()? as usize;
+        ret.push(Section {
+            id: wasm[start],
+            offset: header.original_position(),
+            size,
+            custom,
+        });
+    }
+}
+
+fn section_name(id: u8) -> &'static str {
+    match id {
+        0 => "custom",
+        1 => "type",
+        2 => "import",
+        3 => "function",
+        4 => "table",
+        5 => "memory",
+        6 => "global",
+        7 => "export",
+        8 => "start",
+        9 => "element",
+        10 => "code",
+        11 => "data",
+        12 => "datacount",
+        13 => "event",
+        14 => "module",
+        15 => "instance",
+        16 => "alias",
+        _ => "unknown",
+    }
+}
+
+fn print_sections(sections: &[Section<'_>]) {
+    println!("\nsections:");
+    println!(
+        "  {:>2}  {:<10}  {:>10}  {:>10}",
+        "id", "name", "offset", "size"
+    );
+    for section in sections {
+        let name = match section.custom {
+            Some(name) => format!("custom \"{}\"", name),
+            None => section_name(section.id).to_string(),
+        };
+        println!(
+            "  {:>2}  {:<10}  {:#010x}  {:>10}",
+            section.id, name, section.offset, section.size
+        );
+    }
+}
+
+fn print_imports_exports(module: &Module) {
+    println!("\nimports:");
+    for import in module.imports() {
+        let name = match import.name() {
+            Some(name) => format!("{}::{}", import.module(), name),
+            None => import.module().to_string(),
+        };
+        println!("  {}: {}", name, format_extern_type(&import.ty()));
+    }
+
+    println!("\nexports:");
+    for export in module.exports() {
+        println!("  {}: {}", export.name(), format_extern_type(&export.ty()));
+    }
+}
+
+fn print_native_sizes(module: &Module) {
+    println!("\nfunctions:");
+    println!("  {:>6}  {:>10}  name", "index", "code size");
+    for func in module.function_sizes() {
+        print_function(func.module_index(), func.index(), func.size(), func.name());
+    }
+}
+
+fn print_body_sizes(module: &Module, wasm: &[u8]) -> Result<()> {
+    let num_imported_funcs = module
+        .imports()
+        .filter(|i| matches!(i.ty(), ExternType::Func(_)))
+        .count() as u32;
+
+    // Only the top-level module's functions are listed, and their names come
+    // from the name section, which follows the code section.
+    let mut sizes = Vec::new();
+    let mut names = HashMap::new();
+    let mut depth = 0;
+    for payload in Parser::new(0).parse_all(wasm) {
+        match payload? {
+            Payload::ModuleCodeSectionEntry { .. } => depth += 1,
+            Payload::End if depth > 0 => depth -= 1,
+            _ if depth > 0 => {}
+            Payload::CodeSectionEntry(body) => sizes.push(body.range().len()),
+            Payload::CustomSection {
+                name: "name",
+                data,
+                data_offset,
+            } => {
+                // The name section is only informational, so don't fail on a
+                // malformed one.
+                names = function_names(data, data_offset).unwrap_or_default();
+            }
+            _ => {}
+        }
+    }
+
+    println!("\nfunctions:");
+    println!("  {:>6}  {:>10}  name", "index", "body size");
+    for (i, size) in sizes.into_iter().enumerate() {
+        let index = num_imported_funcs + i as u32;
+        print_function(0, index, size, names.get(&index).copied());
+    }
+    Ok(())
+}
+
+fn print_function(module_index: usize, index: u32, size: usize, name: Option<&str>) {
+    let index = if module_index == 0 {
+        index.to_string()
+    } else {
+        format!("{}:{}", module_index, index)
+    };
+    match name {
+        Some(name) => println!("  {:>6}  {:>10}  ${}", index, size, name),
+        None => println!("  {:>6}  {:>10}", index, size),
+    }
+}
+
+/// Reads the function names out of the contents of a `name` custom section.
+fn function_names(data: &[u8], offset: usize) -> Result<HashMap<u32, &str>> {
+    let mut names = HashMap::new();
+    for subsection in NameSectionReader::new(data, offset)? {
+        if let Name::Function(f) = subsection? {
+            let mut map = f.get_map()?;
+            for _ in 0..map.get_count() {
+                let Naming { index, name } = map.read()?;
+                names.insert(index, name);
+            }
+        }
+    }
+    Ok(names)
+}
@@ -0,0 +1,368 @@
+//! The module that implements the `wasmtime repl` command.
+
+use crate::{init_file_per_thread_logger, CommonOptions};
+use anyhow::{anyhow, bail, Context as _, Result};
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+use structopt::{clap::AppSettings, StructOpt};
+use wasi_common::StdioMode;
+use wasmtime::{
+    Engine, Extern, ExternType, Func, Instance, Linker, Module, Mutability, Store, Val, ValType,
+};
+
+/// The after help text for the `repl` command.
+pub const REPL_AFTER_HELP: &str =
+    "The module is instantiated once, after which commands are read from \
+     standard input, one per line. Type `help` at the prompt for the list \
+     of commands.\n\
+     \n\
+     Usage examples:\n\
+     \n\
+     Exploring a WebAssembly module interactively:\n\
+     \n  \
+     wasmtime repl example.wasm\n\
+     \n\
+     Scripting a sequence of calls:\n\
+     \n  \
+     echo 'add 1 2' | wasmtime repl example.wasm\n";
+
+const REPL_HELP: &str = "\
+Commands:
+  <EXPORT> [ARGS...]            Invoke an exported function (same as `call`)
+  call <EXPORT> [ARGS...]       Invoke an exported function
+  exports                       List the exports of the current instance
+  global <EXPORT>               Print the value of an exported global
+  memory <EXPORT> <OFFSET> [LEN]
+                                Dump LEN bytes (default 16) of an exported memory
+  link <NAME> <MODULE>          Instantiate another module and make its exports
+                                importable under the module name NAME
+  use <NAME>                    Switch the current instance to a linked instance;
+                                `main` is the instance given on the command line
+  instances                     List the instances that have been linked
+  help                          Print this message
+  quit                          Exit the REPL
+
+Integer arguments may be written in decimal or in hexadecimal with a `0x` prefix.";
+
+/// Interactively invokes the exports of a WebAssembly module
+#[derive(StructOpt)]
+#[structopt(
+    name = "repl",
+    version = env!("CARGO_PKG_VERSION"),
+    setting = AppSettings::ColoredHelp,
+    after_help = REPL_AFTER_HELP,
+)]
+pub struct ReplCommand {
+    #[structopt(flatten)]
+    common: CommonOptions,
+
+    /// The path of the WebAssembly module to instantiate
+    #[structopt(index = 1, value_name = "MODULE", parse(from_os_str))]
+    module: PathBuf,
+}
+
+impl ReplCommand {
+    /// Executes the command.
+    pub fn execute(&self) -> Result<()> {
+        if self.common.log_to_files {
+            let prefix = "wasmtime.dbg.";
+            init_file_per_thread_logger(prefix);
+        } else {
+            pretty_env_logger::init();
+        }
+
+        let config = self.common.config(None)?;
+        let engine = Engine::new(&config);
+        let store = Store::new(&engine);
+
+        let argv = vec![self.module.display().to_string()];
+        let mut linker = Linker::new(&store);
+        linker.allow_shadowing(true);
+        super::run::populate_with_wasi(&mut linker, &[], &[], &argv, &[], StdioMode::Text)?;
+
+        let mut repl = Repl {
+            linker,
+            instances: Vec::new(),
+            current: 0,
+        };
+        repl.link("main", &self.module)?;
+
+        let stdin = io::stdin();
+        let mut lines = stdin.lock().lines();
+        loop {
+            eprint!("> ");
+            io::stderr().flush()?;
+            let line = match lines.next() {
+                Some(line) => line?,
+                None => break,
+            };
+            let words = line.split_whitespace().collect::<Vec<_>>();
+            match words.as_slice() {
+                [] => {}
+                ["quit"] | ["exit"] => break,
+                words => {
+                    if let Err(e) = repl.execute(words) {
+                        eprintln!("error: {:?}", e);
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The state of a REPL session.
+struct Repl {
+    linker: Linker,
+    /// All instances created so far, by the name they were linked under.
+    instances: Vec<(String, Instance)>,
+    /// Index into `instances` of the instance whose exports commands refer to.
+    current: usize,
+}
+
+impl Repl {
+    fn execute(&mut self, words: &[&str]) -> Result<()> {
+        match words {
+            ["help"] => println!("{}", REPL_HELP),
+            ["exports"] => self.print_exports(),
+            ["instances"] => {
+                for (i, (name, _)) in self.instances.iter().enumerate() {
+                    let marker = if i == self.current { "*" } else { " " };
+                    println!("{} {}", marker, name);
+                }
+            }
+            ["global", name] => {
+                let global = self
+                    .export(name)?
+                    .into_global()
+                    .ok_or_else(|| anyhow!("export `{}` is not a global", name))?;
+                println!("{}", format_val(&global.get()));
+            }
+            ["memory", name, offset] => self.dump_memory(name, offset, "16")?,
+            ["memory", name, offset, len] => self.dump_memory(name, offset, len)?,
+            ["link", name, path] => {
+                self.link(name, Path::new(path))?;
+                self.current = self.instances.len() - 1;
+            }
+            ["use", name] => {
+                self.current = self
+                    .instances
+                    .iter()
+                    .position(|(n, _)| n == name)
+                    .ok_or_else(|| anyhow!("no instance named `{}`", name))?;
+            }
+            ["call", name, args @ ..] => self.call(name, args)?,
+            [name, args @ ..] => self.call(name, args)?,
+            [] => {}
+        }
+        Ok(())
+    }
+
+    /// Instantiates the module at `path` and registers its exports in the
+    /// linker under the module name `name`.
+    fn link(&mut self, name: &str, path: &Path) -> Result<()> {
+        let module = Module::from_file(self.linker.store().engine(), path)?;
+        let instance = self
+            .linker
+            .instantiate(&module)
+            .with_context(|| format!("failed to instantiate `{}`", path.display()))?;
+        self.linker.instance(name, &instance)?;
+        match self.instances.iter().position(|(n, _)| n == name) {
+            Some(i) => self.instances[i].1 = instance,
+            None => self.instances.push((name.to_string(), instance)),
+        }
+        Ok(())
+    }
+
+    fn export(&self, name: &str) -> Result<Extern> {
+        let (instance_name, instance) = &self.instances[self.current];
+        instance
+            .get_export(name)
+            .ok_or_else(|| anyhow!("`{}` has no export named `{}`", instance_name, name))
+    }
+
+    fn print_exports(&self) {
+        for export in self.instances[self.current].1.exports() {
+            println!("{}: {}", export.name(), format_extern_type(&export.ty()));
+        }
+    }
+
+    fn call(&self, name: &str, args: &[&str]) -> Result<()> {
+        let func = match self.export(name) {
+            Ok(Extern::Func(func)) => func,
+            Ok(_) => bail!("export `{}` is not a function", name),
+            // Give a hint for the common case of mistyping a command.
+            Err(e) => return Err(e.context("unknown command; type `help` for a list")),
+        };
+        let values = parse_args(&func, args)?;
+        let results = func
+            .call(&values)
+            .with_context(|| format!("failed to invoke `{}`", name))?;
+        for result in results.iter() {
+            println!("{}", format_val(result));
+        }
+        Ok(())
+    }
+
+    fn dump_memory(&self, name: &str, offset: &str, len: &str) -> Result<()> {
+        let memory = self
+            .export(name)?
+            .into_memory()
+            .ok_or_else(|| anyhow!("export `{}` is not a memory", name))?;
+        let offset = parse_int(offset)? as usize;
+        let len = parse_int(len)? as usize;
+        // Safety: the slice is only used before any wasm code runs again, so
+        // it can't be invalidated by the memory growing.
+        let data = unsafe { memory.data_unchecked() };
+        let bytes = offset
+            .checked_add(len)
+            .and_then(|end| data.get(offset..end))
+            .ok_or_else(|| {
+                anyhow!(
+                    "range {:#x}..{:#x} is out of bounds of a memory of {:#x} bytes",
+                    offset,
+                    offset.saturating_add(len),
+                    data.len()
+                )
+            })?;
+        for (i, chunk) in bytes.chunks(16).enumerate() {
+            let hex = chunk
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect::<Vec<_>>()
+                .join(" ");
+            let ascii = chunk
+                .iter()
+                .map(|&b| {
+                    if b.is_ascii_graphic() || b == b' ' {
+                        b as char
+                    } else {
+                        '.'
+                    }
+                })
+                .collect::<String>();
+            println!("{:08x}  {:<47}  |{}|", offset + i * 16, hex, ascii);
+        }
+        Ok(())
+    }
+}
+
+/// Parses `args` according to the parameter types of `func`.
+fn parse_args(func: &Func, args: &[&str]) -> Result<Vec<Val>> {
+    let ty = func.ty();
+    if args.len() != ty.params().len() {
+        bail!(
+            "expected {} argument(s) but {} were given",
+            ty.params().len(),
+            args.len()
+        );
+    }
+    ty.params()
+        .zip(args)
+        .map(|(ty, arg)| parse_val(&ty, arg))
+        .collect()
+}
+
+/// Parses an argument of type `ty`. Integers may be written in decimal or in
+/// hexadecimal, and references can only be `null`.
+pub(super) fn parse_val(ty: &ValType, arg: &str) -> Result<Val> {
+    Ok(match ty {
+        ValType::I32 => Val::I32(parse_int(arg, 32)? as i32),
+        ValType::I64 => Val::I64(parse_int(arg, 64)? as i64),
+        ValType::F32 => Val::F32(arg.parse::<f32>()?.to_bits()),
+        ValType::F64 => Val::F64(arg.parse::<f64>()?.to_bits()),
+        ValType::V128 => Val::V128(parse_v128(arg)?),
+        ValType::ExternRef if arg == "null" => Val::ExternRef(None),
+        ValType::FuncRef if arg == "null" => Val::FuncRef(None),
+        t => bail!("unsupported argument `{}` of type {}", arg, t),
+    })
+}
+
+/// Parses a decimal or `0x`-prefixed hexadecimal integer of `bits` bits,
+/// which may be negative.
+///
+/// Decimal integers must be in the signed range of the type, while positive
+/// hexadecimal ones may also use the unsigned range, as in `0xffffffff` for
+/// an `i32` of -1.
+fn parse_int(s: &str, bits: u32) -> Result<i128> {
+    let (negative, digits) = match s.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, s),
+    };
+    let (value, hex) = match digits.strip_prefix("0x") {
+        Some(hex) => (i128::from_str_radix(hex, 16), true),
+        None => (digits.parse(), false),
+    };
+    let value = value.with_context(|| format!("invalid integer `{}`", s))?;
+    let value = if negative { -value } else { value };
+
+    let min = -(1 << (bits - 1));
+    let max = if hex && !negative {
+        (1 << bits) - 1
+    } else {
+        (1 << (bits - 1)) - 1
+    };
+    if value < min || value > max {
+        bail!("integer `{}` is out of range for i{}", s, bits);
+    }
+    Ok(value)
+}
+
+/// Parses a `v128` written as a decimal or `0x`-prefixed hexadecimal integer.
+fn parse_v128(s: &str) -> Result<u128> {
+    match s.strip_prefix("0x") {
+        Some(hex) => u128::from_str_radix(hex, 16),
+        None => s.parse(),
+    }
+    .with_context(|| format!("invalid v128 `{}`", s))
+}
+
+fn format_val(val: &Val) -> String {
+    match val {
+        Val::I32(i) => format!("{}: i32", i),
+        Val::I64(i) => format!("{}: i64", i),
+        Val::F32(f) => format!("{}: f32", f32::from_bits(*f)),
+        Val::F64(f) => format!("{}: f64", f64::from_bits(*f)),
+        Val::V128(v) => format!("{:#034x}: v128", v),
+        Val::ExternRef(None) | Val::FuncRef(None) => format!("null: {}", val.ty()),
+        Val::ExternRef(Some(_)) | Val::FuncRef(Some(_)) => format!("<{}>", val.ty()),
+    }
+}
+
+pub(super) fn format_extern_type(ty: &ExternType) -> String {
+    let list = |tys: &mut dyn Iterator<Item = ValType>| {
+        tys.map(|t| t.to_string()).collect::<Vec<_>>().join(" ")
+    };
+    match ty {
+        ExternType::Func(f) => format!(
+            "func ({}) -> ({})",
+            list(&mut f.params()),
+            list(&mut f.results())
+        ),
+        ExternType::Global(g) => match g.mutability() {
+            Mutability::Const => format!("global {}", g.content()),
+            Mutability::Var => format!("global mut {}", g.content()),
+        },
+        ExternType::Table(t) => format!(
+            "table {} {}",
+            format_limits(t.limits().min(), t.limits().max()),
+            t.element()
+        ),
+        ExternType::Memory(m) => {
+            format!(
+                "memory {}",
+                format_limits(m.limits().min(), m.limits().max())
+            )
+        }
+        ExternType::Instance(_) => "instance".to_string(),
+        ExternType::Module(_) => "module".to_string(),
+    }
+}
+
+fn format_limits(min: u32, max: Option<u32>) -> String {
+    match max {
+        Some(max) => format!("{} {}", min, max),
+        None => min.to_string(),
+    }
+}
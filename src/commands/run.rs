@@ -1,39 +1,55 @@
 //! The module that implements the `wasmtime run` command.
 
-use crate::{init_file_per_thread_logger, CommonOptions};
+use crate::{emit::EmitOptions, init_file_per_thread_logger, CommonOptions};
 use anyhow::{bail, Context as _, Result};
+use serde_json::json;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use std::{
+    env,
     ffi::{OsStr, OsString},
-    fs::File,
-    path::{Component, PathBuf},
-    process,
+    fs::{self, File},
+    path::{Component, Path, PathBuf},
+    process, str,
 };
 use structopt::{clap::AppSettings, StructOpt};
-use wasi_common::{preopen_dir, WasiCtxBuilder};
-use wasmtime::{Engine, Func, Linker, Module, Store, Trap, Val, ValType};
+use wasi_common::{preopen_dir, StdioMode, WasiCtxBuilder};
+use wasmtime::{
+    Engine, FrameInfo, Func, Linker, Module, ProfilingStrategy, Store, StoreLimits, Trap, TrapCode,
+    Val, ValType, WasmCoreDump,
+};
 use wasmtime_wasi::Wasi;
 
 #[cfg(feature = "wasi-nn")]
 use wasmtime_wasi_nn::{WasiNn, WasiNnCtx};
 
+/// Exit status used when the guest is interrupted by `--wasm-timeout`.
+const TIMEOUT_EXIT_STATUS: i32 = 124;
+
+/// How often `--watch` checks the modules for changes.
+const WATCH_INTERVAL: Duration = Duration::from_millis(500);
+
 fn parse_module(s: &OsStr) -> Result<PathBuf, OsString> {
     // Do not accept wasmtime subcommand names as the module name
     match s.to_str() {
-        Some("help") | Some("config") | Some("run") | Some("wasm2obj") | Some("wast") => {
+        Some("help") | Some("bench") | Some("compile") | Some("completion") | Some("config")
+        | Some("objdump") | Some("repl") | Some("run") | Some("serve") | Some("settings")
+        | Some("strip") | Some("wasm2obj") | Some("wast") => {
             Err("module name cannot be the same as a subcommand".into())
         }
         _ => Ok(s.into()),
     }
 }
 
-fn parse_env_var(s: &str) -> Result<(String, String)> {
+fn parse_env_var(s: &str) -> Result<(String, Option<String>)> {
     let parts: Vec<_> = s.splitn(2, '=').collect();
-    if parts.len() != 2 {
-        bail!("must be of the form `key=value`");
+    if parts[0].is_empty() {
+        bail!("must be of the form `key=value`, `key` or `prefix*`");
     }
-    Ok((parts[0].to_owned(), parts[1].to_owned()))
+    Ok((parts[0].to_owned(), parts.get(1).map(|s| (*s).to_owned())))
 }
 
 fn parse_map_dirs(s: &str) -> Result<(String, String)> {
@@ -44,7 +60,7 @@ fn parse_map_dirs(s: &str) -> Result<(String, String)> {
     Ok((parts[0].into(), parts[1].into()))
 }
 
-fn parse_dur(s: &str) -> Result<Duration> {
+pub(super) fn parse_dur(s: &str) -> Result<Duration> {
     // assume an integer without a unit specified is a number of seconds ...
     if let Ok(val) = s.parse() {
         return Ok(Duration::from_secs(val));
@@ -62,6 +78,64 @@ fn parse_preloads(s: &str) -> Result<(String, PathBuf)> {
     Ok((parts[0].into(), parts[1].into()))
 }
 
+/// Default path template for `--coredump-on-trap`.
+const DEFAULT_COREDUMP_PATH: &str = "{module}.{time}.coredump";
+
+/// How `--profile` should profile the guest.
+enum Profile {
+    /// Use one of the profilers built into `Config`.
+    Native(ProfilingStrategy),
+    /// Sample the wasm stack every `interval` units of fuel and write the
+    /// samples to `path` in the collapsed-stack format.
+    Guest { path: PathBuf, interval: u64 },
+}
+
+fn parse_profile(s: &str) -> Result<Profile> {
+    let parts: Vec<&str> = s.split(',').collect();
+    match parts.as_slice() {
+        ["jitdump"] => Ok(Profile::Native(ProfilingStrategy::JitDump)),
+        ["vtune"] => Ok(Profile::Native(ProfilingStrategy::VTune)),
+        ["guest", rest @ ..] if rest.len() <= 2 => {
+            let path = rest.get(0).copied().unwrap_or("wasmtime-guest.folded");
+            let interval = match rest.get(1) {
+                Some(s) => s.parse().context("invalid sampling interval")?,
+                None => 10_000,
+            };
+            if interval == 0 {
+                bail!("the sampling interval must be greater than zero");
+            }
+            Ok(Profile::Guest {
+                path: path.into(),
+                interval,
+            })
+        }
+        _ => bail!("must be `jitdump`, `vtune`, or `guest[,PATH[,INTERVAL]]`"),
+    }
+}
+
+/// The format of the diagnostics printed by `wasmtime run`.
+#[derive(PartialEq)]
+enum MessageFormat {
+    Human,
+    Json,
+}
+
+fn parse_message_format(s: &str) -> Result<MessageFormat> {
+    match s {
+        "human" => Ok(MessageFormat::Human),
+        "json" => Ok(MessageFormat::Json),
+        _ => bail!("must be `human` or `json`"),
+    }
+}
+
+fn parse_stdio_mode(s: &str) -> Result<StdioMode> {
+    match s {
+        "text" => Ok(StdioMode::Text),
+        "raw" => Ok(StdioMode::Raw),
+        _ => bail!("must be `text` or `raw`"),
+    }
+}
+
 /// Runs a WebAssembly module
 #[derive(StructOpt)]
 #[structopt(name = "run", setting = AppSettings::TrailingVarArg)]
@@ -69,15 +143,53 @@ pub struct RunCommand {
     #[structopt(flatten)]
     common: CommonOptions,
 
+    #[structopt(flatten)]
+    emit: EmitOptions,
+
     /// Grant access to the given host directory
     #[structopt(long = "dir", number_of_values = 1, value_name = "DIRECTORY")]
     dirs: Vec<String>,
 
-    /// Pass an environment variable to the program
-    #[structopt(long = "env", number_of_values = 1, value_name = "NAME=VAL", parse(try_from_str = parse_env_var))]
-    vars: Vec<(String, String)>,
+    /// Open a TCP socket listening on the given address and pass it to the
+    /// program
+    ///
+    /// Sockets are given the file descriptors following the preopened
+    /// directories, in the order they're given. This is not supported on
+    /// Windows.
+    #[structopt(long = "tcplisten", number_of_values = 1, value_name = "ADDR:PORT")]
+    tcplisten: Vec<String>,
+
+    /// Pass the sockets inherited through `LISTEN_FDS` to the program
+    ///
+    /// This follows the socket activation protocol used by systemd: the
+    /// sockets are taken from the file descriptors starting at 3 if
+    /// `LISTEN_PID` matches the id of this process, and are passed to the
+    /// program before any sockets opened with `--tcplisten`. The variables
+    /// of the protocol are not passed on to the program. This is not
+    /// supported on Windows.
+    #[structopt(long = "listenfd")]
+    listenfd: bool,
+
+    /// Pass an environment variable to the program.
+    ///
+    /// `NAME=VAL` sets the variable to the given value. A bare `NAME` passes
+    /// through the host's value of the variable, if it is set, and `PREFIX*`
+    /// passes through all of the host's variables whose names start with
+    /// `PREFIX`.
+    #[structopt(long = "env", number_of_values = 1, value_name = "NAME[=VAL]", parse(try_from_str = parse_env_var))]
+    vars: Vec<(String, Option<String>)>,
+
+    /// Pass all of the host's environment variables to the program
+    #[structopt(long = "env-inherit")]
+    env_inherit: bool,
 
     /// The name of the function to run
+    ///
+    /// The function's arguments are taken from the arguments following the
+    /// module. Each may be prefixed with the type of its parameter, as in
+    /// `i32:5`, `f64:3.5`, or `v128:0x1`; integers may be written in decimal
+    /// or in hexadecimal with a `0x` prefix. The function's results are
+    /// printed one per line.
     #[structopt(long, value_name = "FUNCTION")]
     invoke: Option<String>,
 
@@ -94,7 +206,21 @@ pub struct RunCommand {
     )]
     module: PathBuf,
 
+    /// The program name passed to the module as `argv[0]`
+    ///
+    /// Defaults to the file name of the module. Modules that dispatch on
+    /// their program name, like busybox, can be run as a particular program
+    /// with this option.
+    #[structopt(long, value_name = "NAME")]
+    argv0: Option<String>,
+
     /// Load the given WebAssembly module before the main module
+    ///
+    /// The module is instantiated and its exports are made available to
+    /// modules loaded after it, including the main module, under NAME. This
+    /// option can be repeated to link together several modules; preloads are
+    /// loaded in the order they're given, so each may import from the ones
+    /// before it.
     #[structopt(
         long = "preload",
         number_of_values = 1,
@@ -104,6 +230,9 @@ pub struct RunCommand {
     preloads: Vec<(String, PathBuf)>,
 
     /// Maximum execution time of wasm code before timing out (1, 2s, 100ms, etc)
+    ///
+    /// A guest that is stopped because it ran out of time causes wasmtime to
+    /// exit with status 124, the same status used by `timeout(1)`.
     #[structopt(
         long = "wasm-timeout",
         value_name = "TIME",
@@ -111,8 +240,113 @@ pub struct RunCommand {
     )]
     wasm_timeout: Option<Duration>,
 
+    /// Print the time spent compiling each function of the main module
+    #[structopt(long)]
+    compile_report: bool,
+
+    /// Enable fuel metering and give the guest this much fuel to consume
+    ///
+    /// Most WebAssembly instructions consume one unit of fuel, and the guest
+    /// traps once all of its fuel has been consumed.
+    #[structopt(long, value_name = "N")]
+    fuel: Option<u64>,
+
+    /// Maximum size in bytes of each of the guest's linear memories
+    ///
+    /// Instantiating a module whose memory starts out larger fails, and
+    /// `memory.grow` beyond the limit returns -1.
+    #[structopt(long, value_name = "BYTES")]
+    max_memory_size: Option<usize>,
+
+    /// Maximum number of elements in each of the guest's tables
+    #[structopt(long, value_name = "N")]
+    max_table_elements: Option<u32>,
+
+    /// Maximum number of modules that may be instantiated, including the
+    /// main module and any preloaded modules
+    #[structopt(long, value_name = "N")]
+    max_instances: Option<usize>,
+
+    /// Print the amount of fuel consumed by the guest when it exits
+    #[structopt(long, requires = "fuel")]
+    fuel_report: bool,
+
+    /// Profile the guest with the given strategy
+    ///
+    /// `jitdump` and `vtune` register the generated code with an external
+    /// profiler, like `--jitdump` and `--vtune`. `guest[,PATH[,INTERVAL]]`
+    /// instead samples the wasm stack every INTERVAL units of fuel (10000 by
+    /// default) and writes the samples to PATH (`wasmtime-guest.folded` by
+    /// default) in the collapsed-stack format understood by flamegraph tools.
+    #[structopt(
+        long,
+        value_name = "STRATEGY",
+        conflicts_with_all = &["jitdump", "vtune"],
+        parse(try_from_str = parse_profile),
+    )]
+    profile: Option<Profile>,
+
+    /// Write a wasm coredump to PATH when the guest traps
+    ///
+    /// The coredump contains the wasm stack and the contents of memories and
+    /// globals at the time of the trap, and can be inspected offline with
+    /// coredump-aware debugging tools. `{module}` in PATH is replaced with the
+    /// name of the main module's file and `{time}` with the current Unix time
+    /// in seconds. PATH defaults to `{module}.{time}.coredump`.
+    #[structopt(long, value_name = "PATH")]
+    coredump_on_trap: Option<Option<String>>,
+
+    /// Rerun the module each time it changes on disk
+    ///
+    /// The main module and any preloaded modules are checked for changes
+    /// every half second. Each run gets a fresh instance, while compiled code
+    /// is reused through the compilation cache, if it's enabled.
+    #[structopt(long)]
+    watch: bool,
+
+    /// The format of errors and exit information: `human` or `json`
+    ///
+    /// With `json`, errors, traps (with their code, message, and wasm
+    /// backtrace), and the exit status of the program are printed to stderr
+    /// as JSON objects, one per line, each with a `type` of `error`, `trap`,
+    /// or `exit`.
+    #[structopt(
+        long,
+        value_name = "FORMAT",
+        default_value = "human",
+        parse(try_from_str = parse_message_format),
+    )]
+    message_format: MessageFormat,
+
+    /// How the module's stdin and stdout are treated: `text` or `raw`
+    ///
+    /// Both modes pass bytes through unmodified, without any newline
+    /// translation, when stdio is a pipe or a file. With `text`, the default,
+    /// output to a terminal is sanitized so that the module can't emit
+    /// control sequences or invalid UTF-8. With `raw`, stdin and stdout are
+    /// always raw byte streams, which suits modules producing binary output.
+    #[structopt(
+        long,
+        value_name = "MODE",
+        default_value = "text",
+        parse(try_from_str = parse_stdio_mode),
+    )]
+    stdio: StdioMode,
+
+    /// Allow executing precompiled WebAssembly modules as `*.cwasm` files.
+    ///
+    /// Note that this option is not safe to pass if the module being passed in
+    /// is arbitrary user input. Only `wasmtime`-precompiled modules generated
+    /// via the `wasmtime compile` command or equivalent should be passed as an
+    /// argument with this option specified.
+    #[structopt(long = "allow-precompiled")]
+    allow_precompiled: bool,
+
     // NOTE: this must come last for trailing varargs
     /// The arguments to pass to the module
+    ///
+    /// Arguments following `--` are passed to the module verbatim, even if
+    /// they look like options to `wasmtime run`.
     #[structopt(value_name = "ARGS")]
     module_args: Vec<String>,
 }
@@ -127,24 +361,176 @@ impl RunCommand {
             pretty_env_logger::init();
         }
 
-        let mut config = self.common.config()?;
+        let mut config = self.common.config(None)?;
         if self.wasm_timeout.is_some() {
             config.interruptable(true);
         }
+        if self.compile_report {
+            config.compile_stats(true);
+        }
+        if self.fuel.is_some() {
+            config.consume_fuel(true);
+        }
+        if self.coredump_on_trap.is_some() {
+            config.coredump_on_trap(true);
+        }
+        let guest_profiler = match &self.profile {
+            Some(Profile::Native(strategy)) => {
+                config.profiler(*strategy)?;
+                None
+            }
+            Some(Profile::Guest { path, interval }) => {
+                config.consume_fuel(true);
+                Some(GuestProfiler::new(path, *interval))
+            }
+            None => None,
+        };
         let engine = Engine::new(&config);
-        let store = Store::new(&engine);
+
+        // Inherited sockets are taken over only once, since each run (and
+        // each rerun with `--watch`) is handed its own duplicates of them.
+        let listen_fds = if self.listenfd {
+            inherited_listen_fds()?
+        } else {
+            Vec::new()
+        };
+
+        if self.watch {
+            return self.watch(&engine, guest_profiler.as_ref(), &listen_fds);
+        }
+
+        let e = match self.run(&engine, guest_profiler.as_ref(), &listen_fds) {
+            Ok(()) => {
+                self.report_exit(0);
+                return Ok(());
+            }
+            Err(e) => e,
+        };
+
+        // If the program exited because of a non-zero exit status, print
+        // a message and exit.
+        if let Some(trap) = e.downcast_ref::<Trap>() {
+            // Print the error message in the usual way, unless the
+            // program exited explicitly.
+            if trap.i32_exit_status().is_none() {
+                self.report_error(&e);
+            }
+
+            if let Some(coredump) = trap.coredump() {
+                if let Err(e) = self.write_coredump(coredump) {
+                    eprintln!("Error: {:?}", e);
+                }
+            }
+
+            let status = if trap.trap_code() == Some(TrapCode::DeadlineExceeded) {
+                // A guest that was interrupted by `--wasm-timeout` gets
+                // its own exit status so scripts can tell a timeout apart
+                // from a trap raised by the program itself.
+                TIMEOUT_EXIT_STATUS
+            } else {
+                // If the program exited because of a trap, the exit code
+                // indicates a more severe problem to the outside
+                // environment than a simple failure.
+                trap.exit_code()
+            };
+            self.report_exit(status);
+            process::exit(status);
+        }
+
+        // Other errors are normally printed by `main`, but in JSON mode they
+        // need to be reported as diagnostics too.
+        if self.message_format == MessageFormat::Json {
+            self.report_error(&e);
+            self.report_exit(1);
+            process::exit(1);
+        }
+        Err(e)
+    }
+
+    /// Prints an error that stopped the program, in the format chosen with
+    /// `--message-format`.
+    fn report_error(&self, e: &anyhow::Error) {
+        let message = match self.message_format {
+            MessageFormat::Human => {
+                eprintln!("Error: {:?}", e);
+                return;
+            }
+            MessageFormat::Json => match e.downcast_ref::<Trap>() {
+                Some(trap) => {
+                    let frames = trap
+                        .trace()
+                        .iter()
+                        .map(|frame| {
+                            json!({
+                                "module": frame.module_name(),
+                                "func_index": frame.func_index(),
+                                "func_name": frame.func_name(),
+                                "module_offset": frame.module_offset(),
+                                "func_offset": frame.func_offset(),
+                            })
+                        })
+                        .collect::<Vec<_>>();
+                    json!({
+                        "type": "trap",
+                        "code": trap.trap_code().map(|code| format!("{:?}", code)),
+                        "message": trap.message(),
+                        "frames": frames,
+                    })
+                }
+                None => json!({
+                    "type": "error",
+                    "message": e.to_string(),
+                    "causes": e.chain().skip(1).map(|c| c.to_string()).collect::<Vec<_>>(),
+                }),
+            },
+        };
+        eprintln!("{}", message);
+    }
+
+    /// Reports the exit status of the program when `--message-format=json`
+    /// is used.
+    fn report_exit(&self, status: i32) {
+        if self.message_format == MessageFormat::Json {
+            eprintln!("{}", json!({ "type": "exit", "status": status }));
+        }
+    }
+
+    /// Instantiates and runs the main module in a fresh store, returning the
+    /// result of running it.
+    fn run(
+        &self,
+        engine: &Engine,
+        guest_profiler: Option<&GuestProfiler>,
+        listen_fds: &[File],
+    ) -> Result<()> {
+        let store = Store::new(engine);
+        store.limit(self.store_limits());
+        if let Some(profiler) = guest_profiler {
+            profiler.install(&store, self.fuel)?;
+        } else if let Some(fuel) = self.fuel {
+            store.add_fuel(fuel)?;
+        }
 
         // Make wasi available by default.
         let preopen_dirs = self.compute_preopen_dirs()?;
+        let preopen_sockets = self.compute_preopen_sockets(listen_fds)?;
         let argv = self.compute_argv();
+        let vars = self.compute_env();
 
         let mut linker = Linker::new(&store);
-        populate_with_wasi(&mut linker, &preopen_dirs, &argv, &self.vars)?;
+        populate_with_wasi(
+            &mut linker,
+            &preopen_dirs,
+            &preopen_sockets,
+            &argv,
+            &vars,
+            self.stdio,
+        )?;
 
         // Load the preload wasm modules.
         for (name, path) in self.preloads.iter() {
             // Read the wasm module binary either as `*.wat` or a raw binary
-            let module = Module::from_file(&engine, path)?;
+            let module = self.load_module(engine, path)?;
 
             // Add the module's functions to the linker.
             linker.module(name, &module).context(format!(
@@ -155,46 +541,111 @@ impl RunCommand {
         }
 
         // Load the main wasm module.
-        match self
+        let result = self
             .load_main_module(&mut linker)
-            .with_context(|| format!("failed to run main module `{}`", self.module.display()))
-        {
-            Ok(()) => (),
-            Err(e) => {
-                // If the program exited because of a non-zero exit status, print
-                // a message and exit.
-                if let Some(trap) = e.downcast_ref::<Trap>() {
-                    // Print the error message in the usual way.
-                    if let Some(status) = trap.i32_exit_status() {
-                        // On Windows, exit status 3 indicates an abort (see below),
-                        // so return 1 indicating a non-zero status to avoid ambiguity.
-                        if cfg!(windows) && status >= 3 {
-                            process::exit(1);
-                        }
-                        process::exit(status);
-                    }
+            .with_context(|| format!("failed to run main module `{}`", self.module.display()));
 
-                    eprintln!("Error: {:?}", e);
+        // Report fuel before handling the result, since a trap (including an
+        // explicit exit from the program) terminates the process below.
+        if self.fuel_report {
+            if let Some(consumed) = store.fuel_consumed() {
+                eprintln!("fuel consumed: {}", consumed);
+            }
+        }
+        if let Some(profiler) = guest_profiler {
+            profiler.finish()?;
+        }
 
-                    // If the program exited because of a trap, return an error code
-                    // to the outside environment indicating a more severe problem
-                    // than a simple failure.
-                    if cfg!(unix) {
-                        // On Unix, return the error code of an abort.
-                        process::exit(128 + libc::SIGABRT);
-                    } else if cfg!(windows) {
-                        // On Windows, return 3.
-                        // https://docs.microsoft.com/en-us/cpp/c-runtime-library/reference/abort?view=vs-2019
-                        process::exit(3);
+        result
+    }
+
+    /// Runs the main module, and then again each time it or one of the
+    /// preloaded modules changes on disk, until wasmtime is interrupted.
+    fn watch(
+        &self,
+        engine: &Engine,
+        guest_profiler: Option<&GuestProfiler>,
+        listen_fds: &[File],
+    ) -> Result<()> {
+        let mut last_modified = None;
+        loop {
+            let modified = self.modification_times()?;
+            if last_modified.as_ref() != Some(&modified) {
+                last_modified = Some(modified);
+                if let Err(e) = self.run(engine, guest_profiler, listen_fds) {
+                    match e.downcast_ref::<Trap>() {
+                        Some(trap) => {
+                            match trap.i32_exit_status() {
+                                Some(status) => eprintln!("module exited with status {}", status),
+                                None => self.report_error(&e),
+                            }
+                            if let Some(coredump) = trap.coredump() {
+                                if let Err(e) = self.write_coredump(coredump) {
+                                    eprintln!("Error: {:?}", e);
+                                }
+                            }
+                        }
+                        None => self.report_error(&e),
                     }
                 }
-                return Err(e);
+                eprintln!("watching `{}` for changes", self.module.display());
             }
+            thread::sleep(WATCH_INTERVAL);
         }
+    }
+
+    /// Returns the modification times of the main module and the preloaded
+    /// modules.
+    fn modification_times(&self) -> Result<Vec<SystemTime>> {
+        let paths = self.preloads.iter().map(|(_, path)| path);
+        paths
+            .chain(Some(&self.module))
+            .map(|path| {
+                fs::metadata(path)
+                    .and_then(|m| m.modified())
+                    .with_context(|| format!("failed to read `{}`", path.display()))
+            })
+            .collect()
+    }
 
+    fn write_coredump(&self, coredump: &WasmCoreDump) -> Result<()> {
+        let template = match &self.coredump_on_trap {
+            Some(Some(path)) => path.as_str(),
+            Some(None) => DEFAULT_COREDUMP_PATH,
+            None => return Ok(()),
+        };
+        let module = self
+            .module
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("wasm");
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let path = template
+            .replace("{module}", module)
+            .replace("{time}", &time.to_string());
+        fs::write(&path, coredump.serialize(module))
+            .with_context(|| format!("failed to write coredump to `{}`", path))?;
+        eprintln!("wrote coredump to `{}`", path);
         Ok(())
     }
 
+    fn store_limits(&self) -> StoreLimits {
+        let mut limits = StoreLimits::new();
+        if let Some(bytes) = self.max_memory_size {
+            limits.memory_size(bytes);
+        }
+        if let Some(elements) = self.max_table_elements {
+            limits.table_elements(elements);
+        }
+        if let Some(instances) = self.max_instances {
+            limits.instances(instances);
+        }
+        limits
+    }
+
     fn compute_preopen_dirs(&self) -> Result<Vec<(String, File)>> {
         let mut preopen_dirs = Vec::new();
 
@@ -216,20 +667,40 @@ impl RunCommand {
         Ok(preopen_dirs)
     }
 
+    fn compute_preopen_sockets(&self, listen_fds: &[File]) -> Result<Vec<File>> {
+        let mut preopen_sockets = Vec::new();
+
+        for fd in listen_fds {
+            preopen_sockets.push(
+                fd.try_clone()
+                    .context("failed to duplicate inherited socket")?,
+            );
+        }
+
+        for address in self.tcplisten.iter() {
+            preopen_sockets.push(listen(address)?);
+        }
+
+        Ok(preopen_sockets)
+    }
+
     fn compute_argv(&self) -> Vec<String> {
         let mut result = Vec::new();
 
-        // Add argv[0], which is the program name. Only include the base name of the
-        // main wasm module, to avoid leaking path information.
-        result.push(
-            self.module
+        // Add argv[0], which is the program name. Unless it's given explicitly,
+        // only include the base name of the main wasm module, to avoid leaking
+        // path information.
+        result.push(match &self.argv0 {
+            Some(argv0) => argv0.clone(),
+            None => self
+                .module
                 .components()
                 .next_back()
                 .map(Component::as_os_str)
                 .and_then(OsStr::to_str)
                 .unwrap_or("")
                 .to_owned(),
-        );
+        });
 
         // Add the remaining arguments.
         for arg in self.module_args.iter() {
@@ -239,18 +710,57 @@ impl RunCommand {
         result
     }
 
+    fn compute_env(&self) -> Vec<(String, String)> {
+        let mut result: Vec<(String, String)> = Vec::new();
+
+        // Later settings of a variable override earlier ones.
+        let mut set = |name: String, value: String| {
+            result.retain(|(n, _)| *n != name);
+            result.push((name, value));
+        };
+
+        if self.env_inherit {
+            for (name, value) in env::vars() {
+                set(name, value);
+            }
+        }
+
+        for (name, value) in self.vars.iter() {
+            match value {
+                Some(value) => set(name.clone(), value.clone()),
+                None => match name.strip_suffix('*') {
+                    Some(prefix) => {
+                        for (name, value) in env::vars().filter(|(n, _)| n.starts_with(prefix)) {
+                            set(name, value);
+                        }
+                    }
+                    None => {
+                        if let Ok(value) = env::var(name) {
+                            set(name.clone(), value);
+                        }
+                    }
+                },
+            }
+        }
+
+        result
+    }
+
     fn load_main_module(&self, linker: &mut Linker) -> Result<()> {
         if let Some(timeout) = self.wasm_timeout {
-            let handle = linker.store().interrupt_handle()?;
-            thread::spawn(move || {
-                thread::sleep(timeout);
-                handle.interrupt();
-            });
+            linker.store().set_deadline(timeout)?;
         }
 
+        self.emit.emit(linker.store().engine(), &self.module)?;
+
         // Read the wasm module binary either as `*.wat` or a raw binary.
         // Use "" as a default module name.
-        let module = Module::from_file(linker.store().engine(), &self.module)?;
+        let module = self.load_module(linker.store().engine(), &self.module)?;
+        if self.compile_report {
+            if let Some(report) = module.compile_report() {
+                eprint!("{}", report);
+            }
+        }
         linker
             .module("", &module)
             .context(format!("failed to instantiate {:?}", self.module))?;
@@ -264,6 +774,40 @@ impl RunCommand {
         }
     }
 
+    fn load_module(&self, engine: &Engine, path: &Path) -> Result<Module> {
+        let bytes =
+            fs::read(path).with_context(|| format!("failed to read `{}`", path.display()))?;
+
+        // Precompiled modules start with the fingerprint of the configuration
+        // they were compiled with, so only treat the file as one if that
+        // fingerprint matches ours.
+        if !bytes.starts_with(b"\0asm") && Module::is_compatible(engine, &bytes).unwrap_or(false) {
+            if !self.allow_precompiled {
+                bail!(
+                    "`{}` is a precompiled module; running precompiled modules \
+                     requires `--allow-precompiled`",
+                    path.display()
+                );
+            }
+            return Module::deserialize(engine, &bytes);
+        }
+
+        let wasm = match wat::parse_bytes(&bytes) {
+            Ok(wasm) => wasm,
+            Err(_) if self.allow_precompiled && str::from_utf8(&bytes).is_err() => bail!(
+                "`{}` is neither a WebAssembly module nor a module precompiled \
+                 with this Wasmtime version, target, and configuration; \
+                 recompile it with `wasmtime compile`",
+                path.display()
+            ),
+            Err(mut e) => {
+                e.set_path(path);
+                return Err(e.into());
+            }
+        };
+        Module::from_binary(engine, &wasm)
+    }
+
     fn invoke_export(&self, linker: &Linker, name: &str) -> Result<()> {
         let func = match linker.get_one_by_name("", Some(name))?.into_func() {
             Some(func) => func,
@@ -293,16 +837,7 @@ impl RunCommand {
                     }
                 }
             };
-            values.push(match ty {
-                // TODO: integer parsing here should handle hexadecimal notation
-                // like `0x0...`, but the Rust standard library currently only
-                // parses base-10 representations.
-                ValType::I32 => Val::I32(val.parse()?),
-                ValType::I64 => Val::I64(val.parse()?),
-                ValType::F32 => Val::F32(val.parse()?),
-                ValType::F64 => Val::F64(val.parse()?),
-                t => bail!("unsupported argument type {:?}", t),
-            });
+            values.push(parse_invoke_arg(&ty, val)?);
         }
 
         // Invoke the function and then afterwards print all the results that came
@@ -321,38 +856,76 @@ impl RunCommand {
             );
         }
 
-        for result in results.into_vec() {
-            match result {
-                Val::I32(i) => println!("{}", i),
-                Val::I64(i) => println!("{}", i),
-                Val::F32(f) => println!("{}", f),
-                Val::F64(f) => println!("{}", f),
-                Val::ExternRef(_) => println!("<externref>"),
-                Val::FuncRef(_) => println!("<externref>"),
-                Val::V128(i) => println!("{}", i),
-            }
+        for result in results.iter() {
+            println!("{}", format_invoke_result(result));
         }
 
         Ok(())
     }
 }
 
+/// Parses an argument given to `--invoke` for a parameter of type `ty`.
+///
+/// The argument may be prefixed with its type, as in `i32:5` or `f64:3.5`, to
+/// guard against passing it to a parameter of another type.
+pub(super) fn parse_invoke_arg(ty: &ValType, arg: &str) -> Result<Val> {
+    let value = match arg.find(':') {
+        Some(i) => {
+            let (prefix, value) = (&arg[..i], &arg[i + 1..]);
+            if prefix != ty.to_string() {
+                bail!(
+                    "argument `{}` was given for a parameter of type {}",
+                    arg,
+                    ty
+                );
+            }
+            value
+        }
+        None => arg,
+    };
+    super::repl::parse_val(ty, value)
+}
+
+/// Formats a value returned by the function given to `--invoke`. Results are
+/// printed without their types so that scripts can consume them.
+fn format_invoke_result(val: &Val) -> String {
+    match val {
+        Val::I32(i) => i.to_string(),
+        Val::I64(i) => i.to_string(),
+        Val::F32(f) => f32::from_bits(*f).to_string(),
+        Val::F64(f) => f64::from_bits(*f).to_string(),
+        Val::V128(v) => format!("{:#034x}", v),
+        Val::ExternRef(None) | Val::FuncRef(None) => "null".to_string(),
+        Val::ExternRef(Some(_)) => "<externref>".to_string(),
+        Val::FuncRef(Some(_)) => "<funcref>".to_string(),
+    }
+}
+
 /// Populates the given `Linker` with WASI APIs.
-fn populate_with_wasi(
+pub(super) fn populate_with_wasi(
     linker: &mut Linker,
     preopen_dirs: &[(String, File)],
+    preopen_sockets: &[File],
     argv: &[String],
     vars: &[(String, String)],
+    stdio_mode: StdioMode,
 ) -> Result<()> {
     let mk_cx = || {
         // Add the current snapshot to the linker.
         let mut cx = WasiCtxBuilder::new();
-        cx.inherit_stdio().args(argv).envs(vars);
+        cx.stdio_mode(stdio_mode)
+            .inherit_stdio()
+            .args(argv)
+            .envs(vars);
 
         for (name, file) in preopen_dirs {
             cx.preopened_dir(file.try_clone()?, name);
         }
 
+        for socket in preopen_sockets {
+            cx.preopened_socket(socket.try_clone()?);
+        }
+
         cx.build()
     };
     let wasi = Wasi::new(linker.store(), mk_cx()?);
@@ -369,3 +942,142 @@ fn populate_with_wasi(
 
     Ok(())
 }
+
+/// Takes ownership of the sockets passed to this process with the systemd
+/// socket activation protocol.
+#[cfg(unix)]
+fn inherited_listen_fds() -> Result<Vec<File>> {
+    use std::os::unix::io::FromRawFd;
+
+    // The first file descriptor passed, following stdio.
+    const LISTEN_FDS_START: i32 = 3;
+
+    // The sockets are only meant for us if `LISTEN_PID` names this process.
+    match env::var("LISTEN_PID") {
+        Ok(pid) if pid.parse() == Ok(process::id()) => {}
+        _ => return Ok(Vec::new()),
+    }
+    let count = env::var("LISTEN_FDS");
+
+    // Don't pass the variables on, for example with `--env-inherit`: they
+    // describe this process's file descriptors, not the guest's.
+    env::remove_var("LISTEN_PID");
+    env::remove_var("LISTEN_FDS");
+    env::remove_var("LISTEN_FDNAMES");
+
+    let count: i32 = match count {
+        Ok(count) => count.parse().context("invalid `LISTEN_FDS`")?,
+        Err(_) => return Ok(Vec::new()),
+    };
+    let fds = LISTEN_FDS_START..LISTEN_FDS_START.saturating_add(count);
+    for fd in fds.clone() {
+        if unsafe { libc::fcntl(fd, libc::F_GETFD) } == -1 {
+            bail!(
+                "`LISTEN_FDS` names file descriptor {}, which isn't open",
+                fd
+            );
+        }
+    }
+    Ok(fds.map(|fd| unsafe { File::from_raw_fd(fd) }).collect())
+}
+
+#[cfg(windows)]
+fn inherited_listen_fds() -> Result<Vec<File>> {
+    bail!("`--listenfd` is not supported on this platform")
+}
+
+/// Opens a socket listening on `address`, to be passed to the guest.
+#[cfg(unix)]
+fn listen(address: &str) -> Result<File> {
+    use std::net::TcpListener;
+    use std::os::unix::io::{FromRawFd, IntoRawFd};
+
+    let listener =
+        TcpListener::bind(address).with_context(|| format!("failed to listen on '{}'", address))?;
+    Ok(unsafe { File::from_raw_fd(listener.into_raw_fd()) })
+}
+
+// Sockets can't be handed to WASI as files on Windows, where they must be
+// duplicated and closed with the Winsock API rather than as handles.
+#[cfg(windows)]
+fn listen(_address: &str) -> Result<File> {
+    bail!("`--tcplisten` is not supported on this platform")
+}
+
+/// A sampling profiler for the guest, driven by fuel.
+///
+/// The store is only ever given `interval` units of fuel at a time; each time
+/// the guest runs out the current wasm stack is recorded and the guest is
+/// refueled, until the budget given with `--fuel` (if any) is exhausted.
+struct GuestProfiler {
+    path: PathBuf,
+    interval: u64,
+    samples: Rc<RefCell<HashMap<String, u64>>>,
+}
+
+impl GuestProfiler {
+    fn new(path: &Path, interval: u64) -> GuestProfiler {
+        GuestProfiler {
+            path: path.to_path_buf(),
+            interval,
+            samples: Default::default(),
+        }
+    }
+
+    fn install(&self, store: &Store, budget: Option<u64>) -> Result<()> {
+        let interval = self.interval;
+        let mut remaining = budget.unwrap_or(u64::max_value());
+        let mut refuel = move || {
+            let fuel = remaining.min(interval);
+            remaining -= fuel;
+            fuel
+        };
+        store.add_fuel(refuel())?;
+
+        let samples = self.samples.clone();
+        store.out_of_fuel_handler(move |trace| {
+            *samples
+                .borrow_mut()
+                .entry(collapse_stack(trace))
+                .or_insert(0) += 1;
+            match refuel() {
+                0 => Err(Trap::new("all fuel consumed by WebAssembly")),
+                fuel => Ok(fuel),
+            }
+        });
+        Ok(())
+    }
+
+    /// Writes out the samples collected so far, one `stack count` line per
+    /// distinct stack.
+    fn finish(&self) -> Result<()> {
+        let samples = self.samples.borrow();
+        let mut lines = samples
+            .iter()
+            .map(|(stack, count)| format!("{} {}\n", stack, count))
+            .collect::<Vec<_>>();
+        lines.sort();
+        fs::write(&self.path, lines.concat())
+            .with_context(|| format!("failed to write profile to `{}`", self.path.display()))
+    }
+}
+
+/// Formats a wasm stack trace, youngest frame first, as a collapsed stack:
+/// frame names from the root down separated by semicolons.
+fn collapse_stack(trace: &[FrameInfo]) -> String {
+    let frames = trace
+        .iter()
+        .rev()
+        .map(|frame| {
+            let func = match frame.func_name() {
+                Some(name) => name.to_string(),
+                None => format!("wasm-function[{}]", frame.func_index()),
+            };
+            match frame.module_name() {
+                Some(module) => format!("{}!{}", module, func),
+                None => func,
+            }
+        })
+        .collect::<Vec<_>>();
+    frames.join(";")
+}
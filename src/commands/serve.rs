@@ -0,0 +1,439 @@
+//! The module that implements the `wasmtime serve` command.
+
+use super::run::parse_dur;
+use crate::{init_file_per_thread_logger, CommonOptions};
+use anyhow::{bail, Context as _, Result};
+use std::fmt;
+use std::io::{BufRead, BufReader, Cursor, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::sync::{Arc, Condvar, Mutex, RwLock};
+use std::thread;
+use std::time::Duration;
+use structopt::{clap::AppSettings, StructOpt};
+use wasi_common::virtfs::pipe::{ReadPipe, WritePipe};
+use wasi_common::WasiCtxBuilder;
+use wasmtime::{Engine, Linker, Module, Store, Trap};
+use wasmtime_wasi::Wasi;
+
+/// The after help text for the `serve` command.
+pub const SERVE_AFTER_HELP: &str =
+    "Each request is handled by a fresh instance of the module, following the \
+     conventions of CGI: the request body is passed on standard input, and \
+     the request line and headers are passed as environment variables \
+     (`REQUEST_METHOD`, `PATH_INFO`, `QUERY_STRING`, `CONTENT_TYPE`, \
+     `CONTENT_LENGTH`, and `HTTP_*` for the remaining headers).\n\
+     \n\
+     The module writes the response to standard output: header lines, an \
+     empty line, and then the body. A `Status` header, such as \
+     `Status: 404 Not Found`, sets the status of the response, which is \
+     otherwise `200 OK`. A module that traps, exits with a non-zero status, \
+     or runs for longer than `--timeout` produces a \
+     `500 Internal Server Error` response.\n\
+     \n\
+     Up to `--max-connections` connections are handled concurrently. Request \
+     bodies larger than 16 MiB are rejected with `413 Payload Too Large`, \
+     and connections that stall for 30 seconds are dropped.\n\
+     \n\
+     Usage examples:\n\
+     \n\
+     Serving a module on the default address, http://127.0.0.1:8080/:\n\
+     \n  \
+     wasmtime serve handler.wasm\n\
+     \n\
+     Serving a module on all interfaces:\n\
+     \n  \
+     wasmtime serve --addr 0.0.0.0:80 handler.wasm\n";
+
+/// The largest request body that is accepted, in bytes.
+const MAX_BODY_SIZE: usize = 16 << 20;
+
+/// The most headers a request may have.
+const MAX_HEADERS: usize = 100;
+
+/// The longest request line or header line that is accepted, in bytes.
+const MAX_LINE_LENGTH: usize = 8 << 10;
+
+/// How long reading or writing a connection may block before it's dropped.
+const IO_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Serves HTTP requests with a WebAssembly module
+#[derive(StructOpt)]
+#[structopt(
+    name = "serve",
+    version = env!("CARGO_PKG_VERSION"),
+    setting = AppSettings::ColoredHelp,
+    after_help = SERVE_AFTER_HELP,
+)]
+pub struct ServeCommand {
+    #[structopt(flatten)]
+    common: CommonOptions,
+
+    /// The address to listen for requests on
+    #[structopt(long, value_name = "ADDR:PORT", default_value = "127.0.0.1:8080")]
+    addr: String,
+
+    /// Maximum execution time of the module for each request (1, 2s, 100ms,
+    /// etc)
+    #[structopt(
+        long,
+        value_name = "TIME",
+        default_value = "30s",
+        parse(try_from_str = parse_dur),
+    )]
+    timeout: Duration,
+
+    /// The most connections that are handled at once
+    #[structopt(long, value_name = "N", default_value = "64")]
+    max_connections: usize,
+
+    /// The path of the WebAssembly module that handles requests
+    #[structopt(index = 1, value_name = "MODULE", parse(from_os_str))]
+    module: PathBuf,
+}
+
+/// A request that is rejected before it reaches the module, answered with
+/// `status`.
+#[derive(Debug)]
+struct BadRequest {
+    status: &'static str,
+    message: String,
+}
+
+impl fmt::Display for BadRequest {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for BadRequest {}
+
+fn bad_request(status: &'static str, message: impl Into<String>) -> anyhow::Error {
+    BadRequest {
+        status,
+        message: message.into(),
+    }
+    .into()
+}
+
+/// An HTTP request, as read off the connection.
+struct Request {
+    method: String,
+    target: String,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl ServeCommand {
+    /// Executes the command.
+    pub fn execute(&self) -> Result<()> {
+        if self.common.log_to_files {
+            let prefix = "wasmtime.dbg.";
+            init_file_per_thread_logger(prefix);
+        } else {
+            pretty_env_logger::init();
+        }
+
+        let mut config = self.common.config(None)?;
+        config.interruptable(true);
+        let engine = Engine::new(&config);
+
+        // Compile the module once up front; each request only instantiates it.
+        let module = Module::from_file(&engine, &self.module)?;
+
+        let listener = TcpListener::bind(&self.addr)
+            .with_context(|| format!("failed to listen on '{}'", self.addr))?;
+        eprintln!("Serving on http://{}/", listener.local_addr()?);
+
+        let limit = Arc::new(ConnectionLimit::new(self.max_connections));
+        loop {
+            // Wait for a connection to finish before accepting more than
+            // `--max-connections`; new ones queue up in the listen backlog.
+            let slot = limit.acquire();
+            let stream = match listener.accept() {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    eprintln!("error: failed to accept connection: {}", e);
+                    continue;
+                }
+            };
+            // Each connection gets its own thread, so that a slow client
+            // doesn't hold up everyone else.
+            let module = module.clone();
+            let argv0 = self.module.display().to_string();
+            let timeout = self.timeout;
+            thread::spawn(move || {
+                let _slot = slot;
+                if let Err(e) = handle_connection(&module, &argv0, timeout, stream) {
+                    eprintln!("error: {:?}", e);
+                }
+            });
+        }
+    }
+}
+
+/// Counts the connections being handled, to cap how many are handled at
+/// once.
+struct ConnectionLimit {
+    active: Mutex<usize>,
+    released: Condvar,
+    max: usize,
+}
+
+impl ConnectionLimit {
+    fn new(max: usize) -> ConnectionLimit {
+        ConnectionLimit {
+            active: Mutex::new(0),
+            released: Condvar::new(),
+            max: max.max(1),
+        }
+    }
+
+    /// Blocks until fewer than `max` connections are active, and returns a
+    /// slot for a new one that is released when dropped.
+    fn acquire(self: &Arc<Self>) -> ConnectionSlot {
+        let mut active = self.active.lock().unwrap();
+        while *active >= self.max {
+            active = self.released.wait(active).unwrap();
+        }
+        *active += 1;
+        ConnectionSlot(self.clone())
+    }
+}
+
+/// A connection counted by a `ConnectionLimit`.
+struct ConnectionSlot(Arc<ConnectionLimit>);
+
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        *self.0.active.lock().unwrap() -= 1;
+        self.0.released.notify_one();
+    }
+}
+
+fn handle_connection(
+    module: &Module,
+    argv0: &str,
+    timeout: Duration,
+    mut stream: TcpStream,
+) -> Result<()> {
+    stream.set_read_timeout(Some(IO_TIMEOUT))?;
+    stream.set_write_timeout(Some(IO_TIMEOUT))?;
+
+    let request = match read_request(&mut BufReader::new(&stream)) {
+        Ok(request) => request,
+        Err(e) => {
+            if let Some(bad) = e.downcast_ref::<BadRequest>() {
+                write_response(&mut stream, bad.status, Vec::new(), Vec::new())?;
+            }
+            return Err(e);
+        }
+    };
+    let response =
+        handle_request(module, argv0, timeout, &request).and_then(|output| parse_response(&output));
+    let (status, headers, body) = match response {
+        Ok(response) => response,
+        Err(e) => {
+            eprintln!(
+                "error: failed to handle `{} {}`: {:?}",
+                request.method, request.target, e
+            );
+            (
+                "500 Internal Server Error".to_string(),
+                Vec::new(),
+                Vec::new(),
+            )
+        }
+    };
+
+    write_response(&mut stream, &status, headers, body)
+}
+
+fn write_response(
+    stream: &mut TcpStream,
+    status: &str,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+) -> Result<()> {
+    write!(stream, "HTTP/1.1 {}\r\n", status)?;
+    for (name, value) in headers {
+        write!(stream, "{}: {}\r\n", name, value)?;
+    }
+    write!(stream, "Content-Length: {}\r\n", body.len())?;
+    write!(stream, "Connection: close\r\n\r\n")?;
+    stream.write_all(&body)?;
+    stream.flush()?;
+    Ok(())
+}
+
+/// Runs a fresh instance of the module on `request`, returning what it
+/// wrote to standard output. The module is interrupted once it has run for
+/// `timeout`.
+fn handle_request(
+    module: &Module,
+    argv0: &str,
+    timeout: Duration,
+    request: &Request,
+) -> Result<Vec<u8>> {
+    let store = Store::new(module.engine());
+    store.set_deadline(timeout)?;
+    let stdout = Arc::new(RwLock::new(Cursor::new(Vec::new())));
+
+    let (path, query) = match request.target.find('?') {
+        Some(i) => (&request.target[..i], &request.target[i + 1..]),
+        None => (&request.target[..], ""),
+    };
+    let mut vars = vec![
+        ("REQUEST_METHOD".to_string(), request.method.clone()),
+        ("PATH_INFO".to_string(), path.to_string()),
+        ("QUERY_STRING".to_string(), query.to_string()),
+        ("CONTENT_LENGTH".to_string(), request.body.len().to_string()),
+    ];
+    for (name, value) in request.headers.iter() {
+        let name = name.to_uppercase().replace('-', "_");
+        let name = match name.as_str() {
+            "CONTENT_TYPE" => name,
+            "CONTENT_LENGTH" => continue,
+            _ => format!("HTTP_{}", name),
+        };
+        vars.push((name, value.clone()));
+    }
+
+    let argv = [argv0];
+    let mk_cx = || {
+        let mut cx = WasiCtxBuilder::new();
+        cx.args(&argv)
+            .envs(&vars)
+            .stdin(ReadPipe::from(request.body.clone()))
+            .stdout(WritePipe::from_shared(stdout.clone()))
+            .inherit_stderr();
+        cx.build()
+    };
+
+    let mut linker = Linker::new(&store);
+    Wasi::new(&store, mk_cx()?).add_to_linker(&mut linker)?;
+    wasmtime_wasi::old::snapshot_0::Wasi::new(&store, mk_cx()?).add_to_linker(&mut linker)?;
+    linker.module("", module)?;
+
+    match linker.get_default("")?.call(&[]) {
+        Ok(_) => {}
+        Err(e) => match e.downcast_ref::<Trap>().and_then(Trap::i32_exit_status) {
+            Some(0) => {}
+            Some(status) => bail!("module exited with status {}", status),
+            None => return Err(e),
+        },
+    }
+
+    let output = stdout.read().unwrap().get_ref().clone();
+    Ok(output)
+}
+
+fn read_request(reader: &mut impl BufRead) -> Result<Request> {
+    let mut line = String::new();
+    read_line(reader, &mut line, "414 URI Too Long")?;
+    let mut parts = line.split_whitespace();
+    let (method, target) = match (parts.next(), parts.next()) {
+        (Some(method), Some(target)) => (method.to_string(), target.to_string()),
+        _ => {
+            let message = format!("malformed request line `{}`", line.trim_end());
+            return Err(bad_request("400 Bad Request", message));
+        }
+    };
+
+    let mut headers = Vec::new();
+    let mut content_length = 0;
+    loop {
+        line.clear();
+        if read_line(reader, &mut line, "431 Request Header Fields Too Large")? == 0 {
+            return Err(bad_request(
+                "400 Bad Request",
+                "request ended before the end of the headers",
+            ));
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if headers.len() == MAX_HEADERS {
+            return Err(bad_request(
+                "431 Request Header Fields Too Large",
+                format!("more than {} headers", MAX_HEADERS),
+            ));
+        }
+        let (name, value) = match line.find(':') {
+            Some(i) => (line[..i].trim(), line[i + 1..].trim()),
+            None => {
+                let message = format!("malformed header `{}`", line);
+                return Err(bad_request("400 Bad Request", message));
+            }
+        };
+        if name.eq_ignore_ascii_case("content-length") {
+            content_length = value
+                .parse()
+                .map_err(|_| bad_request("400 Bad Request", "invalid `Content-Length`"))?;
+        }
+        headers.push((name.to_string(), value.to_string()));
+    }
+
+    if content_length > MAX_BODY_SIZE {
+        return Err(bad_request(
+            "413 Payload Too Large",
+            format!(
+                "request body of {} bytes exceeds the limit of {} bytes",
+                content_length, MAX_BODY_SIZE
+            ),
+        ));
+    }
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+    Ok(Request {
+        method,
+        target,
+        headers,
+        body,
+    })
+}
+
+/// Reads a line of the request into `line`, returning its length, which is
+/// zero at the end of the request. The request is rejected with `status` if
+/// the line is longer than `MAX_LINE_LENGTH`.
+fn read_line(reader: &mut impl BufRead, line: &mut String, status: &'static str) -> Result<usize> {
+    let len = reader.take(MAX_LINE_LENGTH as u64 + 1).read_line(line)?;
+    if len > MAX_LINE_LENGTH {
+        let message = format!("line longer than {} bytes", MAX_LINE_LENGTH);
+        return Err(bad_request(status, message));
+    }
+    Ok(len)
+}
+
+/// Splits the output of the module into the response status, headers, and
+/// body.
+fn parse_response(output: &[u8]) -> Result<(String, Vec<(String, String)>, Vec<u8>)> {
+    let mut reader = Cursor::new(output);
+    let mut status = "200 OK".to_string();
+    let mut headers = Vec::new();
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            bail!("module output ended before the end of the headers");
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        let (name, value) = match line.find(':') {
+            Some(i) => (line[..i].trim(), line[i + 1..].trim()),
+            None => bail!("malformed header `{}` in module output", line),
+        };
+        if name.eq_ignore_ascii_case("status") {
+            status = value.to_string();
+        } else {
+            headers.push((name.to_string(), value.to_string()));
+        }
+    }
+
+    let mut body = Vec::new();
+    reader.read_to_end(&mut body)?;
+    Ok((status, headers, body))
+}
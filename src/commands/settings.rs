@@ -0,0 +1,74 @@
+//! The module that implements the `wasmtime settings` command.
+
+use crate::CommonOptions;
+use anyhow::Result;
+use structopt::{clap::AppSettings, StructOpt};
+use wasmtime::Config;
+
+/// The after help text for the `settings` command.
+pub const SETTINGS_AFTER_HELP: &str =
+    "The settings reflect any compiler and WebAssembly feature options given, \
+     so they can be used to check what a `run` or `compile` command with the \
+     same options will do.\n\
+     \n\
+     Usage examples:\n\
+     \n\
+     Printing the settings used for the host:\n\
+     \n  \
+     wasmtime settings\n\
+     \n\
+     Printing the settings used when compiling for another platform:\n\
+     \n  \
+     wasmtime settings --target aarch64-unknown-linux\n";
+
+/// Prints the compiler settings, host CPU features, and WebAssembly proposals
+#[derive(StructOpt)]
+#[structopt(
+    name = "settings",
+    version = env!("CARGO_PKG_VERSION"),
+    setting = AppSettings::ColoredHelp,
+    after_help = SETTINGS_AFTER_HELP,
+)]
+pub struct SettingsCommand {
+    #[structopt(flatten)]
+    common: CommonOptions,
+
+    /// The target triple; default is the host triple
+    #[structopt(long, value_name = "TARGET")]
+    target: Option<String>,
+}
+
+impl SettingsCommand {
+    /// Executes the command.
+    pub fn execute(&self) -> Result<()> {
+        let config = self.common.config(self.target.as_deref())?;
+
+        println!("Target: {}", config.target_triple());
+        println!();
+        println!("Compiler settings:");
+        print!("{}", config.compiler_settings());
+
+        // The host's features are only relevant when compiling for the host.
+        if self.target.is_none() {
+            println!();
+            println!("Host CPU features:");
+            for (feature, supported) in Config::host_cpu_features() {
+                let supported = if supported {
+                    "supported"
+                } else {
+                    "unsupported"
+                };
+                println!("  {:<10} {}", feature, supported);
+            }
+        }
+
+        println!();
+        println!("WebAssembly proposals:");
+        for (proposal, enabled) in config.wasm_proposals() {
+            let enabled = if enabled { "enabled" } else { "disabled" };
+            println!("  {:<16} {}", proposal, enabled);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,80 @@
+//! The module that implements the `wasmtime strip` command.
+
+use anyhow::{bail, Context as _, Result};
+use std::fs;
+use std::path::PathBuf;
+use structopt::{clap::AppSettings, StructOpt};
+use wasmtime::Module;
+
+/// The after help text for the `strip` command.
+pub const STRIP_AFTER_HELP: &str =
+    "All custom sections are removed, including the `name` section, DWARF \
+     debug information (the `.debug_*` sections), and the `producers` \
+     section, unless they're kept with `--keep`. The code and data of the \
+     module are left untouched.\n\
+     \n\
+     Usage examples:\n\
+     \n\
+     Stripping a module in place:\n\
+     \n  \
+     wasmtime strip example.wasm\n\
+     \n\
+     Stripping a module into a new file, keeping the `name` section:\n\
+     \n  \
+     wasmtime strip --keep name -o out.wasm example.wasm\n\
+     \n\
+     Keeping the DWARF debug information:\n\
+     \n  \
+     wasmtime strip --keep '.debug_*' example.wasm\n";
+
+/// Removes custom sections from a WebAssembly module
+#[derive(StructOpt)]
+#[structopt(
+    name = "strip",
+    version = env!("CARGO_PKG_VERSION"),
+    setting = AppSettings::ColoredHelp,
+    after_help = STRIP_AFTER_HELP,
+)]
+pub struct StripCommand {
+    /// Keep the custom sections with the given name
+    ///
+    /// A name ending in `*` keeps every custom section starting with the rest
+    /// of the name. This option can be repeated.
+    #[structopt(long, number_of_values = 1, value_name = "NAME")]
+    keep: Vec<String>,
+
+    /// The path of the output module; defaults to overwriting <MODULE>
+    #[structopt(short = "o", long, value_name = "OUTPUT", parse(from_os_str))]
+    output: Option<PathBuf>,
+
+    /// The path of the WebAssembly module to strip
+    #[structopt(index = 1, value_name = "MODULE", parse(from_os_str))]
+    module: PathBuf,
+}
+
+impl StripCommand {
+    /// Executes the command.
+    pub fn execute(&self) -> Result<()> {
+        let input = fs::read(&self.module)
+            .with_context(|| format!("failed to read input file '{}'", self.module.display()))?;
+        let wasm = wat::parse_bytes(&input)?;
+        if !wasm.starts_with(b"\0asm") {
+            bail!("'{}' is not a WebAssembly module", self.module.display());
+        }
+
+        let stripped = Module::strip_custom_sections(&wasm, |name| self.keeps(name))?;
+
+        let output = self.output.as_ref().unwrap_or(&self.module);
+        fs::write(output, stripped)
+            .with_context(|| format!("failed to write output file '{}'", output.display()))?;
+
+        Ok(())
+    }
+
+    fn keeps(&self, name: &str) -> bool {
+        self.keep.iter().any(|keep| match keep.strip_suffix('*') {
+            Some(prefix) => name.starts_with(prefix),
+            None => name == keep,
+        })
+    }
+}
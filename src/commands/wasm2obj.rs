@@ -43,6 +43,10 @@ pub struct WasmToObjCommand {
     /// The target triple; default is the host triple
     #[structopt(long, value_name = "TARGET", parse(try_from_str = parse_target))]
     target: Option<Triple>,
+
+    /// Emit position-independent code, suitable for linking into a shared library
+    #[structopt(long)]
+    pic: bool,
 }
 
 impl WasmToObjCommand {
@@ -70,6 +74,7 @@ impl WasmToObjCommand {
             self.common.enable_simd,
             self.common.opt_level(),
             self.common.debug_info,
+            self.pic,
         )?;
 
         let mut file =
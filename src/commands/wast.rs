@@ -1,12 +1,42 @@
 //! The module that implements the `wasmtime wast` command.
 
 use crate::{init_file_per_thread_logger, CommonOptions};
-use anyhow::{Context as _, Result};
+use anyhow::{bail, Context as _, Result};
 use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
 use structopt::{clap::AppSettings, StructOpt};
 use wasmtime::{Engine, Store};
 use wasmtime_wast::WastContext;
 
+/// The format in which `wasmtime wast` reports its results.
+#[derive(Clone, Copy, PartialEq)]
+enum Format {
+    /// Nothing on success; the first failure is reported as an error.
+    Text,
+    /// A JUnit XML report on stdout, meant for CI systems.
+    Junit,
+}
+
+impl FromStr for Format {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "text" => Ok(Format::Text),
+            "junit" => Ok(Format::Junit),
+            _ => bail!("unknown format `{}`", s),
+        }
+    }
+}
+
+/// The outcome of running a single test script.
+struct ScriptResult {
+    name: String,
+    duration: Duration,
+    error: Option<anyhow::Error>,
+}
+
 /// Runs a WebAssembly test script file
 #[derive(StructOpt)]
 #[structopt(
@@ -18,6 +48,19 @@ pub struct WastCommand {
     #[structopt(flatten)]
     common: CommonOptions,
 
+    /// Only run test scripts whose path contains this string
+    #[structopt(long, value_name = "NAME")]
+    filter: Option<String>,
+
+    /// The format of the results: `text` or `junit`
+    #[structopt(
+        long,
+        value_name = "FORMAT",
+        default_value = "text",
+        possible_values = &["text", "junit"],
+    )]
+    format: Format,
+
     /// The path of the WebAssembly test script to run
     #[structopt(required = true, value_name = "SCRIPT_FILE", parse(from_os_str))]
     scripts: Vec<PathBuf>,
@@ -33,7 +76,7 @@ impl WastCommand {
             pretty_env_logger::init();
         }
 
-        let config = self.common.config()?;
+        let config = self.common.config(None)?;
         let store = Store::new(&Engine::new(&config));
         let mut wast_context = WastContext::new(store);
 
@@ -41,12 +84,88 @@ impl WastCommand {
             .register_spectest()
             .expect("error instantiating \"spectest\"");
 
+        let mut results = Vec::new();
         for script in self.scripts.iter() {
-            wast_context
+            if let Some(filter) = &self.filter {
+                if !script.to_string_lossy().contains(filter.as_str()) {
+                    continue;
+                }
+            }
+
+            let start = Instant::now();
+            let result = wast_context
                 .run_file(script)
-                .with_context(|| format!("failed to run script file '{}'", script.display()))?
+                .with_context(|| format!("failed to run script file '{}'", script.display()));
+            match self.format {
+                Format::Text => result?,
+                Format::Junit => results.push(ScriptResult {
+                    name: script.display().to_string(),
+                    duration: start.elapsed(),
+                    error: result.err(),
+                }),
+            }
+        }
+
+        if self.format == Format::Junit {
+            print!("{}", junit_report(&results));
+            let failed = results.iter().filter(|r| r.error.is_some()).count();
+            if failed > 0 {
+                bail!("{} of {} test scripts failed", failed, results.len());
+            }
         }
 
         Ok(())
     }
 }
+
+/// Renders `results` as a JUnit XML report with one test case per script.
+fn junit_report(results: &[ScriptResult]) -> String {
+    let failed = results.iter().filter(|r| r.error.is_some()).count();
+    let total: Duration = results.iter().map(|r| r.duration).sum();
+    let mut xml = String::new();
+    xml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str("<testsuites>\n");
+    xml.push_str(&format!(
+        "  <testsuite name=\"wast\" tests=\"{}\" failures=\"{}\" time=\"{:.3}\">\n",
+        results.len(),
+        failed,
+        total.as_secs_f64()
+    ));
+    for result in results {
+        let attrs = format!(
+            "name=\"{}\" classname=\"wast\" time=\"{:.3}\"",
+            xml_escape(&result.name),
+            result.duration.as_secs_f64()
+        );
+        match &result.error {
+            None => xml.push_str(&format!("    <testcase {}/>\n", attrs)),
+            Some(e) => {
+                xml.push_str(&format!("    <testcase {}>\n", attrs));
+                xml.push_str(&format!(
+                    "      <failure message=\"{}\">{}</failure>\n",
+                    xml_escape(&e.to_string()),
+                    xml_escape(&format!("{:?}", e))
+                ));
+                xml.push_str("    </testcase>\n");
+            }
+        }
+    }
+    xml.push_str("  </testsuite>\n");
+    xml.push_str("</testsuites>\n");
+    xml
+}
+
+fn xml_escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
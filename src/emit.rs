@@ -0,0 +1,107 @@
+//! Dumping the code generated for a module, for investigating codegen issues.
+
+use anyhow::{bail, Context as _, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use structopt::StructOpt;
+use wasmtime::{Engine, FunctionCode, Module};
+
+/// Options for writing out the code generated for a module.
+#[derive(StructOpt)]
+pub struct EmitOptions {
+    /// Write the final Cranelift IR of each compiled function into DIR
+    #[structopt(long, value_name = "DIR", parse(from_os_str))]
+    emit_clif: Option<PathBuf>,
+
+    /// Write a disassembly of each compiled function's machine code into DIR
+    #[structopt(long, value_name = "DIR", parse(from_os_str))]
+    emit_disas: Option<PathBuf>,
+
+    /// Only emit code for the function with this index or name (may be
+    /// repeated); by default code is emitted for every function
+    #[structopt(long = "emit-func", value_name = "FUNC", number_of_values = 1)]
+    emit_funcs: Vec<String>,
+}
+
+impl EmitOptions {
+    /// Compiles the module at `path` and writes out the requested code for its
+    /// functions, if any was requested.
+    pub fn emit(&self, engine: &Engine, path: &Path) -> Result<()> {
+        if self.emit_clif.is_none() && self.emit_disas.is_none() {
+            return Ok(());
+        }
+
+        let wasm = wat::parse_file(path)?;
+        let funcs = Module::compiled_functions(engine, &wasm)
+            .with_context(|| format!("failed to compile `{}`", path.display()))?;
+
+        for dir in self.emit_clif.iter().chain(&self.emit_disas) {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("failed to create directory `{}`", dir.display()))?;
+        }
+
+        let mut emitted = 0;
+        for func in funcs.iter().filter(|f| self.selected(f)) {
+            if let Some(dir) = &self.emit_clif {
+                if let Some(ir) = func.ir() {
+                    write(dir, func, "clif", ir)?;
+                }
+            }
+            if let Some(dir) = &self.emit_disas {
+                write(dir, func, "s", &disassemble(func)?)?;
+            }
+            emitted += 1;
+        }
+
+        if emitted == 0 && !self.emit_funcs.is_empty() {
+            bail!(
+                "no functions in `{}` match `--emit-func {}`",
+                path.display(),
+                self.emit_funcs.join("` or `--emit-func ")
+            );
+        }
+        Ok(())
+    }
+
+    fn selected(&self, func: &FunctionCode) -> bool {
+        self.emit_funcs.is_empty()
+            || self
+                .emit_funcs
+                .iter()
+                .any(|f| f == &func.index().to_string() || Some(f.as_str()) == func.name())
+    }
+}
+
+/// Writes `contents` for `func` into `dir`, with a header naming the function.
+fn write(dir: &Path, func: &FunctionCode, extension: &str, contents: &str) -> Result<()> {
+    let mut file_name = if func.module_index() == 0 {
+        format!("func{}", func.index())
+    } else {
+        format!("module{}-func{}", func.module_index(), func.index())
+    };
+    file_name.push('.');
+    file_name.push_str(extension);
+    let path = dir.join(file_name);
+
+    let mut header = format!("; wasm function {}", func.index());
+    if let Some(name) = func.name() {
+        header.push_str(&format!(" (${})", name));
+    }
+    if func.module_index() != 0 {
+        header.push_str(&format!(" in module {}", func.module_index()));
+    }
+    header.push('\n');
+
+    fs::write(&path, header + contents)
+        .with_context(|| format!("failed to write `{}`", path.display()))
+}
+
+#[cfg(feature = "disas")]
+fn disassemble(func: &FunctionCode) -> Result<String> {
+    func.disassembly()
+}
+
+#[cfg(not(feature = "disas"))]
+fn disassemble(_func: &FunctionCode) -> Result<String> {
+    bail!("`--emit-disas` requires wasmtime to be built with the `disas` feature")
+}
@@ -24,15 +24,137 @@
 )]
 
 pub mod commands;
+mod emit;
 mod obj;
 
-use anyhow::{bail, Result};
+use anyhow::{bail, Context as _, Result};
+use std::fs;
 use std::path::PathBuf;
-use structopt::StructOpt;
+use structopt::{
+    clap::{App, ErrorKind},
+    StructOpt,
+};
 use wasmtime::{Config, ProfilingStrategy, Strategy};
 
 pub use obj::compile_to_obj;
 
+/// How deeply flags files may include other flags files.
+const MAX_FLAGS_FILE_DEPTH: usize = 16;
+
+/// Expands each `@FILE` argument after the program name into the arguments
+/// listed in FILE, one per line.
+///
+/// Empty lines and lines starting with `#` are skipped, and flags files may
+/// themselves contain `@FILE` arguments. An argument that starts with a
+/// literal `@` can be passed by doubling it, as in `@@name`.
+///
+/// Only the arguments meant for wasmtime itself are expanded: everything
+/// after the module path (the first positional argument of the subcommand)
+/// or after `--` is passed through untouched, since it belongs to the guest.
+/// `app` is used to tell which options take a separate value.
+pub fn expand_flags_files(
+    app: &App<'static, 'static>,
+    args: impl IntoIterator<Item = String>,
+) -> Result<Vec<String>> {
+    let mut args = args.into_iter();
+    let mut expander = FlagsFileExpander {
+        app,
+        expanded: args.next().into_iter().collect(),
+        subcommand: None,
+        seen_positional: false,
+        pending_value: false,
+        done: false,
+    };
+    expander.expand(args, 0)?;
+    Ok(expander.expanded)
+}
+
+struct FlagsFileExpander<'a> {
+    app: &'a App<'static, 'static>,
+    expanded: Vec<String>,
+    /// The subcommand named by the first positional argument, if any.
+    subcommand: Option<String>,
+    seen_positional: bool,
+    /// Whether the previous argument was an option expecting a value.
+    pending_value: bool,
+    /// Whether the module path or `--` has been reached.
+    done: bool,
+}
+
+impl FlagsFileExpander<'_> {
+    fn expand(&mut self, args: impl IntoIterator<Item = String>, depth: usize) -> Result<()> {
+        for arg in args {
+            if self.done {
+                self.expanded.push(arg);
+            } else if self.pending_value {
+                self.pending_value = false;
+                self.expanded.push(arg);
+            } else if let Some(rest) = arg.strip_prefix("@@") {
+                self.positional(format!("@{}", rest));
+            } else if let Some(path) = arg.strip_prefix('@') {
+                if depth == MAX_FLAGS_FILE_DEPTH {
+                    bail!("flags files nested too deeply at `{}`", path);
+                }
+                let contents = fs::read_to_string(path)
+                    .with_context(|| format!("failed to read flags file `{}`", path))?;
+                let lines = contents
+                    .lines()
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                    .map(str::to_string)
+                    .collect::<Vec<_>>();
+                self.expand(lines, depth + 1)?;
+            } else if arg == "--" {
+                self.done = true;
+                self.expanded.push(arg);
+            } else if arg.starts_with("--") {
+                self.pending_value = !arg.contains('=') && self.takes_value(&arg);
+                self.expanded.push(arg);
+            } else if arg.starts_with('-') && arg.len() > 1 {
+                self.pending_value = arg.len() == 2 && self.takes_value(&arg);
+                self.expanded.push(arg);
+            } else {
+                self.positional(arg);
+            }
+        }
+        Ok(())
+    }
+
+    /// Records a positional argument, which is either the subcommand or the
+    /// module path that ends expansion.
+    fn positional(&mut self, arg: String) {
+        let first = !self.seen_positional;
+        self.seen_positional = true;
+        if first && self.is_subcommand(&arg) {
+            self.subcommand = Some(arg.clone());
+        } else {
+            self.done = true;
+        }
+        self.expanded.push(arg);
+    }
+
+    /// Returns whether `name` is one of the subcommands, by checking whether
+    /// clap accepts `--help` after it.
+    fn is_subcommand(&self, name: &str) -> bool {
+        let args = ["wasmtime", name, "--help"];
+        match self.app.clone().get_matches_from_safe(&args) {
+            Err(e) => e.kind == ErrorKind::HelpDisplayed,
+            Ok(_) => false,
+        }
+    }
+
+    /// Returns whether `option` of the current subcommand takes a value in
+    /// the following argument, by asking clap to parse it on its own.
+    fn takes_value(&self, option: &str) -> bool {
+        let subcommand = self.subcommand.as_deref().unwrap_or("run");
+        let args = ["wasmtime", subcommand, option];
+        match self.app.clone().get_matches_from_safe(&args) {
+            Err(e) => e.kind == ErrorKind::EmptyValue,
+            Ok(_) => false,
+        }
+    }
+}
+
 fn pick_compilation_strategy(cranelift: bool, lightbeam: bool) -> Result<Strategy> {
     Ok(match (lightbeam, cranelift) {
         (true, false) => Strategy::Lightbeam,
@@ -127,6 +249,10 @@ struct CommonOptions {
     #[structopt(long)]
     enable_multi_memory: bool,
 
+    /// Enable support for the memory64 proposal
+    #[structopt(long)]
+    enable_memory64: bool,
+
     /// Enable support for the module-linking proposal
     #[structopt(long)]
     enable_module_linking: bool,
@@ -151,8 +277,8 @@ struct CommonOptions {
     #[structopt(short = "O", long)]
     optimize: bool,
 
-    /// Optimization level for generated functions (0 (none), 1, 2 (most), or s
-    /// (size))
+    /// Optimization level for generated functions (0 (none), 1, 2 (most), s
+    /// (speed and size), or size (smallest code))
     #[structopt(
         long,
         parse(try_from_str = parse_opt_level),
@@ -181,16 +307,28 @@ struct CommonOptions {
     #[structopt(long)]
     enable_cranelift_debug_verifier: bool,
 
+    /// Compile every module twice and fail unless the generated code is
+    /// identical (doubles compile time)
+    #[structopt(long)]
+    verify_determinism: bool,
+
     /// Enable Cranelift's internal NaN canonicalization
     #[structopt(long)]
     enable_cranelift_nan_canonicalization: bool,
 }
 
 impl CommonOptions {
-    fn config(&self) -> Result<Config> {
+    fn config(&self, target: Option<&str>) -> Result<Config> {
         let mut config = Config::new();
+
+        // Set the target before setting any cranelift options
+        if let Some(target) = target {
+            config.target(target)?;
+        }
+
         config
             .cranelift_debug_verifier(self.enable_cranelift_debug_verifier)
+            .verify_determinism(self.verify_determinism)
             .debug_info(self.debug_info)
             .wasm_simd(self.enable_simd || self.enable_all)
             .wasm_bulk_memory(self.enable_bulk_memory.unwrap_or(true) || self.enable_all)
@@ -202,6 +340,7 @@ impl CommonOptions {
             .wasm_multi_value(self.enable_multi_value.unwrap_or(true) || self.enable_all)
             .wasm_threads(self.enable_threads || self.enable_all)
             .wasm_multi_memory(self.enable_multi_memory || self.enable_all)
+            .wasm_memory64(self.enable_memory64 || self.enable_all)
             .wasm_module_linking(self.enable_module_linking || self.enable_all)
             .cranelift_opt_level(self.opt_level())
             .strategy(pick_compilation_strategy(self.cranelift, self.lightbeam)?)?
@@ -245,11 +384,12 @@ impl CommonOptions {
 fn parse_opt_level(opt_level: &str) -> Result<wasmtime::OptLevel> {
     match opt_level {
         "s" => Ok(wasmtime::OptLevel::SpeedAndSize),
+        "size" => Ok(wasmtime::OptLevel::Size),
         "0" => Ok(wasmtime::OptLevel::None),
         "1" => Ok(wasmtime::OptLevel::Speed),
         "2" => Ok(wasmtime::OptLevel::Speed),
         other => bail!(
-            "unknown optimization level `{}`, only 0,1,2,s accepted",
+            "unknown optimization level `{}`, only 0,1,2,s,size accepted",
             other
         ),
     }
@@ -1,6 +1,6 @@
 use anyhow::{bail, Context as _, Result};
 use object::write::Object;
-use target_lexicon::Triple;
+use target_lexicon::{Architecture, Triple};
 use wasmparser::WasmFeatures;
 use wasmtime::Strategy;
 use wasmtime_environ::{settings, settings::Configurable, ModuleEnvironment, Tunables};
@@ -14,6 +14,7 @@ pub fn compile_to_obj(
     enable_simd: bool,
     opt_level: wasmtime::OptLevel,
     debug_info: bool,
+    pic: bool,
 ) -> Result<Object> {
     let isa_builder = match target {
         Some(target) => native::lookup(target.clone())?,
@@ -26,6 +27,19 @@ pub fn compile_to_obj(
     // we get the proper one if code traps.
     flag_builder.enable("avoid_div_traps").unwrap();
 
+    if pic {
+        // Only the x86 backends know how to emit position-independent code;
+        // the others would still embed absolute addresses.
+        let arch = match target {
+            Some(target) => target.architecture,
+            None => Triple::host().architecture,
+        };
+        if arch != Architecture::X86_64 {
+            bail!("position-independent code is not supported on {}", arch);
+        }
+        flag_builder.enable("is_pic").unwrap();
+    }
+
     if enable_simd {
         flag_builder.enable("enable_simd").unwrap();
         features.simd = true;
@@ -39,6 +53,9 @@ pub fn compile_to_obj(
         wasmtime::OptLevel::SpeedAndSize => {
             flag_builder.set("opt_level", "speed_and_size").unwrap();
         }
+        wasmtime::OptLevel::Size => {
+            flag_builder.set("opt_level", "size").unwrap();
+        }
         other => bail!("unknown optimization level {:?}", other),
     }
 
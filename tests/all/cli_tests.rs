@@ -1,11 +1,11 @@
 use anyhow::{bail, Result};
 use std::io::Write;
 use std::path::Path;
-use std::process::{Command, Output};
+use std::process::{Command, Output, Stdio};
 use tempfile::NamedTempFile;
 
-// Run the wasmtime CLI with the provided args and return the `Output`.
-fn run_wasmtime_for_output(args: &[&str]) -> Result<Output> {
+// Build a `Command` which runs the wasmtime CLI with the provided args.
+fn wasmtime_command(args: &[&str]) -> Result<Command> {
     let runner = std::env::vars()
         .filter(|(k, _v)| k.starts_with("CARGO_TARGET") && k.ends_with("RUNNER"))
         .next();
@@ -28,7 +28,25 @@ fn run_wasmtime_for_output(args: &[&str]) -> Result<Output> {
     } else {
         Command::new(&me)
     };
-    cmd.args(args).output().map_err(Into::into)
+    cmd.args(args);
+    Ok(cmd)
+}
+
+// Run the wasmtime CLI with the provided args and return the `Output`.
+fn run_wasmtime_for_output(args: &[&str]) -> Result<Output> {
+    wasmtime_command(args)?.output().map_err(Into::into)
+}
+
+// Run the wasmtime CLI with the provided args, feeding it `stdin`, and return
+// the `Output`.
+fn run_wasmtime_with_stdin(args: &[&str], stdin: &str) -> Result<Output> {
+    let mut child = wasmtime_command(args)?
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    child.stdin.take().unwrap().write_all(stdin.as_bytes())?;
+    child.wait_with_output().map_err(Into::into)
 }
 
 // Run the wasmtime CLI with the provided args and, if it succeeds, return
@@ -151,11 +169,11 @@ fn timeout_in_start() -> Result<()> {
         "1ms",
         "--disable-cache",
     ])?;
-    assert!(!output.status.success());
+    assert_eq!(output.status.code(), Some(124));
     assert_eq!(output.stdout, b"");
     let stderr = String::from_utf8_lossy(&output.stderr);
     assert!(
-        stderr.contains("wasm trap: interrupt"),
+        stderr.contains("wasm trap: deadline exceeded"),
         "bad stderr: {}",
         stderr
     );
@@ -172,17 +190,149 @@ fn timeout_in_invoke() -> Result<()> {
         "1ms",
         "--disable-cache",
     ])?;
+    assert_eq!(output.status.code(), Some(124));
+    assert_eq!(output.stdout, b"");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("wasm trap: deadline exceeded"),
+        "bad stderr: {}",
+        stderr
+    );
+    Ok(())
+}
+
+#[test]
+fn fuel_in_start() -> Result<()> {
+    let wasm = build_wasm("tests/wasm/iloop-start.wat")?;
+    let output = run_wasmtime_for_output(&[
+        "run",
+        wasm.path().to_str().unwrap(),
+        "--fuel",
+        "1000",
+        "--fuel-report",
+        "--disable-cache",
+    ])?;
     assert!(!output.status.success());
     assert_eq!(output.stdout, b"");
     let stderr = String::from_utf8_lossy(&output.stderr);
     assert!(
-        stderr.contains("wasm trap: interrupt"),
+        stderr.contains("all fuel consumed"),
+        "bad stderr: {}",
+        stderr
+    );
+    assert!(stderr.contains("fuel consumed: "), "bad stderr: {}", stderr);
+    Ok(())
+}
+
+#[test]
+fn fuel_report() -> Result<()> {
+    let wasm = build_wasm("tests/wasm/minimal-command.wat")?;
+    let output = run_wasmtime_for_output(&[
+        "run",
+        wasm.path().to_str().unwrap(),
+        "--fuel",
+        "1000",
+        "--fuel-report",
+        "--disable-cache",
+    ])?;
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(stderr, "fuel consumed: 1\n");
+    Ok(())
+}
+
+#[test]
+fn profile_guest() -> Result<()> {
+    let wasm = build_wasm("tests/wasm/iloop-start.wat")?;
+    let profile = NamedTempFile::new()?;
+    let output = run_wasmtime_for_output(&[
+        "run",
+        wasm.path().to_str().unwrap(),
+        "--fuel",
+        "1000",
+        "--profile",
+        &format!("guest,{},100", profile.path().display()),
+        "--disable-cache",
+    ])?;
+    assert!(!output.status.success());
+    let samples = std::fs::read_to_string(profile.path())?;
+    assert_eq!(samples, "wasm-function[0] 10\n");
+    Ok(())
+}
+
+#[test]
+fn coredump_on_trap() -> Result<()> {
+    let wasm = build_wasm("tests/wasm/unreachable.wat")?;
+    let dir = tempfile::tempdir()?;
+    let template = dir.path().join("{module}.coredump");
+    let output = run_wasmtime_for_output(&[
+        "run",
+        wasm.path().to_str().unwrap(),
+        "--coredump-on-trap",
+        template.to_str().unwrap(),
+        "--disable-cache",
+    ])?;
+    assert!(!output.status.success());
+
+    let module = wasm.path().file_stem().unwrap().to_str().unwrap();
+    let path = dir.path().join(format!("{}.coredump", module));
+    let coredump = std::fs::read(&path)?;
+    assert!(coredump.starts_with(b"\0asm"));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("wrote coredump to"),
         "bad stderr: {}",
         stderr
     );
     Ok(())
 }
 
+// `--message-format=json` reports traps and the exit status as JSON.
+#[test]
+fn message_format_json() -> Result<()> {
+    let wasm = build_wasm("tests/wasm/unreachable.wat")?;
+    let output = run_wasmtime_for_output(&[
+        "run",
+        "--disable-cache",
+        "--message-format",
+        "json",
+        wasm.path().to_str().unwrap(),
+    ])?;
+    assert!(!output.status.success());
+    let messages = String::from_utf8(output.stderr)?
+        .lines()
+        .map(serde_json::from_str)
+        .collect::<Result<Vec<serde_json::Value>, _>>()?;
+    assert_eq!(messages.len(), 2, "bad messages: {:?}", messages);
+    assert_eq!(messages[0]["type"], "trap");
+    assert_eq!(messages[0]["code"], "UnreachableCodeReached");
+    assert_eq!(messages[0]["message"], "wasm trap: unreachable");
+    assert_eq!(messages[0]["frames"][0]["func_index"], 0);
+    assert_eq!(messages[1]["type"], "exit");
+    assert_eq!(messages[1]["status"], output.status.code().unwrap());
+
+    // Errors that aren't traps, like a module that fails to validate, are
+    // reported too.
+    let mut invalid = NamedTempFile::new()?;
+    invalid.write_all(b"\0asm\x01\0\0\0\xff")?;
+    let output = run_wasmtime_for_output(&[
+        "run",
+        "--disable-cache",
+        "--message-format=json",
+        invalid.path().to_str().unwrap(),
+    ])?;
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8(output.stderr)?;
+    let messages = stderr
+        .lines()
+        .map(serde_json::from_str)
+        .collect::<Result<Vec<serde_json::Value>, _>>()?;
+    assert_eq!(messages[0]["type"], "error", "bad stderr: {}", stderr);
+    assert_eq!(messages[1]["type"], "exit");
+    assert_eq!(messages[1]["status"], 1);
+    Ok(())
+}
+
 // Exit with a valid non-zero exit code, snapshot0 edition.
 #[test]
 fn exit2_wasi_snapshot0() -> Result<()> {
@@ -275,6 +425,69 @@ fn minimal_reactor() -> Result<()> {
     Ok(())
 }
 
+// `--invoke` accepts arguments of every numeric type, optionally prefixed
+// with their type, and prints every result.
+#[test]
+fn invoke_typed_args() -> Result<()> {
+    let wasm = build_wasm("tests/wasm/invoke_typed.wat")?;
+    let wasm = wasm.path().to_str().unwrap();
+    let stdout = run_wasmtime(&[
+        "run",
+        "--disable-cache",
+        "--invoke",
+        "echo",
+        wasm,
+        "i32:-5",
+        "0x10",
+        "f32:1.5",
+        "2.25",
+        "v128:0x1",
+    ])?;
+    assert_eq!(
+        stdout.lines().collect::<Vec<_>>(),
+        [
+            "-5",
+            "16",
+            "1.5",
+            "2.25",
+            "0x00000000000000000000000000000001"
+        ]
+    );
+
+    // The type of an argument has to match its parameter's.
+    assert!(run_wasmtime(&[
+        "run",
+        "--disable-cache",
+        "--invoke",
+        "echo",
+        wasm,
+        "i64:1",
+        "2",
+        "3",
+        "4",
+        "5",
+    ])
+    .is_err());
+
+    // Integers out of range for their type are rejected rather than truncated.
+    for (i32, i64) in [("4294967296", "0"), ("0", "0x10000000000000000")].iter() {
+        assert!(run_wasmtime(&[
+            "run",
+            "--disable-cache",
+            "--invoke",
+            "echo",
+            wasm,
+            i32,
+            i64,
+            "3",
+            "4",
+            "5",
+        ])
+        .is_err());
+    }
+    Ok(())
+}
+
 // Attempt to call invoke on a command.
 #[test]
 fn command_invoke() -> Result<()> {
@@ -321,6 +534,27 @@ fn greeter() -> Result<()> {
     Ok(())
 }
 
+// Run the greeter test with a chain of preloads, where a later preload imports
+// from an earlier one.
+#[test]
+fn greeter_preload_chain() -> Result<()> {
+    let wasm = build_wasm("tests/wasm/greeter_command.wat")?;
+    let stdout = run_wasmtime(&[
+        "run",
+        wasm.path().to_str().unwrap(),
+        "--disable-cache",
+        "--preload",
+        "base=tests/wasm/greeter_reactor.wat",
+        "--preload",
+        "reactor=tests/wasm/greeter_middleware.wat",
+    ])?;
+    assert_eq!(
+        stdout,
+        "Hello _initialize\nHello _start\nHello middleware\nHello greet\nHello done\n"
+    );
+    Ok(())
+}
+
 // Run the greeter test, but this time preload a command.
 #[test]
 fn greeter_preload_command() -> Result<()> {
@@ -361,3 +595,748 @@ fn exit_with_saved_fprs() -> Result<()> {
     assert!(output.stdout.is_empty());
     Ok(())
 }
+
+// Compile a module ahead of time into the serialized module format.
+#[test]
+fn compile_to_cwasm() -> Result<()> {
+    let output = NamedTempFile::new()?;
+    run_wasmtime(&[
+        "compile",
+        "--disable-cache",
+        "tests/wasm/simple.wat",
+        "-o",
+        output.path().to_str().unwrap(),
+    ])?;
+    assert!(std::fs::metadata(output.path())?.len() > 0);
+
+    // An unknown target is rejected.
+    assert!(run_wasmtime(&[
+        "compile",
+        "--disable-cache",
+        "--target",
+        "not-a-target",
+        "tests/wasm/simple.wat",
+    ])
+    .is_err());
+    Ok(())
+}
+
+// The Cranelift IR of selected functions can be written out while compiling.
+#[test]
+fn compile_emit_clif() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let clif_dir = dir.path().join("clif");
+    let output = dir.path().join("counter.cwasm");
+    run_wasmtime(&[
+        "compile",
+        "--disable-cache",
+        "--emit-clif",
+        clif_dir.to_str().unwrap(),
+        "--emit-func",
+        "1",
+        "tests/wasm/counter.wat",
+        "-o",
+        output.to_str().unwrap(),
+    ])?;
+    let files = std::fs::read_dir(&clif_dir)?
+        .map(|e| Ok(e?.file_name().into_string().unwrap()))
+        .collect::<Result<Vec<_>>>()?;
+    assert_eq!(files, ["func1.clif"]);
+    let clif = std::fs::read_to_string(clif_dir.join("func1.clif"))?;
+    assert!(
+        clif.starts_with("; wasm function 1\n"),
+        "bad clif: {}",
+        clif
+    );
+    assert!(clif.contains("function u0:1("), "bad clif: {}", clif);
+
+    // Selecting a function which doesn't exist is an error.
+    assert!(run_wasmtime(&[
+        "compile",
+        "--disable-cache",
+        "--emit-clif",
+        clif_dir.to_str().unwrap(),
+        "--emit-func",
+        "7",
+        "tests/wasm/counter.wat",
+        "-o",
+        output.to_str().unwrap(),
+    ])
+    .is_err());
+    Ok(())
+}
+
+// Run a module which was compiled ahead of time with `wasmtime compile`.
+#[test]
+fn run_precompiled() -> Result<()> {
+    let cwasm = NamedTempFile::new()?;
+    let cwasm_path = cwasm.path().to_str().unwrap();
+    run_wasmtime(&[
+        "compile",
+        "--disable-cache",
+        "tests/wasm/simple.wat",
+        "-o",
+        cwasm_path,
+    ])?;
+
+    let stdout = run_wasmtime(&[
+        "run",
+        "--allow-precompiled",
+        "--disable-cache",
+        "--invoke",
+        "simple",
+        cwasm_path,
+        "4",
+    ])?;
+    assert_eq!(stdout, "4\n");
+
+    // Precompiled modules must be explicitly allowed.
+    let output = run_wasmtime_for_output(&[
+        "run",
+        "--disable-cache",
+        "--invoke",
+        "simple",
+        cwasm_path,
+        "4",
+    ])?;
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--allow-precompiled"));
+
+    // Modules compiled with a different configuration are rejected.
+    let output = run_wasmtime_for_output(&[
+        "run",
+        "--allow-precompiled",
+        "--disable-cache",
+        "--opt-level",
+        "0",
+        "--invoke",
+        "simple",
+        cwasm_path,
+        "4",
+    ])?;
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("wasmtime compile"));
+    Ok(())
+}
+
+// A host directory can be exposed under a different path in the guest.
+#[test]
+fn mapdir_guest_path() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let mapping = format!("/guest/data::{}", dir.path().to_str().unwrap());
+    let wasm = build_wasm("tests/wasm/print_preopen_name.wat")?;
+    let stdout = run_wasmtime(&[
+        "run",
+        "--disable-cache",
+        "--mapdir",
+        &mapping,
+        wasm.path().to_str().unwrap(),
+    ])?;
+    assert_eq!(stdout, "/guest/data");
+
+    // The mapping needs both a guest and a host path.
+    assert!(run_wasmtime(&[
+        "run",
+        "--disable-cache",
+        "--mapdir",
+        "/guest/data",
+        wasm.path().to_str().unwrap(),
+    ])
+    .is_err());
+    Ok(())
+}
+
+// Host environment variables can be passed through by name or by prefix.
+#[test]
+fn env_passthrough() -> Result<()> {
+    std::env::set_var("WASMTIME_TEST_ENV_A", "a");
+    std::env::set_var("WASMTIME_TEST_ENV_B", "b");
+    let wasm = build_wasm("tests/wasm/print_env.wat")?;
+    let wasm = wasm.path().to_str().unwrap();
+
+    let stdout = run_wasmtime(&[
+        "run",
+        "--disable-cache",
+        "--env",
+        "WASMTIME_TEST_ENV_*",
+        "--env",
+        "WASMTIME_TEST_ENV_B=override",
+        "--env",
+        "WASMTIME_TEST_ENV_UNSET",
+        "--env",
+        "PLAIN=1",
+        wasm,
+    ])?;
+    let mut lines = stdout.lines().collect::<Vec<_>>();
+    lines.sort();
+    assert_eq!(
+        lines,
+        [
+            "PLAIN=1",
+            "WASMTIME_TEST_ENV_A=a",
+            "WASMTIME_TEST_ENV_B=override"
+        ]
+    );
+
+    let stdout = run_wasmtime(&["run", "--disable-cache", "--env-inherit", wasm])?;
+    assert!(stdout.lines().any(|l| l == "WASMTIME_TEST_ENV_A=a"));
+    assert!(stdout.lines().any(|l| l == "WASMTIME_TEST_ENV_B=b"));
+    Ok(())
+}
+
+// A listening socket opened with `--tcplisten` is passed to the guest after
+// the preopened directories.
+#[test]
+#[cfg(unix)]
+fn tcplisten_preopen() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let wasm = build_wasm("tests/wasm/fd_filetype.wat")?;
+    let stdout = run_wasmtime(&[
+        "run",
+        "--disable-cache",
+        "--dir",
+        dir.path().to_str().unwrap(),
+        "--tcplisten",
+        "127.0.0.1:0",
+        wasm.path().to_str().unwrap(),
+        "--invoke",
+        "filetype",
+        "4",
+    ])?;
+    // `SOCKET_STREAM`
+    assert_eq!(stdout, "6\n");
+    Ok(())
+}
+
+// `--watch` runs the module again once its file changes.
+#[test]
+fn watch_reruns_on_change() -> Result<()> {
+    use std::io::{BufRead, BufReader};
+
+    let wasm = build_wasm("tests/wasm/hello_wasi_snapshot1.wat")?;
+    let mut child = wasmtime_command(&[
+        "run",
+        "--disable-cache",
+        "--watch",
+        wasm.path().to_str().unwrap(),
+    ])?
+    .stdout(Stdio::piped())
+    .stderr(Stdio::piped())
+    .spawn()?;
+    let mut stdout = BufReader::new(child.stdout.take().unwrap()).lines();
+    let mut stderr = BufReader::new(child.stderr.take().unwrap()).lines();
+    let mut wait_for_run = || -> Result<()> {
+        assert_eq!(stdout.next().unwrap()?, "Hello, world!");
+        let line = stderr.next().unwrap()?;
+        assert!(line.starts_with("watching"), "bad stderr: {}", line);
+        Ok(())
+    };
+
+    let result = wait_for_run().and_then(|()| {
+        // Leave enough time for the modification time to change.
+        std::thread::sleep(std::time::Duration::from_secs(1));
+        std::fs::write(wasm.path(), std::fs::read(wasm.path())?)?;
+        wait_for_run()
+    });
+    child.kill()?;
+    child.wait()?;
+    result
+}
+
+// `wasmtime serve` passes the request body to the module on stdin and sends
+// back what the module writes to stdout.
+#[test]
+fn serve_echo() -> Result<()> {
+    use std::io::{BufRead, BufReader, Read};
+    use std::net::TcpStream;
+
+    let wasm = build_wasm("tests/wasm/http_echo.wat")?;
+    let mut child = wasmtime_command(&[
+        "serve",
+        "--disable-cache",
+        "--addr",
+        "127.0.0.1:0",
+        wasm.path().to_str().unwrap(),
+    ])?
+    .stderr(Stdio::piped())
+    .spawn()?;
+    let mut stderr = BufReader::new(child.stderr.take().unwrap());
+    let mut line = String::new();
+
+    let result = (|| -> Result<(String, String, String)> {
+        stderr.read_line(&mut line)?;
+        let addr = match line.trim_end().strip_prefix("Serving on http://") {
+            Some(addr) => addr.trim_end_matches('/'),
+            None => bail!("bad stderr: {}", line),
+        };
+        // A client that never finishes its request doesn't block others.
+        let mut stalled = TcpStream::connect(addr)?;
+        stalled.write_all(b"POST / HTTP/1.1\r\n")?;
+
+        let mut stream = TcpStream::connect(addr)?;
+        stream.write_all(b"POST / HTTP/1.1\r\nContent-Length: 5\r\n\r\nhello")?;
+        let mut response = String::new();
+        stream.read_to_string(&mut response)?;
+
+        let mut stream = TcpStream::connect(addr)?;
+        stream.write_all(b"POST / HTTP/1.1\r\nContent-Length: 99999999999\r\n\r\n")?;
+        let mut too_large = String::new();
+        stream.read_to_string(&mut too_large)?;
+
+        let mut stream = TcpStream::connect(addr)?;
+        stream.write_all(b"POST / HTTP/1.1\r\nContent-Length: 0\r\n")?;
+        stream.shutdown(std::net::Shutdown::Write)?;
+        let mut truncated = String::new();
+        stream.read_to_string(&mut truncated)?;
+        Ok((response, too_large, truncated))
+    })();
+    child.kill()?;
+    child.wait()?;
+
+    let (response, too_large, truncated) = result?;
+    assert!(
+        truncated.starts_with("HTTP/1.1 400 Bad Request\r\n"),
+        "bad response: {}",
+        truncated
+    );
+    assert!(
+        too_large.starts_with("HTTP/1.1 413 Payload Too Large\r\n"),
+        "bad response: {}",
+        too_large
+    );
+    assert!(
+        response.starts_with("HTTP/1.1 200 OK\r\n"),
+        "bad response: {}",
+        response
+    );
+    assert!(
+        response.contains("Content-Type: text/plain\r\n"),
+        "bad response: {}",
+        response
+    );
+    assert!(
+        response.ends_with("\r\n\r\nhello"),
+        "bad response: {}",
+        response
+    );
+    Ok(())
+}
+
+// `wasmtime serve` stops modules that run for longer than `--timeout`.
+#[test]
+fn serve_timeout() -> Result<()> {
+    use std::io::{BufRead, BufReader, Read};
+    use std::net::TcpStream;
+
+    let wasm = build_wasm("tests/wasm/http_loop.wat")?;
+    let mut child = wasmtime_command(&[
+        "serve",
+        "--disable-cache",
+        "--addr",
+        "127.0.0.1:0",
+        "--timeout",
+        "100ms",
+        wasm.path().to_str().unwrap(),
+    ])?
+    .stderr(Stdio::piped())
+    .spawn()?;
+    let mut stderr = BufReader::new(child.stderr.take().unwrap());
+    let mut line = String::new();
+
+    let result = (|| -> Result<String> {
+        stderr.read_line(&mut line)?;
+        let addr = match line.trim_end().strip_prefix("Serving on http://") {
+            Some(addr) => addr.trim_end_matches('/'),
+            None => bail!("bad stderr: {}", line),
+        };
+        let mut stream = TcpStream::connect(addr)?;
+        stream.write_all(b"GET / HTTP/1.1\r\n\r\n")?;
+        let mut response = String::new();
+        stream.read_to_string(&mut response)?;
+        Ok(response)
+    })();
+    child.kill()?;
+    child.wait()?;
+
+    let response = result?;
+    assert!(
+        response.starts_with("HTTP/1.1 500 Internal Server Error\r\n"),
+        "bad response: {}",
+        response
+    );
+    Ok(())
+}
+
+// The REPL keeps a single instance alive across commands.
+#[test]
+fn repl() -> Result<()> {
+    let wasm = build_wasm("tests/wasm/counter.wat")?;
+    let wasm = wasm.path().to_str().unwrap();
+    let script = format!(
+        "add 1 0x10\n\
+         incr\n\
+         call incr\n\
+         global count\n\
+         memory memory 16 5\n\
+         nope\n\
+         link other {}\n\
+         incr\n\
+         use main\n\
+         incr\n",
+        wasm
+    );
+    let output = run_wasmtime_with_stdin(&["repl", "--disable-cache", wasm], &script)?;
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout)?;
+    assert_eq!(
+        stdout.lines().collect::<Vec<_>>(),
+        [
+            "17: i32",
+            "1: i32",
+            "2: i32",
+            "2: i32",
+            "00000010  68 65 6c 6c 6f                                   |hello|",
+            "1: i32",
+            "3: i32",
+        ]
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("no export named `nope`"),
+        "bad stderr: {}",
+        stderr
+    );
+    Ok(())
+}
+
+// `wasmtime wast` can select scripts by name and report failures as JUnit.
+#[test]
+fn wast_filter_and_junit() -> Result<()> {
+    let scripts = ["tests/wasm/wast_pass.wast", "tests/wasm/wast_fail.wast"];
+
+    let mut args = vec!["wast", "--filter", "pass"];
+    args.extend(&scripts);
+    let stdout = run_wasmtime(&args)?;
+    assert_eq!(stdout, "");
+
+    let mut args = vec!["wast", "--format", "junit"];
+    args.extend(&scripts);
+    let output = run_wasmtime_for_output(&args)?;
+    assert!(!output.status.success());
+    let stdout = String::from_utf8(output.stdout)?;
+    assert!(stdout.starts_with("<?xml"), "bad stdout: {}", stdout);
+    assert!(stdout.contains("tests=\"2\" failures=\"1\""));
+    assert!(stdout.contains("<testcase name=\"tests/wasm/wast_pass.wast\" classname=\"wast\""));
+    assert!(stdout.contains("<failure message="));
+    Ok(())
+}
+
+// `wasmtime objdump` describes both wasm and precompiled modules.
+#[test]
+fn objdump() -> Result<()> {
+    let stdout = run_wasmtime(&["objdump", "--disable-cache", "tests/wasm/counter.wat"])?;
+    assert!(
+        stdout.contains("WebAssembly module"),
+        "bad stdout: {}",
+        stdout
+    );
+    assert!(stdout.contains("\nsections:\n"), "bad stdout: {}", stdout);
+    assert!(stdout.contains("  add: func (i32 i32) -> (i32)\n"));
+    assert!(stdout.contains("  count: global mut i32\n"));
+    assert!(stdout.contains("  memory: memory 1\n"));
+    assert!(stdout.contains("body size"));
+
+    let cwasm = NamedTempFile::new()?;
+    let cwasm_path = cwasm.path().to_str().unwrap();
+    run_wasmtime(&[
+        "compile",
+        "--disable-cache",
+        "tests/wasm/counter.wat",
+        "-o",
+        cwasm_path,
+    ])?;
+    let stdout = run_wasmtime(&["objdump", "--disable-cache", cwasm_path])?;
+    assert!(
+        stdout.contains("precompiled module"),
+        "bad stdout: {}",
+        stdout
+    );
+    assert!(stdout.contains("  add: func (i32 i32) -> (i32)\n"));
+    assert!(stdout.contains("code size"));
+    Ok(())
+}
+
+// `wasmtime settings` reflects the target and feature options it's given.
+#[test]
+fn settings() -> Result<()> {
+    let stdout = run_wasmtime(&["settings"])?;
+    assert!(
+        stdout.contains("Compiler settings:\n[shared]\n"),
+        "{}",
+        stdout
+    );
+    assert!(stdout.contains("Host CPU features:"), "{}", stdout);
+    assert!(
+        stdout.contains("  simd             disabled\n"),
+        "{}",
+        stdout
+    );
+
+    let stdout = run_wasmtime(&["settings", "--enable-simd"])?;
+    assert!(
+        stdout.contains("  simd             enabled\n"),
+        "{}",
+        stdout
+    );
+
+    // Only the host target is sure to be supported by this build.
+    let target = stdout
+        .lines()
+        .next()
+        .unwrap()
+        .trim_start_matches("Target: ");
+    let stdout = run_wasmtime(&["settings", "--target", target])?;
+    assert!(!stdout.contains("Host CPU features:"), "{}", stdout);
+    Ok(())
+}
+
+// `@FILE` arguments are replaced with the arguments listed in FILE.
+#[test]
+fn flags_file() -> Result<()> {
+    let wasm = build_wasm("tests/wasm/simple.wat")?;
+    let mut flags = NamedTempFile::new()?;
+    writeln!(flags, "# Options for running `simple`")?;
+    writeln!(flags, "--disable-cache")?;
+    writeln!(flags)?;
+    writeln!(flags, "--invoke")?;
+    writeln!(flags, "simple")?;
+    let flags = format!("@{}", flags.path().to_str().unwrap());
+    let stdout = run_wasmtime(&["run", &flags, wasm.path().to_str().unwrap(), "4"])?;
+    assert_eq!(stdout, "4\n");
+
+    assert!(run_wasmtime(&["run", "@/does/not/exist", wasm.path().to_str().unwrap()]).is_err());
+    Ok(())
+}
+
+// Arguments after the module belong to the guest, so `@` in them is left alone.
+#[test]
+fn flags_file_not_expanded_in_guest_args() -> Result<()> {
+    let wasm = build_wasm("tests/wasm/print_args.wat")?;
+    let path = wasm.path();
+    let wasm = path.to_str().unwrap();
+    let name = path.file_name().unwrap().to_str().unwrap();
+
+    let stdout = run_wasmtime(&["run", "--disable-cache", wasm, "@user", "@@x"])?;
+    assert_eq!(stdout, format!("{}\n@user\n@@x\n", name));
+
+    let stdout = run_wasmtime(&["--disable-cache", wasm, "@user"])?;
+    assert_eq!(stdout, format!("{}\n@user\n", name));
+    Ok(())
+}
+
+// Shell completions cover the subcommands and their options.
+#[test]
+fn completion() -> Result<()> {
+    let stdout = run_wasmtime(&["completion", "bash"])?;
+    assert!(stdout.contains("wasmtime"), "{}", stdout);
+    assert!(stdout.contains("--invoke"), "{}", stdout);
+    assert!(run_wasmtime(&["completion", "not-a-shell"]).is_err());
+    Ok(())
+}
+
+// Resource limits can be set on the command line.
+#[test]
+fn resource_limits() -> Result<()> {
+    let wasm = build_wasm("tests/wasm/hello_wasi_snapshot1.wat")?;
+    let wasm = wasm.path().to_str().unwrap();
+    let stdout = run_wasmtime(&[
+        "run",
+        "--disable-cache",
+        "--max-memory-size",
+        "65536",
+        "--max-instances",
+        "1",
+        wasm,
+    ])?;
+    assert_eq!(stdout, "Hello, world!\n");
+
+    // The module's memory is one page, so it can't be instantiated with a
+    // smaller limit.
+    let output =
+        run_wasmtime_for_output(&["run", "--disable-cache", "--max-memory-size", "65535", wasm])?;
+    assert!(!output.status.success());
+
+    // Each preloaded reactor counts as an instance.
+    let reactor = build_wasm("tests/wasm/minimal-reactor.wat")?;
+    let output = run_wasmtime_for_output(&[
+        "run",
+        "--disable-cache",
+        "--max-instances",
+        "1",
+        "--preload",
+        &format!("reactor={}", reactor.path().to_str().unwrap()),
+        wasm,
+    ])?;
+    assert!(!output.status.success());
+    Ok(())
+}
+
+#[test]
+fn binary_stdio() -> Result<()> {
+    let wasm = build_wasm("tests/wasm/binary_stdout.wat")?;
+    let wasm = wasm.path().to_str().unwrap();
+    let expected = b"\x00\xff\xfe\n\r\n\x1b\x80";
+
+    // Output to a pipe is passed through unmodified in both modes.
+    for mode in ["text", "raw"].iter() {
+        let output = run_wasmtime_for_output(&["run", "--disable-cache", "--stdio", mode, wasm])?;
+        assert!(output.status.success());
+        assert_eq!(output.stdout, expected);
+    }
+
+    let output = run_wasmtime_for_output(&["run", "--disable-cache", "--stdio", "binary", wasm])?;
+    assert!(!output.status.success());
+    Ok(())
+}
+
+// `--argv0` sets the program name, and arguments after `--` are passed
+// through verbatim.
+#[test]
+fn argv0() -> Result<()> {
+    let wasm = build_wasm("tests/wasm/print_args.wat")?;
+    let path = wasm.path();
+    let wasm = path.to_str().unwrap();
+    let name = path.file_name().unwrap().to_str().unwrap();
+
+    let stdout = run_wasmtime(&["run", "--disable-cache", wasm, "a"])?;
+    assert_eq!(stdout, format!("{}\na\n", name));
+
+    let stdout = run_wasmtime(&[
+        "run",
+        "--disable-cache",
+        "--argv0",
+        "ls",
+        wasm,
+        "--",
+        "--argv0",
+        "-l",
+    ])?;
+    assert_eq!(stdout, "ls\n--argv0\n-l\n");
+    Ok(())
+}
+
+// Custom sections are removed by `strip` unless they're kept.
+#[test]
+fn strip() -> Result<()> {
+    fn custom_section_names(path: &Path) -> Result<Vec<String>> {
+        let wasm = std::fs::read(path)?;
+        let mut names = Vec::new();
+        for payload in wasmparser::Parser::new(0).parse_all(&wasm) {
+            if let wasmparser::Payload::CustomSection { name, .. } = payload? {
+                names.push(name.to_string());
+            }
+        }
+        Ok(names)
+    }
+
+    // The `name` section comes from the text format; add some others.
+    let mut wasm = wat::parse_str(r#"(module (func $f (export "f")))"#)?;
+    for name in ["producers", ".debug_info", ".debug_line"].iter() {
+        wasm.push(0);
+        wasm.push(name.len() as u8 + 2);
+        wasm.push(name.len() as u8);
+        wasm.extend_from_slice(name.as_bytes());
+        wasm.extend_from_slice(b"\x01\x02");
+    }
+    let mut input = NamedTempFile::new()?;
+    input.write_all(&wasm)?;
+    assert_eq!(
+        custom_section_names(input.path())?,
+        ["name", "producers", ".debug_info", ".debug_line"]
+    );
+
+    let output = NamedTempFile::new()?;
+    let input_path = input.path().to_str().unwrap();
+    let output_path = output.path().to_str().unwrap();
+    run_wasmtime(&["strip", input_path, "-o", output_path])?;
+    assert!(custom_section_names(output.path())?.is_empty());
+    assert_eq!(
+        run_wasmtime(&["run", "--disable-cache", output_path, "--invoke", "f"])?,
+        ""
+    );
+
+    run_wasmtime(&[
+        "strip",
+        "--keep",
+        "name",
+        "--keep",
+        ".debug_*",
+        input_path,
+        "-o",
+        output_path,
+    ])?;
+    assert_eq!(
+        custom_section_names(output.path())?,
+        ["name", ".debug_info", ".debug_line"]
+    );
+
+    // Without `-o`, the module is stripped in place.
+    run_wasmtime(&["strip", input_path])?;
+    assert!(custom_section_names(input.path())?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn bench() -> Result<()> {
+    let stdout = run_wasmtime(&[
+        "bench",
+        "--disable-cache",
+        "--invoke",
+        "simple",
+        "--iters",
+        "5",
+        "tests/wasm/simple.wat",
+        "4",
+    ])?;
+    assert!(
+        stdout.starts_with("compilation: "),
+        "bad stdout: {}",
+        stdout
+    );
+    assert!(
+        stdout.contains("\ninstantiation: "),
+        "bad stdout: {}",
+        stdout
+    );
+    assert!(
+        stdout.contains("\ncall `simple`: "),
+        "bad stdout: {}",
+        stdout
+    );
+    assert!(stdout.contains("(5 iterations)"), "bad stdout: {}", stdout);
+
+    // Commands run `_start`, including the warmup iteration.
+    let stdout = run_wasmtime(&[
+        "bench",
+        "--disable-cache",
+        "--iters",
+        "2",
+        "tests/wasm/hello_wasi_snapshot1.wat",
+    ])?;
+    assert_eq!(stdout.matches("Hello, world!\n").count(), 3);
+    assert!(
+        stdout.contains("\ncall `_start`: "),
+        "bad stdout: {}",
+        stdout
+    );
+
+    let output = run_wasmtime_for_output(&[
+        "bench",
+        "--disable-cache",
+        "--iters",
+        "0",
+        "tests/wasm/simple.wat",
+    ])?;
+    assert!(!output.status.success());
+    Ok(())
+}
@@ -0,0 +1,74 @@
+use anyhow::Result;
+use std::time::Duration;
+use wasmtime::*;
+
+const WAT: &str = r#"
+    (module
+        (func $add (export "add") (param i32 i32) (result i32)
+            local.get 0
+            local.get 1
+            i32.add)
+        (func (export "loop") (param i32) (result i32)
+            (loop
+                local.get 0
+                i32.const 1
+                i32.sub
+                local.tee 0
+                br_if 0)
+            local.get 0))
+"#;
+
+#[test]
+fn disabled_by_default() -> Result<()> {
+    let module = Module::new(&Engine::default(), WAT)?;
+    assert!(module.compile_report().is_none());
+    Ok(())
+}
+
+#[test]
+fn reports_every_function() -> Result<()> {
+    let mut config = Config::new();
+    config.compile_stats(true);
+    let engine = Engine::new(&config);
+    let module = Module::new(&engine, WAT)?;
+    let report = module.compile_report().unwrap();
+
+    let functions = report.functions();
+    assert_eq!(functions.len(), 2);
+    assert_eq!(functions[0].index(), 0);
+    assert_eq!(functions[0].name(), Some("add"));
+    assert_eq!(functions[1].index(), 1);
+    assert_eq!(functions[1].name(), None);
+
+    for func in functions {
+        assert!(!func.passes().is_empty());
+        let passes = func
+            .passes()
+            .iter()
+            .map(|(_, time)| *time)
+            .sum::<Duration>();
+        assert!(func.total() >= passes);
+        assert!(func
+            .passes()
+            .iter()
+            .any(|(desc, _)| *desc == "Register allocation"));
+    }
+    assert_eq!(report.total(), functions[0].total() + functions[1].total());
+
+    let text = report.to_string();
+    assert!(text.starts_with("compiled 2 functions in "));
+    assert!(text.contains("function 0 (add)"));
+    assert!(text.contains("Register allocation"));
+    Ok(())
+}
+
+#[test]
+fn not_serialized() -> Result<()> {
+    let mut config = Config::new();
+    config.compile_stats(true);
+    let engine = Engine::new(&config);
+    let module = Module::new(&engine, WAT)?;
+    let module = Module::deserialize(&engine, &module.serialize()?)?;
+    assert!(module.compile_report().is_none());
+    Ok(())
+}
@@ -0,0 +1,89 @@
+use anyhow::Result;
+use wasmtime::*;
+
+const WAT: &str = r#"
+    (module $m
+        (func $exit (import "" "exit"))
+        (memory 1)
+        (global $g (mut i32) (i32.const 0))
+        (data (i32.const 16) "hello")
+        (func $trap (export "trap")
+            i32.const 0
+            i32.const 42
+            i32.store8
+            i32.const 7
+            global.set $g
+            unreachable)
+        (func (export "exit")
+            call $exit))
+"#;
+
+fn run(coredump_on_trap: bool, name: &str) -> Result<Trap> {
+    let mut config = Config::new();
+    config.coredump_on_trap(coredump_on_trap);
+    let store = Store::new(&Engine::new(&config));
+    let module = Module::new(store.engine(), WAT)?;
+    let exit = Func::wrap(&store, || -> Result<(), Trap> { Err(Trap::i32_exit(1)) });
+    let instance = Instance::new(&store, &module, &[exit.into()])?;
+    let err = instance.get_func(name).unwrap().call(&[]).unwrap_err();
+    Ok(err.downcast::<Trap>()?)
+}
+
+#[test]
+fn disabled_by_default() -> Result<()> {
+    assert!(run(false, "trap")?.coredump().is_none());
+    Ok(())
+}
+
+#[test]
+fn not_captured_on_exit() -> Result<()> {
+    let trap = run(true, "exit")?;
+    assert_eq!(trap.i32_exit_status(), Some(1));
+    assert!(trap.coredump().is_none());
+    Ok(())
+}
+
+#[test]
+fn captures_memory() -> Result<()> {
+    let trap = run(true, "trap")?;
+    let coredump = trap.coredump().unwrap();
+    assert_eq!(coredump.modules(), ["m"]);
+    let memories = coredump.memories().collect::<Vec<_>>();
+    assert_eq!(memories.len(), 1);
+    assert_eq!(memories[0].len(), 65536);
+    assert_eq!(memories[0][0], 42);
+    assert_eq!(&memories[0][16..21], b"hello");
+    Ok(())
+}
+
+#[test]
+fn serialize() -> Result<()> {
+    let trap = run(true, "trap")?;
+    let bytes = trap.coredump().unwrap().serialize("test");
+
+    // A coredump is itself a valid module holding the state of the store.
+    Module::validate(&Engine::default(), &bytes)?;
+
+    let sections = Module::custom_sections(&bytes)?;
+    let names = sections.iter().map(|(name, _)| *name).collect::<Vec<_>>();
+    assert_eq!(names, ["core", "coremodules", "coreinstances", "corestack"]);
+    assert_eq!(sections[0].1, b"\x00\x04test");
+    assert_eq!(sections[1].1, b"\x01\x00\x01m");
+
+    // One instance of module 0 with memory 0 and global 0.
+    assert_eq!(sections[2].1, [0x01, 0x00, 0x00, 0x01, 0x00, 0x01, 0x00]);
+
+    // The one frame, in `$trap` (function 1 after the import), is in
+    // instance 0.
+    let stack = sections[3].1;
+    assert_eq!(&stack[..7], b"\x00\x04main\x01");
+    assert_eq!(&stack[7..10], [0x00, 0x00, 0x01]);
+
+    // The data section holds both the initial data and what was written
+    // before the trap, and the global holds its value at the time of the
+    // trap.
+    let global = [0x7f, 0x01, 0x41, 0x07, 0x0b];
+    assert!(bytes.windows(global.len()).any(|w| w == global));
+    assert!(bytes.windows(5).any(|w| w == b"hello"));
+    Ok(())
+}
@@ -0,0 +1,131 @@
+use anyhow::Result;
+use wasmtime::*;
+
+fn with_custom_sections(sections: &[(&str, &[u8])]) -> Result<Vec<u8>> {
+    let mut wasm = wat::parse_str("(module (func (export \"f\")))")?;
+    for (name, data) in sections {
+        let size = 1 + name.len() + data.len();
+        assert!(size < 0x80 && name.len() < 0x80);
+        wasm.push(0);
+        wasm.push(size as u8);
+        wasm.push(name.len() as u8);
+        wasm.extend_from_slice(name.as_bytes());
+        wasm.extend_from_slice(data);
+    }
+    Ok(wasm)
+}
+
+#[test]
+fn list_custom_sections() -> Result<()> {
+    let wasm = with_custom_sections(&[("a", b"1"), ("b", b""), ("a", b"23")])?;
+    let sections = Module::custom_sections(&wasm)?;
+    assert_eq!(
+        sections,
+        [("a", &b"1"[..]), ("b", &b""[..]), ("a", &b"23"[..])]
+    );
+    Ok(())
+}
+
+#[test]
+fn strip_custom_sections() -> Result<()> {
+    let wasm = with_custom_sections(&[("a", b"1"), ("b", b"2")])?;
+
+    let stripped = Module::strip_custom_sections(&wasm, |name| name == "b")?;
+    assert_eq!(Module::custom_sections(&stripped)?, [("b", &b"2"[..])]);
+
+    let stripped = Module::strip_custom_sections(&wasm, |_| false)?;
+    assert!(Module::custom_sections(&stripped)?.is_empty());
+    assert_eq!(stripped, wat::parse_str("(module (func (export \"f\")))")?);
+
+    let module = Module::new(&Engine::default(), &stripped)?;
+    assert!(module.get_export("f").is_some());
+    Ok(())
+}
+
+#[test]
+fn malformed_sections() {
+    assert!(Module::custom_sections(b"(module)").is_err());
+    assert!(Module::custom_sections(b"\0asm\x01\0\0\0\0\x05\x01a").is_err());
+    assert!(Module::strip_custom_sections(b"\0asm", |_| true).is_err());
+}
+
+#[test]
+fn producers() -> Result<()> {
+    let wasm = with_custom_sections(&[])?;
+    assert_eq!(Module::producers(&wasm)?, None);
+
+    let wasm = with_custom_sections(&[(
+        "producers",
+        b"\x02\
+          \x08language\x01\x04Rust\x00\
+          \x0cprocessed-by\x02\x05rustc\x041.49\x08wasm-opt\x0299",
+    )])?;
+    let producers = Module::producers(&wasm)?.unwrap();
+    let names = producers
+        .fields()
+        .iter()
+        .map(|f| f.name())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["language", "processed-by"]);
+    let tools = producers
+        .field("processed-by")
+        .unwrap()
+        .values()
+        .iter()
+        .map(|v| (v.name(), v.version()))
+        .collect::<Vec<_>>();
+    assert_eq!(tools, [("rustc", "1.49"), ("wasm-opt", "99")]);
+    assert!(producers.field("sdk").is_none());
+
+    let wasm = with_custom_sections(&[("producers", b"\x01\x08language\x02")])?;
+    assert!(Module::producers(&wasm).is_err());
+    Ok(())
+}
+
+#[test]
+fn branch_hints() -> Result<()> {
+    // `br_if` is at offset 5 of the first body and `if` at offset 3 of the
+    // second, counting from the local declarations.
+    let wat = r#"
+        (module
+            (func (export "br_if") (param i32) (result i32)
+                (block
+                    (br_if 0 (local.get 0))
+                    (return (i32.const 1)))
+                i32.const 2)
+            (func (export "if") (param i32) (result i32)
+                (if (result i32) (local.get 0)
+                    (then (i32.const 1))
+                    (else (i32.const 2)))))
+    "#;
+    for &likely in &[0u8, 1] {
+        let mut hints = wat::parse_str(wat)?;
+        let data = [2, 0, 1, 5, 1, likely, 1, 1, 3, 1, likely];
+        let name = "metadata.code.branch_hint";
+        hints.push(0);
+        hints.push((1 + name.len() + data.len()) as u8);
+        hints.push(name.len() as u8);
+        hints.extend_from_slice(name.as_bytes());
+        hints.extend_from_slice(&data);
+
+        // Hints must not change behavior, whichever way they point.
+        let store = Store::default();
+        let module = Module::new(store.engine(), &hints)?;
+        let instance = Instance::new(&store, &module, &[])?;
+        let br_if = instance.get_func("br_if").unwrap().get1::<i32, i32>()?;
+        assert_eq!(br_if(0)?, 1);
+        assert_eq!(br_if(1)?, 2);
+        let if_ = instance.get_func("if").unwrap().get1::<i32, i32>()?;
+        assert_eq!(if_(1)?, 1);
+        assert_eq!(if_(0)?, 2);
+    }
+    Ok(())
+}
+
+#[test]
+fn malformed_branch_hints() -> Result<()> {
+    // A malformed hint section is ignored rather than rejected.
+    let wasm = with_custom_sections(&[("metadata.code.branch_hint", b"\x01\x00\x01\x00\x07")])?;
+    Module::new(&Engine::default(), &wasm)?;
+    Ok(())
+}
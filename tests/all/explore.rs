@@ -0,0 +1,109 @@
+use anyhow::Result;
+use wasmtime::*;
+
+#[test]
+fn functions_are_indexed_after_imports() -> Result<()> {
+    let engine = Engine::default();
+    let wasm = wat::parse_str(
+        r#"
+            (module
+                (import "" "" (func))
+                (func $first)
+                (func (result i32) i32.const 42))
+        "#,
+    )?;
+    let funcs = Module::compiled_functions(&engine, &wasm)?;
+    assert_eq!(funcs.len(), 2);
+
+    assert_eq!(funcs[0].module_index(), 0);
+    assert_eq!(funcs[0].index(), 1);
+    assert_eq!(funcs[0].name(), Some("first"));
+
+    assert_eq!(funcs[1].index(), 2);
+    assert_eq!(funcs[1].name(), None);
+    assert!(funcs[1].ir().unwrap().contains("iconst.i32 42"));
+    assert!(!funcs[1].code().is_empty());
+    Ok(())
+}
+
+#[test]
+fn invalid_module() {
+    let engine = Engine::default();
+    let wasm = wat::parse_str("(module (func (result i32) i64.const 0))").unwrap();
+    assert!(Module::compiled_functions(&engine, &wasm).is_err());
+}
+
+#[test]
+fn optimization_passes_can_be_disabled() -> Result<()> {
+    let wasm = wat::parse_str(
+        r#"
+            (module
+                (func (param i32) (result i32)
+                    local.get 0
+                    i32.const 1
+                    i32.add
+                    local.get 0
+                    i32.const 1
+                    i32.add
+                    i32.mul))
+        "#,
+    )?;
+    let adds = |config: &Config| -> Result<usize> {
+        let funcs = Module::compiled_functions(&Engine::new(config), &wasm)?;
+        Ok(funcs[0].ir().unwrap().matches("iadd").count())
+    };
+
+    let mut config = Config::new();
+    assert_eq!(adds(&config)?, 1);
+    unsafe {
+        config.cranelift_other_flag("enable_gvn", "false")?;
+    }
+    assert_eq!(adds(&config)?, 2);
+    Ok(())
+}
+
+#[test]
+fn spectre_mitigation_on_table_accesses() -> Result<()> {
+    let wasm = wat::parse_str(
+        r#"
+            (module
+                (type (func))
+                (table 1 funcref)
+                (func (param i32)
+                    local.get 0
+                    call_indirect (type 0)))
+        "#,
+    )?;
+    let guarded = |config: &Config| -> Result<bool> {
+        let funcs = Module::compiled_functions(&Engine::new(config), &wasm)?;
+        Ok(funcs[0].ir().unwrap().contains("selectif_spectre_guard"))
+    };
+
+    let mut config = Config::new();
+    assert!(guarded(&config)?);
+    config.cranelift_spectre_mitigation(SpectreMitigation::None);
+    assert!(!guarded(&config)?);
+    Ok(())
+}
+
+#[test]
+fn function_sizes_of_deserialized_module() -> Result<()> {
+    let engine = Engine::default();
+    let module = Module::new(
+        &engine,
+        r#"
+            (module
+                (import "" "" (func))
+                (func $first)
+                (func (result i32) i32.const 42))
+        "#,
+    )?;
+    let module = Module::deserialize(&engine, &module.serialize()?)?;
+    let sizes = module.function_sizes();
+    assert_eq!(sizes.len(), 2);
+    assert_eq!(sizes[0].index(), 1);
+    assert_eq!(sizes[0].name(), Some("first"));
+    assert_eq!(sizes[1].index(), 2);
+    assert!(sizes.iter().all(|f| f.size() > 0));
+    Ok(())
+}
@@ -340,3 +340,26 @@ fn grow_externref_tables_via_api() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn read_write_memory_via_api() -> anyhow::Result<()> {
+    let store = Store::default();
+    let memory = Memory::new(&store, MemoryType::new(Limits::new(1, None)));
+
+    memory.write(100, b"hello")?;
+    let mut buffer = [0; 5];
+    memory.read(100, &mut buffer)?;
+    assert_eq!(&buffer, b"hello");
+
+    // Accesses that run off the end of memory fail without side effects.
+    let end = memory.data_size();
+    assert!(memory.write(end - 2, b"abc").is_err());
+    assert!(memory.read(end - 2, &mut buffer).is_err());
+    assert!(memory.read(usize::max_value(), &mut buffer).is_err());
+    assert_eq!(&buffer, b"hello");
+    memory.write(end - 3, b"abc")?;
+    memory.read(end - 5, &mut buffer)?;
+    assert_eq!(&buffer, b"\0\0abc");
+
+    Ok(())
+}
@@ -0,0 +1,228 @@
+use anyhow::Result;
+use std::cell::{Cell, RefCell};
+use std::panic::{self, AssertUnwindSafe};
+use std::rc::Rc;
+use wasmtime::*;
+
+fn store() -> Store {
+    let mut config = Config::new();
+    config.consume_fuel(true);
+    Store::new(&Engine::new(&config))
+}
+
+fn fuel_consumed(wat: &str) -> Result<u64> {
+    let store = store();
+    store.add_fuel(u64::max_value())?;
+    let module = Module::new(store.engine(), wat)?;
+    let instance = Instance::new(&store, &module, &[])?;
+    instance.get_func("f").unwrap().call(&[])?;
+    Ok(store.fuel_consumed().unwrap())
+}
+
+#[test]
+fn disabled_by_default() -> Result<()> {
+    let store = Store::default();
+    assert_eq!(store.fuel_consumed(), None);
+    assert!(store.add_fuel(1).is_err());
+    Ok(())
+}
+
+#[test]
+fn starts_with_no_fuel() -> Result<()> {
+    let store = store();
+    assert_eq!(store.fuel_consumed(), Some(0));
+    let module = Module::new(store.engine(), r#"(module (func (export "f")))"#)?;
+    let instance = Instance::new(&store, &module, &[])?;
+    let trap = instance
+        .get_func("f")
+        .unwrap()
+        .call(&[])
+        .unwrap_err()
+        .downcast::<Trap>()?;
+    assert!(
+        trap.to_string().contains("all fuel consumed"),
+        "bad trap: {}",
+        trap
+    );
+    Ok(())
+}
+
+#[test]
+fn counts_instructions() -> Result<()> {
+    // Even an empty function consumes a unit of fuel.
+    assert_eq!(fuel_consumed(r#"(module (func (export "f")))"#)?, 1);
+
+    // `nop` and `drop` are free, everything else costs one unit.
+    assert_eq!(
+        fuel_consumed(
+            r#"
+                (module
+                    (func (export "f")
+                        nop
+                        i32.const 1
+                        i32.const 2
+                        i32.add
+                        drop))
+            "#
+        )?,
+        4
+    );
+
+    // Fuel consumed by callees is included.
+    assert_eq!(
+        fuel_consumed(
+            r#"
+                (module
+                    (func $g
+                        i32.const 1
+                        drop)
+                    (func (export "f")
+                        call $g
+                        call $g))
+            "#
+        )?,
+        7
+    );
+    Ok(())
+}
+
+#[test]
+fn infinite_loop_runs_out_of_fuel() -> Result<()> {
+    let store = store();
+    store.add_fuel(10_000)?;
+    let module = Module::new(
+        store.engine(),
+        r#"(module (func (export "f") (loop br 0)))"#,
+    )?;
+    let instance = Instance::new(&store, &module, &[])?;
+    let trap = instance
+        .get_func("f")
+        .unwrap()
+        .call(&[])
+        .unwrap_err()
+        .downcast::<Trap>()?;
+    assert!(
+        trap.to_string().contains("all fuel consumed"),
+        "bad trap: {}",
+        trap
+    );
+    assert!(store.fuel_consumed().unwrap() >= 10_000);
+    Ok(())
+}
+
+#[test]
+fn add_fuel_after_running_out() -> Result<()> {
+    let store = store();
+    let module = Module::new(
+        store.engine(),
+        r#"
+            (module
+                (func (export "f") (param i32)
+                    (loop
+                        local.get 0
+                        i32.const 1
+                        i32.sub
+                        local.tee 0
+                        br_if 0)))
+        "#,
+    )?;
+    let instance = Instance::new(&store, &module, &[])?;
+    let f = instance.get_func("f").unwrap();
+
+    store.add_fuel(10)?;
+    assert!(f.call(&[Val::I32(100)]).is_err());
+
+    store.add_fuel(10_000)?;
+    f.call(&[Val::I32(100)])?;
+    Ok(())
+}
+
+#[test]
+fn out_of_fuel_handler_refuels() -> Result<()> {
+    let store = store();
+    let module = Module::new(
+        store.engine(),
+        r#"
+            (module
+                (func (export "f") (param i32)
+                    (loop
+                        local.get 0
+                        i32.const 1
+                        i32.sub
+                        local.tee 0
+                        br_if 0)))
+        "#,
+    )?;
+    let instance = Instance::new(&store, &module, &[])?;
+    let f = instance.get_func("f").unwrap();
+
+    let calls = Rc::new(Cell::new(0));
+    let calls2 = calls.clone();
+    store.out_of_fuel_handler(move |_| {
+        calls2.set(calls2.get() + 1);
+        Ok(10)
+    });
+    store.add_fuel(10)?;
+    f.call(&[Val::I32(100)])?;
+    assert!(calls.get() > 0);
+    Ok(())
+}
+
+#[test]
+fn out_of_fuel_handler_sees_stack() -> Result<()> {
+    let store = store();
+    let module = Module::new(
+        store.engine(),
+        r#"
+            (module
+                (func $inner (loop br 0))
+                (func $outer (export "f") call $inner))
+        "#,
+    )?;
+    let instance = Instance::new(&store, &module, &[])?;
+
+    let stacks = Rc::new(RefCell::new(Vec::new()));
+    let stacks2 = stacks.clone();
+    store.out_of_fuel_handler(move |trace| {
+        let names = trace
+            .iter()
+            .map(|frame| frame.func_name().unwrap_or("").to_string())
+            .collect::<Vec<_>>();
+        stacks2.borrow_mut().push(names);
+        Err(Trap::new("stopped by handler"))
+    });
+    store.add_fuel(1_000)?;
+    let trap = instance
+        .get_func("f")
+        .unwrap()
+        .call(&[])
+        .unwrap_err()
+        .downcast::<Trap>()?;
+    assert!(
+        trap.to_string().contains("stopped by handler"),
+        "bad trap: {}",
+        trap
+    );
+    assert_eq!(*stacks.borrow(), [["inner", "outer"]]);
+    Ok(())
+}
+
+#[test]
+fn out_of_fuel_handler_panics_propagate() -> Result<()> {
+    let store = store();
+    let module = Module::new(
+        store.engine(),
+        r#"(module (func (export "f") (loop br 0)))"#,
+    )?;
+    let instance = Instance::new(&store, &module, &[])?;
+    let f = instance.get_func("f").unwrap();
+
+    store.out_of_fuel_handler(|_| panic!("out of fuel"));
+    store.add_fuel(1_000)?;
+    let err = match panic::catch_unwind(AssertUnwindSafe(|| f.call(&[]))) {
+        Ok(_) => panic!("the handler's panic wasn't propagated"),
+        Err(err) => err,
+    };
+    assert_eq!(err.downcast_ref::<&'static str>(), Some(&"out of fuel"));
+    Ok(())
+}
@@ -549,3 +549,106 @@ fn trampolines_always_valid() -> anyhow::Result<()> {
     func.call(&[])?;
     Ok(())
 }
+
+#[test]
+fn linear_scan_regalloc() -> Result<()> {
+    let mut config = Config::new();
+    config.cranelift_regalloc(RegallocAlgorithm::LinearScan);
+    let store = Store::new(&Engine::new(&config));
+    let module = Module::new(
+        store.engine(),
+        r#"
+            (module
+                (func (export "sum") (param i32) (result i32)
+                    (local i32)
+                    block
+                        loop
+                            local.get 0
+                            i32.eqz
+                            br_if 1
+                            local.get 0
+                            local.get 1
+                            i32.add
+                            local.set 1
+                            local.get 0
+                            i32.const 1
+                            i32.sub
+                            local.set 0
+                            br 0
+                        end
+                    end
+                    local.get 1))
+        "#,
+    )?;
+    let instance = Instance::new(&store, &module, &[])?;
+    let sum = instance.get_func("sum").unwrap().get1::<i32, i32>()?;
+    assert_eq!(sum(100)?, 5050);
+    Ok(())
+}
+
+#[test]
+fn intrinsics() -> Result<()> {
+    static HITS: AtomicUsize = AtomicUsize::new(0);
+
+    let store = Store::default();
+    let module = Module::new(
+        store.engine(),
+        r#"
+            (module
+                (import "" "tick" (func $tick))
+                (import "" "add" (func $add (param i32 i64) (result i64)))
+                (import "" "mul" (func $mul (param f32 f64) (result f64)))
+                (func (export "run") (param i32) (result f64)
+                    call $tick
+                    local.get 0
+                    i64.const 10
+                    call $add
+                    f32.const 0.5
+                    f64.const 3
+                    call $mul
+                    f64.convert_i64_s
+                    f64.add))
+        "#,
+    )?;
+    let tick = Func::wrap_intrinsic(&store, || {
+        HITS.fetch_add(1, SeqCst);
+    });
+    let add = Func::wrap_intrinsic(&store, |a: i32, b: i64| a as i64 + b);
+    let mul = Func::wrap_intrinsic(&store, |a: f32, b: f64| a as f64 * b);
+    assert_eq!(
+        add.ty(),
+        FuncType::new(vec![ValType::I32, ValType::I64], vec![ValType::I64])
+    );
+
+    let instance = Instance::new(&store, &module, &[tick.into(), add.into(), mul.into()])?;
+    let run = instance.get_func("run").unwrap().get1::<i32, f64>()?;
+    assert_eq!(run(5)?, 16.5);
+    assert_eq!(HITS.load(SeqCst), 1);
+
+    // Intrinsics can also be called from the host.
+    let results = add.call(&[Val::I32(1), Val::I64(2)])?;
+    assert_eq!(results[0].unwrap_i64(), 3);
+    assert_eq!(add.get2::<i32, i64, i64>()?(3, 4)?, 7);
+    Ok(())
+}
+
+#[test]
+fn intrinsic_panics_propagate() -> Result<()> {
+    let store = Store::default();
+    let mut linker = Linker::new(&store);
+    linker.intrinsic("", "boom", || -> i32 { panic!("boom") })?;
+    let module = Module::new(
+        store.engine(),
+        r#"
+            (module
+                (import "" "boom" (func $boom (result i32)))
+                (func (export "run") (result i32)
+                    call $boom))
+        "#,
+    )?;
+    let instance = linker.instantiate(&module)?;
+    let run = instance.get_func("run").unwrap().get0::<i32>()?;
+    let err = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| run())).unwrap_err();
+    assert_eq!(err.downcast_ref::<&'static str>(), Some(&"boom"));
+    Ok(())
+}
@@ -91,3 +91,96 @@ fn use_after_drop() -> anyhow::Result<()> {
     assert_eq!(g.get().i32(), Some(101));
     Ok(())
 }
+
+#[test]
+fn v128() -> anyhow::Result<()> {
+    let store = Store::default();
+    let g = Global::new(
+        &store,
+        GlobalType::new(ValType::V128, Mutability::Const),
+        Val::V128(0x0123_4567_89ab_cdef_0011_2233_4455_6677),
+    )?;
+    assert_eq!(
+        g.get().v128(),
+        Some(0x0123_4567_89ab_cdef_0011_2233_4455_6677)
+    );
+    assert!(g.set(Val::V128(0)).is_err());
+
+    let g = Global::new(
+        &store,
+        GlobalType::new(ValType::V128, Mutability::Var),
+        Val::V128(1),
+    )?;
+    g.set(Val::V128(u128::max_value()))?;
+    assert_eq!(g.get().v128(), Some(u128::max_value()));
+    assert!(g.set(1i64.into()).is_err());
+    Ok(())
+}
+
+#[test]
+fn shared_between_instances() -> anyhow::Result<()> {
+    let mut config = Config::new();
+    config.wasm_simd(true);
+    let store = Store::new(&Engine::new(&config));
+    let module = Module::new(
+        store.engine(),
+        r#"
+            (module
+                (global $i (import "" "i32") (mut i32))
+                (global $v (import "" "v128") (mut v128))
+                (func (export "get") (result i32) global.get $i)
+                (func (export "set") (param i32)
+                    local.get 0
+                    global.set $i)
+                (func (export "get_v") (result i32)
+                    global.get $v
+                    i32x4.extract_lane 3)
+                (func (export "set_v") (param i32)
+                    local.get 0
+                    i32x4.splat
+                    global.set $v))
+        "#,
+    )?;
+    let i = Global::new(
+        &store,
+        GlobalType::new(ValType::I32, Mutability::Var),
+        1.into(),
+    )?;
+    let v = Global::new(
+        &store,
+        GlobalType::new(ValType::V128, Mutability::Var),
+        Val::V128(2 << 96),
+    )?;
+    let imports = [i.clone().into(), v.clone().into()];
+    let a = Instance::new(&store, &module, &imports)?;
+    let b = Instance::new(&store, &module, &imports)?;
+    let get = |instance: &Instance, name| -> anyhow::Result<i32> {
+        Ok(instance.get_func(name).unwrap().get0::<i32>()?()?)
+    };
+    let set = |instance: &Instance, name, val| -> anyhow::Result<()> {
+        Ok(instance.get_func(name).unwrap().get1::<i32, ()>()?(val)?)
+    };
+
+    assert_eq!(get(&a, "get")?, 1);
+    assert_eq!(get(&b, "get_v")?, 2);
+
+    // Writes by the host are visible to all instances...
+    i.set(3.into())?;
+    v.set(Val::V128(4 << 96))?;
+    assert_eq!(get(&a, "get")?, 3);
+    assert_eq!(get(&b, "get")?, 3);
+    assert_eq!(get(&a, "get_v")?, 4);
+    assert_eq!(get(&b, "get_v")?, 4);
+
+    // ... as are writes by any instance.
+    set(&a, "set", 5)?;
+    set(&b, "set_v", 6)?;
+    assert_eq!(get(&b, "get")?, 5);
+    assert_eq!(get(&a, "get_v")?, 6);
+    assert_eq!(i.get().i32(), Some(5));
+    assert_eq!(
+        v.get().v128(),
+        Some(0x0000_0006_0000_0006_0000_0006_0000_0006)
+    );
+    Ok(())
+}
@@ -1,4 +1,5 @@
 use std::sync::atomic::{AtomicUsize, Ordering::SeqCst};
+use std::time::Duration;
 use wasmtime::*;
 
 fn interruptable_store() -> Store {
@@ -134,3 +135,46 @@ fn function_interrupt_from_afar() -> anyhow::Result<()> {
     );
     Ok(())
 }
+
+#[test]
+fn deadline_interrupts_loop() -> anyhow::Result<()> {
+    let store = interruptable_store();
+    let module = Module::new(store.engine(), r#"(func (export "loop") (loop br 0))"#)?;
+    let instance = Instance::new(&store, &module, &[])?;
+    let iloop = instance.get_func("loop").unwrap().get0::<()>()?;
+    store.set_deadline(Duration::from_millis(10))?;
+    let trap = iloop().unwrap_err();
+    assert_eq!(trap.trap_code(), Some(TrapCode::DeadlineExceeded));
+    assert!(trap.to_string().contains("wasm trap: deadline exceeded"));
+
+    // Only the trap raised by the deadline is attributed to it.
+    store.interrupt_handle()?.interrupt();
+    let trap = iloop().unwrap_err();
+    assert_eq!(trap.trap_code(), Some(TrapCode::Interrupt));
+    Ok(())
+}
+
+#[test]
+fn deadline_can_be_cleared() -> anyhow::Result<()> {
+    let store = interruptable_store();
+    let module = Module::new(store.engine(), r#"(func (export "f"))"#)?;
+    let instance = Instance::new(&store, &module, &[])?;
+    let f = instance.get_func("f").unwrap().get0::<()>()?;
+    store.set_deadline(Duration::from_millis(10))?;
+    store.clear_deadline();
+    std::thread::sleep(Duration::from_millis(50));
+    f()?;
+
+    // A new deadline replaces the old one.
+    store.set_deadline(Duration::from_millis(10))?;
+    store.set_deadline(Duration::from_secs(3600))?;
+    std::thread::sleep(Duration::from_millis(50));
+    f()?;
+    Ok(())
+}
+
+#[test]
+fn deadline_requires_interrupts() {
+    let store = Store::default();
+    assert!(store.set_deadline(Duration::from_secs(1)).is_err());
+}
@@ -0,0 +1,72 @@
+use anyhow::Result;
+use wasmtime::*;
+
+const WASM_PAGE_SIZE: usize = 0x10000;
+
+#[test]
+fn memory_size_limit() -> Result<()> {
+    let store = Store::default();
+    let mut limits = StoreLimits::new();
+    limits.memory_size(2 * WASM_PAGE_SIZE);
+    store.limit(limits);
+
+    let module = Module::new(
+        store.engine(),
+        r#"(module
+            (memory (export "m") 1)
+            (func (export "grow") (param i32) (result i32)
+                local.get 0
+                memory.grow))"#,
+    )?;
+    let instance = Instance::new(&store, &module, &[])?;
+    let grow = instance.get_func("grow").unwrap().get1::<i32, i32>()?;
+    assert_eq!(grow(1)?, 1);
+    assert_eq!(grow(1)?, -1);
+    assert_eq!(instance.get_memory("m").unwrap().size(), 2);
+
+    // A memory that starts out larger than the limit can't be created at all.
+    let module = Module::new(store.engine(), "(module (memory 3))")?;
+    assert!(Instance::new(&store, &module, &[]).is_err());
+    Ok(())
+}
+
+#[test]
+fn table_elements_limit() -> Result<()> {
+    let store = Store::default();
+    let mut limits = StoreLimits::new();
+    limits.table_elements(5);
+    store.limit(limits);
+
+    let module = Module::new(store.engine(), r#"(module (table (export "t") 1 funcref))"#)?;
+    let instance = Instance::new(&store, &module, &[])?;
+    let table = instance.get_table("t").unwrap();
+    table.grow(4, Val::FuncRef(None))?;
+    assert!(table.grow(1, Val::FuncRef(None)).is_err());
+    assert_eq!(table.size(), 5);
+
+    let module = Module::new(store.engine(), "(module (table 6 funcref))")?;
+    assert!(Instance::new(&store, &module, &[]).is_err());
+    Ok(())
+}
+
+#[test]
+fn instances_limit() -> Result<()> {
+    let store = Store::default();
+    let mut limits = StoreLimits::new();
+    limits.instances(2);
+    store.limit(limits);
+
+    // Host-defined items don't count against the limit.
+    Func::wrap(&store, || {});
+
+    let module = Module::new(store.engine(), "(module)")?;
+    Instance::new(&store, &module, &[])?;
+    Instance::new(&store, &module, &[])?;
+    let err = Instance::new(&store, &module, &[]).unwrap_err();
+    assert!(
+        err.to_string().contains("instance limit of 2 exceeded"),
+        "bad error: {}",
+        err
+    );
+    Ok(())
+}
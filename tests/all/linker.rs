@@ -160,3 +160,60 @@ fn module_interposition() -> Result<()> {
     assert_eq!(func()?, 112);
     Ok(())
 }
+
+#[test]
+fn define_unknown_imports() -> Result<()> {
+    let store = Store::default();
+    let mut linker = Linker::new(&store);
+    linker.func("host", "known", || 1)?;
+
+    let store2 = store.clone();
+    linker.define_unknown_imports_with(move |import| {
+        assert_eq!(import.module(), "dynamic");
+        match import.ty() {
+            ExternType::Func(ty) => {
+                let func = Func::new(&store2, ty, |_, _, results| {
+                    results[0] = Val::I32(2);
+                    Ok(())
+                });
+                Ok(func.into())
+            }
+            ExternType::Global(ty) => Ok(Global::new(&store2, ty, Val::I32(3))?.into()),
+            ExternType::Memory(ty) => Ok(Memory::new(&store2, ty).into()),
+            _ => anyhow::bail!("unsupported import"),
+        }
+    });
+
+    let module = Module::new(
+        store.engine(),
+        r#"
+            (module
+                (import "host" "known" (func $known (result i32)))
+                (import "dynamic" "f" (func $f (result i32)))
+                (import "dynamic" "g" (global $g i32))
+                (import "dynamic" "m" (memory 1))
+                (func (export "sum") (result i32)
+                    call $known
+                    call $f
+                    i32.add
+                    global.get $g
+                    i32.add)
+            )
+        "#,
+    )?;
+    let instance = linker.instantiate(&module)?;
+    let sum = instance.get_func("sum").unwrap().get0::<i32>()?;
+    assert_eq!(sum()?, 6);
+
+    // Items synthesized by the handler are not recorded in the linker.
+    assert!(linker.get_by_name("dynamic", Some("f")).next().is_none());
+
+    // Errors from the handler fail instantiation.
+    let module = Module::new(
+        store.engine(),
+        r#"(module (import "dynamic" "t" (table 1 funcref)))"#,
+    )?;
+    let err = linker.instantiate(&module).unwrap_err();
+    assert!(err.to_string().contains("dynamic::t"), "{}", err);
+    Ok(())
+}
@@ -1,7 +1,12 @@
 mod cli_tests;
+mod compile_report;
+mod coredump;
+mod custom_sections;
 mod custom_signal_handler;
 mod debug;
+mod explore;
 mod externals;
+mod fuel;
 mod func;
 mod fuzzing;
 mod globals;
@@ -10,15 +15,24 @@ mod import_calling_export;
 mod import_indexes;
 mod instance;
 mod invoke_func_via_table;
+mod limits;
 mod linker;
 mod memory_creator;
+mod metrics;
 mod module_linking;
 mod module_serialize;
 mod name;
+mod nan_canonicalization;
+mod pic;
+mod preinitialize;
+mod profile_guided_layout;
+mod resources;
+mod runtime_stats;
 mod stack_overflow;
 mod table;
 mod traps;
 mod use_after_drop;
+mod validate;
 mod wast;
 
 // TODO(#1886): Cranelift only supports reference types on x64.
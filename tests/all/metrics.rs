@@ -0,0 +1,150 @@
+use anyhow::Result;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use wasmtime::*;
+
+#[test]
+fn counts_compilations_and_instantiations() -> Result<()> {
+    let engine = Engine::default();
+    let module = Module::new(&engine, "(module (func))")?;
+    Module::new(&engine, "(module (func))")?;
+    let store = Store::new(&engine);
+    Instance::new(&store, &module, &[])?;
+    Instance::new(&store, &module, &[])?;
+    Func::wrap(&store, || {});
+
+    let metrics = engine.metrics();
+    assert_eq!(metrics.compilations, 2);
+    assert_eq!(metrics.cache_hits, 0);
+    assert_eq!(metrics.instantiations, 2);
+    assert!(metrics.traps.is_empty());
+    Ok(())
+}
+
+#[test]
+fn counts_cache_hits() -> Result<()> {
+    #[derive(Default)]
+    struct MemoryStore(Mutex<HashMap<String, Vec<u8>>>);
+
+    impl CacheStore for MemoryStore {
+        fn get(&self, key: &str) -> Option<Vec<u8>> {
+            self.0.lock().unwrap().get(key).cloned()
+        }
+
+        fn put(&self, key: &str, value: &[u8]) -> bool {
+            self.0
+                .lock()
+                .unwrap()
+                .insert(key.to_string(), value.to_vec());
+            true
+        }
+    }
+
+    let mut config = Config::new();
+    config.with_cache_store(Arc::new(MemoryStore::default()));
+    let engine = Engine::new(&config);
+    Module::new(&engine, "(module (func))")?;
+    Module::new(&engine, "(module (func))")?;
+    Module::new(&engine, "(module (func) (func))")?;
+
+    let metrics = engine.metrics();
+    assert_eq!(metrics.compilations, 2);
+    assert_eq!(metrics.cache_hits, 1);
+    Ok(())
+}
+
+#[test]
+fn counts_traps_by_code() -> Result<()> {
+    let engine = Engine::default();
+    let module = Module::new(
+        &engine,
+        r#"
+            (module
+                (func (export "unreachable") unreachable)
+                (func (export "div") (param i32) (result i32)
+                    i32.const 1
+                    local.get 0
+                    i32.div_u)
+                (func (export "host") (call $host))
+                (import "" "" (func $host)))
+        "#,
+    )?;
+    let store = Store::new(&engine);
+    let host = Func::wrap(&store, || -> Result<(), Trap> { Err(Trap::new("host")) });
+    let instance = Instance::new(&store, &module, &[host.into()])?;
+    let unreachable = instance.get_func("unreachable").unwrap();
+    let div = instance.get_func("div").unwrap();
+    assert!(unreachable.call(&[]).is_err());
+    assert!(unreachable.call(&[]).is_err());
+    assert!(div.call(&[0.into()]).is_err());
+    div.call(&[1.into()])?;
+    assert!(instance.get_func("host").unwrap().call(&[]).is_err());
+
+    let traps = engine.metrics().traps;
+    assert_eq!(traps.len(), 2);
+    assert_eq!(traps[&TrapCode::UnreachableCodeReached], 2);
+    assert_eq!(traps[&TrapCode::IntegerDivisionByZero], 1);
+    Ok(())
+}
+
+#[test]
+fn tracks_fuel_and_memory() -> Result<()> {
+    let mut config = Config::new();
+    config.consume_fuel(true);
+    let engine = Engine::new(&config);
+    let module = Module::new(
+        &engine,
+        r#"
+            (module
+                (memory 1)
+                (func (export "grow")
+                    i32.const 2
+                    memory.grow
+                    drop))
+        "#,
+    )?;
+
+    let store = Store::new(&engine);
+    store.add_fuel(10_000)?;
+    let instance = Instance::new(&store, &module, &[])?;
+    assert_eq!(engine.metrics().memory_bytes, 65536);
+
+    instance.get_func("grow").unwrap().call(&[])?;
+    let metrics = engine.metrics();
+    assert_eq!(metrics.memory_bytes, 3 * 65536);
+    assert_eq!(metrics.fuel_consumed, store.fuel_consumed().unwrap());
+    assert!(metrics.fuel_consumed > 0);
+
+    drop((instance, store));
+    assert_eq!(engine.metrics().memory_bytes, 0);
+    Ok(())
+}
+
+#[test]
+fn callbacks_see_every_event() -> Result<()> {
+    let engine = Engine::default();
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let events2 = events.clone();
+    engine.on_metrics_event(move |event| events2.lock().unwrap().push(event));
+
+    let module = Module::new(
+        &engine,
+        r#"(module (memory 1) (func (export "f") unreachable))"#,
+    )?;
+    let store = Store::new(&engine);
+    let instance = Instance::new(&store, &module, &[])?;
+    assert!(instance.get_func("f").unwrap().call(&[]).is_err());
+    drop((instance, store));
+
+    assert_eq!(
+        *events.lock().unwrap(),
+        [
+            MetricsEvent::Compilation,
+            MetricsEvent::Instantiation,
+            MetricsEvent::MemoryInUse(65536),
+            MetricsEvent::Trap(TrapCode::UnreachableCodeReached),
+            MetricsEvent::MemoryInUse(0),
+        ]
+    );
+    Ok(())
+}
@@ -46,3 +46,126 @@ fn test_module_serialize_fail() -> Result<()> {
     }
     Ok(())
 }
+
+#[test]
+fn test_module_serialize_is_compatible() -> Result<()> {
+    let engine = Engine::default();
+    let buffer = serialize(&engine, "(module)")?;
+    assert!(Module::is_compatible(&engine, &buffer)?);
+
+    let mut config = Config::new();
+    config.cranelift_opt_level(OptLevel::None);
+    assert!(!Module::is_compatible(&Engine::new(&config), &buffer)?);
+
+    assert!(Module::is_compatible(&engine, &[]).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_module_serialize_cpu_features() -> Result<()> {
+    let mut config = Config::new();
+    config.cranelift_baseline_cpu_features();
+    let baseline = Engine::new(&config);
+    let buffer = serialize(
+        &baseline,
+        "(module (func (export \"run\") (result i32) i32.const 42))",
+    )?;
+    assert!(Module::is_compatible(&Engine::new(&config), &buffer)?);
+
+    let store = Store::new(&baseline);
+    let instance = deserialize_and_instantiate(&store, &buffer)?;
+    let run = instance.get_func("run").unwrap().get0::<i32>()?;
+    assert_eq!(run()?, 42);
+
+    assert!(config.cranelift_cpu_feature("not-a-feature", true).is_err());
+
+    #[cfg(target_arch = "x86_64")]
+    {
+        // Artifacts compiled for different CPU features aren't compatible.
+        if is_x86_feature_detected!("sse3") {
+            assert!(!Module::is_compatible(&Engine::default(), &buffer)?);
+            config.cranelift_cpu_feature("sse3", true)?;
+            assert!(!Module::is_compatible(&Engine::new(&config), &buffer)?);
+        }
+        config.cranelift_cpu_feature("sse3", false)?;
+        assert!(Module::is_compatible(&Engine::new(&config), &buffer)?);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_module_serialize_deterministic() -> Result<()> {
+    // Lots of passive segments and function names, which are kept in hash
+    // maps, as well as enough functions to compile in parallel.
+    let mut wat = String::from("(module (memory 1) (table 1 funcref)\n");
+    for i in 0..50 {
+        wat.push_str(&format!(
+            "(func $f{0} (export \"f{0}\") (param i32) (result i32)
+               local.get 0 i32.const {0} i32.add)
+             (data \"segment {0}\")
+             (elem func $f{0})\n",
+            i
+        ));
+    }
+    wat.push_str(")");
+
+    let mut config = Config::new();
+    config.verify_determinism(true);
+    let engine = Engine::new(&config);
+    let expected = Module::new(&engine, &wat)?.serialize()?;
+    for _ in 0..5 {
+        assert_eq!(Module::new(&engine, &wat)?.serialize()?, expected);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_precompile_module() -> Result<()> {
+    let engine = Engine::default();
+    let buffer =
+        engine.precompile_module(b"(module (func (export \"run\") (result i32) i32.const 42))")?;
+
+    let store = Store::new(&engine);
+    let instance = deserialize_and_instantiate(&store, &buffer)?;
+    let run = instance.get_func("run").unwrap().get0::<i32>()?;
+    assert_eq!(42, run()?);
+    Ok(())
+}
+
+#[test]
+fn test_precompile_module_for_other_target() -> Result<()> {
+    let target = if cfg!(target_arch = "x86_64") {
+        "aarch64-unknown-linux-gnu"
+    } else {
+        "x86_64-unknown-linux-gnu"
+    };
+    let mut config = Config::new();
+    config.target(target)?;
+    let buffer = Engine::new(&config).precompile_module(b"(module (func (export \"run\")))")?;
+
+    // The artifact can't be loaded on the host.
+    let engine = Engine::default();
+    assert!(!Module::is_compatible(&engine, &buffer)?);
+    assert!(Module::deserialize(&engine, &buffer).is_err());
+
+    assert!(Config::new().target("not-a-target").is_err());
+    Ok(())
+}
+
+#[test]
+fn test_cpu_features_for_other_target() -> Result<()> {
+    let mut config = Config::new();
+    if cfg!(target_arch = "x86_64") {
+        // Features are validated against the target's architecture.
+        config.target("aarch64-unknown-linux-gnu")?;
+        assert!(config.cranelift_cpu_feature("avx2", true).is_err());
+    } else {
+        // Features the host lacks can be enabled for another target.
+        config.target("x86_64-unknown-linux-gnu")?;
+        config.cranelift_cpu_feature("avx2", true)?;
+    }
+    let triple = config.target_triple();
+    config.cranelift_baseline_cpu_features();
+    assert_eq!(config.target_triple(), triple);
+    Ok(())
+}
@@ -0,0 +1,84 @@
+use anyhow::Result;
+use wasmtime::*;
+
+const CANON_32BIT_NAN: u32 = 0x7fc0_0000;
+const CANON_64BIT_NAN: u64 = 0x7ff8_0000_0000_0000;
+
+fn instance() -> Result<Instance> {
+    let mut config = Config::new();
+    config.wasm_simd(true).cranelift_nan_canonicalization(true);
+    let store = Store::new(&Engine::new(&config));
+    let module = Module::new(
+        store.engine(),
+        r#"
+            (module
+                (func (export "f32.div") (param f32 f32) (result i32)
+                    local.get 0
+                    local.get 1
+                    f32.div
+                    i32.reinterpret_f32)
+                (func (export "f64.promote_f32") (param i32) (result i64)
+                    local.get 0
+                    f32.reinterpret_i32
+                    f64.promote_f32
+                    i64.reinterpret_f64)
+                (func (export "f32x4.div") (param f32 f32) (result i32)
+                    local.get 0
+                    f32x4.splat
+                    local.get 1
+                    f32x4.splat
+                    f32x4.div
+                    i32x4.extract_lane 3)
+                (func (export "f64x2.add") (param i64) (result i64)
+                    local.get 0
+                    i64x2.splat
+                    local.get 0
+                    i64x2.splat
+                    f64x2.add
+                    i64x2.extract_lane 1))
+        "#,
+    )?;
+    Instance::new(&store, &module, &[])
+}
+
+#[test]
+fn scalar() -> Result<()> {
+    let instance = instance()?;
+
+    let div = instance
+        .get_func("f32.div")
+        .unwrap()
+        .get2::<f32, f32, i32>()?;
+    assert_eq!(div(0.0, 0.0)? as u32, CANON_32BIT_NAN);
+    assert_eq!(div(1.0, 2.0)?, 0.5f32.to_bits() as i32);
+
+    // Promotion preserves the payload of a NaN, so a non-canonical input
+    // would otherwise leak into the result.
+    let promote = instance
+        .get_func("f64.promote_f32")
+        .unwrap()
+        .get1::<i32, i64>()?;
+    assert_eq!(promote(0x7fa0_0000)? as u64, CANON_64BIT_NAN);
+    assert_eq!(promote(1.5f32.to_bits() as i32)?, 1.5f64.to_bits() as i64);
+    Ok(())
+}
+
+#[test]
+fn vector() -> Result<()> {
+    let instance = instance()?;
+
+    let div = instance
+        .get_func("f32x4.div")
+        .unwrap()
+        .get2::<f32, f32, i32>()?;
+    assert_eq!(div(0.0, 0.0)? as u32, CANON_32BIT_NAN);
+    assert_eq!(div(1.0, 2.0)?, 0.5f32.to_bits() as i32);
+
+    let add = instance.get_func("f64x2.add").unwrap().get1::<i64, i64>()?;
+    assert_eq!(
+        add(0xfff4_0000_0000_0001u64 as i64)? as u64,
+        CANON_64BIT_NAN
+    );
+    assert_eq!(add(1.5f64.to_bits() as i64)?, 3.0f64.to_bits() as i64);
+    Ok(())
+}
@@ -0,0 +1,68 @@
+use anyhow::Result;
+use object::{Object, ObjectSection, RelocationKind};
+use target_lexicon::Triple;
+use wasmtime::{OptLevel, Strategy};
+use wasmtime_cli::compile_to_obj;
+
+const WAT: &str = r#"
+    (module
+        (func (export "floor") (param f32) (result f32)
+            local.get 0
+            f32.floor)
+        (func (export "call") (param f32) (result f32)
+            local.get 0
+            call 0))
+"#;
+
+fn text_relocation_kinds(pic: bool) -> Result<Vec<RelocationKind>> {
+    let wasm = wat::parse_str(WAT)?;
+    let target: Triple = "x86_64-unknown-linux-gnu".parse().unwrap();
+    let obj = compile_to_obj(
+        &wasm,
+        Some(&target),
+        Strategy::Cranelift,
+        false,
+        OptLevel::None,
+        false,
+        pic,
+    )?;
+    let bytes = obj.write()?;
+    let file = object::File::parse(&bytes)?;
+    let text = file.section_by_name(".text").unwrap();
+    Ok(text.relocations().map(|(_, r)| r.kind()).collect())
+}
+
+#[test]
+fn pic_text_has_no_absolute_relocations() -> Result<()> {
+    // The `f32.floor` is a libcall without SSE4.1, which is addressed
+    // absolutely unless we ask for position-independent code.
+    let kinds = text_relocation_kinds(false)?;
+    assert!(kinds.contains(&RelocationKind::Absolute));
+
+    let kinds = text_relocation_kinds(true)?;
+    assert!(!kinds.is_empty());
+    assert!(!kinds.contains(&RelocationKind::Absolute), "{:?}", kinds);
+    Ok(())
+}
+
+#[test]
+fn pic_unsupported_target() {
+    let wasm = wat::parse_str(WAT).unwrap();
+    let target: Triple = "aarch64-unknown-linux-gnu".parse().unwrap();
+    let err = compile_to_obj(
+        &wasm,
+        Some(&target),
+        Strategy::Cranelift,
+        false,
+        OptLevel::None,
+        false,
+        true,
+    )
+    .err()
+    .unwrap();
+    assert!(
+        err.to_string().contains("not supported"),
+        "bad error: {}",
+        err
+    );
+}
@@ -0,0 +1,100 @@
+use anyhow::Result;
+use wasmtime::*;
+
+const WAT: &str = r#"
+    (module
+        (memory (export "memory") 1 3)
+        (global $g (mut i32) (i32.const 0))
+        (global $h (mut f64) (f64.const 0))
+        (global (export "const") i32 (i32.const 7))
+        (data (i32.const 0) "abc")
+        (data $passive "xyz")
+
+        (func $start (global.set $h (f64.const 1.5)))
+        (start $start)
+
+        (func (export "init")
+            (global.set $g (i32.const 42))
+            (i32.store8 (i32.const 100) (i32.const 1))
+            (drop (memory.grow (i32.const 1)))
+            (i32.store8 (i32.const 70000) (i32.const 2)))
+
+        (func (export "g") (result i32) (global.get $g))
+        (func (export "h") (result f64) (global.get $h))
+        (func (export "init-passive") (memory.init $passive (i32.const 200) (i32.const 0) (i32.const 3)))
+    )
+"#;
+
+#[test]
+fn snapshot_memory_and_globals() -> Result<()> {
+    let store = Store::default();
+    let linker = Linker::new(&store);
+    let wasm = linker.preinitialize(&wat::parse_str(WAT)?, "init")?;
+    Module::validate(store.engine(), &wasm)?;
+
+    let module = Module::new(store.engine(), &wasm)?;
+    let instance = linker.instantiate(&module)?;
+    assert_eq!(instance.get_func("g").unwrap().get0::<i32>()?()?, 42);
+    assert_eq!(instance.get_func("h").unwrap().get0::<f64>()?()?, 1.5);
+    assert_eq!(instance.get_global("const").unwrap().get().unwrap_i32(), 7);
+
+    let memory = instance.get_memory("memory").unwrap();
+    assert_eq!(memory.size(), 2);
+    assert_eq!(memory.ty().limits().max(), Some(3));
+    unsafe {
+        let data = memory.data_unchecked();
+        assert_eq!(&data[..4], b"abc\0");
+        assert_eq!(data[100], 1);
+        assert_eq!(data[70000], 2);
+    }
+
+    // Passive segments remain usable after snapshotting.
+    instance.get_func("init-passive").unwrap().get0::<()>()?()?;
+    unsafe {
+        assert_eq!(&memory.data_unchecked()[200..203], b"xyz");
+    }
+    Ok(())
+}
+
+#[test]
+fn snapshot_errors() -> Result<()> {
+    let store = Store::default();
+    let linker = Linker::new(&store);
+    let wasm = wat::parse_str(WAT)?;
+    assert!(linker.preinitialize(&wasm, "missing").is_err());
+
+    let wasm = wat::parse_str(r#"(module (memory (import "" "m") 1) (func (export "init")))"#)?;
+    let mut linker = Linker::new(&store);
+    let memory = Memory::new(&store, MemoryType::new(Limits::new(1, None)));
+    linker.define("", "m", memory)?;
+    assert!(linker.preinitialize(&wasm, "init").is_err());
+
+    // Table contents aren't snapshotted, so anything that could change them
+    // is rejected.
+    let linker = Linker::new(&store);
+    let wasm = wat::parse_str(
+        r#"
+            (module
+                (table 1 funcref)
+                (elem declare func $f)
+                (func $f)
+                (func $start (table.set (i32.const 0) (ref.func $f)))
+                (start $start)
+                (func (export "init")))
+        "#,
+    )?;
+    let err = linker.preinitialize(&wasm, "init").unwrap_err();
+    assert!(err.to_string().contains("modify tables"), "{}", err);
+
+    let wasm = wat::parse_str(
+        r#"
+            (module
+                (table 1 funcref)
+                (elem $e func $f)
+                (func $f)
+                (func (export "init") (table.init $e (i32.const 0) (i32.const 0) (i32.const 1))))
+        "#,
+    )?;
+    assert!(linker.preinitialize(&wasm, "init").is_err());
+    Ok(())
+}
@@ -0,0 +1,61 @@
+use anyhow::Result;
+use wasmtime::*;
+
+const WAT: &str = r#"
+    (module
+        (func (export "a") (param i32) (result i32)
+            (block
+                (br_if 0 (local.get 0))
+                (return (i32.const 1)))
+            i32.const 2)
+        (func (export "b") (param i32) (result i32)
+            (if (result i32) (local.get 0)
+                (then (i32.const 3))
+                (else (i32.const 4))))
+        (func (export "c") (result i32)
+            (call 0 (i32.const 0))
+            (call 1 (i32.const 0))
+            i32.add))
+"#;
+
+fn check(module: &Module) -> Result<()> {
+    let store = Store::new(module.engine());
+    let instance = Instance::new(&store, module, &[])?;
+    let a = instance.get_func("a").unwrap().get1::<i32, i32>()?;
+    assert_eq!(a(0)?, 1);
+    assert_eq!(a(1)?, 2);
+    let b = instance.get_func("b").unwrap().get1::<i32, i32>()?;
+    assert_eq!(b(1)?, 3);
+    assert_eq!(b(0)?, 4);
+    let c = instance.get_func("c").unwrap().get0::<i32>()?;
+    assert_eq!(c()?, 5);
+    Ok(())
+}
+
+#[test]
+fn profile_does_not_change_behavior() -> Result<()> {
+    let wasm = wat::parse_str(WAT)?;
+    let engine = Engine::default();
+
+    // Function `c` is hottest; in `a`, the `return` after the `br_if` (body
+    // offset 7) is cold, as is the `then` arm of `b` (body offset 5).
+    let mut profile = ExecutionProfile::new();
+    profile
+        .set_function_count(0, 100)
+        .set_function_count(1, 10)
+        .set_function_count(2, 1000)
+        .set_block_count(0, 7, 0)
+        .set_block_count(1, 5, 0);
+    check(&Module::from_binary_with_profile(&engine, &wasm, &profile)?)?;
+
+    // An empty profile, or one for functions that don't exist, is fine too.
+    check(&Module::from_binary_with_profile(
+        &engine,
+        &wasm,
+        &ExecutionProfile::new(),
+    )?)?;
+    let mut profile = ExecutionProfile::new();
+    profile.set_function_count(7, 1).set_block_count(7, 3, 0);
+    check(&Module::from_binary_with_profile(&engine, &wasm, &profile)?)?;
+    Ok(())
+}
@@ -0,0 +1,45 @@
+use anyhow::Result;
+use wasmtime::*;
+
+#[test]
+fn code_is_released_with_last_store() -> Result<()> {
+    let engine = Engine::default();
+    let module = Module::new(&engine, r#"(module (func (export "f")))"#)?;
+    let resources = engine.resources();
+    assert_eq!(resources.modules, 1);
+    assert!(resources.code_bytes > 0);
+    assert_eq!(resources.stores, 0);
+
+    let store1 = Store::new(&engine);
+    let store2 = Store::new(&engine);
+    Instance::new(&store1, &module, &[])?;
+    Instance::new(&store2, &module, &[])?;
+    Func::wrap(&store2, || {});
+    drop(module);
+
+    // Both stores still hold on to the module's code.
+    let resources = engine.resources();
+    assert_eq!(resources.modules, 1);
+    assert_eq!(resources.stores, 2);
+    assert_eq!(resources.instances, 3);
+
+    drop(store1);
+    let resources = engine.resources();
+    assert_eq!(resources.modules, 1);
+    assert_eq!(resources.stores, 1);
+    assert_eq!(resources.instances, 2);
+
+    drop(store2);
+    assert_eq!(engine.resources(), EngineResources::default());
+    Ok(())
+}
+
+#[test]
+fn compile_after_purge() -> Result<()> {
+    let engine = Engine::default();
+    Module::new(&engine, "(module (func))")?;
+    engine.purge();
+    Module::new(&engine, "(module (func))")?;
+    assert_eq!(engine.resources(), EngineResources::default());
+    Ok(())
+}
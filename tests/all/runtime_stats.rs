@@ -0,0 +1,71 @@
+use anyhow::Result;
+use std::time::Duration;
+use wasmtime::*;
+
+fn store() -> Store {
+    let mut config = Config::new();
+    config.runtime_stats(true);
+    Store::new(&Engine::new(&config))
+}
+
+#[test]
+fn disabled_by_default() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(store.engine(), r#"(module (func (export "f")))"#)?;
+    let instance = Instance::new(&store, &module, &[])?;
+    instance.get_func("f").unwrap().call(&[])?;
+    assert_eq!(store.runtime_stats(), RuntimeStats::default());
+    Ok(())
+}
+
+#[test]
+fn host_time_is_separate() -> Result<()> {
+    let store = store();
+    let module = Module::new(
+        store.engine(),
+        r#"
+            (module
+                (import "" "wrapped" (func $wrapped))
+                (import "" "dynamic" (func $dynamic))
+                (func (export "f")
+                    call $wrapped
+                    call $dynamic
+                    call $wrapped))
+        "#,
+    )?;
+    let wrapped = Func::wrap(&store, || std::thread::sleep(Duration::from_millis(10)));
+    let dynamic = Func::new(&store, FuncType::new(None, None), |_, _, _| {
+        std::thread::sleep(Duration::from_millis(10));
+        Ok(())
+    });
+    let instance = Instance::new(&store, &module, &[wrapped.into(), dynamic.into()])?;
+    let f = instance.get_func("f").unwrap().get0::<()>()?;
+
+    let before = store.runtime_stats();
+    f()?;
+    let stats = store.runtime_stats();
+    assert_eq!(stats.host_calls - before.host_calls, 3);
+    assert!(stats.host_time - before.host_time >= Duration::from_millis(30));
+    assert!(stats.wasm_time < stats.host_time);
+    Ok(())
+}
+
+#[test]
+fn trapping_host_function() -> Result<()> {
+    let store = store();
+    let module = Module::new(
+        store.engine(),
+        r#"
+            (module
+                (import "" "" (func $trap))
+                (func (export "f") call $trap))
+        "#,
+    )?;
+    let trap = Func::wrap(&store, || -> Result<(), Trap> { Err(Trap::new("boom")) });
+    let instance = Instance::new(&store, &module, &[trap.into()])?;
+    let f = instance.get_func("f").unwrap().get0::<()>()?;
+    assert!(f().is_err());
+    assert!(f().is_err());
+    assert_eq!(store.runtime_stats().host_calls, 2);
+    Ok(())
+}
@@ -58,3 +58,41 @@ fn host_always_has_some_stack() -> anyhow::Result<()> {
         consume_some_stack(space.as_mut_ptr() as usize, stack.saturating_sub(1024))
     }
 }
+
+#[test]
+fn max_wasm_stack_bounds_recursion() -> anyhow::Result<()> {
+    fn max_depth(stack: usize) -> anyhow::Result<i32> {
+        let mut config = Config::new();
+        config.max_wasm_stack(stack);
+        let store = Store::new(&Engine::new(&config));
+        let module = Module::new(
+            store.engine(),
+            r#"
+                (module
+                    (global $depth (export "depth") (mut i32) (i32.const 0))
+                    (func $recursive (export "foo")
+                        global.get $depth
+                        i32.const 1
+                        i32.add
+                        global.set $depth
+                        call $recursive)
+                )
+            "#,
+        )?;
+        let instance = Instance::new(&store, &module, &[])?;
+        let foo = instance.get_func("foo").unwrap().get0::<()>()?;
+        let trap = foo().unwrap_err();
+        assert!(
+            trap.to_string().contains("call stack exhausted"),
+            "{}",
+            trap.to_string()
+        );
+        Ok(instance.get_global("depth").unwrap().get().unwrap_i32())
+    }
+
+    let small = max_depth(64 << 10)?;
+    let large = max_depth(512 << 10)?;
+    assert!(small > 0);
+    assert!(small * 4 < large, "{} vs {}", small, large);
+    Ok(())
+}
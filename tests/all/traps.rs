@@ -31,6 +31,27 @@ fn test_trap_return() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_trap_message() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(
+        store.engine(),
+        r#"(module (func (export "run") unreachable))"#,
+    )?;
+    let instance = Instance::new(&store, &module, &[])?;
+    let run_func = instance.get_func("run").expect("expected function export");
+
+    let e = run_func
+        .call(&[])
+        .err()
+        .expect("error calling function")
+        .downcast::<Trap>()?;
+
+    assert_eq!(e.message(), "wasm trap: unreachable");
+    assert!(e.to_string().contains("wasm backtrace"));
+    Ok(())
+}
+
 #[test]
 #[cfg_attr(all(target_os = "windows", target_arch = "aarch64"), ignore)] // FIXME(#1642)
 #[cfg_attr(all(target_os = "windows", feature = "experimental_x64"), ignore)] // FIXME(#2079)
@@ -618,3 +639,68 @@ note: run with `WASMTIME_BACKTRACE_DETAILS=1` environment variable to display mo
     );
     Ok(())
 }
+
+#[test]
+fn trap_exit_code() -> Result<()> {
+    assert_eq!(Trap::i32_exit(0).exit_code(), 0);
+    assert_eq!(Trap::i32_exit(2).exit_code(), 2);
+
+    let store = Store::default();
+    let module = Module::new(
+        store.engine(),
+        r#"(module (func (export "f") unreachable))"#,
+    )?;
+    let instance = Instance::new(&store, &module, &[])?;
+    let trap = instance
+        .get_func("f")
+        .unwrap()
+        .call(&[])
+        .unwrap_err()
+        .downcast::<Trap>()?;
+    assert_eq!(trap.trap_code(), Some(TrapCode::UnreachableCodeReached));
+    #[cfg(unix)]
+    assert_eq!(trap.exit_code(), 128 + libc::SIGABRT);
+    #[cfg(windows)]
+    assert_eq!(trap.exit_code(), 3);
+    Ok(())
+}
+
+// Conditional traps are outlined to the end of the function by the new
+// backends, and still need unwind info to produce a backtrace.
+#[test]
+#[cfg_attr(all(target_os = "windows", target_arch = "aarch64"), ignore)] // FIXME(#1642)
+#[cfg_attr(all(target_os = "windows", feature = "experimental_x64"), ignore)] // FIXME(#2079)
+fn outlined_trap_has_trace() -> Result<()> {
+    let mut config = Config::new();
+    if config
+        .cranelift_backend(CraneliftBackend::MachInst)
+        .is_err()
+    {
+        // The new backend wasn't built for this host.
+        return Ok(());
+    }
+    let store = Store::new(&Engine::new(&config));
+    let wat = r#"
+        (module
+            (func (export "run") (result i32)
+                (call $div (i32.const 1) (i32.const 0)))
+            (func $div (param i32 i32) (result i32)
+                (i32.div_u (local.get 0) (local.get 1)))
+        )
+    "#;
+
+    let module = Module::new(store.engine(), wat)?;
+    let instance = Instance::new(&store, &module, &[])?;
+    let trap = instance
+        .get_func("run")
+        .unwrap()
+        .call(&[])
+        .unwrap_err()
+        .downcast::<Trap>()?;
+    assert_eq!(trap.trap_code(), Some(TrapCode::IntegerDivisionByZero));
+    let trace = trap.trace();
+    assert_eq!(trace.len(), 2);
+    assert_eq!(trace[0].func_index(), 1);
+    assert_eq!(trace[1].func_index(), 0);
+    Ok(())
+}
@@ -0,0 +1,126 @@
+use anyhow::Result;
+use wasmtime::*;
+
+fn validation_error(wat: &str) -> Result<ValidationError> {
+    let wasm = wat::parse_str(wat)?;
+    let err = Module::validate(&Engine::default(), &wasm).unwrap_err();
+    Ok(err.downcast::<ValidationError>()?)
+}
+
+#[test]
+fn error_in_named_function() -> Result<()> {
+    let err = validation_error(
+        r#"
+            (module
+                (import "" "" (func))
+                (func $ok)
+                (func $bad (result i32) i64.const 0)
+            )
+        "#,
+    )?;
+    assert_eq!(err.func_index(), Some(2));
+    assert_eq!(err.func_name(), Some("bad"));
+    assert!(err.message().contains("type mismatch"), "{}", err);
+    assert!(err.to_string().contains("in function `bad`"), "{}", err);
+    Ok(())
+}
+
+#[test]
+fn error_in_unnamed_function() -> Result<()> {
+    let wasm = wat::parse_str("(module (func (result i32) i64.const 0))")?;
+    let wasm = Module::strip_custom_sections(&wasm, |_| false)?;
+    let err = Module::validate(&Engine::default(), &wasm).unwrap_err();
+    let err = err.downcast::<ValidationError>()?;
+    assert_eq!(err.func_index(), Some(0));
+    assert_eq!(err.func_name(), None);
+    assert!(err.to_string().contains("in function 0"), "{}", err);
+    Ok(())
+}
+
+#[test]
+fn error_outside_function() -> Result<()> {
+    let err = validation_error("(module (start 0))")?;
+    assert_eq!(err.func_index(), None);
+    assert!(err.offset() > 8);
+    Ok(())
+}
+
+fn limited(f: impl FnOnce(&mut Config) -> &mut Config) -> Engine {
+    let mut config = Config::new();
+    f(&mut config);
+    Engine::new(&config)
+}
+
+#[test]
+fn module_size_limit() -> Result<()> {
+    let wasm = wat::parse_str("(module (func) (func))")?;
+    let engine = limited(|c| c.max_module_size(wasm.len()));
+    Module::validate(&engine, &wasm)?;
+
+    let engine = limited(|c| c.max_module_size(wasm.len() - 1));
+    let err = Module::new(&engine, &wasm).unwrap_err();
+    assert!(err.downcast_ref::<ValidationError>().is_some(), "{}", err);
+    Ok(())
+}
+
+#[test]
+fn function_limit() -> Result<()> {
+    let wat = r#"(module (import "" "" (func)) (func) (func))"#;
+    Module::new(&limited(|c| c.max_module_functions(3)), wat)?;
+    let err = Module::new(&limited(|c| c.max_module_functions(2)), wat).unwrap_err();
+    assert!(err.to_string().contains("3 functions"), "{}", err);
+    Ok(())
+}
+
+#[test]
+fn locals_limit() -> Result<()> {
+    let wat = "(module (func (param i32) (local i32 i64)) (func (local f32)))";
+    Module::new(&limited(|c| c.max_function_locals(2)), wat)?;
+    let wasm = wat::parse_str(wat)?;
+    let engine = limited(|c| c.max_function_locals(1));
+    let err = Module::validate(&engine, &wasm).unwrap_err();
+    let err = err.downcast::<ValidationError>()?;
+    assert_eq!(err.func_index(), Some(0));
+    Ok(())
+}
+
+#[test]
+fn type_limit() -> Result<()> {
+    let wat = "(module (type (func)) (type (func (param i32))))";
+    Module::new(&limited(|c| c.max_module_types(2)), wat)?;
+    assert!(Module::new(&limited(|c| c.max_module_types(1)), wat).is_err());
+    Ok(())
+}
+
+#[test]
+fn exception_handling_rejected() -> Result<()> {
+    // Exception handling isn't supported yet, so modules using it must fail
+    // validation rather than reaching compilation. This module has a single
+    // function whose body is `try end`.
+    let wasm = b"\0asm\x01\0\0\0\
+                 \x01\x04\x01\x60\x00\x00\
+                 \x03\x02\x01\x00\
+                 \x0a\x07\x01\x05\x00\x06\x40\x0b\x0b";
+    let engine = Engine::default();
+    let err = Module::validate(&engine, wasm).unwrap_err();
+    let err = err.downcast::<ValidationError>()?;
+    assert_eq!(err.func_index(), Some(0));
+    assert!(Module::new(&engine, &wasm[..]).is_err());
+    Ok(())
+}
+
+#[test]
+fn tail_calls_rejected() -> Result<()> {
+    // Tail calls aren't supported yet either. This module has a single
+    // function whose body is `return_call 0`.
+    let wasm = b"\0asm\x01\0\0\0\
+                 \x01\x04\x01\x60\x00\x00\
+                 \x03\x02\x01\x00\
+                 \x0a\x06\x01\x04\x00\x12\x00\x0b";
+    let engine = Engine::default();
+    let err = Module::validate(&engine, wasm).unwrap_err();
+    let err = err.downcast::<ValidationError>()?;
+    assert_eq!(err.func_index(), Some(0));
+    assert!(Module::new(&engine, &wasm[..]).is_err());
+    Ok(())
+}
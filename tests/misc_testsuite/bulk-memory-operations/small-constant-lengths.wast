@@ -0,0 +1,66 @@
+;; `memory.copy` and `memory.fill` with small constant lengths are expanded
+;; inline rather than calling into the runtime; check that they still behave
+;; like the out-of-line versions.
+
+(module
+  (memory 1 1)
+  (data (i32.const 0) "\00\01\02\03\04\05\06\07\08\09\0a\0b\0c\0d\0e\0f\10\11\12\13\14\15\16\17\18\19\1a\1b\1c\1d\1e\1f")
+
+  (func (export "load8") (param i32) (result i32)
+    (i32.load8_u (local.get 0)))
+
+  (func (export "copy 15") (param i32 i32)
+    (memory.copy (local.get 0) (local.get 1) (i32.const 15)))
+
+  (func (export "copy 32") (param i32 i32)
+    (memory.copy (local.get 0) (local.get 1) (i32.const 32)))
+
+  (func (export "fill 11") (param i32 i32)
+    (memory.fill (local.get 0) (local.get 1) (i32.const 11)))
+
+  (func (export "fill 0") (param i32)
+    (memory.fill (local.get 0) (i32.const 0) (i32.const 0))))
+
+;; Overlapping copies behave like `memmove`, in both directions.
+(invoke "copy 15" (i32.const 2) (i32.const 0))
+(assert_return (invoke "load8" (i32.const 0)) (i32.const 0))
+(assert_return (invoke "load8" (i32.const 1)) (i32.const 1))
+(assert_return (invoke "load8" (i32.const 2)) (i32.const 0))
+(assert_return (invoke "load8" (i32.const 9)) (i32.const 7))
+(assert_return (invoke "load8" (i32.const 16)) (i32.const 14))
+(assert_return (invoke "load8" (i32.const 17)) (i32.const 17))
+
+(invoke "copy 15" (i32.const 0) (i32.const 2))
+(assert_return (invoke "load8" (i32.const 0)) (i32.const 0))
+(assert_return (invoke "load8" (i32.const 7)) (i32.const 7))
+(assert_return (invoke "load8" (i32.const 14)) (i32.const 14))
+(assert_return (invoke "load8" (i32.const 15)) (i32.const 13))
+
+(invoke "copy 32" (i32.const 100) (i32.const 0))
+(assert_return (invoke "load8" (i32.const 100)) (i32.const 0))
+(assert_return (invoke "load8" (i32.const 131)) (i32.const 31))
+(assert_return (invoke "load8" (i32.const 132)) (i32.const 0))
+
+;; Only the low byte of the value is used.
+(invoke "fill 11" (i32.const 200) (i32.const 0x1234))
+(assert_return (invoke "load8" (i32.const 199)) (i32.const 0))
+(assert_return (invoke "load8" (i32.const 200)) (i32.const 0x34))
+(assert_return (invoke "load8" (i32.const 210)) (i32.const 0x34))
+(assert_return (invoke "load8" (i32.const 211)) (i32.const 0))
+
+;; Out-of-bounds accesses trap without modifying memory, even if the range is
+;; only partially out of bounds.
+(assert_trap (invoke "copy 32" (i32.const 65520) (i32.const 0)) "out of bounds")
+(assert_return (invoke "load8" (i32.const 65520)) (i32.const 0))
+(assert_return (invoke "load8" (i32.const 65535)) (i32.const 0))
+(assert_trap (invoke "copy 32" (i32.const 0) (i32.const 65520)) "out of bounds")
+(assert_return (invoke "load8" (i32.const 0)) (i32.const 0))
+(assert_return (invoke "load8" (i32.const 1)) (i32.const 1))
+(assert_trap (invoke "fill 11" (i32.const 65530) (i32.const 0xff)) "out of bounds")
+(assert_return (invoke "load8" (i32.const 65530)) (i32.const 0))
+(assert_return (invoke "load8" (i32.const 65535)) (i32.const 0))
+(assert_trap (invoke "copy 32" (i32.const -1) (i32.const 0)) "out of bounds")
+
+;; Zero-length operations at the end of memory are fine, past it they trap.
+(invoke "fill 0" (i32.const 65536))
+(assert_trap (invoke "fill 0" (i32.const 65537)) "out of bounds")
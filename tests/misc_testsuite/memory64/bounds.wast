@@ -0,0 +1,124 @@
+(module
+  (memory i64 1)
+  (data (i64.const 8) "\01\02\03\04")
+
+  (func (export "load") (param i64) (result i32)
+    local.get 0
+    i32.load)
+
+  (func (export "load_offset") (param i64) (result i32)
+    local.get 0
+    i32.load offset=8)
+
+  (func (export "store") (param i64 i32)
+    local.get 0
+    local.get 1
+    i32.store)
+
+  (func (export "size") (result i64)
+    memory.size)
+
+  (func (export "grow") (param i64) (result i64)
+    local.get 0
+    memory.grow)
+
+  (func (export "fill") (param i64 i32 i64)
+    local.get 0
+    local.get 1
+    local.get 2
+    memory.fill)
+
+  (func (export "copy") (param i64 i64 i64)
+    local.get 0
+    local.get 1
+    local.get 2
+    memory.copy)
+)
+
+(assert_return (invoke "load" (i64.const 8)) (i32.const 0x04030201))
+(assert_return (invoke "load_offset" (i64.const 0)) (i32.const 0x04030201))
+(assert_return (invoke "load" (i64.const 65532)) (i32.const 0))
+(assert_trap (invoke "load" (i64.const 65533)) "out of bounds memory access")
+
+;; Addresses whose low 32 bits are in bounds must still trap.
+(assert_trap (invoke "load" (i64.const 0x1_0000_0000)) "out of bounds memory access")
+(assert_trap (invoke "store" (i64.const 0x1_0000_0008) (i32.const 1)) "out of bounds memory access")
+(assert_return (invoke "load" (i64.const 8)) (i32.const 0x04030201))
+
+;; Adding the offset must not wrap around to an in-bounds address.
+(assert_trap (invoke "load_offset" (i64.const -8)) "out of bounds memory access")
+(assert_trap (invoke "load_offset" (i64.const -4)) "out of bounds memory access")
+
+(assert_return (invoke "size") (i64.const 1))
+(assert_return (invoke "grow" (i64.const 0x1_0000_0000)) (i64.const -1))
+(assert_return (invoke "grow" (i64.const -1)) (i64.const -1))
+(assert_return (invoke "size") (i64.const 1))
+(assert_return (invoke "grow" (i64.const 1)) (i64.const 1))
+(assert_return (invoke "size") (i64.const 2))
+(assert_return (invoke "load" (i64.const 131068)) (i32.const 0))
+(assert_trap (invoke "load" (i64.const 131069)) "out of bounds memory access")
+
+(assert_trap (invoke "fill" (i64.const 0x1_0000_0000) (i32.const 0) (i64.const 0)) "out of bounds memory access")
+(assert_trap (invoke "fill" (i64.const 0) (i32.const 0) (i64.const 0x1_0000_0000)) "out of bounds memory access")
+(invoke "fill" (i64.const 16) (i32.const 0xaa) (i64.const 4))
+(assert_return (invoke "load" (i64.const 16)) (i32.const 0xaaaaaaaa))
+
+(assert_trap (invoke "copy" (i64.const 0) (i64.const 0x1_0000_0008) (i64.const 4)) "out of bounds memory access")
+(invoke "copy" (i64.const 24) (i64.const 8) (i64.const 4))
+(assert_return (invoke "load" (i64.const 24)) (i32.const 0x04030201))
+
+;; On a 4GiB memory an address of exactly 4GiB is in bounds for a zero-length
+;; operation.
+(module
+  (memory i64 65536)
+  (data $d "\01")
+
+  (func (export "fill") (param i64 i64)
+    local.get 0
+    i32.const 0
+    local.get 1
+    memory.fill)
+
+  (func (export "copy") (param i64 i64 i64)
+    local.get 0
+    local.get 1
+    local.get 2
+    memory.copy)
+
+  (func (export "init") (param i64 i32)
+    local.get 0
+    i32.const 0
+    local.get 1
+    memory.init $d)
+)
+
+(invoke "fill" (i64.const 0x1_0000_0000) (i64.const 0))
+(invoke "copy" (i64.const 0x1_0000_0000) (i64.const 0) (i64.const 0))
+(invoke "copy" (i64.const 0) (i64.const 0x1_0000_0000) (i64.const 0))
+(invoke "init" (i64.const 0x1_0000_0000) (i32.const 0))
+(assert_trap (invoke "fill" (i64.const 0x1_0000_0000) (i64.const 1)) "out of bounds memory access")
+(assert_trap (invoke "fill" (i64.const 0x1_0000_0001) (i64.const 0)) "out of bounds memory access")
+(assert_trap (invoke "copy" (i64.const 0x1_0000_0000) (i64.const 0) (i64.const 1)) "out of bounds memory access")
+(assert_trap (invoke "copy" (i64.const 0) (i64.const 0x1_0000_0001) (i64.const 0)) "out of bounds memory access")
+(assert_trap (invoke "init" (i64.const 0x1_0000_0000) (i32.const 1)) "out of bounds memory access")
+
+;; Data segments that don't fit fail instantiation.
+(assert_trap
+  (module
+    (memory i64 1)
+    (data (i64.const 0x1_0000_0000) "\01"))
+  "out of bounds memory access")
+
+;; 32-bit and 64-bit memories don't match each other's imports.
+(module $m32 (memory (export "mem") 1))
+(register "m32" $m32)
+(module $m64 (memory (export "mem") i64 1))
+(register "m64" $m64)
+
+(module (import "m64" "mem" (memory i64 1)))
+(assert_unlinkable
+  (module (import "m32" "mem" (memory i64 1)))
+  "incompatible import type")
+(assert_unlinkable
+  (module (import "m64" "mem" (memory 1)))
+  "incompatible import type")
@@ -0,0 +1,61 @@
+(module
+  (func (export "i16x8.extmul_low_i8x16_s") (param v128 v128) (result v128)
+    (i16x8.extmul_low_i8x16_s (local.get 0) (local.get 1)))
+  (func (export "i16x8.extmul_high_i8x16_u") (param v128 v128) (result v128)
+    (i16x8.extmul_high_i8x16_u (local.get 0) (local.get 1)))
+  (func (export "i32x4.extmul_low_i16x8_u") (param v128 v128) (result v128)
+    (i32x4.extmul_low_i16x8_u (local.get 0) (local.get 1)))
+  (func (export "i32x4.extmul_high_i16x8_s") (param v128 v128) (result v128)
+    (i32x4.extmul_high_i16x8_s (local.get 0) (local.get 1)))
+  (func (export "i64x2.extmul_low_i32x4_s") (param v128 v128) (result v128)
+    (i64x2.extmul_low_i32x4_s (local.get 0) (local.get 1)))
+  (func (export "i64x2.extmul_low_i32x4_u") (param v128 v128) (result v128)
+    (i64x2.extmul_low_i32x4_u (local.get 0) (local.get 1)))
+  (func (export "i64x2.extmul_high_i32x4_s") (param v128 v128) (result v128)
+    (i64x2.extmul_high_i32x4_s (local.get 0) (local.get 1)))
+  (func (export "i64x2.extmul_high_i32x4_u") (param v128 v128) (result v128)
+    (i64x2.extmul_high_i32x4_u (local.get 0) (local.get 1)))
+)
+
+(assert_return
+  (invoke "i16x8.extmul_low_i8x16_s"
+    (v128.const i8x16 2 2 2 2 2 2 2 2 -1 -1 -1 -1 -1 -1 -1 -1)
+    (v128.const i8x16 -128 -128 -128 -128 -128 -128 -128 -128 -128 -128 -128 -128 -128 -128 -128 -128))
+  (v128.const i16x8 -256 -256 -256 -256 -256 -256 -256 -256))
+(assert_return
+  (invoke "i16x8.extmul_high_i8x16_u"
+    (v128.const i8x16 2 2 2 2 2 2 2 2 -1 -1 -1 -1 -1 -1 -1 -1)
+    (v128.const i8x16 -128 -128 -128 -128 -128 -128 -128 -128 -128 -128 -128 -128 -128 -128 -128 -128))
+  (v128.const i16x8 32640 32640 32640 32640 32640 32640 32640 32640))
+
+(assert_return
+  (invoke "i32x4.extmul_low_i16x8_u"
+    (v128.const i16x8 -1 -1 2 3 1 1 1 1)
+    (v128.const i16x8 -1 2 -1 3 -1 -1 -1 -1))
+  (v128.const i32x4 -131071 131070 131070 9))
+(assert_return
+  (invoke "i32x4.extmul_high_i16x8_s"
+    (v128.const i16x8 1 1 1 1 -1 -32768 2 -3)
+    (v128.const i16x8 -1 -1 -1 -1 -32768 -32768 -4 5))
+  (v128.const i32x4 32768 1073741824 -8 -15))
+
+(assert_return
+  (invoke "i64x2.extmul_low_i32x4_s"
+    (v128.const i32x4 -1 0x7fffffff 5 5)
+    (v128.const i32x4 0x7fffffff 0x7fffffff 1 1))
+  (v128.const i64x2 -2147483647 4611686014132420609))
+(assert_return
+  (invoke "i64x2.extmul_low_i32x4_u"
+    (v128.const i32x4 -1 2 5 5)
+    (v128.const i32x4 2 3 1 1))
+  (v128.const i64x2 8589934590 6))
+(assert_return
+  (invoke "i64x2.extmul_high_i32x4_s"
+    (v128.const i32x4 1 1 -2 3)
+    (v128.const i32x4 1 1 3 -4))
+  (v128.const i64x2 -6 -12))
+(assert_return
+  (invoke "i64x2.extmul_high_i32x4_u"
+    (v128.const i32x4 1 1 -1 -1)
+    (v128.const i32x4 1 1 -1 2))
+  (v128.const i64x2 -8589934591 8589934590))
@@ -0,0 +1,42 @@
+(module
+  (memory 1 2 shared)
+
+  (func (export "load") (param i32) (result i32)
+    local.get 0
+    i32.atomic.load)
+
+  (func (export "load_offset") (param i32) (result i32)
+    local.get 0
+    i32.atomic.load offset=32)
+
+  (func (export "store_offset") (param i32 i32)
+    local.get 0
+    local.get 1
+    i32.atomic.store offset=32)
+
+  (func (export "rmw_offset") (param i32 i32) (result i32)
+    local.get 0
+    local.get 1
+    i32.atomic.rmw.add offset=32)
+
+  (func (export "grow") (param i32) (result i32)
+    local.get 0
+    memory.grow)
+)
+
+(assert_return (invoke "load_offset" (i32.const 65500)) (i32.const 0))
+(assert_trap (invoke "load_offset" (i32.const 65504)) "out of bounds memory access")
+
+;; Adding the offset must not wrap around to an in-bounds address.
+(assert_trap (invoke "load_offset" (i32.const 0xfffffff0)) "out of bounds memory access")
+(assert_trap (invoke "store_offset" (i32.const 0xfffffff0) (i32.const 1)) "out of bounds memory access")
+(assert_trap (invoke "rmw_offset" (i32.const 0xfffffff0) (i32.const 1)) "out of bounds memory access")
+(assert_return (invoke "load" (i32.const 16)) (i32.const 0))
+
+;; Shared memories can grow up to their maximum, and the new pages are
+;; accessible.
+(assert_trap (invoke "load" (i32.const 65536)) "out of bounds memory access")
+(assert_return (invoke "grow" (i32.const 1)) (i32.const 1))
+(invoke "store_offset" (i32.const 65536) (i32.const 42))
+(assert_return (invoke "load" (i32.const 65568)) (i32.const 42))
+(assert_return (invoke "grow" (i32.const 1)) (i32.const -1))
@@ -0,0 +1,15 @@
+(module
+  (memory (export "memory") 1)
+  (data (i32.const 16) "hello")
+  (global $count (export "count") (mut i32) (i32.const 0))
+  (func (export "add") (param i32 i32) (result i32)
+    local.get 0
+    local.get 1
+    i32.add)
+  (func (export "incr") (result i32)
+    global.get $count
+    i32.const 1
+    i32.add
+    global.set $count
+    global.get $count)
+)
@@ -0,0 +1,13 @@
+(module
+  (import "wasi_snapshot_preview1" "fd_fdstat_get"
+    (func $fd_fdstat_get (param i32 i32) (result i32)))
+  (memory (export "memory") 1)
+
+  ;; Return the WASI filetype of the given file descriptor.
+  (func (export "filetype") (param $fd i32) (result i32)
+    ;; The fdstat is at address 0, with the filetype at offset 0.
+    (if (call $fd_fdstat_get (local.get $fd) (i32.const 0))
+      (then unreachable))
+    (i32.load8_u (i32.const 0))
+  )
+)
@@ -0,0 +1,30 @@
+(module
+  (import "wasi_snapshot_preview1" "fd_read"
+    (func $fd_read (param i32 i32 i32 i32) (result i32)))
+  (import "wasi_snapshot_preview1" "fd_write"
+    (func $fd_write (param i32 i32 i32 i32) (result i32)))
+  (memory (export "memory") 1)
+  (data (i32.const 64) "Content-Type: text/plain\n\n")
+
+  ;; Respond to an HTTP request for `wasmtime serve` with its own body.
+  (func (export "_start")
+    ;; Write the headers through an iovec at address 0.
+    (i32.store (i32.const 0) (i32.const 64))
+    (i32.store (i32.const 4) (i32.const 26))
+    (if (call $fd_write (i32.const 1) (i32.const 0) (i32.const 1) (i32.const 8))
+      (then unreachable))
+
+    ;; Copy stdin to stdout through a buffer at address 1024.
+    (i32.store (i32.const 0) (i32.const 1024))
+    (block $done
+      (loop $copy
+        (i32.store (i32.const 4) (i32.const 1024))
+        (if (call $fd_read (i32.const 0) (i32.const 0) (i32.const 1) (i32.const 8))
+          (then unreachable))
+        (br_if $done (i32.eqz (i32.load (i32.const 8))))
+        (i32.store (i32.const 4) (i32.load (i32.const 8)))
+        (if (call $fd_write (i32.const 1) (i32.const 0) (i32.const 1) (i32.const 8))
+          (then unreachable))
+        (br $copy)))
+  )
+)
@@ -0,0 +1,5 @@
+(module
+  ;; Never respond to an HTTP request for `wasmtime serve`.
+  (func (export "_start")
+    (loop $forever
+      (br $forever))))
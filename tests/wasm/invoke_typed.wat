@@ -0,0 +1,10 @@
+(module
+  ;; Return every argument, to check how `--invoke` parses and prints them.
+  (func (export "echo") (param i32 i64 f32 f64 v128) (result i32 i64 f32 f64 v128)
+    local.get 0
+    local.get 1
+    local.get 2
+    local.get 3
+    local.get 4
+  )
+)
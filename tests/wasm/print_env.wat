@@ -0,0 +1,37 @@
+(module
+  (import "wasi_snapshot_preview1" "environ_sizes_get"
+    (func $environ_sizes_get (param i32 i32) (result i32)))
+  (import "wasi_snapshot_preview1" "environ_get"
+    (func $environ_get (param i32 i32) (result i32)))
+  (import "wasi_snapshot_preview1" "fd_write"
+    (func $fd_write (param i32 i32 i32 i32) (result i32)))
+  (memory (export "memory") 2)
+
+  ;; Print each environment variable on its own line.
+  (func (export "_start")
+    (local $i i32)
+    (local $size i32)
+    (if (call $environ_sizes_get (i32.const 0) (i32.const 4))
+      (then unreachable))
+    (local.set $size (i32.load (i32.const 4)))
+
+    ;; The pointers go at address 1024, the strings at 8192.
+    (if (call $environ_get (i32.const 1024) (i32.const 8192))
+      (then unreachable))
+
+    ;; Turn the NUL terminators into newlines.
+    (block $done
+      (loop $loop
+        (br_if $done (i32.ge_u (local.get $i) (local.get $size)))
+        (if (i32.eqz (i32.load8_u offset=8192 (local.get $i)))
+          (then (i32.store8 offset=8192 (local.get $i) (i32.const 10))))
+        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+        (br $loop)))
+
+    ;; Write the strings out through an iovec at address 16.
+    (i32.store (i32.const 16) (i32.const 8192))
+    (i32.store (i32.const 20) (local.get $size))
+    (if (call $fd_write (i32.const 1) (i32.const 16) (i32.const 1) (i32.const 24))
+      (then unreachable))
+  )
+)
@@ -0,0 +1,24 @@
+(module
+  (import "wasi_snapshot_preview1" "fd_prestat_get"
+    (func $fd_prestat_get (param i32 i32) (result i32)))
+  (import "wasi_snapshot_preview1" "fd_prestat_dir_name"
+    (func $fd_prestat_dir_name (param i32 i32 i32) (result i32)))
+  (import "wasi_snapshot_preview1" "fd_write"
+    (func $fd_write (param i32 i32 i32 i32) (result i32)))
+  (memory (export "memory") 1)
+
+  ;; Print the guest path of the first preopened directory.
+  (func (export "_start")
+    ;; The prestat is at address 0, with the name length at offset 4.
+    (if (call $fd_prestat_get (i32.const 3) (i32.const 0))
+      (then unreachable))
+    (if (call $fd_prestat_dir_name (i32.const 3) (i32.const 64) (i32.load (i32.const 4)))
+      (then unreachable))
+
+    ;; Write the name out through an iovec at address 16.
+    (i32.store (i32.const 16) (i32.const 64))
+    (i32.store (i32.const 20) (i32.load (i32.const 4)))
+    (if (call $fd_write (i32.const 1) (i32.const 16) (i32.const 1) (i32.const 24))
+      (then unreachable))
+  )
+)